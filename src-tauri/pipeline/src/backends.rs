@@ -0,0 +1,126 @@
+// Backend traits implemented by the macOS modules and by the test harness mocks
+
+use crate::error::PromptOsError;
+use std::sync::Arc;
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Default)]
+pub struct FieldBounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// CGDirectDisplayID of the screen the field is on, when known
+    pub display_id: Option<u32>,
+    /// Backing scale factor of that screen (2.0 on Retina), when known
+    pub scale_factor: Option<f64>,
+    pub coordinates: CoordinateSpace,
+}
+
+/// What unit bounds are measured in
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CoordinateSpace {
+    /// Points, as on macOS; multiply by the scale factor for pixels
+    #[default]
+    Logical,
+    /// Device pixels, as UI Automation and X11 report them
+    Physical,
+}
+
+/// How text should reach a field, as hinted by detecting it
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectStrategy {
+    /// Accessibility insertion, falling back to paste
+    #[default]
+    Insert,
+    /// Paste only, as in terminals: they refuse or garble Accessibility writes, and their paste
+    /// is bracketed, so a multi-line response isn't run line by line
+    Paste,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq, Default)]
+pub struct AppInfo {
+    pub bundle_id: Option<String>,
+    pub name: Option<String>,
+    pub pid: i32,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq, Default)]
+pub struct FieldText {
+    pub selection: Option<String>,
+    pub value: Option<String>,
+}
+
+/// Finds the focused text field and reads from it
+pub trait Detector: Send + Sync {
+    /// Where to anchor the overlay; Err when no text field is focused
+    fn focused_field_bounds(&self) -> Result<FieldBounds, PromptOsError>;
+    fn frontmost_app(&self) -> Option<AppInfo>;
+    /// Selection and value of the focused field (pid targets an app that may not be frontmost)
+    fn field_text(&self, pid: Option<i32>) -> FieldText;
+    /// How the focused field takes text
+    fn inject_strategy(&self) -> InjectStrategy;
+}
+
+/// Writes text into the focused field
+pub trait Injector: Send + Sync {
+    /// Direct insertion (replaces the selection)
+    fn insert(&self, text: &str) -> Result<(), PromptOsError>;
+    /// Clipboard + paste fallback for apps that ignore direct insertion
+    fn paste(&self, text: &str) -> Result<(), PromptOsError>;
+}
+
+/// Produces model output
+pub trait Generator: Send + Sync {
+    fn generate(&self, prompt: &str, system_prompt: Option<&str>) -> Result<String, PromptOsError>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// Trigger character typed in a text field: open the overlay
+    Slash,
+    /// Slash command typed after the trigger (e.g. "fix" from "/fix"): open the overlay with it
+    Command(String),
+    /// Quick-transform hotkey: rewrite the selection in place
+    QuickTransform,
+    /// Hotkey bound to a user-defined action (by id): rewrite the selection with it
+    Action(String),
+    /// Triple- or middle-click, or the selection hotkey, on selected text: open the overlay
+    /// pre-filled with the selection
+    Selection,
+    /// Snippet abbreviation typed anywhere (e.g. ":sig"): replace it with this expansion
+    Snippet(String),
+    /// User-configured trigger (e.g. "::fix"): open the overlay routed to its action, if any
+    Named {
+        name: String,
+        action: Option<String>,
+    },
+}
+
+impl Trigger {
+    /// Stable name of the kind of trigger, e.g. "slash" or "named"
+    pub fn id(&self) -> &'static str {
+        match self {
+            Trigger::Slash => "slash",
+            Trigger::Command(_) => "command",
+            Trigger::QuickTransform => "quick_transform",
+            Trigger::Action(_) => "action",
+            Trigger::Selection => "selection",
+            Trigger::Snippet(_) => "snippet",
+            Trigger::Named { .. } => "named",
+        }
+    }
+}
+
+/// Called by a monitor for every trigger; returns whether the key should be swallowed
+pub type TriggerSink = Arc<dyn Fn(Trigger) -> bool + Send + Sync>;
+
+/// Watches input and reports triggers
+pub trait Monitor: Send + Sync {
+    fn start(&self, sink: TriggerSink) -> Result<(), PromptOsError>;
+    fn stop(&self) -> Result<(), PromptOsError>;
+    /// Whether it was started, not stopped since, and has died (its thread exited or its hook is
+    /// gone), so it must be started again
+    fn died(&self) -> bool;
+}
@@ -0,0 +1,91 @@
+// Writing direction and script of a field's text, read off its letters
+// Accessibility reports no writing direction, so the detectors pass their field's value through this
+
+// Enough letters for a stable answer without scanning huge documents
+const MAX_SCANNED_CHARS: usize = 2000;
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TextDirection {
+    #[default]
+    Ltr,
+    Rtl,
+}
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Hebrew,
+    Arabic,
+    Syriac,
+    Thaana,
+    Devanagari,
+    Thai,
+    Hangul,
+    /// Hiragana and katakana
+    Kana,
+    Han,
+    /// Letters of any other script
+    Other,
+}
+
+impl Script {
+    /// Script of a letter; None for digits, punctuation, spaces, and symbols
+    pub fn of(c: char) -> Option<Script> {
+        if !c.is_alphabetic() {
+            return None;
+        }
+        let script = match c as u32 {
+            0x0041..=0x024F | 0x1E00..=0x1EFF | 0xFF21..=0xFF5A => Script::Latin,
+            0x0370..=0x03FF | 0x1F00..=0x1FFF => Script::Greek,
+            0x0400..=0x052F => Script::Cyrillic,
+            0x0590..=0x05FF | 0xFB1D..=0xFB4F => Script::Hebrew,
+            0x0600..=0x06FF | 0x0750..=0x077F | 0x08A0..=0x08FF | 0xFB50..=0xFDFF => Script::Arabic,
+            0xFE70..=0xFEFF => Script::Arabic,
+            0x0700..=0x074F => Script::Syriac,
+            0x0780..=0x07BF => Script::Thaana,
+            0x0900..=0x097F => Script::Devanagari,
+            0x0E00..=0x0E7F => Script::Thai,
+            0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Script::Hangul,
+            0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F => Script::Kana,
+            0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x2FFFF => Script::Han,
+            _ => Script::Other,
+        };
+        Some(script)
+    }
+
+    pub fn direction(self) -> TextDirection {
+        match self {
+            Script::Hebrew | Script::Arabic | Script::Syriac | Script::Thaana => TextDirection::Rtl,
+            _ => TextDirection::Ltr,
+        }
+    }
+}
+
+/// Dominant script of a field's text and the direction it's written in
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WritingDirection {
+    pub direction: TextDirection,
+    pub script: Script,
+}
+
+/// The script with the most letters in text (the first to appear on a tie); None without letters
+pub fn detect_direction(text: &str) -> Option<WritingDirection> {
+    let mut counts: Vec<(Script, usize)> = Vec::new();
+    for script in text.chars().take(MAX_SCANNED_CHARS).filter_map(Script::of) {
+        match counts.iter_mut().find(|(seen, _)| *seen == script) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((script, 1)),
+        }
+    }
+
+    // max_by_key keeps the last of equal maxima, so scan from the end for the first
+    let (script, _) = counts.into_iter().rev().max_by_key(|(_, count)| *count)?;
+    Some(WritingDirection {
+        direction: script.direction(),
+        script,
+    })
+}
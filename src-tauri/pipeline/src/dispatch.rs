@@ -0,0 +1,104 @@
+// What happens to a trigger once a monitor reports it, shared by the app and the test harness
+// Debounce, then the permission check, then the handler for that kind of trigger
+
+use crate::backends::Trigger;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// What the overlay should do once it opens
+#[derive(serde::Serialize, Clone, Debug, PartialEq, Default)]
+pub struct OverlayRoute {
+    /// Slash command typed after the trigger, e.g. "fix" from "/fix"
+    pub command: Option<String>,
+    /// Name of the named trigger that fired, if one did
+    pub trigger: Option<String>,
+    /// Action id the named trigger routes to
+    pub action: Option<String>,
+    /// Selected text to pre-fill the overlay with
+    pub selection: Option<String>,
+}
+
+/// When the last trigger fired, to ignore one that follows too closely to be meant
+#[derive(Default)]
+pub struct Debouncer {
+    last: Mutex<Option<Instant>>,
+}
+
+impl Debouncer {
+    pub const fn new() -> Self {
+        Self {
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Whether a trigger comes within `window` of the last one; a steady stream of them (a
+    /// bouncing or held key) keeps being ignored
+    pub fn debounced(&self, trigger: &Trigger, window: Duration) -> bool {
+        // Every snippet is typed out in full, so two in a row are both meant
+        if matches!(trigger, Trigger::Snippet(_)) {
+            return false;
+        }
+
+        let now = Instant::now();
+        let Ok(mut last) = self.last.lock() else {
+            return false;
+        };
+        let too_soon = last.is_some_and(|last| now.saturating_duration_since(last) < window);
+        *last = Some(now);
+        too_soon
+    }
+}
+
+/// The platform side of each kind of trigger; the methods that act on one return whether its key
+/// should be swallowed
+pub trait TriggerHandler {
+    /// Whether the trigger follows the last one too closely to be meant
+    fn debounced(&self, trigger: &Trigger) -> bool;
+    /// Whether the focused field may be read and written; permissions can be revoked while running
+    fn permitted(&self) -> bool;
+    /// A trigger while the focused field is off limits
+    fn clipboard_only(&self, trigger: Trigger) -> bool;
+    /// Rewrite the selection with the quick action, or with a user-defined action (by id)
+    fn quick_transform(&self, action: Option<String>) -> bool;
+    /// Open the overlay over the focused field
+    fn open_overlay(&self, trigger_id: &'static str, route: OverlayRoute) -> bool;
+    /// Open the overlay pre-filled with the selected text
+    fn open_selection_overlay(&self) -> bool;
+    /// Replace the typed abbreviation with its expansion
+    fn expand_snippet(&self, expansion: String) -> bool;
+}
+
+/// React to a trigger from a monitor; returns whether the key should be swallowed
+pub fn dispatch(handler: &impl TriggerHandler, trigger: Trigger) -> bool {
+    // Not swallowed, so a typed trigger stays in the field as is
+    if handler.debounced(&trigger) {
+        return false;
+    }
+    if !handler.permitted() {
+        return handler.clipboard_only(trigger);
+    }
+
+    let trigger_id = trigger.id();
+    match trigger {
+        Trigger::QuickTransform => handler.quick_transform(None),
+        Trigger::Action(id) => handler.quick_transform(Some(id)),
+        Trigger::Slash => handler.open_overlay(trigger_id, OverlayRoute::default()),
+        Trigger::Command(command) => {
+            let route = OverlayRoute {
+                command: Some(command),
+                ..OverlayRoute::default()
+            };
+            handler.open_overlay(trigger_id, route)
+        }
+        Trigger::Named { name, action } => {
+            let route = OverlayRoute {
+                trigger: Some(name),
+                action,
+                ..OverlayRoute::default()
+            };
+            handler.open_overlay(trigger_id, route)
+        }
+        Trigger::Selection => handler.open_selection_overlay(),
+        Trigger::Snippet(expansion) => handler.expand_snippet(expansion),
+    }
+}
@@ -0,0 +1,73 @@
+// Error type shared by the pipeline, its backends, and every app command
+// Serialized as { code, message } so the frontend can branch on the kind of failure
+
+use serde::ser::SerializeStruct;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PromptOsError {
+    /// A macOS permission (Accessibility, Calendars, Automation) is missing
+    #[error("{0}")]
+    NoPermission(String),
+    #[error("No focused text field")]
+    NoFocus,
+    #[error("The focused field is a password field")]
+    SecureField,
+    /// Another app or field took focus between the trigger and writing the response
+    #[error("{0}")]
+    FocusChanged(String),
+    /// Missing or rejected API key
+    #[error("{0}")]
+    ProviderAuth(String),
+    #[error("Rate limited. Please wait.")]
+    RateLimited,
+    #[error("{0}")]
+    Timeout(String),
+    /// Refused by the content filter or the app blocklist
+    #[error("{0}")]
+    Blocked(String),
+    /// Any other provider (Gemini) failure
+    #[error("{0}")]
+    Provider(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl PromptOsError {
+    /// Stable identifier the frontend matches on; never rename these
+    pub fn code(&self) -> &'static str {
+        match self {
+            PromptOsError::NoPermission(_) => "no_permission",
+            PromptOsError::NoFocus => "no_focus",
+            PromptOsError::SecureField => "secure_field",
+            PromptOsError::FocusChanged(_) => "focus_changed",
+            PromptOsError::ProviderAuth(_) => "provider_auth",
+            PromptOsError::RateLimited => "rate_limited",
+            PromptOsError::Timeout(_) => "timeout",
+            PromptOsError::Blocked(_) => "blocked",
+            PromptOsError::Provider(_) => "provider",
+            PromptOsError::Internal(_) => "internal",
+        }
+    }
+}
+
+impl serde::Serialize for PromptOsError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("PromptOsError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+// Untyped failures (lock poisoning, I/O, ObjC lookups) surface as "internal"
+impl From<String> for PromptOsError {
+    fn from(message: String) -> Self {
+        PromptOsError::Internal(message)
+    }
+}
+
+impl From<&str> for PromptOsError {
+    fn from(message: &str) -> Self {
+        PromptOsError::Internal(message.to_string())
+    }
+}
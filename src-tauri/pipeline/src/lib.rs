@@ -0,0 +1,23 @@
+// Platform-independent core of Prompt OS
+// Backends (detector, injector, generator, monitor) are traits so the pipeline runs without macOS
+
+mod backends;
+mod direction;
+mod dispatch;
+mod error;
+mod markdown;
+mod matcher;
+mod pipeline;
+mod placement;
+
+pub use backends::{
+    AppInfo, CoordinateSpace, Detector, FieldBounds, FieldText, Generator, InjectStrategy,
+    Injector, Monitor, Trigger, TriggerSink,
+};
+pub use direction::{detect_direction, Script, TextDirection, WritingDirection};
+pub use dispatch::{dispatch, Debouncer, OverlayRoute, TriggerHandler};
+pub use error::PromptOsError;
+pub use markdown::{render_markdown, RenderedMarkdown};
+pub use matcher::{Chord, KeyPress, MatcherConfig, TriggerMatcher, TypedKey, MAX_TYPED_CHARS};
+pub use pipeline::{InjectMethod, Pipeline, PipelineContext};
+pub use placement::{dock_to_window, place_overlay, PlacementMode, Rect, OVERLAY_GAP};
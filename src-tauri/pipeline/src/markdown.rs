@@ -0,0 +1,446 @@
+// Markdown rendering for injection, so a response lands formatted instead of full of asterisks
+// Covers the subset models write (headings, lists, emphasis, code, links, quotes), not CommonMark
+
+/// A response rendered for the pasteboard; editors take the richest type they understand
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenderedMarkdown {
+    /// Markers stripped, for plain fields and Accessibility writes
+    pub plain: String,
+    pub html: String,
+    pub rtf: String,
+}
+
+pub fn render_markdown(markdown: &str) -> RenderedMarkdown {
+    let blocks = parse_blocks(markdown);
+    RenderedMarkdown {
+        plain: plain(&blocks),
+        html: html(&blocks),
+        rtf: rtf(&blocks),
+    }
+}
+
+enum Block {
+    Heading(usize, Vec<Inline>),
+    /// Lines kept apart, since models use single newlines as line breaks
+    Paragraph(Vec<Vec<Inline>>),
+    /// Items, and the first number when ordered
+    List(Option<u64>, Vec<Vec<Inline>>),
+    Code(String),
+    Quote(Vec<Vec<Inline>>),
+    Rule,
+}
+
+enum Inline {
+    Text(String),
+    Bold(Vec<Inline>),
+    Italic(Vec<Inline>),
+    Code(String),
+    Link(Vec<Inline>, String),
+}
+
+fn parse_blocks(markdown: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut lines = markdown.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if trimmed.starts_with("```") {
+            let mut code = Vec::new();
+            for line in lines.by_ref() {
+                if line.trim().starts_with("```") {
+                    break;
+                }
+                code.push(line);
+            }
+            blocks.push(Block::Code(code.join("\n")));
+        } else if let Some((level, text)) = heading(trimmed) {
+            blocks.push(Block::Heading(level, parse_inline(text)));
+        } else if is_rule(trimmed) {
+            blocks.push(Block::Rule);
+        } else if trimmed.starts_with('>') {
+            let mut quoted = vec![parse_inline(unquote(trimmed))];
+            while let Some(line) = lines.next_if(|line| line.trim().starts_with('>')) {
+                quoted.push(parse_inline(unquote(line.trim())));
+            }
+            blocks.push(Block::Quote(quoted));
+        } else if let Some((start, item)) = list_item(trimmed) {
+            let mut items = vec![item.to_string()];
+            let continues = |line: &&str| {
+                let line = line.trim();
+                list_item(line).is_some() || continues_paragraph(line)
+            };
+            while let Some(line) = lines.next_if(continues) {
+                match list_item(line.trim()) {
+                    // Nested lists are flattened into the outer one
+                    Some((_, item)) => items.push(item.to_string()),
+                    // A line that isn't an item continues the one before it
+                    None => {
+                        let last = items.last_mut().expect("a list starts with an item");
+                        last.push(' ');
+                        last.push_str(line.trim());
+                    }
+                }
+            }
+            let items = items.iter().map(|item| parse_inline(item)).collect();
+            blocks.push(Block::List(start, items));
+        } else {
+            let mut paragraph = vec![parse_inline(trimmed)];
+            while let Some(line) = lines.next_if(|line| continues_paragraph(line.trim())) {
+                paragraph.push(parse_inline(line.trim()));
+            }
+            blocks.push(Block::Paragraph(paragraph));
+        }
+    }
+    blocks
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then_some((level, text.trim()))
+}
+
+fn is_rule(line: &str) -> bool {
+    let marker = line.chars().next();
+    matches!(marker, Some('-' | '*' | '_'))
+        && line.chars().filter(|c| !c.is_whitespace()).count() >= 3
+        && line.chars().all(|c| Some(c) == marker || c.is_whitespace())
+}
+
+fn unquote(line: &str) -> &str {
+    let line = line.strip_prefix('>').unwrap_or(line);
+    line.strip_prefix(' ').unwrap_or(line)
+}
+
+/// The item's text, and its number when the list is ordered
+fn list_item(line: &str) -> Option<(Option<u64>, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = line.strip_prefix(bullet) {
+            return Some((None, item.trim()));
+        }
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let rest = &line[digits..];
+    let item = rest
+        .strip_prefix(". ")
+        .or_else(|| rest.strip_prefix(") "))?;
+    let number = line[..digits].parse().ok()?;
+    Some((Some(number), item.trim()))
+}
+
+/// Whether a line goes on with the paragraph above it rather than starting a block of its own
+fn continues_paragraph(line: &str) -> bool {
+    !line.is_empty()
+        && !line.starts_with("```")
+        && !line.starts_with('>')
+        && heading(line).is_none()
+        && list_item(line).is_none()
+        && !is_rule(line)
+}
+
+fn parse_inline(text: &str) -> Vec<Inline> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut inlines = Vec::new();
+    let mut plain = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let rest = &chars[i..];
+        let intraword = i > 0 && chars[i - 1].is_alphanumeric();
+        let parsed = match rest[0] {
+            '\\' if rest.len() > 1 && rest[1].is_ascii_punctuation() => {
+                plain.push(rest[1]);
+                i += 2;
+                continue;
+            }
+            '`' => closing(rest, 1, "`").map(|end| {
+                let code = rest[1..end].iter().collect();
+                (Inline::Code(code), end + 1)
+            }),
+            '[' => link(rest),
+            // snake_case and 2*3*4 stay as they are
+            '_' if intraword => None,
+            '*' | '_' if rest.get(1) == Some(&rest[0]) => {
+                let marker: String = rest[..2].iter().collect();
+                closing(rest, 2, &marker).map(|end| {
+                    let inner: String = rest[2..end].iter().collect();
+                    (Inline::Bold(parse_inline(&inner)), end + 2)
+                })
+            }
+            '*' | '_' if !intraword => {
+                let marker = rest[0].to_string();
+                closing(rest, 1, &marker).map(|end| {
+                    let inner: String = rest[1..end].iter().collect();
+                    (Inline::Italic(parse_inline(&inner)), end + 1)
+                })
+            }
+            _ => None,
+        };
+
+        match parsed {
+            Some((inline, len)) => {
+                if !plain.is_empty() {
+                    inlines.push(Inline::Text(std::mem::take(&mut plain)));
+                }
+                inlines.push(inline);
+                i += len;
+            }
+            None => {
+                plain.push(rest[0]);
+                i += 1;
+            }
+        }
+    }
+    if !plain.is_empty() {
+        inlines.push(Inline::Text(plain));
+    }
+    inlines
+}
+
+/// Where the marker closing a span opened by the first `open` chars starts; the span can't be
+/// empty or start or end with a space
+fn closing(chars: &[char], open: usize, marker: &str) -> Option<usize> {
+    let marker: Vec<char> = marker.chars().collect();
+    let first = *chars.get(open)?;
+    if first.is_whitespace() {
+        return None;
+    }
+    (open + 1..=chars.len().checked_sub(marker.len())?)
+        .find(|&end| chars[end..].starts_with(&marker) && !chars[end - 1].is_whitespace())
+}
+
+/// A [text](url) link and how many chars it takes
+fn link(chars: &[char]) -> Option<(Inline, usize)> {
+    let text_end = chars.iter().position(|c| *c == ']')?;
+    if chars.get(text_end + 1) != Some(&'(') {
+        return None;
+    }
+    let url_end = text_end + 2 + chars[text_end + 2..].iter().position(|c| *c == ')')?;
+    let text: String = chars[1..text_end].iter().collect();
+    let url: String = chars[text_end + 2..url_end].iter().collect();
+    if text.is_empty() || url.is_empty() || url.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((Inline::Link(parse_inline(&text), url), url_end + 1))
+}
+
+fn plain(blocks: &[Block]) -> String {
+    let lines = |lines: &[Vec<Inline>]| {
+        lines
+            .iter()
+            .map(|line| plain_inline(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    blocks
+        .iter()
+        .map(|block| match block {
+            Block::Heading(_, text) => plain_inline(text),
+            Block::Paragraph(paragraph) | Block::Quote(paragraph) => lines(paragraph),
+            Block::List(start, items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| format!("{} {}", list_marker(*start, i), plain_inline(item)))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::Code(code) => code.clone(),
+            Block::Rule => "———".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn plain_inline(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text) | Inline::Code(text) => text.clone(),
+            Inline::Bold(inner) | Inline::Italic(inner) => plain_inline(inner),
+            Inline::Link(inner, url) => {
+                let text = plain_inline(inner);
+                if text == *url {
+                    text
+                } else {
+                    format!("{} ({})", text, url)
+                }
+            }
+        })
+        .collect()
+}
+
+fn list_marker(start: Option<u64>, index: usize) -> String {
+    match start {
+        Some(start) => format!("{}.", start + index as u64),
+        None => "•".to_string(),
+    }
+}
+
+fn html(blocks: &[Block]) -> String {
+    // Without a charset, Cocoa's HTML import reads the pasteboard as Latin-1
+    let mut html = String::from("<meta charset=\"utf-8\">");
+    let lines = |lines: &[Vec<Inline>]| {
+        lines
+            .iter()
+            .map(|line| html_inline(line))
+            .collect::<Vec<_>>()
+            .join("<br>")
+    };
+    for block in blocks {
+        match block {
+            Block::Heading(level, text) => {
+                html.push_str(&format!("<h{0}>{1}</h{0}>", level, html_inline(text)));
+            }
+            Block::Paragraph(paragraph) => {
+                html.push_str(&format!("<p>{}</p>", lines(paragraph)));
+            }
+            Block::List(start, items) => {
+                let tag = match start {
+                    Some(1) => "<ol>".to_string(),
+                    Some(start) => format!("<ol start=\"{}\">", start),
+                    None => "<ul>".to_string(),
+                };
+                html.push_str(&tag);
+                for item in items {
+                    html.push_str(&format!("<li>{}</li>", html_inline(item)));
+                }
+                html.push_str(if start.is_some() { "</ol>" } else { "</ul>" });
+            }
+            Block::Code(code) => {
+                html.push_str(&format!("<pre><code>{}</code></pre>", escape_html(code)));
+            }
+            Block::Quote(quoted) => {
+                html.push_str(&format!(
+                    "<blockquote><p>{}</p></blockquote>",
+                    lines(quoted)
+                ));
+            }
+            Block::Rule => html.push_str("<hr>"),
+        }
+    }
+    html
+}
+
+fn html_inline(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text) => escape_html(text),
+            Inline::Bold(inner) => format!("<strong>{}</strong>", html_inline(inner)),
+            Inline::Italic(inner) => format!("<em>{}</em>", html_inline(inner)),
+            Inline::Code(code) => format!("<code>{}</code>", escape_html(code)),
+            Inline::Link(inner, url) => {
+                format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape_html(url),
+                    html_inline(inner)
+                )
+            }
+        })
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Point sizes (RTF counts half points) by heading level
+const RTF_HEADING_SIZES: [u32; 6] = [48, 40, 32, 28, 24, 24];
+
+fn rtf(blocks: &[Block]) -> String {
+    let mut rtf = String::from(
+        "{\\rtf1\\ansi\\deff0{\\fonttbl{\\f0\\fswiss Helvetica;}{\\f1\\fmodern Menlo;}}\\f0\\fs24\n",
+    );
+    let lines = |lines: &[Vec<Inline>]| {
+        lines
+            .iter()
+            .map(|line| rtf_inline(line))
+            .collect::<Vec<_>>()
+            .join("\\line ")
+    };
+    for (i, block) in blocks.iter().enumerate() {
+        // A blank paragraph between blocks, and each one's formatting from scratch
+        if i > 0 {
+            rtf.push_str("\\par\n");
+        }
+        rtf.push_str("\\pard ");
+        match block {
+            Block::Heading(level, text) => {
+                let size = RTF_HEADING_SIZES[level - 1];
+                rtf.push_str(&format!("{{\\b\\fs{} {}}}\\par\n", size, rtf_inline(text)));
+            }
+            Block::Paragraph(paragraph) => {
+                rtf.push_str(&format!("{}\\par\n", lines(paragraph)));
+            }
+            Block::List(start, items) => {
+                for (index, item) in items.iter().enumerate() {
+                    let marker = match start {
+                        Some(_) => escape_rtf(&list_marker(*start, index)),
+                        None => "\\bullet".to_string(),
+                    };
+                    rtf.push_str(&format!("{}\\tab {}\\par\n", marker, rtf_inline(item)));
+                }
+            }
+            Block::Code(code) => {
+                let code = code
+                    .lines()
+                    .map(escape_rtf)
+                    .collect::<Vec<_>>()
+                    .join("\\line ");
+                rtf.push_str(&format!("{{\\f1 {}}}\\par\n", code));
+            }
+            Block::Quote(quoted) => {
+                rtf.push_str(&format!("\\li720 {}\\par\n", lines(quoted)));
+            }
+            Block::Rule => rtf.push_str("\\emdash\\emdash\\emdash\\par\n"),
+        }
+    }
+    rtf.push('}');
+    rtf
+}
+
+fn rtf_inline(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text) => escape_rtf(text),
+            Inline::Bold(inner) => format!("{{\\b {}}}", rtf_inline(inner)),
+            Inline::Italic(inner) => format!("{{\\i {}}}", rtf_inline(inner)),
+            Inline::Code(code) => format!("{{\\f1 {}}}", escape_rtf(code)),
+            Inline::Link(inner, url) => format!(
+                "{{\\field{{\\*\\fldinst{{HYPERLINK \"{}\"}}}}{{\\fldrslt{{\\ul {}}}}}}}",
+                escape_rtf(url),
+                rtf_inline(inner)
+            ),
+        })
+        .collect()
+}
+
+/// Escape RTF control characters; anything outside ASCII goes as \uN with a '?' fallback
+fn escape_rtf(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\t' => escaped.push_str("\\tab "),
+            c if c.is_ascii() => escaped.push(c),
+            c => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    // RTF takes signed 16-bit numbers
+                    escaped.push_str(&format!("\\u{}?", *unit as i16));
+                }
+            }
+        }
+    }
+    escaped
+}
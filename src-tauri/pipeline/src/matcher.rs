@@ -0,0 +1,262 @@
+// Trigger matching as a state machine over key-downs, shared by every platform monitor
+// Chords, typed sequences and the slash command after them, abbreviations, and a timeout
+
+use crate::backends::Trigger;
+use std::time::{Duration, Instant};
+
+/// How much typing the matcher keeps; sequences and abbreviations can't be longer
+pub const MAX_TYPED_CHARS: usize = 32;
+// Longer than any sensible command word; past this the user is just typing
+const MAX_COMMAND_CHARS: usize = 32;
+
+/// A key plus modifiers, in macOS virtual keycodes and CGEventFlags
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chord {
+    pub keycode: i64,
+    /// Only Shift, Control, Option, and Command bits
+    pub modifiers: u64,
+}
+
+impl Chord {
+    pub fn matches(&self, keycode: i64, modifiers: u64) -> bool {
+        self.keycode == keycode && self.modifiers == modifiers
+    }
+}
+
+/// One key-down, as a platform monitor classifies it
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypedKey {
+    /// The text it types (empty for a bare modifier or a dead key)
+    Text(String),
+    Backspace,
+    Return,
+    /// Escape, navigation, or a shortcut: the caret may have moved
+    Reset,
+}
+
+/// A key-down with what the matcher needs to know about it
+#[derive(Clone, Debug)]
+pub struct KeyPress {
+    pub key: TypedKey,
+    /// Key and modifiers for chord matching; None where the monitor doesn't report macOS keycodes
+    pub chord: Option<Chord>,
+    /// Autorepeat of a held key: it types again but never fires a chord again; false where the
+    /// monitor can't tell
+    pub repeat: bool,
+    pub at: Instant,
+}
+
+/// What to match; platform monitors share one, rebuilt whenever a setting changes
+#[derive(Clone, Debug, Default)]
+pub struct MatcherConfig {
+    /// Chords and what they trigger, first match wins
+    pub chords: Vec<(Chord, Trigger)>,
+    /// Typed sequences that open the overlay, e.g. "/" or "::ai"
+    pub sequences: Vec<String>,
+    /// Abbreviations and their expansions; matched anywhere, even mid-word
+    pub snippets: Vec<(String, String)>,
+    /// Typed triggers and what they fire; matched like abbreviations, the longest one winning
+    pub typed: Vec<(String, Trigger)>,
+    /// A longer pause between keys forgets what was typed before it
+    pub timeout: Option<Duration>,
+}
+
+/// Recently typed characters
+#[derive(Default)]
+struct TypedBuffer(String);
+
+impl TypedBuffer {
+    /// Append typed text, keeping only the last MAX_TYPED_CHARS characters
+    fn push(&mut self, text: &str) {
+        let typed = &mut self.0;
+        typed.push_str(text);
+
+        let excess = typed.chars().count().saturating_sub(MAX_TYPED_CHARS);
+        if excess > 0 {
+            let cut = typed.char_indices().nth(excess).map_or(0, |(i, _)| i);
+            typed.drain(..cut);
+        }
+    }
+
+    /// Length (in chars) of the longest sequence the buffer now ends with
+    fn sequence(&self, sequences: &[String]) -> Option<usize> {
+        let typed = &self.0;
+        let matched = sequences
+            .iter()
+            .filter(|sequence| !sequence.is_empty() && typed.ends_with(sequence.as_str()))
+            .map(|sequence| sequence.chars().count())
+            .max()?;
+
+        // Only at the start of a word, so "and/or" stays text
+        let mid_word = typed
+            .chars()
+            .rev()
+            .nth(matched)
+            .is_some_and(char::is_alphanumeric);
+        (!mid_word).then_some(matched)
+    }
+
+    /// What the longest abbreviation the buffer now ends with stands for, and its length (in
+    /// chars)
+    fn abbreviation<'a, T>(&self, abbreviations: &'a [(String, T)]) -> Option<(&'a T, usize)> {
+        abbreviations
+            .iter()
+            .filter(|(abbreviation, _)| {
+                !abbreviation.is_empty() && self.0.ends_with(abbreviation.as_str())
+            })
+            .max_by_key(|(abbreviation, _)| abbreviation.chars().count())
+            .map(|(abbreviation, value)| (value, abbreviation.chars().count()))
+    }
+}
+
+/// What a key did to the command being captured
+enum CaptureStep {
+    /// Keep buffering; the key reaches the field as usual
+    Continue,
+    /// Not a command after all; what was typed stays in the field
+    Cancelled,
+    /// Space or Return ended it; `typed_chars` counts the sequence and word now in the field
+    Done { command: String, typed_chars: usize },
+}
+
+/// The command word typed after a trigger sequence, e.g. "fix" in "/fix"
+struct CommandCapture {
+    sequence_len: usize,
+    word: String,
+}
+
+impl CommandCapture {
+    fn step(&mut self, key: &TypedKey) -> CaptureStep {
+        let done = |word: &str| CaptureStep::Done {
+            command: word.to_string(),
+            typed_chars: self.sequence_len + word.chars().count(),
+        };
+        match key {
+            TypedKey::Return => done(&self.word),
+            // "/ " is just a slash in prose
+            TypedKey::Text(text) if text == " " && self.word.is_empty() => CaptureStep::Cancelled,
+            TypedKey::Text(text) if text == " " => done(&self.word),
+            TypedKey::Text(text) => {
+                let word_char = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+                if !text.chars().all(word_char) {
+                    return CaptureStep::Cancelled;
+                }
+                self.word.push_str(text);
+                if self.word.chars().count() > MAX_COMMAND_CHARS {
+                    return CaptureStep::Cancelled;
+                }
+                CaptureStep::Continue
+            }
+            // Deleting back into the sequence abandons the command
+            TypedKey::Backspace => match self.word.pop() {
+                Some(_) => CaptureStep::Continue,
+                None => CaptureStep::Cancelled,
+            },
+            TypedKey::Reset => CaptureStep::Cancelled,
+        }
+    }
+}
+
+/// Per-monitor matching state, kept on the monitor's hook thread
+#[derive(Default)]
+pub struct TriggerMatcher {
+    typed: TypedBuffer,
+    capture: Option<CommandCapture>,
+    last_key: Option<Instant>,
+}
+
+impl TriggerMatcher {
+    /// Forget everything typed so far
+    pub fn clear(&mut self) {
+        self.typed.0.clear();
+        self.capture = None;
+    }
+
+    /// Feed one key-down; returns the trigger it completes and how many characters of it are
+    /// already in the field (not counting this key's, which the monitor may swallow)
+    ///
+    /// A chord fires on its own key. A sequence fires once Space or Return ends the command
+    /// typed after it, and an abbreviation or typed trigger fires on its last character.
+    pub fn feed(&mut self, press: KeyPress, config: &MatcherConfig) -> Option<(Trigger, usize)> {
+        let paused = match (config.timeout, self.last_key) {
+            (Some(timeout), Some(last)) => press.at.saturating_duration_since(last) > timeout,
+            _ => false,
+        };
+        self.last_key = Some(press.at);
+        if paused {
+            self.clear();
+        }
+
+        if let Some(chord) = press.chord.filter(|_| !press.repeat) {
+            let bound = config
+                .chords
+                .iter()
+                .find(|(bound, _)| *bound == chord)
+                .map(|(_, trigger)| trigger.clone());
+            if let Some(trigger) = bound {
+                self.clear();
+                return Some((trigger, 0));
+            }
+        }
+
+        if let Some(capture) = &mut self.capture {
+            match capture.step(&press.key) {
+                CaptureStep::Continue => return None,
+                CaptureStep::Done {
+                    command,
+                    typed_chars,
+                } => {
+                    self.capture = None;
+                    let trigger = if command.is_empty() {
+                        Trigger::Slash
+                    } else {
+                        Trigger::Command(command)
+                    };
+                    return Some((trigger, typed_chars));
+                }
+                // The key that ended it may start a new sequence
+                CaptureStep::Cancelled => self.capture = None,
+            }
+        }
+
+        let sequence_len = match press.key {
+            TypedKey::Text(text) if !text.is_empty() => {
+                self.typed.push(&text);
+                let snippet = self
+                    .typed
+                    .abbreviation(&config.snippets)
+                    .map(|(expansion, len)| (Trigger::Snippet(expansion.clone()), len));
+                let typed = self
+                    .typed
+                    .abbreviation(&config.typed)
+                    .map(|(trigger, len)| (trigger.clone(), len));
+                let abbreviation = [snippet, typed]
+                    .into_iter()
+                    .flatten()
+                    .max_by_key(|(_, len)| *len);
+                if let Some((trigger, abbreviation_len)) = abbreviation {
+                    self.typed.0.clear();
+                    let typed_chars = abbreviation_len.saturating_sub(text.chars().count());
+                    return Some((trigger, typed_chars));
+                }
+                let sequence_len = self.typed.sequence(&config.sequences)?;
+                self.typed.0.clear();
+                sequence_len
+            }
+            TypedKey::Text(_) => return None,
+            TypedKey::Backspace => {
+                self.typed.0.pop();
+                return None;
+            }
+            TypedKey::Return | TypedKey::Reset => {
+                self.typed.0.clear();
+                return None;
+            }
+        };
+        self.capture = Some(CommandCapture {
+            sequence_len,
+            word: String::new(),
+        });
+        None
+    }
+}
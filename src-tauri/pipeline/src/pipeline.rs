@@ -0,0 +1,146 @@
+// Trigger -> context -> generate -> inject, written only against the backend traits
+
+use crate::backends::{
+    AppInfo, Detector, FieldBounds, FieldText, Generator, InjectStrategy, Injector,
+};
+use crate::error::PromptOsError;
+use std::sync::Arc;
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq, Default)]
+pub struct PipelineContext {
+    pub app: Option<AppInfo>,
+    pub field: FieldText,
+}
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InjectMethod {
+    Insert,
+    Paste,
+}
+
+#[derive(Clone)]
+pub struct Pipeline {
+    detector: Arc<dyn Detector>,
+    injector: Arc<dyn Injector>,
+    generator: Arc<dyn Generator>,
+}
+
+impl Pipeline {
+    pub fn new(
+        detector: Arc<dyn Detector>,
+        injector: Arc<dyn Injector>,
+        generator: Arc<dyn Generator>,
+    ) -> Self {
+        Self {
+            detector,
+            injector,
+            generator,
+        }
+    }
+
+    /// Overlay trigger: where to show the overlay and which app it was opened from
+    pub fn overlay_target(&self) -> Result<(FieldBounds, Option<AppInfo>), PromptOsError> {
+        let bounds = self.detector.focused_field_bounds()?;
+        Ok((bounds, self.detector.frontmost_app()))
+    }
+
+    /// Selection trigger: the overlay target plus the selected text, which must not be blank
+    pub fn selection_target(
+        &self,
+    ) -> Result<(FieldBounds, Option<AppInfo>, String), PromptOsError> {
+        let (bounds, app) = self.overlay_target()?;
+        let selection = self
+            .detector
+            .field_text(None)
+            .selection
+            .filter(|selection| !selection.trim().is_empty())
+            .ok_or("Nothing selected")?;
+        Ok((bounds, app, selection))
+    }
+
+    /// Read the target app's focused field
+    pub fn collect_context(&self, app: Option<AppInfo>) -> PipelineContext {
+        let field = self.detector.field_text(app.as_ref().map(|app| app.pid));
+        PipelineContext { app, field }
+    }
+
+    /// Generate with the context folded into the system prompt
+    pub fn generate(
+        &self,
+        prompt: &str,
+        system_prompt: Option<&str>,
+        context: &PipelineContext,
+    ) -> Result<String, PromptOsError> {
+        let system_prompt = build_system_prompt(system_prompt, context);
+        self.generator.generate(prompt, system_prompt.as_deref())
+    }
+
+    /// Insert directly, falling back to paste, unless the focused field only takes pastes
+    pub fn inject(&self, text: &str) -> Result<InjectMethod, PromptOsError> {
+        if self.detector.inject_strategy() == InjectStrategy::Paste {
+            return self.injector.paste(text).map(|()| InjectMethod::Paste);
+        }
+
+        match self.injector.insert(text) {
+            Ok(()) => Ok(InjectMethod::Insert),
+            Err(insert_error @ PromptOsError::Internal(_)) => self
+                .injector
+                .paste(text)
+                .map(|()| InjectMethod::Paste)
+                .map_err(|paste_error| match paste_error {
+                    PromptOsError::Internal(paste_error) => PromptOsError::Internal(format!(
+                        "{}; paste fallback: {}",
+                        insert_error, paste_error
+                    )),
+                    paste_error => paste_error,
+                }),
+            // Secure fields, blocked responses, and missing permissions would fail a paste too
+            Err(insert_error) => Err(insert_error),
+        }
+    }
+
+    /// Full run for a prompt typed against the current frontmost app
+    pub fn run(&self, prompt: &str, system_prompt: Option<&str>) -> Result<String, PromptOsError> {
+        let context = self.collect_context(self.detector.frontmost_app());
+        let output = self.generate(prompt, system_prompt, &context)?;
+        self.inject(&output)?;
+        Ok(output)
+    }
+
+    /// Rewrite the focused selection in place with an instruction
+    pub fn quick_transform(&self, instruction: &str) -> Result<String, PromptOsError> {
+        let field = self.detector.field_text(None);
+        let selection = field
+            .selection
+            .filter(|selection| !selection.trim().is_empty())
+            .ok_or("Nothing selected")?;
+
+        let output = self.generator.generate(&selection, Some(instruction))?;
+        self.inject(&output)?;
+        Ok(output)
+    }
+}
+
+/// Append app and selection context to the caller's system prompt
+fn build_system_prompt(base: Option<&str>, context: &PipelineContext) -> Option<String> {
+    let mut sections: Vec<String> = base.map(str::to_string).into_iter().collect();
+
+    if let Some(name) = context.app.as_ref().and_then(|app| app.name.as_deref()) {
+        sections.push(format!("The user is writing in {}.", name));
+    }
+    if let Some(selection) = context
+        .field
+        .selection
+        .as_deref()
+        .filter(|selection| !selection.trim().is_empty())
+    {
+        sections.push(format!("The user has selected this text:\n{}", selection));
+    }
+
+    if sections.is_empty() {
+        None
+    } else {
+        Some(sections.join("\n\n"))
+    }
+}
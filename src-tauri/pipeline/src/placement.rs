@@ -0,0 +1,113 @@
+// Overlay placement: where the overlay goes relative to the caret, its field, and the screen
+// Plain geometry in global top-left coordinates (points), so it runs without a window server
+
+use crate::backends::FieldBounds;
+
+// Space left between the overlay and the caret or field it sits next to
+pub const OVERLAY_GAP: f64 = 8.0;
+
+/// What the overlay is anchored to when it opens
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PlacementMode {
+    /// Next to the caret, following it while the overlay is open
+    #[default]
+    Caret,
+    /// Docked to the side of the focused window, e.g. to the right of a chat window
+    WindowEdge,
+}
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Default)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn right(&self) -> f64 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> f64 {
+        self.y + self.height
+    }
+}
+
+impl From<FieldBounds> for Rect {
+    fn from(bounds: FieldBounds) -> Self {
+        Rect {
+            x: bounds.x,
+            y: bounds.y,
+            width: bounds.width,
+            height: bounds.height,
+        }
+    }
+}
+
+/// Top-left corner for an overlay of `size` (width, height) on `screen` (its visible frame)
+///
+/// The overlay goes just below the caret, or above the whole field when that would run off the
+/// bottom of the screen, then above the caret for fields taller than the room left. Only when no
+/// side has room does it overlap, on whichever side has more space; it never leaves the screen.
+pub fn place_overlay(
+    caret: Rect,
+    field: Option<Rect>,
+    size: (f64, f64),
+    screen: Rect,
+) -> (f64, f64) {
+    let (width, height) = size;
+    let x = clamp(caret.x, screen.x, screen.right() - width);
+
+    let below_caret = caret.bottom() + OVERLAY_GAP;
+    let above_caret = caret.y - OVERLAY_GAP - height;
+    let above_field = field.map_or(above_caret, |field| {
+        field.y.min(caret.y) - OVERLAY_GAP - height
+    });
+
+    let fits = |y: f64| y >= screen.y && y + height <= screen.bottom();
+    let y = [below_caret, above_field, above_caret]
+        .into_iter()
+        .find(|y| fits(*y))
+        .unwrap_or_else(|| {
+            let room_below = screen.bottom() - caret.bottom();
+            let room_above = caret.y - screen.y;
+            let y = if room_below >= room_above {
+                below_caret
+            } else {
+                above_caret
+            };
+            clamp(y, screen.y, screen.bottom() - height)
+        });
+
+    (x, y)
+}
+
+/// Top-left corner for an overlay of `size` docked to `window`, top-aligned with it
+///
+/// The overlay goes outside the window's right edge, else outside its left edge, and just inside
+/// the right edge when the window leaves no room on either side (e.g. it fills the screen).
+pub fn dock_to_window(window: Rect, size: (f64, f64), screen: Rect) -> (f64, f64) {
+    let (width, height) = size;
+    let outside_right = window.right() + OVERLAY_GAP;
+    let outside_left = window.x - OVERLAY_GAP - width;
+
+    let x = if outside_right + width <= screen.right() {
+        outside_right
+    } else if outside_left >= screen.x {
+        outside_left
+    } else {
+        window.right() - OVERLAY_GAP - width
+    };
+
+    (
+        clamp(x, screen.x, screen.right() - width),
+        clamp(window.y, screen.y, screen.bottom() - height),
+    )
+}
+
+/// Clamp to min..=max, preferring min when the overlay is larger than the screen
+fn clamp(value: f64, min: f64, max: f64) -> f64 {
+    value.min(max).max(min)
+}
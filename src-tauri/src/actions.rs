@@ -0,0 +1,463 @@
+// User-defined prompt actions loaded from <app data>/actions
+// One TOML or YAML file per action; the directory is watched so edits apply without a restart
+
+use crate::error::PromptOsError;
+use crate::hotkey::Chord;
+use crate::llm::GenerationOptions;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use prompt_os_pipeline::Generator;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+
+const ACTIONS_DIR: &str = "actions";
+const EXTENSIONS: &[&str] = &["toml", "yaml", "yml"];
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PostProcess {
+    Trim,
+    /// Drop one pair of surrounding quotes
+    StripQuotes,
+    /// Unwrap a response that is a single fenced code block
+    StripCodeFences,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ActionParams {
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub max_output_tokens: Option<u32>,
+}
+
+/// On-disk format; the id is the file name without its extension
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ActionFile {
+    label: Option<String>,
+    /// Instruction applied to the selection (sent as the system prompt)
+    template: String,
+    model: Option<String>,
+    #[serde(default)]
+    params: ActionParams,
+    #[serde(default)]
+    post_process: Vec<PostProcess>,
+    /// e.g. "ctrl+alt+p"; needs Control, Option, or Command
+    hotkey: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct Action {
+    pub id: String,
+    pub label: String,
+    pub template: String,
+    pub model: Option<String>,
+    pub params: ActionParams,
+    pub post_process: Vec<PostProcess>,
+    pub hotkey: Option<String>,
+    pub file: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct ActionLoadError {
+    pub file: String,
+    pub message: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct ActionsSnapshot {
+    pub directory: String,
+    pub actions: Vec<Action>,
+    /// Files that were skipped, so a typo doesn't silently drop an action
+    pub errors: Vec<ActionLoadError>,
+}
+
+struct ActionStore {
+    dir: PathBuf,
+    actions: Vec<Action>,
+    errors: Vec<ActionLoadError>,
+}
+
+static ACTIONS: Mutex<Option<ActionStore>> = Mutex::new(None);
+static WATCHER: Mutex<Option<RecommendedWatcher>> = Mutex::new(None);
+
+/// Load the actions directory and start watching it (called once during setup)
+pub fn init(app: AppHandle, data_dir: PathBuf) -> Result<(), PromptOsError> {
+    let dir = data_dir.join(ACTIONS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create actions directory: {}", e))?;
+
+    *ACTIONS
+        .lock()
+        .map_err(|_| "Failed to lock actions".to_string())? = Some(ActionStore {
+        dir: dir.clone(),
+        actions: Vec::new(),
+        errors: Vec::new(),
+    });
+    reload()?;
+
+    let mut watcher =
+        notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) if event.paths.iter().any(|path| is_action_file(path)) => match reload() {
+                Ok(snapshot) => {
+                    eprintln!("[DEBUG] Reloaded {} actions", snapshot.actions.len());
+                    let _ = app.emit("actions-changed", snapshot);
+                }
+                Err(e) => eprintln!("[ERROR] Failed to reload actions: {}", e),
+            },
+            Ok(_) => {}
+            Err(e) => eprintln!("[ERROR] Actions watcher: {}", e),
+        })
+        .map_err(|e| format!("Failed to create actions watcher: {}", e))?;
+
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch actions directory: {}", e))?;
+
+    *WATCHER
+        .lock()
+        .map_err(|_| "Failed to lock actions watcher".to_string())? = Some(watcher);
+    Ok(())
+}
+
+fn is_action_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Re-read every action file, replacing the loaded set
+fn reload() -> Result<ActionsSnapshot, PromptOsError> {
+    let mut guard = ACTIONS
+        .lock()
+        .map_err(|_| "Failed to lock actions".to_string())?;
+    let store = guard.as_mut().ok_or("Actions are not initialized")?;
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(&store.dir)
+        .map_err(|e| format!("Failed to read actions directory: {}", e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && is_action_file(path))
+        .collect();
+    paths.sort();
+
+    let (actions, errors, hotkeys) = load_all(paths);
+    crate::keystroke_monitor::set_action_hotkeys(hotkeys);
+    store.actions = actions;
+    store.errors = errors;
+    Ok(snapshot(store))
+}
+
+/// Load action files in order, skipping (and reporting) any that fail or that reuse an id or a
+/// hotkey an earlier one has
+fn load_all(paths: Vec<PathBuf>) -> (Vec<Action>, Vec<ActionLoadError>, Vec<(Chord, String)>) {
+    let mut actions: Vec<Action> = Vec::new();
+    let mut errors = Vec::new();
+    let mut hotkeys: Vec<(Chord, String)> = Vec::new();
+
+    for path in paths {
+        let file = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let loaded = load_action(&path).and_then(|action| {
+            if actions.iter().any(|existing| existing.id == action.id) {
+                return Err(
+                    format!("Another file already defines action \"{}\"", action.id).into(),
+                );
+            }
+            if let Some(hotkey) = action.hotkey.as_deref() {
+                let chord = crate::hotkey::parse(hotkey)?;
+                if let Some((_, other)) = hotkeys.iter().find(|(c, _)| *c == chord) {
+                    return Err(
+                        format!("Hotkey {} is already used by \"{}\"", hotkey, other).into(),
+                    );
+                }
+                hotkeys.push((chord, action.id.clone()));
+            }
+            Ok(action)
+        });
+
+        match loaded {
+            Ok(action) => actions.push(action),
+            Err(e) => {
+                eprintln!("[ERROR] Skipping action file {}: {}", file, e);
+                errors.push(ActionLoadError {
+                    file,
+                    message: e.to_string(),
+                });
+            }
+        }
+    }
+
+    (actions, errors, hotkeys)
+}
+
+fn load_action(path: &Path) -> Result<Action, PromptOsError> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read: {}", e))?;
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let parsed: ActionFile = if extension == "toml" {
+        toml::from_str(&contents).map_err(|e| format!("Invalid TOML: {}", e))?
+    } else {
+        serde_yaml::from_str(&contents).map_err(|e| format!("Invalid YAML: {}", e))?
+    };
+
+    if parsed.template.trim().is_empty() {
+        return Err("template is empty".into());
+    }
+    // The model name becomes part of the request URL
+    if let Some(model) = parsed.model.as_deref().filter(|model| !valid_model(model)) {
+        return Err(format!(
+            "model \"{}\" may only contain letters, digits, '.', '_', and '-'",
+            model
+        )
+        .into());
+    }
+
+    let id = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .ok_or("File has no name")?;
+
+    Ok(Action {
+        label: parsed
+            .label
+            .filter(|label| !label.trim().is_empty())
+            .unwrap_or_else(|| id.clone()),
+        id,
+        template: parsed.template,
+        model: parsed.model,
+        params: parsed.params,
+        post_process: parsed.post_process,
+        hotkey: parsed.hotkey,
+        file: path.to_string_lossy().into_owned(),
+    })
+}
+
+fn valid_model(model: &str) -> bool {
+    !model.is_empty()
+        && model
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+fn snapshot(store: &ActionStore) -> ActionsSnapshot {
+    ActionsSnapshot {
+        directory: store.dir.to_string_lossy().into_owned(),
+        actions: store.actions.clone(),
+        errors: store.errors.clone(),
+    }
+}
+
+/// Every loaded action (empty before init)
+pub fn actions() -> Vec<Action> {
+    ACTIONS
+        .lock()
+        .ok()
+        .and_then(|guard| guard.as_ref().map(|store| store.actions.clone()))
+        .unwrap_or_default()
+}
+
+/// Rewrite the selection in place with an action
+pub fn run(id: &str) -> Result<String, PromptOsError> {
+    let action = actions()
+        .into_iter()
+        .find(|action| action.id == id)
+        .ok_or_else(|| format!("No action named \"{}\"", id))?;
+
+    let template = action.template.clone();
+    crate::backends::pipeline_with_generator(Arc::new(ActionGenerator(action)))
+        .quick_transform(&template)
+}
+
+/// Gemini with the action's model, params, and post-processing
+struct ActionGenerator(Action);
+
+impl Generator for ActionGenerator {
+    fn generate(&self, prompt: &str, system_prompt: Option<&str>) -> Result<String, PromptOsError> {
+        let action = &self.0;
+        let options = GenerationOptions {
+            model: action.model.clone(),
+            temperature: action.params.temperature,
+            top_p: action.params.top_p,
+            max_output_tokens: action.params.max_output_tokens,
+        };
+
+        let output = crate::llm::generate_with(prompt, system_prompt, &options)?;
+        Ok(action
+            .post_process
+            .iter()
+            .fold(output, |text, step| post_process(*step, &text)))
+    }
+}
+
+fn post_process(step: PostProcess, text: &str) -> String {
+    match step {
+        PostProcess::Trim => text.trim().to_string(),
+        PostProcess::StripQuotes => {
+            let trimmed = text.trim();
+            for (open, close) in [('"', '"'), ('\'', '\''), ('“', '”'), ('「', '」')] {
+                if let Some(inner) = trimmed
+                    .strip_prefix(open)
+                    .and_then(|rest| rest.strip_suffix(close))
+                {
+                    return inner.to_string();
+                }
+            }
+            text.to_string()
+        }
+        PostProcess::StripCodeFences => {
+            let trimmed = text.trim();
+            let Some(body) = trimmed
+                .strip_prefix("```")
+                .and_then(|rest| rest.strip_suffix("```"))
+            else {
+                return text.to_string();
+            };
+            // Drop the language tag on the opening fence
+            match body.split_once('\n') {
+                Some((_, code)) => code.trim_end_matches('\n').to_string(),
+                None => body.to_string(),
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub fn list_actions() -> Result<ActionsSnapshot, PromptOsError> {
+    let guard = ACTIONS
+        .lock()
+        .map_err(|_| "Failed to lock actions".to_string())?;
+    guard
+        .as_ref()
+        .map(snapshot)
+        .ok_or_else(|| "Actions are not initialized".into())
+}
+
+/// Force a re-read (the watcher normally does this)
+#[tauri::command]
+pub fn reload_actions() -> Result<ActionsSnapshot, PromptOsError> {
+    reload()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory for one test's action files
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("prompt-os-actions-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(dir: &Path, file: &str, contents: &str) -> PathBuf {
+        let path = dir.join(file);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn toml_and_yaml_actions_load_with_their_file_name_as_id() {
+        let dir = test_dir("formats");
+        let toml = write(
+            &dir,
+            "formal.toml",
+            "label = \"Formal\"\ntemplate = \"Make it formal\"\nmodel = \"gemini-2.5-pro\"\n\
+             post_process = [\"trim\"]\nhotkey = \"ctrl+alt+f\"\n\n[params]\ntemperature = 0.2\n",
+        );
+        let yaml = write(&dir, "short.yaml", "template: Make it shorter\n");
+
+        let action = load_action(&toml).unwrap();
+        assert_eq!(action.id, "formal");
+        assert_eq!(action.label, "Formal");
+        assert_eq!(action.model.as_deref(), Some("gemini-2.5-pro"));
+        assert_eq!(action.params.temperature, Some(0.2));
+        assert_eq!(action.post_process, vec![PostProcess::Trim]);
+        assert_eq!(action.hotkey.as_deref(), Some("ctrl+alt+f"));
+
+        // Without a label, the id stands in
+        let action = load_action(&yaml).unwrap();
+        assert_eq!(action.id, "short");
+        assert_eq!(action.label, "short");
+    }
+
+    #[test]
+    fn invalid_action_files_are_rejected() {
+        let dir = test_dir("invalid");
+        let empty = write(&dir, "empty.toml", "template = \"  \"\n");
+        let unknown = write(
+            &dir,
+            "unknown.toml",
+            "template = \"x\"\ntemperature = 1.0\n",
+        );
+        let model = write(
+            &dir,
+            "model.yaml",
+            "template: x\nmodel: ../../upload?key=\n",
+        );
+
+        assert!(load_action(&empty).is_err());
+        assert!(load_action(&unknown).is_err());
+        let error = load_action(&model).unwrap_err().to_string();
+        assert!(error.contains("may only contain"), "{}", error);
+    }
+
+    #[test]
+    fn a_reused_hotkey_skips_the_later_file() {
+        let dir = test_dir("hotkeys");
+        let paths = vec![
+            write(
+                &dir,
+                "a.toml",
+                "template = \"A\"\nhotkey = \"ctrl+alt+p\"\n",
+            ),
+            write(
+                &dir,
+                "b.toml",
+                "template = \"B\"\nhotkey = \"alt+ctrl+p\"\n",
+            ),
+            write(
+                &dir,
+                "c.toml",
+                "template = \"C\"\nhotkey = \"cmd+shift+p\"\n",
+            ),
+        ];
+
+        let (actions, errors, hotkeys) = load_all(paths);
+        let ids: Vec<&str> = actions.iter().map(|action| action.id.as_str()).collect();
+        assert_eq!(ids, ["a", "c"]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].file, "b.toml");
+        assert!(errors[0].message.contains("\"a\""));
+        assert_eq!(hotkeys.len(), 2);
+    }
+
+    #[test]
+    fn post_processing_steps() {
+        assert_eq!(post_process(PostProcess::Trim, "  hi \n"), "hi");
+        assert_eq!(post_process(PostProcess::StripQuotes, " \"hi\" "), "hi");
+        assert_eq!(
+            post_process(PostProcess::StripQuotes, "「こんにちは」"),
+            "こんにちは"
+        );
+        // Mismatched quotes are left alone
+        assert_eq!(post_process(PostProcess::StripQuotes, "\"hi'"), "\"hi'");
+        assert_eq!(
+            post_process(PostProcess::StripCodeFences, "```rust\nfn main() {}\n```"),
+            "fn main() {}"
+        );
+        // Fences around only part of the response stay
+        assert_eq!(
+            post_process(PostProcess::StripCodeFences, "Here:\n```\nx\n```"),
+            "Here:\n```\nx\n```"
+        );
+    }
+}
@@ -0,0 +1,290 @@
+// Sensitive-app blocklist
+// The monitor never triggers and context is never collected while one of these apps is frontmost
+
+use crate::error::PromptOsError;
+use minisign_verify::{PublicKey, Signature};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Bundle ids, or prefixes ending in '.', shipped with the app
+const DEFAULT_BLOCKLIST: &[&str] = &[
+    // Password managers
+    "com.1password.",
+    "com.agilebits.",
+    "com.bitwarden.desktop",
+    "com.lastpass.",
+    "com.dashlane.",
+    "org.keepassxc.keepassxc",
+    "com.keepersecurity.",
+    "com.apple.keychainaccess",
+    "com.apple.Passwords",
+    // Banking and finance
+    "com.quicken.",
+    "com.moneymoney-app.",
+    "com.intuit.",
+    // MDM and device management consoles
+    "com.jamfsoftware.",
+    "com.jamf.",
+    "io.kandji.",
+    "com.microsoft.CompanyPortalMac",
+    "com.vmware.hub.mac",
+    // Screen sharing and remote control
+    "com.apple.ScreenSharing",
+    "com.teamviewer.",
+    "com.philandro.anydesk",
+    "com.microsoft.rdc.macos",
+    "com.parsec.",
+];
+
+const REMOTE_LIST_FILE: &str = "blocklist.json";
+const REMOTE_SIGNATURE_FILE: &str = "blocklist.json.minisig";
+const USER_LIST_FILE: &str = "blocklist_user.json";
+
+// Release signing key and update URL are supplied at build time; without a key, updates are disabled
+const BLOCKLIST_PUBLIC_KEY: Option<&str> = option_env!("PROMPTOS_BLOCKLIST_PUBKEY");
+const BLOCKLIST_UPDATE_URL: Option<&str> = option_env!("PROMPTOS_BLOCKLIST_URL");
+
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct RemoteBlocklist {
+    pub version: u64,
+    pub bundle_ids: Vec<String>,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct BlocklistInfo {
+    pub builtin: Vec<String>,
+    pub remote_version: Option<u64>,
+    pub remote: Vec<String>,
+    pub user: Vec<String>,
+    /// Whether this build can download signed updates
+    pub updates_available: bool,
+}
+
+struct BlocklistStore {
+    data_dir: PathBuf,
+    remote: Option<RemoteBlocklist>,
+    user: Vec<String>,
+}
+
+static BLOCKLIST: Mutex<Option<BlocklistStore>> = Mutex::new(None);
+
+/// Load the downloaded and user lists from the app data directory (called once during setup)
+pub fn init(data_dir: PathBuf) -> Result<(), PromptOsError> {
+    fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+
+    // A tampered or unsigned cached list is ignored rather than trusted
+    let remote = match load_remote(&data_dir) {
+        Ok(remote) => remote,
+        Err(e) => {
+            eprintln!("[ERROR] Ignoring downloaded blocklist: {}", e);
+            None
+        }
+    };
+
+    let user = fs::read_to_string(data_dir.join(USER_LIST_FILE))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default();
+
+    *BLOCKLIST
+        .lock()
+        .map_err(|_| "Failed to lock blocklist".to_string())? = Some(BlocklistStore {
+        data_dir,
+        remote,
+        user,
+    });
+    Ok(())
+}
+
+fn load_remote(data_dir: &Path) -> Result<Option<RemoteBlocklist>, PromptOsError> {
+    let Ok(list) = fs::read(data_dir.join(REMOTE_LIST_FILE)) else {
+        return Ok(None);
+    };
+    let signature = fs::read_to_string(data_dir.join(REMOTE_SIGNATURE_FILE))
+        .map_err(|e| format!("Missing signature: {}", e))?;
+
+    verify(&list, &signature)?;
+    serde_json::from_slice(&list)
+        .map(Some)
+        .map_err(|e| format!("Invalid blocklist: {}", e).into())
+}
+
+fn verify(list: &[u8], signature: &str) -> Result<(), PromptOsError> {
+    let key = BLOCKLIST_PUBLIC_KEY.ok_or("Blocklist updates are not enabled in this build")?;
+    let key = PublicKey::from_base64(key).map_err(|e| format!("Invalid public key: {}", e))?;
+    let signature =
+        Signature::decode(signature).map_err(|e| format!("Invalid signature: {}", e))?;
+    key.verify(list, &signature, false)
+        .map_err(|e| format!("Signature verification failed: {}", e).into())
+}
+
+fn matches(entry: &str, bundle_id: &str) -> bool {
+    if entry.ends_with('.') {
+        bundle_id.starts_with(entry)
+    } else {
+        bundle_id == entry
+    }
+}
+
+/// Whether an app is on the built-in, downloaded, or user blocklist
+pub fn is_blocked(bundle_id: &str) -> bool {
+    if DEFAULT_BLOCKLIST
+        .iter()
+        .any(|entry| matches(entry, bundle_id))
+    {
+        return true;
+    }
+
+    let Ok(guard) = BLOCKLIST.lock() else {
+        return false;
+    };
+    let Some(store) = guard.as_ref() else {
+        return false;
+    };
+
+    store
+        .remote
+        .iter()
+        .flat_map(|remote| remote.bundle_ids.iter())
+        .chain(store.user.iter())
+        .any(|entry| matches(entry, bundle_id))
+}
+
+/// Whether the current frontmost app is blocked
+#[cfg(target_os = "macos")]
+pub fn frontmost_blocked() -> bool {
+    crate::frontmost_app::frontmost_app()
+        .and_then(|app| app.bundle_id)
+        .is_some_and(|bundle_id| is_blocked(&bundle_id))
+}
+
+fn info(store: Option<&BlocklistStore>) -> BlocklistInfo {
+    BlocklistInfo {
+        builtin: DEFAULT_BLOCKLIST.iter().map(|s| s.to_string()).collect(),
+        remote_version: store.and_then(|s| s.remote.as_ref()).map(|r| r.version),
+        remote: store
+            .and_then(|s| s.remote.as_ref())
+            .map(|r| r.bundle_ids.clone())
+            .unwrap_or_default(),
+        user: store.map(|s| s.user.clone()).unwrap_or_default(),
+        updates_available: BLOCKLIST_PUBLIC_KEY.is_some(),
+    }
+}
+
+#[tauri::command]
+pub fn get_blocklist() -> Result<BlocklistInfo, PromptOsError> {
+    let guard = BLOCKLIST
+        .lock()
+        .map_err(|_| "Failed to lock blocklist".to_string())?;
+    Ok(info(guard.as_ref()))
+}
+
+/// Add an app (or a prefix ending in '.') to the user's blocklist
+#[tauri::command]
+pub fn add_blocked_app(bundle_id: String) -> Result<BlocklistInfo, PromptOsError> {
+    let bundle_id = bundle_id.trim().to_string();
+    if bundle_id.is_empty() {
+        return Err("Bundle id is empty".into());
+    }
+
+    let mut guard = BLOCKLIST
+        .lock()
+        .map_err(|_| "Failed to lock blocklist".to_string())?;
+    let store = guard.as_mut().ok_or("Blocklist not initialized")?;
+
+    if !store.user.contains(&bundle_id) {
+        store.user.push(bundle_id);
+        save_user(store)?;
+    }
+    Ok(info(Some(store)))
+}
+
+/// Remove a user-added entry (built-in and downloaded entries cannot be removed)
+#[tauri::command]
+pub fn remove_blocked_app(bundle_id: String) -> Result<BlocklistInfo, PromptOsError> {
+    let mut guard = BLOCKLIST
+        .lock()
+        .map_err(|_| "Failed to lock blocklist".to_string())?;
+    let store = guard.as_mut().ok_or("Blocklist not initialized")?;
+
+    store.user.retain(|entry| entry != &bundle_id);
+    save_user(store)?;
+    Ok(info(Some(store)))
+}
+
+fn save_user(store: &BlocklistStore) -> Result<(), PromptOsError> {
+    let json = serde_json::to_string_pretty(&store.user)
+        .map_err(|e| format!("Failed to serialize blocklist: {}", e))?;
+    fs::write(store.data_dir.join(USER_LIST_FILE), json)
+        .map_err(|e| format!("Failed to save blocklist: {}", e).into())
+}
+
+/// Download a newer signed blocklist (the list must verify against the release key)
+#[tauri::command]
+pub async fn update_blocklist(url: Option<String>) -> Result<BlocklistInfo, PromptOsError> {
+    // Two downloads, each up to DOWNLOAD_TIMEOUT; keep them off the main thread
+    tauri::async_runtime::spawn_blocking(move || download_blocklist(url))
+        .await
+        .map_err(|e| format!("Blocklist update failed: {}", e))?
+}
+
+fn download_blocklist(url: Option<String>) -> Result<BlocklistInfo, PromptOsError> {
+    let url = url
+        .or_else(|| BLOCKLIST_UPDATE_URL.map(str::to_string))
+        .ok_or("No blocklist update URL configured")?;
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let download = |url: &str| -> Result<Vec<u8>, String> {
+        let response = client
+            .get(url)
+            .send()
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Download failed: {}", e))?;
+        response
+            .bytes()
+            .map(|bytes| bytes.to_vec())
+            .map_err(|e| format!("Download failed: {}", e))
+    };
+
+    let list = download(&url)?;
+    let signature = download(&format!("{}.minisig", url))?;
+    let signature = String::from_utf8(signature).map_err(|_| "Invalid signature encoding")?;
+
+    verify(&list, &signature)?;
+    let remote: RemoteBlocklist =
+        serde_json::from_slice(&list).map_err(|e| format!("Invalid blocklist: {}", e))?;
+
+    let mut guard = BLOCKLIST
+        .lock()
+        .map_err(|_| "Failed to lock blocklist".to_string())?;
+    let store = guard.as_mut().ok_or("Blocklist not initialized")?;
+
+    // Never roll back to an older list
+    if store
+        .remote
+        .as_ref()
+        .is_some_and(|current| current.version >= remote.version)
+    {
+        return Ok(info(Some(store)));
+    }
+
+    fs::write(store.data_dir.join(REMOTE_LIST_FILE), &list)
+        .map_err(|e| format!("Failed to save blocklist: {}", e))?;
+    fs::write(store.data_dir.join(REMOTE_SIGNATURE_FILE), &signature)
+        .map_err(|e| format!("Failed to save blocklist signature: {}", e))?;
+
+    eprintln!(
+        "[DEBUG] Blocklist updated to version {} ({} entries)",
+        remote.version,
+        remote.bundle_ids.len()
+    );
+    store.remote = Some(remote);
+    Ok(info(Some(store)))
+}
@@ -0,0 +1,180 @@
+// Light or dark appearance of the UI around the caret, so the overlay can match it
+// Samples the pixels under the caret when Screen Recording is granted, else the system setting
+
+use crate::error::PromptOsError;
+use crate::field_capture::{capture_image, release_image, CGImageRef};
+use crate::permissions::Permission;
+use crate::text_field_detector::TextFieldBounds;
+use cocoa::base::{id, nil};
+use cocoa::foundation::NSString;
+use objc::msg_send;
+use objc::sel;
+use objc::sel_impl;
+use std::ffi::{c_void, CStr};
+
+// Area sampled around the caret, in points: mostly the field's background behind a line of text
+const SAMPLE_WIDTH: f64 = 200.0;
+const SAMPLE_HEIGHT: f64 = 40.0;
+// The capture is averaged down to this many pixels a side
+const THUMBNAIL_SIDE: usize = 8;
+// Mean luminance (0-1) below which the UI counts as dark
+const DARK_LUMINANCE: f64 = 0.5;
+
+// kCGImageAlphaPremultipliedLast: RGBA bytes
+const BITMAP_RGBA: u32 = 1;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct CGPoint {
+    x: f64,
+    y: f64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct CGSize {
+    width: f64,
+    height: f64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct CGRect {
+    origin: CGPoint,
+    size: CGSize,
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGColorSpaceCreateDeviceRGB() -> *mut c_void;
+    fn CGColorSpaceRelease(space: *mut c_void);
+    fn CGBitmapContextCreate(
+        data: *mut c_void,
+        width: usize,
+        height: usize,
+        bits_per_component: usize,
+        bytes_per_row: usize,
+        space: *mut c_void,
+        bitmap_info: u32,
+    ) -> *mut c_void;
+    fn CGContextDrawImage(context: *mut c_void, rect: CGRect, image: CGImageRef);
+    fn CGContextRelease(context: *mut c_void);
+}
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Appearance {
+    Light,
+    Dark,
+}
+
+#[derive(serde::Serialize, Clone, Copy)]
+pub struct TargetAppearance {
+    pub appearance: Appearance,
+    /// Read off the pixels around the caret rather than the system setting, which apps with
+    /// their own theme ignore
+    pub sampled: bool,
+}
+
+/// Appearance of the UI around a screen point (the caret), for theming the overlay opened there
+pub fn appearance_at(x: f64, y: f64) -> TargetAppearance {
+    if Permission::ScreenRecording.granted() {
+        let area = TextFieldBounds::new(
+            x - SAMPLE_WIDTH / 2.0,
+            y - SAMPLE_HEIGHT / 2.0,
+            SAMPLE_WIDTH,
+            SAMPLE_HEIGHT,
+        );
+        if let Some(luminance) = unsafe { mean_luminance(&area) } {
+            let appearance = if luminance < DARK_LUMINANCE {
+                Appearance::Dark
+            } else {
+                Appearance::Light
+            };
+            return TargetAppearance {
+                appearance,
+                sampled: true,
+            };
+        }
+    }
+
+    TargetAppearance {
+        appearance: system_appearance(),
+        sampled: false,
+    }
+}
+
+/// Appearance around the focused field's caret in the frontmost app
+#[tauri::command]
+pub fn get_target_appearance() -> Result<TargetAppearance, PromptOsError> {
+    let caret = crate::text_field_detector::get_focused_text_field_bounds()?;
+    Ok(appearance_at(
+        caret.x + caret.width / 2.0,
+        caret.y + caret.height / 2.0,
+    ))
+}
+
+/// Average luminance (0-1) of a screen area, by drawing its capture into a tiny RGBA bitmap
+unsafe fn mean_luminance(area: &TextFieldBounds) -> Option<f64> {
+    let image = capture_image(area)
+        .inspect_err(|e| eprintln!("[DEBUG] No appearance sample: {}", e))
+        .ok()?;
+
+    let mut pixels = [0u8; THUMBNAIL_SIDE * THUMBNAIL_SIDE * 4];
+    let space = CGColorSpaceCreateDeviceRGB();
+    let context = CGBitmapContextCreate(
+        pixels.as_mut_ptr() as *mut c_void,
+        THUMBNAIL_SIDE,
+        THUMBNAIL_SIDE,
+        8,
+        THUMBNAIL_SIDE * 4,
+        space,
+        BITMAP_RGBA,
+    );
+    CGColorSpaceRelease(space);
+    if context.is_null() {
+        release_image(image);
+        return None;
+    }
+
+    let side = THUMBNAIL_SIDE as f64;
+    let rect = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: CGSize {
+            width: side,
+            height: side,
+        },
+    };
+    CGContextDrawImage(context, rect, image);
+    CGContextRelease(context);
+    release_image(image);
+
+    let total: f64 = pixels
+        .chunks_exact(4)
+        .map(|px| 0.299 * px[0] as f64 + 0.587 * px[1] as f64 + 0.114 * px[2] as f64)
+        .sum();
+    Some(total / (side * side * 255.0))
+}
+
+/// The system-wide Light/Dark setting
+fn system_appearance() -> Appearance {
+    unsafe {
+        let Some(cls) = objc::runtime::Class::get("NSUserDefaults") else {
+            return Appearance::Light;
+        };
+        let defaults: id = msg_send![cls, standardUserDefaults];
+        let key = NSString::alloc(nil).init_str("AppleInterfaceStyle");
+        let style: id = msg_send![defaults, stringForKey: key];
+        // Unset in Light mode
+        if style == nil {
+            return Appearance::Light;
+        }
+
+        let c_str: *const i8 = msg_send![style, UTF8String];
+        if !c_str.is_null() && CStr::from_ptr(c_str).to_bytes() == b"Dark" {
+            Appearance::Dark
+        } else {
+            Appearance::Light
+        }
+    }
+}
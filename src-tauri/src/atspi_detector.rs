@@ -0,0 +1,615 @@
+// Linux text field detection over AT-SPI2, the accessibility bus GTK and Qt apps publish to
+// Same commands and TextFieldBounds contract as text_field_detector; Wayland may omit coordinates
+
+use crate::error::PromptOsError;
+use prompt_os_pipeline::{detect_direction, CoordinateSpace, InjectStrategy, WritingDirection};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::sync::Mutex;
+use zbus::blocking::{connection, proxy, Connection, Proxy};
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::OwnedObjectPath;
+
+const A11Y_BUS_DESTINATION: &str = "org.a11y.Bus";
+const A11Y_BUS_PATH: &str = "/org/a11y/bus";
+const A11Y_BUS_INTERFACE: &str = "org.a11y.Bus";
+const A11Y_STATUS_INTERFACE: &str = "org.a11y.Status";
+const REGISTRY_DESTINATION: &str = "org.a11y.atspi.Registry";
+const ROOT_PATH: &str = "/org/a11y/atspi/accessible/root";
+const ACCESSIBLE_INTERFACE: &str = "org.a11y.atspi.Accessible";
+const TEXT_INTERFACE: &str = "org.a11y.atspi.Text";
+const COMPONENT_INTERFACE: &str = "org.a11y.atspi.Component";
+const EDITABLE_TEXT_INTERFACE: &str = "org.a11y.atspi.EditableText";
+
+// AtspiStateType values, bits of the 64-bit set GetState returns
+const STATE_ACTIVE: u32 = 1;
+const STATE_EDITABLE: u32 = 7;
+const STATE_FOCUSED: u32 = 12;
+// AtspiCoordType: relative to the whole screen
+const COORD_TYPE_SCREEN: u32 = 0;
+
+// Windows nest their widgets a few dozen levels deep at most; a browser's page can go far deeper
+const MAX_TREE_DEPTH: usize = 60;
+// Bounds the D-Bus round trips spent looking through a large window
+const MAX_TREE_NODES: usize = 2000;
+
+// Characters either side of the caret that get_surrounding_text returns unless asked otherwise
+const DEFAULT_SURROUNDING_CHARS: usize = 1000;
+const SURROUNDING_CHARS_RANGE: RangeInclusive<usize> = 1..=20_000;
+
+// Hints for classifying the focused field, by executable name
+const TERMINAL_EXECUTABLES: &[&str] = &[
+    "gnome-terminal-server",
+    "konsole",
+    "xfce4-terminal",
+    "tilix",
+    "kitty",
+    "alacritty",
+    "wezterm-gui",
+    "ghostty",
+];
+const CODE_EDITOR_EXECUTABLES: &[&str] = &["code", "cursor", "zed", "gnome-builder", "kate"];
+const EMAIL_EXECUTABLES: &[&str] = &["thunderbird", "evolution", "geary"];
+const CHAT_EXECUTABLES: &[&str] = &["slack", "discord", "telegram-desktop", "signal-desktop"];
+const CHAT_TITLES: &[&str] = &[
+    "chatgpt",
+    "claude",
+    "gemini",
+    "slack",
+    "discord",
+    "messenger",
+];
+
+// Connection to the accessibility bus, opened on first use
+static A11Y_BUS: Mutex<Option<Connection>> = Mutex::new(None);
+
+#[derive(serde::Serialize, Clone)]
+pub struct TextFieldBounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Always None: Linux has no CGDirectDisplayID
+    pub display_id: Option<u32>,
+    /// Always None: AT-SPI doesn't say which output a widget is on
+    pub scale_factor: Option<f64>,
+    /// Always physical: X11 and AT-SPI screen coordinates are device pixels
+    pub coordinates: CoordinateSpace,
+}
+
+impl TextFieldBounds {
+    fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        TextFieldBounds {
+            x,
+            y,
+            width,
+            height,
+            display_id: None,
+            scale_factor: None,
+            coordinates: CoordinateSpace::Physical,
+        }
+    }
+}
+
+/// What the focused field is for, so default prompts can suit it
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldKind {
+    Chat,
+    CodeEditor,
+    EmailComposer,
+    Terminal,
+    SearchBar,
+    Generic,
+}
+
+impl FieldKind {
+    /// How a field of this kind takes text: terminals only by paste
+    pub fn inject_strategy(self) -> InjectStrategy {
+        match self {
+            FieldKind::Terminal => InjectStrategy::Paste,
+            _ => InjectStrategy::Insert,
+        }
+    }
+}
+
+/// What kind of element has focus and where it lives, so prompts can be tailored to it
+#[derive(serde::Serialize, Clone)]
+pub struct FocusedFieldInfo {
+    /// AT-SPI role name, e.g. "entry" or "text"
+    pub role: Option<String>,
+    pub subrole: Option<String>,
+    /// Executable name (e.g. "slack"), standing in for a bundle id
+    pub bundle_id: Option<String>,
+    pub app_name: Option<String>,
+    pub window_title: Option<String>,
+    pub placeholder: Option<String>,
+    /// Whether the field implements EditableText, i.e. text can be set directly
+    pub value_settable: bool,
+    pub kind: FieldKind,
+    /// How a response should be written into it, from kind
+    pub strategy: InjectStrategy,
+    /// Script and writing direction of its text, else of its placeholder; None without letters
+    pub direction: Option<WritingDirection>,
+    /// Screen frame of its window, for docking the overlay to the window's edge
+    pub window_frame: Option<TextFieldBounds>,
+}
+
+/// What is already written in the focused field, and where the caret sits in it
+#[derive(serde::Serialize, Clone)]
+pub struct FieldContent {
+    pub value: Option<String>,
+    /// Caret offset, or where the selection starts, in UTF-16 code units of value
+    pub selection_start: Option<usize>,
+    /// 0 when nothing is selected
+    pub selection_length: Option<usize>,
+}
+
+/// Text either side of the caret (or selection), cut to a limit so a long document stays local
+#[derive(serde::Serialize, Clone)]
+pub struct SurroundingText {
+    pub before: String,
+    pub after: String,
+    /// The field holds more text before before, or after after, than was returned
+    pub truncated_before: bool,
+    pub truncated_after: bool,
+}
+
+fn dbus_error(e: zbus::Error) -> PromptOsError {
+    format!("D-Bus error: {}", e).into()
+}
+
+fn bus_proxy<'a>(
+    conn: &Connection,
+    destination: String,
+    path: String,
+    interface: &'static str,
+) -> Result<Proxy<'a>, PromptOsError> {
+    proxy::Builder::new(conn)
+        .destination(destination)
+        .and_then(|builder| builder.path(path))
+        .and_then(|builder| builder.interface(interface))
+        .map_err(dbus_error)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .map_err(dbus_error)
+}
+
+/// The accessibility bus, whose address the session bus hands out
+fn a11y_bus() -> Result<Connection, PromptOsError> {
+    let mut bus = A11Y_BUS
+        .lock()
+        .map_err(|_| "Failed to lock the accessibility bus".to_string())?;
+    if let Some(conn) = bus.as_ref() {
+        return Ok(conn.clone());
+    }
+
+    let session = Connection::session().map_err(dbus_error)?;
+    let launcher = bus_proxy(
+        &session,
+        A11Y_BUS_DESTINATION.to_string(),
+        A11Y_BUS_PATH.to_string(),
+        A11Y_BUS_INTERFACE,
+    )?;
+    let address: String = launcher.call("GetAddress", &()).map_err(dbus_error)?;
+    let conn = connection::Builder::address(address.as_str())
+        .and_then(|builder| builder.build())
+        .map_err(dbus_error)?;
+    *bus = Some(conn.clone());
+    Ok(conn)
+}
+
+/// An object on the accessibility bus: a widget, window, or app
+#[derive(Clone)]
+struct Accessible {
+    conn: Connection,
+    bus_name: String,
+    path: OwnedObjectPath,
+}
+
+impl Accessible {
+    fn proxy(&self, interface: &'static str) -> Result<Proxy<'static>, PromptOsError> {
+        bus_proxy(
+            &self.conn,
+            self.bus_name.clone(),
+            self.path.to_string(),
+            interface,
+        )
+    }
+
+    fn call<B, R>(&self, interface: &'static str, method: &str, body: &B) -> Option<R>
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+        R: serde::de::DeserializeOwned + zbus::zvariant::Type,
+    {
+        self.proxy(interface).ok()?.call(method, body).ok()
+    }
+
+    fn children(&self) -> Vec<Accessible> {
+        self.call::<_, Vec<(String, OwnedObjectPath)>>(ACCESSIBLE_INTERFACE, "GetChildren", &())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(bus_name, path)| Accessible {
+                conn: self.conn.clone(),
+                bus_name,
+                path,
+            })
+            .collect()
+    }
+
+    fn has_state(&self, state: u32) -> bool {
+        let words: Vec<u32> = self
+            .call(ACCESSIBLE_INTERFACE, "GetState", &())
+            .unwrap_or_default();
+        words
+            .get(state as usize / 32)
+            .is_some_and(|word| word & (1 << (state % 32)) != 0)
+    }
+
+    fn role_name(&self) -> Option<String> {
+        self.call(ACCESSIBLE_INTERFACE, "GetRoleName", &())
+    }
+
+    fn name(&self) -> Option<String> {
+        self.proxy(ACCESSIBLE_INTERFACE)
+            .ok()?
+            .get_property::<String>("Name")
+            .ok()
+            .filter(|name| !name.is_empty())
+    }
+
+    fn implements(&self, interface: &str) -> bool {
+        self.call::<_, Vec<String>>(ACCESSIBLE_INTERFACE, "GetInterfaces", &())
+            .is_some_and(|interfaces| interfaces.iter().any(|i| i == interface))
+    }
+
+    fn attributes(&self) -> HashMap<String, String> {
+        self.call(ACCESSIBLE_INTERFACE, "GetAttributes", &())
+            .unwrap_or_default()
+    }
+}
+
+/// The focused widget with the app and window it belongs to
+struct Focus {
+    element: Accessible,
+    app: Accessible,
+    window: Accessible,
+}
+
+/// Find the focused widget: the active window of some app, then the focused widget inside it
+/// (AT-SPI has no call that asks for the focus directly)
+fn focus() -> Result<Focus, PromptOsError> {
+    let root = Accessible {
+        conn: a11y_bus()?,
+        bus_name: REGISTRY_DESTINATION.to_string(),
+        path: OwnedObjectPath::try_from(ROOT_PATH).map_err(|e| format!("{}", e))?,
+    };
+
+    for app in root.children() {
+        let Some(window) = app
+            .children()
+            .into_iter()
+            .find(|window| window.has_state(STATE_ACTIVE))
+        else {
+            continue;
+        };
+        let mut visited = 0;
+        let element = find_focused(&window, 0, &mut visited).ok_or(PromptOsError::NoFocus)?;
+        return Ok(Focus {
+            element,
+            app,
+            window,
+        });
+    }
+    Err(PromptOsError::NoFocus)
+}
+
+/// Depth-first search for the widget with the FOCUSED state
+fn find_focused(node: &Accessible, depth: usize, visited: &mut usize) -> Option<Accessible> {
+    *visited += 1;
+    if depth > MAX_TREE_DEPTH || *visited > MAX_TREE_NODES {
+        return None;
+    }
+    if node.has_state(STATE_FOCUSED) {
+        return Some(node.clone());
+    }
+    node.children()
+        .iter()
+        .find_map(|child| find_focused(child, depth + 1, visited))
+}
+
+/// The focused widget, refused with SecureField when it is a password entry
+fn focused_text_element() -> Result<Accessible, PromptOsError> {
+    let element = focus()?.element;
+    if is_password(&element) {
+        return Err(PromptOsError::SecureField);
+    }
+    Ok(element)
+}
+
+/// Whether assistive technologies are switched on for the session; toolkits publish nothing to
+/// the accessibility bus otherwise
+#[tauri::command]
+pub fn check_accessibility_permission() -> Result<bool, PromptOsError> {
+    let session = Connection::session().map_err(dbus_error)?;
+    let status = bus_proxy(
+        &session,
+        A11Y_BUS_DESTINATION.to_string(),
+        A11Y_BUS_PATH.to_string(),
+        A11Y_STATUS_INTERFACE,
+    )?;
+    Ok(status.get_property::<bool>("IsEnabled").unwrap_or(false))
+}
+
+/// Get the mouse cursor position as fallback (X11 only; Wayland keeps the pointer to itself)
+#[tauri::command]
+pub fn get_cursor_position() -> Result<TextFieldBounds, PromptOsError> {
+    unsafe {
+        let display = x11::xlib::XOpenDisplay(std::ptr::null());
+        if display.is_null() {
+            return Err("Failed to get cursor position: no X display".into());
+        }
+        let (mut root, mut child) = (0, 0);
+        let (mut x, mut y, mut window_x, mut window_y) = (0, 0, 0, 0);
+        let mut mask = 0;
+        let found = x11::xlib::XQueryPointer(
+            display,
+            x11::xlib::XDefaultRootWindow(display),
+            &mut root,
+            &mut child,
+            &mut x,
+            &mut y,
+            &mut window_x,
+            &mut window_y,
+            &mut mask,
+        );
+        x11::xlib::XCloseDisplay(display);
+        if found == 0 {
+            return Err("Failed to get cursor position".into());
+        }
+        Ok(TextFieldBounds::new(x as f64, y as f64, 0.0, 0.0))
+    }
+}
+
+/// Bounds of the caret if a text field is focused, or the mouse position when the field can't
+/// say where its caret is
+#[tauri::command]
+pub fn get_focused_text_field_bounds() -> Result<TextFieldBounds, PromptOsError> {
+    let element = focus()?.element;
+    if is_password(&element) {
+        return Err(PromptOsError::SecureField);
+    }
+    if !element.has_state(STATE_EDITABLE) {
+        eprintln!("[DEBUG] No text field focused");
+        return Err(PromptOsError::NoFocus);
+    }
+    if let Some(caret) = caret_bounds(&element) {
+        eprintln!("[DEBUG] Caret bounds: x={}, y={}", caret.x, caret.y);
+        return Ok(caret);
+    }
+    eprintln!("[DEBUG] No caret bounds, getting cursor position...");
+    get_cursor_position()
+}
+
+/// Extents of the character at the caret; past the last character, the one before it
+fn caret_bounds(element: &Accessible) -> Option<TextFieldBounds> {
+    let text = element.proxy(TEXT_INTERFACE).ok()?;
+    let caret = text.get_property::<i32>("CaretOffset").ok()?;
+    let extents = |offset: i32| {
+        text.call::<_, _, (i32, i32, i32, i32)>("GetCharacterExtents", &(offset, COORD_TYPE_SCREEN))
+            .ok()
+            .filter(|(_, _, width, height)| *width > 0 || *height > 0)
+    };
+
+    let (x, y, height) = match extents(caret) {
+        Some((x, y, _, height)) => (x, y, height),
+        None if caret > 0 => {
+            let (x, y, width, height) = extents(caret - 1)?;
+            (x + width, y, height)
+        }
+        None => return None,
+    };
+    Some(TextFieldBounds::new(x as f64, y as f64, 0.0, height as f64))
+}
+
+/// Screen frame of the window containing the focused widget
+pub fn focused_window_frame() -> Option<TextFieldBounds> {
+    extents(&focus().ok()?.window)
+}
+
+/// Screen frame of a widget or window, through its Component interface
+fn extents(object: &Accessible) -> Option<TextFieldBounds> {
+    let (x, y, width, height): (i32, i32, i32, i32) =
+        object.call(COMPONENT_INTERFACE, "GetExtents", &(COORD_TYPE_SCREEN,))?;
+    (width > 0 && height > 0)
+        .then(|| TextFieldBounds::new(x as f64, y as f64, width as f64, height as f64))
+}
+
+/// Role, owning app, window, and placeholder of the focused widget
+#[tauri::command]
+pub fn get_focused_field_info() -> Result<FocusedFieldInfo, PromptOsError> {
+    let Focus {
+        element,
+        app,
+        window,
+    } = focus()?;
+    let value = text_range(&element, 0, -1);
+
+    let mut info = FocusedFieldInfo {
+        role: element.role_name(),
+        subrole: None,
+        bundle_id: executable_name(&element),
+        app_name: app.name(),
+        window_title: window.name(),
+        placeholder: element.attributes().remove("placeholder-text"),
+        value_settable: element.has_state(STATE_EDITABLE)
+            && element.implements(EDITABLE_TEXT_INTERFACE),
+        kind: FieldKind::Generic,
+        strategy: InjectStrategy::Insert,
+        direction: None,
+        window_frame: extents(&window),
+    };
+    info.kind = classify_field(&element, &info);
+    info.strategy = info.kind.inject_strategy();
+    info.direction = value
+        .as_deref()
+        .or(info.placeholder.as_deref())
+        .and_then(detect_direction);
+    Ok(info)
+}
+
+/// Whether the executable is a terminal, whose prompt takes keystrokes but not AT-SPI edits
+pub fn is_terminal(executable: &str) -> bool {
+    TERMINAL_EXECUTABLES.contains(&executable)
+}
+
+/// Guess what the field is for from its role, app, and window title
+fn classify_field(element: &Accessible, info: &FocusedFieldInfo) -> FieldKind {
+    let executable = info.bundle_id.as_deref().unwrap_or_default();
+    let title = info
+        .window_title
+        .as_deref()
+        .unwrap_or_default()
+        .to_lowercase();
+    let hint = element
+        .name()
+        .or_else(|| info.placeholder.clone())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    if info.role.as_deref() == Some("terminal") || is_terminal(executable) {
+        FieldKind::Terminal
+    } else if CODE_EDITOR_EXECUTABLES.contains(&executable) {
+        FieldKind::CodeEditor
+    } else if EMAIL_EXECUTABLES.contains(&executable) {
+        FieldKind::EmailComposer
+    } else if CHAT_EXECUTABLES.contains(&executable)
+        || CHAT_TITLES.iter().any(|chat| title.contains(chat))
+    {
+        FieldKind::Chat
+    } else if hint.contains("search") {
+        FieldKind::SearchBar
+    } else {
+        FieldKind::Generic
+    }
+}
+
+/// Selection and full value of the focused field (AT-SPI can't ask another app for its focus, so
+/// pid is ignored)
+pub fn read_field_text(_pid: Option<i32>) -> (Option<String>, Option<String>) {
+    let Ok(element) = focused_text_element() else {
+        return (None, None);
+    };
+    let selection = selection_offsets(&element)
+        .filter(|(start, end)| end > start)
+        .and_then(|(start, end)| text_range(&element, start, end));
+    (selection, text_range(&element, 0, -1))
+}
+
+/// What the user highlighted in the focused widget, read without touching the clipboard (None
+/// when nothing is selected)
+#[tauri::command]
+pub fn get_selected_text() -> Result<Option<String>, PromptOsError> {
+    let element = focused_text_element()?;
+    Ok(selection_offsets(&element)
+        .filter(|(start, end)| end > start)
+        .and_then(|(start, end)| text_range(&element, start, end))
+        .filter(|text| !text.is_empty()))
+}
+
+/// Full value of the focused field plus the caret offset and selection, for completions that
+/// follow on from what is already written
+#[tauri::command]
+pub fn get_field_content() -> Result<FieldContent, PromptOsError> {
+    let element = focused_text_element()?;
+    let value = text_range(&element, 0, -1);
+    let offsets = value
+        .as_deref()
+        .zip(selection_offsets(&element))
+        .map(|(value, (start, end))| {
+            let start_utf16 = utf16_offset(value, start);
+            (start_utf16, utf16_offset(value, end) - start_utf16)
+        });
+    Ok(FieldContent {
+        value,
+        selection_start: offsets.map(|(start, _)| start),
+        selection_length: offsets.map(|(_, length)| length),
+    })
+}
+
+/// Up to chars characters (UTF-16 code units) before and after the caret in the focused field,
+/// 1000 unless set
+#[tauri::command]
+pub fn get_surrounding_text(chars: Option<usize>) -> Result<SurroundingText, PromptOsError> {
+    let chars = chars.unwrap_or(DEFAULT_SURROUNDING_CHARS);
+    if !SURROUNDING_CHARS_RANGE.contains(&chars) {
+        return Err(format!(
+            "Surrounding text of {} characters must be {} to {} characters",
+            chars,
+            SURROUNDING_CHARS_RANGE.start(),
+            SURROUNDING_CHARS_RANGE.end()
+        )
+        .into());
+    }
+
+    let element = focused_text_element()?;
+    let text = text_range(&element, 0, -1).ok_or(PromptOsError::NoFocus)?;
+    let (start, end) = selection_offsets(&element).ok_or(PromptOsError::NoFocus)?;
+    let (start, end) = (utf16_offset(&text, start), utf16_offset(&text, end));
+
+    let value: Vec<u16> = text.encode_utf16().collect();
+    let before_start = start.saturating_sub(chars);
+    let after_end = value.len().min(end + chars);
+    Ok(SurroundingText {
+        before: String::from_utf16_lossy(&value[before_start..start]),
+        after: String::from_utf16_lossy(&value[end..after_end]),
+        truncated_before: before_start > 0,
+        truncated_after: after_end < value.len(),
+    })
+}
+
+/// Start and end of the first selection, or the caret twice when nothing is selected (in
+/// characters, as AT-SPI counts them)
+fn selection_offsets(element: &Accessible) -> Option<(i32, i32)> {
+    let text = element.proxy(TEXT_INTERFACE).ok()?;
+    let selections: i32 = text.call("GetNSelections", &()).ok()?;
+    if selections > 0 {
+        return text.call("GetSelection", &(0i32,)).ok();
+    }
+    let caret = text.get_property::<i32>("CaretOffset").ok()?;
+    Some((caret, caret))
+}
+
+/// Text between two character offsets; an end of -1 reads to the end
+fn text_range(element: &Accessible, start: i32, end: i32) -> Option<String> {
+    element.call(TEXT_INTERFACE, "GetText", &(start, end))
+}
+
+/// UTF-16 offset of a character offset into text
+fn utf16_offset(text: &str, chars: i32) -> usize {
+    text.chars()
+        .take(chars.max(0) as usize)
+        .map(char::len_utf16)
+        .sum()
+}
+
+/// Password entries refuse (and must never receive) injected text
+fn is_password(element: &Accessible) -> bool {
+    element.role_name().as_deref() == Some("password text")
+}
+
+/// Name of the executable behind the widget's bus connection, e.g. "slack"
+fn executable_name(element: &Accessible) -> Option<String> {
+    let dbus = bus_proxy(
+        &element.conn,
+        "org.freedesktop.DBus".to_string(),
+        "/org/freedesktop/DBus".to_string(),
+        "org.freedesktop.DBus",
+    )
+    .ok()?;
+    let pid: u32 = dbus
+        .call("GetConnectionUnixProcessID", &(element.bus_name.as_str(),))
+        .ok()?;
+    let exe = std::fs::read_link(format!("/proc/{}/exe", pid)).ok()?;
+    Some(exe.file_name()?.to_string_lossy().into_owned())
+}
@@ -0,0 +1,87 @@
+// macOS implementations of the pipeline backends
+// Thin adapters over text_field_detector, text_injector, and llm (mocks live in test-harness)
+
+use prompt_os_pipeline::{
+    AppInfo, Detector, FieldBounds, FieldText, Generator, InjectStrategy, Injector, Pipeline,
+    PromptOsError,
+};
+use std::sync::Arc;
+
+pub struct MacDetector;
+
+impl Detector for MacDetector {
+    fn focused_field_bounds(&self) -> Result<FieldBounds, PromptOsError> {
+        let bounds = crate::text_field_detector::get_focused_text_field_bounds()?;
+        Ok(FieldBounds {
+            x: bounds.x,
+            y: bounds.y,
+            width: bounds.width,
+            height: bounds.height,
+            display_id: bounds.display_id,
+            scale_factor: bounds.scale_factor,
+            coordinates: bounds.coordinates,
+        })
+    }
+
+    fn frontmost_app(&self) -> Option<AppInfo> {
+        crate::frontmost_app::frontmost_app().map(|app| AppInfo {
+            bundle_id: app.bundle_id,
+            name: app.name,
+            pid: app.pid,
+        })
+    }
+
+    fn field_text(&self, pid: Option<i32>) -> FieldText {
+        let (selection, value) = crate::text_field_detector::read_field_text(pid);
+        FieldText { selection, value }
+    }
+
+    fn inject_strategy(&self) -> InjectStrategy {
+        crate::text_field_detector::get_focused_field_info()
+            .map(|info| info.strategy)
+            .unwrap_or_default()
+    }
+}
+
+pub struct MacInjector;
+
+#[cfg(target_os = "macos")]
+impl Injector for MacInjector {
+    fn insert(&self, text: &str) -> Result<(), PromptOsError> {
+        crate::text_injector::insert_text(text.to_string(), None, None, None)
+    }
+
+    fn paste(&self, text: &str) -> Result<(), PromptOsError> {
+        crate::text_injector::insert_text_via_paste(text.to_string(), None)
+    }
+}
+
+// Writing into other apps is only implemented on macOS so far
+#[cfg(not(target_os = "macos"))]
+impl Injector for MacInjector {
+    fn insert(&self, _text: &str) -> Result<(), PromptOsError> {
+        Err("Inserting text is not supported on this platform yet".into())
+    }
+
+    fn paste(&self, _text: &str) -> Result<(), PromptOsError> {
+        Err("Pasting text is not supported on this platform yet".into())
+    }
+}
+
+pub struct GeminiGenerator;
+
+impl Generator for GeminiGenerator {
+    fn generate(&self, prompt: &str, system_prompt: Option<&str>) -> Result<String, PromptOsError> {
+        crate::llm::generate(prompt, system_prompt)
+    }
+}
+
+/// Pipeline wired to the real macOS backends
+pub fn pipeline() -> Pipeline {
+    pipeline_with_generator(Arc::new(GeminiGenerator))
+}
+
+/// Real detector and injector around a different generator (e.g. a user action's settings)
+pub fn pipeline_with_generator(generator: Arc<dyn Generator>) -> Pipeline {
+    Pipeline::new(Arc::new(MacDetector), Arc::new(MacInjector), generator)
+}
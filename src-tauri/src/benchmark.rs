@@ -0,0 +1,200 @@
+// Latency benchmark for diagnosing slow setups
+// Times AX focus lookup, context collection, provider first token, and injection for the target app
+
+use crate::error::PromptOsError;
+use crate::frontmost_app::{frontmost_app, FrontmostApp};
+use crate::objc_util::cf_release;
+use crate::text_field_detector::{
+    copy_focused_element, copy_string_attribute, select_range, selection_range,
+};
+use accessibility_sys::*;
+use core_foundation::base::{CFTypeRef, TCFType};
+use core_foundation::string::CFString;
+use std::time::{Duration, Instant};
+
+const AX_SAMPLES: usize = 10;
+const CONTEXT_SAMPLES: usize = 3;
+const INJECTION_SAMPLES: usize = 5;
+
+const BENCHMARK_PROMPT: &str = "Reply with the single word: ok";
+
+#[derive(serde::Serialize, Clone, Default)]
+pub struct StageTiming {
+    pub samples: usize,
+    pub min_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    pub error: Option<PromptOsError>,
+}
+
+#[derive(serde::Serialize, Clone, Default)]
+pub struct ProviderTiming {
+    pub time_to_first_token_ms: Option<f64>,
+    pub total_ms: Option<f64>,
+    pub error: Option<PromptOsError>,
+}
+
+#[derive(serde::Serialize, Clone, Default)]
+pub struct InjectionTiming {
+    pub timing: StageTiming,
+    /// Characters written per sample (the current selection, written back over itself)
+    pub chars: usize,
+    pub chars_per_second: Option<f64>,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct BenchmarkReport {
+    pub app: Option<FrontmostApp>,
+    pub ax_focus_lookup: StageTiming,
+    pub context_collection: StageTiming,
+    pub provider: ProviderTiming,
+    pub injection: InjectionTiming,
+}
+
+/// Measure each pipeline stage for the current frontmost app
+#[tauri::command]
+pub async fn run_benchmark() -> Result<BenchmarkReport, PromptOsError> {
+    // Network and AX round-trips take seconds; keep them off the main thread
+    tauri::async_runtime::spawn_blocking(benchmark)
+        .await
+        .map_err(|e| format!("Benchmark failed: {}", e).into())
+}
+
+fn benchmark() -> BenchmarkReport {
+    // When started from our own settings window, measure the app the user came from
+    let app = frontmost_app()
+        .filter(|app| app.pid != std::process::id() as i32)
+        .or_else(crate::context::target_app);
+    let pid = app.as_ref().map(|app| app.pid);
+
+    eprintln!("[DEBUG] Running benchmark against {:?}", app);
+
+    let ax_focus_lookup = time_samples(AX_SAMPLES, || unsafe {
+        let focused = copy_focused_element(pid).ok_or(PromptOsError::NoFocus)?;
+        cf_release(focused);
+        Ok(())
+    });
+
+    let context_collection = time_samples(CONTEXT_SAMPLES, || {
+        crate::context::prompt_context().map(|_| ())
+    });
+
+    let provider = match crate::llm::measure_stream(BENCHMARK_PROMPT) {
+        Ok((first_token, total)) => ProviderTiming {
+            time_to_first_token_ms: Some(millis(first_token)),
+            total_ms: Some(millis(total)),
+            error: None,
+        },
+        Err(e) => ProviderTiming {
+            error: Some(e),
+            ..Default::default()
+        },
+    };
+
+    BenchmarkReport {
+        app,
+        ax_focus_lookup,
+        context_collection,
+        provider,
+        injection: injection_timing(pid),
+    }
+}
+
+/// Write the focused field's selection back over itself, reselecting it before each sample (a
+/// write leaves the caret after the text), so the field ends up exactly as it was
+fn injection_timing(pid: Option<i32>) -> InjectionTiming {
+    let failed = |error: PromptOsError| InjectionTiming {
+        timing: StageTiming {
+            error: Some(error),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let Some(focused) = (unsafe { copy_focused_element(pid) }) else {
+        return failed(PromptOsError::NoFocus);
+    };
+    let element = focused as AXUIElementRef;
+    let selection = unsafe {
+        selection_range(element).map(|range| {
+            let text = copy_string_attribute(element, "AXSelectedText");
+            (range, text.unwrap_or_default())
+        })
+    };
+    let Some(((location, length), selection)) = selection else {
+        unsafe { cf_release(focused) };
+        return failed("Focused field has no selection to write back".into());
+    };
+
+    let reselect = || -> Result<(), PromptOsError> {
+        if unsafe { select_range(element, location, length) } {
+            Ok(())
+        } else {
+            Err("Failed to restore the focused field's selection".into())
+        }
+    };
+    let mut timing = time_prepared_samples(INJECTION_SAMPLES, reselect, || unsafe {
+        let attr = CFString::new("AXSelectedText");
+        let value = CFString::new(&selection);
+        let result = AXUIElementSetAttributeValue(
+            element,
+            attr.as_concrete_TypeRef(),
+            value.as_concrete_TypeRef() as CFTypeRef,
+        );
+        if result != 0 {
+            return Err("Focused element rejected AXSelectedText".into());
+        }
+        Ok(())
+    });
+    // Leave the user's selection as it was found
+    if let Err(e) = reselect() {
+        timing.error.get_or_insert(e);
+    }
+    unsafe { cf_release(focused) };
+
+    let chars = selection.chars().count();
+    let chars_per_second = (chars > 0 && timing.error.is_none() && timing.avg_ms > 0.0)
+        .then(|| chars as f64 / (timing.avg_ms / 1000.0));
+
+    InjectionTiming {
+        timing,
+        chars,
+        chars_per_second,
+    }
+}
+
+/// Run a stage several times; the first error aborts and is reported
+fn time_samples(samples: usize, stage: impl FnMut() -> Result<(), PromptOsError>) -> StageTiming {
+    time_prepared_samples(samples, || Ok(()), stage)
+}
+
+/// time_samples with untimed setup before each sample
+fn time_prepared_samples(
+    samples: usize,
+    mut prepare: impl FnMut() -> Result<(), PromptOsError>,
+    mut stage: impl FnMut() -> Result<(), PromptOsError>,
+) -> StageTiming {
+    let mut durations = Vec::with_capacity(samples);
+    for _ in 0..samples {
+        let prepared = prepare();
+        let started = Instant::now();
+        if let Err(e) = prepared.and_then(|_| stage()) {
+            return StageTiming {
+                error: Some(e),
+                ..Default::default()
+            };
+        }
+        durations.push(millis(started.elapsed()));
+    }
+
+    StageTiming {
+        samples: durations.len(),
+        min_ms: durations.iter().copied().fold(f64::INFINITY, f64::min),
+        avg_ms: durations.iter().sum::<f64>() / durations.len().max(1) as f64,
+        max_ms: durations.iter().copied().fold(0.0, f64::max),
+        error: None,
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -0,0 +1,224 @@
+// Date and calendar awareness for prompts
+// Supplies current date/time and (with permission) calendar events via EventKit
+
+use crate::error::PromptOsError;
+use crate::objc_util::nsstring_to_string;
+use block::ConcreteBlock;
+use chrono::{DateTime, Duration, Local, TimeZone};
+use cocoa::base::{id, nil};
+use objc::msg_send;
+use objc::runtime::{BOOL, YES};
+use objc::sel;
+use objc::sel_impl;
+use std::sync::Mutex;
+
+#[link(name = "EventKit", kind = "framework")]
+extern "C" {}
+
+// EKEntityTypeEvent
+const EK_ENTITY_TYPE_EVENT: u64 = 0;
+
+// EKAuthorizationStatus values
+const EK_AUTHORIZATION_NOT_DETERMINED: i64 = 0;
+const EK_AUTHORIZATION_FULL_ACCESS: i64 = 3;
+
+const MAX_DAYS_AHEAD: u32 = 31;
+
+// Shared event store, kept alive so access requests can complete (stored as usize for thread safety)
+static EVENT_STORE: Mutex<Option<usize>> = Mutex::new(None);
+
+#[derive(serde::Serialize, Clone)]
+pub struct DateContext {
+    /// RFC 3339 local timestamp
+    pub now: String,
+    pub weekday: String,
+    pub timezone: String,
+    pub utc_offset_minutes: i32,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct CalendarEvent {
+    pub title: String,
+    /// RFC 3339 local timestamps
+    pub start: String,
+    pub end: String,
+    pub all_day: bool,
+    pub location: Option<String>,
+}
+
+/// Current local date, time, and timezone
+pub fn date_context() -> DateContext {
+    let now = Local::now();
+
+    DateContext {
+        now: now.to_rfc3339(),
+        weekday: now.format("%A").to_string(),
+        timezone: system_timezone_name().unwrap_or_else(|| now.format("%Z").to_string()),
+        utc_offset_minutes: now.offset().local_minus_utc() / 60,
+    }
+}
+
+#[tauri::command]
+pub fn get_date_context() -> Result<DateContext, PromptOsError> {
+    Ok(date_context())
+}
+
+/// Whether the user has granted full calendar access
+pub fn calendar_access_granted() -> bool {
+    authorization_status() == EK_AUTHORIZATION_FULL_ACCESS
+}
+
+fn authorization_status() -> i64 {
+    unsafe {
+        match objc::runtime::Class::get("EKEventStore") {
+            Some(cls) => msg_send![cls, authorizationStatusForEntityType: EK_ENTITY_TYPE_EVENT],
+            None => EK_AUTHORIZATION_NOT_DETERMINED,
+        }
+    }
+}
+
+#[tauri::command]
+pub fn check_calendar_permission() -> Result<bool, PromptOsError> {
+    Ok(calendar_access_granted())
+}
+
+/// Show the system calendar access prompt (result arrives asynchronously)
+#[tauri::command]
+pub fn request_calendar_permission() -> Result<(), PromptOsError> {
+    if authorization_status() != EK_AUTHORIZATION_NOT_DETERMINED {
+        // macOS only prompts once; the user must change it in System Settings
+        return Ok(());
+    }
+
+    unsafe {
+        let store = event_store()?;
+
+        let completion = ConcreteBlock::new(|granted: BOOL, _error: id| {
+            eprintln!("[DEBUG] Calendar access granted: {}", granted == YES);
+        });
+        let completion = completion.copy();
+
+        // macOS 14 split calendar access into full/write-only
+        let responds: BOOL =
+            msg_send![store, respondsToSelector: sel!(requestFullAccessToEventsWithCompletion:)];
+        if responds == YES {
+            let _: () = msg_send![store, requestFullAccessToEventsWithCompletion: &*completion];
+        } else {
+            let _: () = msg_send![store, requestAccessToEntityType: EK_ENTITY_TYPE_EVENT completion: &*completion];
+        }
+    }
+
+    Ok(())
+}
+
+/// Events from the start of today through `days_ahead` days (empty without permission)
+pub fn calendar_events(days_ahead: u32) -> Result<Vec<CalendarEvent>, PromptOsError> {
+    if !calendar_access_granted() {
+        return Ok(Vec::new());
+    }
+
+    let today = Local::now().date_naive();
+    let start = Local
+        .from_local_datetime(&today.and_hms_opt(0, 0, 0).unwrap_or_default())
+        .earliest()
+        .ok_or("Failed to compute start of day")?;
+    let end = start + Duration::days(i64::from(days_ahead.min(MAX_DAYS_AHEAD)) + 1);
+
+    unsafe {
+        let store = event_store()?;
+
+        let start_date = ns_date(start)?;
+        let end_date = ns_date(end)?;
+        let predicate: id = msg_send![store, predicateForEventsWithStartDate: start_date endDate: end_date calendars: nil];
+        if predicate == nil {
+            return Err("Failed to create calendar predicate".into());
+        }
+
+        let events: id = msg_send![store, eventsMatchingPredicate: predicate];
+        if events == nil {
+            return Ok(Vec::new());
+        }
+
+        let count: usize = msg_send![events, count];
+        let mut result = Vec::with_capacity(count);
+        for i in 0..count {
+            let event: id = msg_send![events, objectAtIndex: i];
+            let title: id = msg_send![event, title];
+            let start_date: id = msg_send![event, startDate];
+            let end_date: id = msg_send![event, endDate];
+            let all_day: BOOL = msg_send![event, isAllDay];
+            let location: id = msg_send![event, location];
+
+            result.push(CalendarEvent {
+                title: nsstring_to_string(title).unwrap_or_default(),
+                start: local_rfc3339(start_date),
+                end: local_rfc3339(end_date),
+                all_day: all_day == YES,
+                location: nsstring_to_string(location).filter(|s| !s.is_empty()),
+            });
+        }
+
+        result.sort_by(|a, b| a.start.cmp(&b.start));
+        Ok(result)
+    }
+}
+
+/// Calendar lookup for tool-calling (e.g. "propose three meeting times this week")
+#[tauri::command]
+pub fn get_calendar_events(days_ahead: Option<u32>) -> Result<Vec<CalendarEvent>, PromptOsError> {
+    calendar_events(days_ahead.unwrap_or(0))
+}
+
+/// Get (or lazily create) the shared EKEventStore
+unsafe fn event_store() -> Result<id, PromptOsError> {
+    let mut guard = EVENT_STORE
+        .lock()
+        .map_err(|_| "Failed to lock event store".to_string())?;
+
+    if let Some(addr) = *guard {
+        return Ok(addr as id);
+    }
+
+    let cls =
+        objc::runtime::Class::get("EKEventStore").ok_or("Failed to get EKEventStore class")?;
+    let store: id = msg_send![cls, new];
+    if store == nil {
+        return Err("Failed to create event store".into());
+    }
+
+    *guard = Some(store as usize);
+    Ok(store)
+}
+
+unsafe fn ns_date(time: DateTime<Local>) -> Result<id, PromptOsError> {
+    let cls = objc::runtime::Class::get("NSDate").ok_or("Failed to get NSDate class")?;
+    let seconds = time.timestamp() as f64;
+    let date: id = msg_send![cls, dateWithTimeIntervalSince1970: seconds];
+    Ok(date)
+}
+
+unsafe fn local_rfc3339(date: id) -> String {
+    if date == nil {
+        return String::new();
+    }
+
+    let seconds: f64 = msg_send![date, timeIntervalSince1970];
+    Local
+        .timestamp_opt(seconds as i64, 0)
+        .single()
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// IANA timezone name (e.g. "Asia/Tokyo") from NSTimeZone
+fn system_timezone_name() -> Option<String> {
+    unsafe {
+        let cls = objc::runtime::Class::get("NSTimeZone")?;
+        let zone: id = msg_send![cls, localTimeZone];
+        if zone == nil {
+            return None;
+        }
+        let name: id = msg_send![zone, name];
+        nsstring_to_string(name)
+    }
+}
@@ -0,0 +1,218 @@
+// Clipboard-change suggestions (opt-in)
+// Watches the pasteboard and offers quick actions when a sizable chunk of text is copied
+
+use crate::error::PromptOsError;
+use crate::objc_util::nsstring_to_string;
+use cocoa::base::{id, nil};
+use cocoa::foundation::NSString;
+use objc::msg_send;
+use objc::sel;
+use objc::sel_impl;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Shorter copies are usually names, links, or single words
+const MIN_CHARS: usize = 200;
+const PREVIEW_CHARS: usize = 120;
+
+const SUGGESTED_ACTIONS: &[&str] = &["summarize", "fix", "translate"];
+
+// Marker types set by password managers and other apps that copy secrets (nspasteboard.org)
+const SENSITIVE_PASTEBOARD_TYPES: &[&str] = &[
+    "org.nspasteboard.ConcealedType",
+    "org.nspasteboard.TransientType",
+    "org.nspasteboard.AutoGeneratedType",
+    "com.agilebits.onepassword",
+    "de.petermaurer.TransientPasteboardType",
+    "Pasteboard generator type",
+];
+
+// Prefixes of common API keys and credentials
+const SECRET_PREFIXES: &[&str] = &[
+    "-----BEGIN",
+    "sk-",
+    "sk_live_",
+    "pk_live_",
+    "ghp_",
+    "gho_",
+    "github_pat_",
+    "xoxb-",
+    "xoxp-",
+    "AKIA",
+    "AIza",
+    "eyJ",
+];
+
+const SETTINGS_FILE: &str = "clipboard_suggestions.json";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+#[derive(serde::Serialize, Clone)]
+pub struct ClipboardSuggestion {
+    pub text: String,
+    pub preview: String,
+    pub char_count: usize,
+    pub actions: Vec<String>,
+    pub source_app: Option<String>,
+}
+
+#[tauri::command]
+pub fn set_clipboard_suggestions_enabled(
+    app: AppHandle,
+    enabled: bool,
+) -> Result<(), PromptOsError> {
+    apply(app, enabled);
+    crate::settings::save(SETTINGS_FILE, &enabled)
+}
+
+/// Restore the saved setting, starting the watcher if it was on (called once during setup)
+pub fn load_settings(app: &AppHandle) {
+    crate::settings::restore(SETTINGS_FILE, |enabled| {
+        apply(app.clone(), enabled);
+        Ok(())
+    });
+}
+
+fn apply(app: AppHandle, enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+
+    // One watcher thread at a time; it exits on its own once disabled
+    if enabled && !WATCHING.swap(true, Ordering::SeqCst) {
+        std::thread::spawn(move || {
+            watch_pasteboard(app);
+            WATCHING.store(false, Ordering::SeqCst);
+        });
+    }
+}
+
+#[tauri::command]
+pub fn get_clipboard_suggestions_enabled() -> Result<bool, PromptOsError> {
+    Ok(ENABLED.load(Ordering::Relaxed))
+}
+
+fn watch_pasteboard(app: AppHandle) {
+    eprintln!("[DEBUG] Clipboard watcher started");
+
+    // Only react to copies made after the feature was turned on
+    let mut last_change = unsafe { change_count() };
+
+    while ENABLED.load(Ordering::Relaxed) {
+        std::thread::sleep(crate::power::poll_interval(POLL_INTERVAL));
+
+        let current = unsafe { change_count() };
+        if current == last_change {
+            continue;
+        }
+        last_change = current;
+
+        // Suggestions are notifications; hold them back while paused for Focus
+        if crate::focus_mode::triggers_paused() {
+            continue;
+        }
+
+        if let Some(suggestion) = unsafe { check_pasteboard() } {
+            eprintln!(
+                "[DEBUG] Clipboard suggestion for {} chars",
+                suggestion.char_count
+            );
+            let _ = app.emit("clipboard-suggestion", suggestion);
+        }
+    }
+
+    eprintln!("[DEBUG] Clipboard watcher stopped");
+}
+
+unsafe fn general_pasteboard() -> id {
+    match objc::runtime::Class::get("NSPasteboard") {
+        Some(cls) => msg_send![cls, generalPasteboard],
+        None => nil,
+    }
+}
+
+unsafe fn change_count() -> i64 {
+    let pasteboard = general_pasteboard();
+    if pasteboard == nil {
+        return 0;
+    }
+    msg_send![pasteboard, changeCount]
+}
+
+/// Read the new pasteboard contents, rejecting anything that might be a secret
+unsafe fn check_pasteboard() -> Option<ClipboardSuggestion> {
+    let pasteboard = general_pasteboard();
+    if pasteboard == nil {
+        return None;
+    }
+
+    if has_sensitive_type(pasteboard) {
+        eprintln!("[DEBUG] Ignoring concealed pasteboard contents");
+        return None;
+    }
+
+    let app = crate::frontmost_app::frontmost_app();
+    if app
+        .as_ref()
+        .and_then(|app| app.bundle_id.as_deref())
+        .is_some_and(crate::app_blocklist::is_blocked)
+    {
+        return None;
+    }
+
+    let string_type = NSString::alloc(nil).init_str("public.utf8-plain-text");
+    let contents: id = msg_send![pasteboard, stringForType: string_type];
+    let text = nsstring_to_string(contents)?;
+
+    let char_count = text.chars().count();
+    if char_count < MIN_CHARS || looks_like_secret(&text) {
+        return None;
+    }
+
+    Some(ClipboardSuggestion {
+        preview: text.chars().take(PREVIEW_CHARS).collect(),
+        text,
+        char_count,
+        actions: SUGGESTED_ACTIONS.iter().map(|a| a.to_string()).collect(),
+        source_app: app.and_then(|app| app.name),
+    })
+}
+
+unsafe fn has_sensitive_type(pasteboard: id) -> bool {
+    let types: id = msg_send![pasteboard, types];
+    if types == nil {
+        return false;
+    }
+
+    let count: usize = msg_send![types, count];
+    (0..count).any(|i| {
+        let item: id = msg_send![types, objectAtIndex: i];
+        nsstring_to_string(item).is_some_and(|t| SENSITIVE_PASTEBOARD_TYPES.contains(&t.as_str()))
+    })
+}
+
+/// Keys, tokens, and other credential-shaped text (never offered as suggestions)
+fn looks_like_secret(text: &str) -> bool {
+    let trimmed = text.trim();
+    if SECRET_PREFIXES
+        .iter()
+        .any(|prefix| trimmed.starts_with(prefix))
+    {
+        return true;
+    }
+
+    // A long run with no whitespace is a token or hash, not prose
+    let words = trimmed.split_whitespace().count();
+    if words <= 2 {
+        return true;
+    }
+
+    // Prose has far more letters and spaces than symbols and digits
+    let symbolic = trimmed
+        .chars()
+        .filter(|c| !c.is_alphabetic() && !c.is_whitespace())
+        .count();
+    symbolic * 2 > trimmed.chars().count()
+}
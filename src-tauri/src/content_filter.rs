@@ -0,0 +1,218 @@
+// Response content filter applied before text reaches the injector
+// Masks or blocks profanity, banned-term regexes, and over-long responses
+
+use crate::error::PromptOsError;
+use regex::{Regex, RegexBuilder};
+use std::sync::{Mutex, OnceLock};
+
+// Deliberately short; teams add their own terms via banned_patterns
+const PROFANITY: &[&str] = &[
+    "fuck",
+    "fucking",
+    "shit",
+    "bullshit",
+    "bitch",
+    "asshole",
+    "bastard",
+    "cunt",
+    "dickhead",
+    "motherfucker",
+    "wanker",
+    "twat",
+];
+
+const SETTINGS_FILE: &str = "content_filter.json";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterAction {
+    /// Mask matches and truncate to max_length
+    Sanitize,
+    /// Refuse to inject at all
+    Block,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct ContentFilterConfig {
+    pub enabled: bool,
+    pub profanity: bool,
+    /// Case-insensitive regexes for terms that must never be injected
+    pub banned_patterns: Vec<String>,
+    /// Maximum response length in characters
+    pub max_length: Option<usize>,
+    pub action: FilterAction,
+}
+
+impl Default for ContentFilterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            profanity: true,
+            banned_patterns: Vec::new(),
+            max_length: None,
+            action: FilterAction::Sanitize,
+        }
+    }
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct FilterResult {
+    pub blocked: bool,
+    /// Text to inject (sanitized if needed); empty when blocked
+    pub text: String,
+    pub reasons: Vec<String>,
+}
+
+struct CompiledConfig {
+    config: ContentFilterConfig,
+    banned: Vec<Regex>,
+}
+
+static CONFIG: Mutex<Option<CompiledConfig>> = Mutex::new(None);
+
+fn profanity_regex() -> &'static Regex {
+    static CELL: OnceLock<Regex> = OnceLock::new();
+    CELL.get_or_init(|| {
+        let pattern = format!(r"\b(?:{})\b", PROFANITY.join("|"));
+        RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .expect("built-in profanity pattern is valid")
+    })
+}
+
+#[tauri::command]
+pub fn get_content_filter_config() -> Result<ContentFilterConfig, PromptOsError> {
+    let guard = CONFIG
+        .lock()
+        .map_err(|_| "Failed to lock content filter config".to_string())?;
+    Ok(guard
+        .as_ref()
+        .map(|compiled| compiled.config.clone())
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn set_content_filter_config(config: ContentFilterConfig) -> Result<(), PromptOsError> {
+    apply(config.clone())?;
+    crate::settings::save(SETTINGS_FILE, &config)
+}
+
+/// Restore the saved config (called once during setup)
+pub fn load_settings() {
+    crate::settings::restore(SETTINGS_FILE, apply);
+}
+
+fn apply(config: ContentFilterConfig) -> Result<(), PromptOsError> {
+    let banned = config
+        .banned_patterns
+        .iter()
+        .map(|pattern| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| format!("Invalid pattern \"{}\": {}", pattern, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    *CONFIG
+        .lock()
+        .map_err(|_| "Failed to lock content filter config".to_string())? =
+        Some(CompiledConfig { config, banned });
+    Ok(())
+}
+
+/// Run the filter over a model response
+pub fn filter(text: &str) -> FilterResult {
+    let allow = || FilterResult {
+        blocked: false,
+        text: text.to_string(),
+        reasons: Vec::new(),
+    };
+
+    // The config can't be read, so nothing can be cleared to go out
+    let Ok(guard) = CONFIG.lock() else {
+        return FilterResult {
+            blocked: true,
+            text: String::new(),
+            reasons: vec!["Content filter settings couldn't be read".to_string()],
+        };
+    };
+    let Some(compiled) = guard.as_ref() else {
+        return allow();
+    };
+    if !compiled.config.enabled {
+        return allow();
+    }
+
+    let config = &compiled.config;
+    let mut reasons = Vec::new();
+    let mut output = text.to_string();
+
+    if config.profanity && profanity_regex().is_match(&output) {
+        reasons.push("Response contains profanity".to_string());
+        output = mask(profanity_regex(), &output);
+    }
+
+    for (pattern, regex) in config.banned_patterns.iter().zip(&compiled.banned) {
+        if regex.is_match(&output) {
+            reasons.push(format!("Response matches banned term \"{}\"", pattern));
+            output = mask(regex, &output);
+        }
+    }
+
+    if let Some(max_length) = config.max_length {
+        let length = output.chars().count();
+        if length > max_length {
+            reasons.push(format!(
+                "Response is {} characters (limit {})",
+                length, max_length
+            ));
+            output = output.chars().take(max_length).collect();
+        }
+    }
+
+    if !reasons.is_empty() && config.action == FilterAction::Block {
+        return FilterResult {
+            blocked: true,
+            text: String::new(),
+            reasons,
+        };
+    }
+
+    FilterResult {
+        blocked: false,
+        text: output,
+        reasons,
+    }
+}
+
+/// Filter a response headed for the injector, failing with the reasons if blocked
+#[cfg(target_os = "macos")]
+pub fn filter_for_injection(text: &str) -> Result<String, PromptOsError> {
+    let result = filter(text);
+    if result.blocked {
+        return Err(PromptOsError::Blocked(format!(
+            "Response blocked: {}",
+            result.reasons.join("; ")
+        )));
+    }
+    if !result.reasons.is_empty() {
+        eprintln!("[DEBUG] Response sanitized: {}", result.reasons.join("; "));
+    }
+    Ok(result.text)
+}
+
+fn mask(regex: &Regex, text: &str) -> String {
+    regex
+        .replace_all(text, |caps: &regex::Captures| {
+            "*".repeat(caps[0].chars().count())
+        })
+        .into_owned()
+}
+
+/// Preview what the filter would do, so the UI can show the reason before injecting
+#[tauri::command]
+pub fn filter_response(text: String) -> Result<FilterResult, PromptOsError> {
+    Ok(filter(&text))
+}
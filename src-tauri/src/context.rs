@@ -0,0 +1,179 @@
+// Prompt context collection
+// Remembers the app that was targeted at trigger time and gathers app-specific context
+
+use crate::frontmost_app::{frontmost_app, FrontmostApp};
+use std::sync::Mutex;
+
+// The context sources read other apps through macOS APIs
+#[cfg(target_os = "macos")]
+use crate::app_blocklist::is_blocked;
+#[cfg(target_os = "macos")]
+use crate::calendar_context::{calendar_events, date_context, CalendarEvent, DateContext};
+#[cfg(target_os = "macos")]
+use crate::document_context::{focused_document, WindowDocument};
+#[cfg(target_os = "macos")]
+use crate::editor_context::{extract_editor_context, EditorContext};
+#[cfg(target_os = "macos")]
+use crate::error::PromptOsError;
+#[cfg(target_os = "macos")]
+use crate::language::{
+    auto_language_enabled, detect_confident_language, detect_field_language, DetectedLanguage,
+};
+#[cfg(target_os = "macos")]
+use crate::mail_context::{extract_reply_context, is_mail_app, MailMessage};
+#[cfg(target_os = "macos")]
+use crate::ocr::{recognize_unreadable_field, RecognizedText};
+#[cfg(target_os = "macos")]
+use crate::style_memory::style_profile;
+#[cfg(target_os = "macos")]
+use crate::typing_context::typed_before;
+
+// App that was frontmost when the trigger fired (the overlay steals focus afterwards)
+static TARGET_APP: Mutex<Option<FrontmostApp>> = Mutex::new(None);
+
+#[cfg(target_os = "macos")]
+#[derive(serde::Serialize, Clone)]
+pub struct PromptContext {
+    pub app: Option<FrontmostApp>,
+    pub reply_to: Option<MailMessage>,
+    /// Open file and language when the target is a known code editor
+    pub editor: Option<EditorContext>,
+    /// File or page open in the target app's focused window
+    pub document: Option<WindowDocument>,
+    /// Detected language of the target field's selection or contents, else of the email being
+    /// replied to or the OCR text
+    pub language: Option<DetectedLanguage>,
+    /// Set when auto-language mode is on: the model should answer in this language
+    pub respond_in_language: Option<String>,
+    /// Learned writing style for the target app (style memory is opt-in)
+    pub style_summary: Option<String>,
+    /// What the user typed into the field just before the trigger (typing context is opt-in)
+    pub typed_before: Option<String>,
+    /// OCR of the field when Accessibility can't read it (needs Screen Recording); approximate
+    pub screen_text: Option<RecognizedText>,
+    pub date: DateContext,
+    /// Today's events; empty unless calendar access was granted
+    pub calendar_events: Vec<CalendarEvent>,
+}
+
+/// Record the current frontmost app (and its focused field) as the trigger target
+pub fn remember_target_app() {
+    let app = frontmost_app();
+    #[cfg(target_os = "macos")]
+    if let Some(app) = &app {
+        crate::text_injector::remember_injection_target(app);
+    }
+    if let Ok(mut guard) = TARGET_APP.lock() {
+        *guard = app;
+    }
+}
+
+/// The remembered trigger target, or the current frontmost app if none was recorded
+pub fn target_app() -> Option<FrontmostApp> {
+    TARGET_APP
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone())
+        .or_else(frontmost_app)
+}
+
+/// The app a response would be written to: the frontmost one, or the trigger target while the
+/// overlay has focus
+#[cfg(target_os = "macos")]
+pub fn write_target_app() -> Option<FrontmostApp> {
+    let own_pid = std::process::id() as i32;
+    frontmost_app()
+        .filter(|app| app.pid != own_pid)
+        .or_else(target_app)
+}
+
+/// Gather context for the prompt about the app the user triggered from
+#[cfg(target_os = "macos")]
+#[tauri::command]
+pub async fn collect_prompt_context() -> Result<PromptContext, PromptOsError> {
+    // Apple Events, AX walks, and OCR can take seconds; keep them off the main thread
+    tauri::async_runtime::spawn_blocking(prompt_context)
+        .await
+        .map_err(|e| format!("Failed to collect context: {}", e))?
+}
+
+/// collect_prompt_context, on the calling thread
+#[cfg(target_os = "macos")]
+pub fn prompt_context() -> Result<PromptContext, PromptOsError> {
+    let app = target_app();
+
+    // Sensitive apps get no app-derived context at all
+    if app
+        .as_ref()
+        .and_then(|app| app.bundle_id.as_deref())
+        .is_some_and(is_blocked)
+    {
+        return Ok(PromptContext {
+            app,
+            reply_to: None,
+            editor: None,
+            document: None,
+            language: None,
+            respond_in_language: None,
+            style_summary: None,
+            typed_before: None,
+            screen_text: None,
+            date: date_context(),
+            calendar_events: Vec::new(),
+        });
+    }
+
+    let reply_to = app.as_ref().and_then(|app| {
+        let bundle_id = app.bundle_id.as_deref()?;
+        if is_mail_app(bundle_id) {
+            extract_reply_context(app)
+        } else {
+            None
+        }
+    });
+
+    let editor = app.as_ref().and_then(extract_editor_context);
+    let document = app.as_ref().and_then(|app| focused_document(app.pid));
+
+    let style_summary = app.as_ref().and_then(|app| {
+        let bundle_id = app.bundle_id.as_deref()?;
+        style_profile(bundle_id, app.name.as_deref()).map(|profile| profile.summary)
+    });
+
+    let typed_before = typed_before(app.as_ref().map(|app| app.pid));
+    let screen_text = app
+        .as_ref()
+        .and_then(|app| recognize_unreadable_field(app.pid));
+
+    // A fresh reply starts empty, so the thread (or text read off screen) sets the language
+    let language = detect_field_language(app.as_ref().map(|app| app.pid)).or_else(|| {
+        reply_to
+            .as_ref()
+            .map(|message| message.body.as_str())
+            .or(screen_text.as_ref().map(|text| text.text.as_str()))
+            .and_then(detect_confident_language)
+    });
+    let respond_in_language = language
+        .as_ref()
+        .filter(|_| auto_language_enabled())
+        .map(|language| language.name.clone());
+
+    let calendar_events = calendar_events(0).unwrap_or_else(|e| {
+        eprintln!("[DEBUG] Calendar lookup failed: {}", e);
+        Vec::new()
+    });
+
+    Ok(PromptContext {
+        app,
+        reply_to,
+        editor,
+        document,
+        language,
+        respond_in_language,
+        style_summary,
+        typed_before,
+        screen_text,
+        date: date_context(),
+        calendar_events,
+    })
+}
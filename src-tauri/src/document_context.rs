@@ -0,0 +1,88 @@
+// Document open in the target app's focused window
+// Reads AXDocument (a file, or Safari's page) or AXURL, so prompts can say what is being edited
+
+use crate::error::PromptOsError;
+use crate::objc_util::cf_release;
+use crate::text_field_detector::{application_element, copy_attribute, copy_string_attribute};
+use accessibility_sys::*;
+use core_foundation::base::{CFGetTypeID, CFTypeRef, TCFType};
+use core_foundation::url::{CFURLRef, CFURL};
+use std::path::Path;
+
+#[derive(serde::Serialize, Clone)]
+pub struct WindowDocument {
+    /// As the window reports it, e.g. "file:///Users/me/report.md" or a web page's address
+    pub url: String,
+    /// Local path, for file URLs
+    pub path: Option<String>,
+    /// Last path segment, e.g. "report.md"
+    pub name: Option<String>,
+}
+
+/// What the focused window of the app being written to has open; None for windows without a
+/// document (and for blocklisted apps)
+#[tauri::command]
+pub fn get_focused_document() -> Result<Option<WindowDocument>, PromptOsError> {
+    let app = crate::context::write_target_app().ok_or(PromptOsError::NoFocus)?;
+    if app
+        .bundle_id
+        .as_deref()
+        .is_some_and(crate::app_blocklist::is_blocked)
+    {
+        return Ok(None);
+    }
+
+    Ok(focused_document(app.pid))
+}
+
+/// AXDocument, else AXURL, of an app's focused window
+pub fn focused_document(pid: i32) -> Option<WindowDocument> {
+    let url = unsafe { read_focused_window_url(pid) }?;
+    let path = crate::editor_context::file_url_to_path(&url);
+    let name = match &path {
+        Some(path) => Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned()),
+        None => url::Url::parse(&url).ok().and_then(|url| {
+            url.path_segments()?
+                .rev()
+                .find(|segment| !segment.is_empty())
+                .map(str::to_string)
+        }),
+    };
+
+    Some(WindowDocument { url, path, name })
+}
+
+unsafe fn read_focused_window_url(pid: i32) -> Option<String> {
+    let app_element = application_element(pid);
+    if app_element.is_null() {
+        return None;
+    }
+
+    let window = copy_attribute(app_element, "AXFocusedWindow");
+    cf_release(app_element as CFTypeRef);
+    let window = window? as AXUIElementRef;
+
+    let url = copy_string_attribute(window, "AXDocument")
+        .or_else(|| copy_url_attribute(window, "AXURL"))
+        .filter(|url| !url.is_empty());
+    cf_release(window as CFTypeRef);
+    url
+}
+
+/// Read a URL-valued AX attribute as a string
+unsafe fn copy_url_attribute(element: AXUIElementRef, attribute: &str) -> Option<String> {
+    let value_ref = copy_attribute(element, attribute)?;
+
+    if CFGetTypeID(value_ref) != CFURL::type_id() {
+        cf_release(value_ref);
+        return None;
+    }
+
+    Some(
+        CFURL::wrap_under_create_rule(value_ref as CFURLRef)
+            .get_string()
+            .to_string(),
+    )
+}
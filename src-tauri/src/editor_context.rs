@@ -0,0 +1,174 @@
+// Editor file-path and language context
+// For known code editors, reads the open file from AXDocument or the window title
+
+use crate::frontmost_app::FrontmostApp;
+use crate::objc_util::cf_release;
+use crate::text_field_detector::{application_element, copy_attribute, copy_string_attribute};
+use accessibility_sys::*;
+use core_foundation::base::CFTypeRef;
+use std::path::Path;
+
+// Bundle ids (or prefixes, for families like JetBrains) of supported editors
+const EDITOR_BUNDLE_IDS: &[&str] = &[
+    "com.microsoft.VSCode",
+    "com.microsoft.VSCodeInsiders",
+    "com.todesktop.230313mf7k", // Cursor
+    "com.exafunction.windsurf",
+    "dev.zed.Zed",
+    "com.apple.dt.Xcode",
+    "com.sublimetext.",
+    "com.jetbrains.",
+    "com.google.android.studio",
+    "com.panic.Nova",
+    "com.barebones.bbedit",
+    "com.macromates.TextMate",
+];
+
+// Window titles use " — " (VS Code, Xcode) or " - " (Sublime, JetBrains) between segments
+const TITLE_SEPARATORS: &[&str] = &[" — ", " – ", " - "];
+
+// Unsaved-changes markers some editors prefix to the title
+const DIRTY_MARKERS: &[&str] = &["● ", "• ", "* "];
+
+#[derive(serde::Serialize, Clone)]
+pub struct EditorContext {
+    pub file_path: Option<String>,
+    pub file_name: Option<String>,
+    pub language: Option<String>,
+}
+
+pub fn is_code_editor(bundle_id: &str) -> bool {
+    EDITOR_BUNDLE_IDS.iter().any(|id| {
+        if id.ends_with('.') {
+            bundle_id.starts_with(id)
+        } else {
+            bundle_id == *id
+        }
+    })
+}
+
+/// Read the open file of a known code editor
+pub fn extract_editor_context(app: &FrontmostApp) -> Option<EditorContext> {
+    if !is_code_editor(app.bundle_id.as_deref()?) {
+        return None;
+    }
+
+    let (document, title) = unsafe { read_focused_window(app.pid) };
+
+    let file_path = document.as_deref().and_then(file_url_to_path);
+    let file_name = file_path
+        .as_deref()
+        .and_then(|path| Path::new(path).file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .or_else(|| title.as_deref().and_then(file_name_from_title));
+
+    let file_name = file_name?;
+    let language = language_for_file(&file_name).map(str::to_string);
+
+    Some(EditorContext {
+        file_path,
+        file_name: Some(file_name),
+        language,
+    })
+}
+
+/// AXDocument and AXTitle of the app's focused window
+unsafe fn read_focused_window(pid: i32) -> (Option<String>, Option<String>) {
+    let app_element = application_element(pid);
+    if app_element.is_null() {
+        return (None, None);
+    }
+
+    let window = copy_attribute(app_element, "AXFocusedWindow");
+    cf_release(app_element as CFTypeRef);
+
+    let Some(window) = window else {
+        return (None, None);
+    };
+
+    let document = copy_string_attribute(window as AXUIElementRef, "AXDocument");
+    let title = copy_string_attribute(window as AXUIElementRef, "AXTitle");
+    cf_release(window);
+
+    (document, title)
+}
+
+pub(crate) fn file_url_to_path(document: &str) -> Option<String> {
+    let url = url::Url::parse(document).ok()?;
+    let path = url.to_file_path().ok()?;
+    Some(path.to_string_lossy().into_owned())
+}
+
+/// First title segment that looks like a file name (e.g. "main.rs — project — Visual Studio Code")
+fn file_name_from_title(title: &str) -> Option<String> {
+    let mut segments = vec![title];
+    for separator in TITLE_SEPARATORS {
+        segments = segments
+            .iter()
+            .flat_map(|segment| segment.split(separator))
+            .collect();
+    }
+
+    segments
+        .into_iter()
+        .map(|segment| {
+            let segment = segment.trim();
+            DIRTY_MARKERS
+                .iter()
+                .find_map(|marker| segment.strip_prefix(marker))
+                .unwrap_or(segment)
+        })
+        .find(|segment| language_for_file(segment).is_some())
+        .map(str::to_string)
+}
+
+/// Infer a language name from a file name's extension
+pub fn language_for_file(file_name: &str) -> Option<&'static str> {
+    match file_name {
+        "Dockerfile" => return Some("Dockerfile"),
+        "Makefile" => return Some("Makefile"),
+        _ => {}
+    }
+
+    let extension = Path::new(file_name).extension()?.to_str()?.to_lowercase();
+    let language = match extension.as_str() {
+        "rs" => "Rust",
+        "ts" | "mts" | "cts" => "TypeScript",
+        "tsx" => "TypeScript React",
+        "js" | "mjs" | "cjs" => "JavaScript",
+        "jsx" => "JavaScript React",
+        "py" => "Python",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "swift" => "Swift",
+        "m" => "Objective-C",
+        "mm" => "Objective-C++",
+        "c" | "h" => "C",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => "C++",
+        "cs" => "C#",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "scala" => "Scala",
+        "dart" => "Dart",
+        "ex" | "exs" => "Elixir",
+        "hs" => "Haskell",
+        "lua" => "Lua",
+        "sh" | "bash" | "zsh" => "Shell",
+        "sql" => "SQL",
+        "html" | "htm" => "HTML",
+        "css" => "CSS",
+        "scss" | "sass" => "SCSS",
+        "vue" => "Vue",
+        "svelte" => "Svelte",
+        "json" => "JSON",
+        "yaml" | "yml" => "YAML",
+        "toml" => "TOML",
+        "xml" | "plist" => "XML",
+        "md" | "markdown" => "Markdown",
+        "tex" => "LaTeX",
+        _ => return None,
+    };
+
+    Some(language)
+}
@@ -0,0 +1,59 @@
+// Electron apps (Slack, VS Code, Discord) keep their accessibility tree off until asked for it
+// Sets AXManualAccessibility on such an app once, so the fields inside it can be detected
+
+use crate::text_field_detector::application_element;
+use accessibility_sys::*;
+use core_foundation::base::{CFTypeRef, TCFType};
+use core_foundation::boolean::CFBoolean;
+use core_foundation::string::CFString;
+use std::collections::HashSet;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+// Present in every Electron app's bundle
+const ELECTRON_FRAMEWORK: &str = "Contents/Frameworks/Electron Framework.framework";
+// The tree is built asynchronously once requested
+const TREE_BUILD_DELAY: Duration = Duration::from_millis(150);
+
+// Apps already asked, so each is only asked (and waited on) once while it runs
+static ASKED: LazyLock<Mutex<HashSet<i32>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Whether the app is built on Electron
+pub(crate) fn is_electron(pid: i32) -> bool {
+    crate::frontmost_app::bundle_path(pid)
+        .is_some_and(|path| path.join(ELECTRON_FRAMEWORK).exists())
+}
+
+/// Turn on an Electron app's accessibility tree, returning once it has had time to build; true
+/// only when it was just turned on, so the caller knows to look again
+pub fn enable_accessibility(pid: i32) -> bool {
+    let first_ask = ASKED.lock().is_ok_and(|mut asked| asked.insert(pid));
+    if !first_ask || !is_electron(pid) {
+        return false;
+    }
+
+    unsafe {
+        let app_element = application_element(pid);
+        if app_element.is_null() {
+            return false;
+        }
+        let attr = CFString::new("AXManualAccessibility");
+        let result = AXUIElementSetAttributeValue(
+            app_element,
+            attr.as_concrete_TypeRef(),
+            CFBoolean::true_value().as_CFTypeRef(),
+        );
+        core_foundation::base::CFRelease(app_element as CFTypeRef);
+        if result != 0 {
+            eprintln!(
+                "[ERROR] Failed to enable accessibility in Electron app {}: {}",
+                pid, result
+            );
+            return false;
+        }
+    }
+
+    eprintln!("[DEBUG] Enabled accessibility in Electron app {}", pid);
+    std::thread::sleep(TREE_BUILD_DELAY);
+    true
+}
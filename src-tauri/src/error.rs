@@ -0,0 +1,4 @@
+// Typed errors returned by every command
+// Defined in the pipeline crate so backends, mocks, and commands share one set of error codes
+
+pub use prompt_os_pipeline::PromptOsError;
@@ -0,0 +1,720 @@
+// macOS keystroke monitor: a CGEvent tap on its own thread
+// Turns key-downs and trigger clicks into pipeline triggers; keystroke_monitor decides what they do
+
+use crate::error::PromptOsError;
+use crate::keystroke_monitor::{self, MonitorEvent, MonitorHealth, StatusReport};
+use core_foundation::base::TCFType;
+use core_foundation::mach_port::CFMachPort;
+use core_foundation::runloop::{kCFRunLoopCommonModes, kCFRunLoopDefaultMode, CFRunLoop};
+use core_graphics::event::CGEvent;
+use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+use prompt_os_pipeline::{
+    Chord, KeyPress, Monitor, Trigger, TriggerMatcher, TriggerSink, TypedKey,
+};
+use std::cell::{Cell, RefCell};
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+// FFI declarations for CGEvent APIs
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn CGEventTapCreate(
+        tap: u32,
+        place: u32,
+        options: u32,
+        eventsOfInterest: u64,
+        callback: CGEventTapCallBack,
+        userInfo: *mut c_void,
+    ) -> CFMachPortRef;
+
+    fn CGEventTapEnable(tap: CFMachPortRef, enable: bool);
+    fn CGEventGetIntegerValueField(event: CGEventRef, field: u32) -> i64;
+    fn CGEventGetFlags(event: CGEventRef) -> u64;
+    fn CGEventKeyboardGetUnicodeString(
+        event: CGEventRef,
+        maxStringLength: usize,
+        actualStringLength: *mut usize,
+        unicodeString: *mut u16,
+    );
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFMachPortInvalidate(port: CFMachPortRef);
+    fn CFMachPortIsValid(port: CFMachPortRef) -> bool;
+}
+
+type CFMachPortRef = *mut c_void;
+type CGEventRef = *mut c_void;
+type CGEventTapCallBack = unsafe extern "C" fn(
+    proxy: *mut c_void,
+    event_type: u32,
+    event: CGEventRef,
+    user_info: *mut c_void,
+) -> CGEventRef;
+
+// Event type constants
+const K_CG_EVENT_LEFT_MOUSE_DOWN: u32 = 1;
+const K_CG_EVENT_LEFT_MOUSE_UP: u32 = 2;
+const K_CG_EVENT_KEY_DOWN: u32 = 10;
+const K_CG_EVENT_KEY_UP: u32 = 11;
+const K_CG_EVENT_FLAGS_CHANGED: u32 = 12;
+const K_CG_EVENT_OTHER_MOUSE_DOWN: u32 = 25;
+const K_CG_EVENT_TAP_LOCATION_HID: u32 = 0;
+const K_CG_EVENT_TAP_HEAD_INSERT: u32 = 0;
+const K_CG_EVENT_TAP_OPTION_DEFAULT: u32 = 0;
+const K_CG_EVENT_TAP_OPTION_LISTEN_ONLY: u32 = 1;
+const K_CG_MOUSE_EVENT_CLICK_STATE: u32 = 1;
+const K_CG_MOUSE_EVENT_BUTTON_NUMBER: u32 = 3;
+const K_CG_KEYBOARD_EVENT_AUTOREPEAT: u32 = 8;
+const K_CG_KEYBOARD_EVENT_KEYCODE: u32 = 9;
+const K_CG_EVENT_SOURCE_USER_DATA: u32 = 42;
+// Sent to the callback when macOS switches the tap off
+const K_CG_EVENT_TAP_DISABLED_BY_TIMEOUT: u32 = 0xFFFF_FFFE;
+const K_CG_EVENT_TAP_DISABLED_BY_USER_INPUT: u32 = 0xFFFF_FFFF;
+
+// How long the tap thread sleeps in its run loop between stop checks
+const RUN_LOOP_SLICE: Duration = Duration::from_secs(1);
+
+const MIDDLE_BUTTON: i64 = 2;
+// Lets the app finish selecting what was clicked before the selection is read
+const SELECTION_SETTLE_DELAY: Duration = Duration::from_millis(50);
+// Modifier mode: a modifier held longer than this was held for something else, not tapped
+const MODIFIER_TAP_TIME: Duration = Duration::from_millis(500);
+
+// Keys that move the caret or leave the field, so earlier keystrokes no longer lead up to it
+const VK_BACKSPACE: i64 = 0x33;
+const VK_RETURN: i64 = 0x24;
+const VK_ENTER: i64 = 0x4C;
+const RESET_KEYCODES: &[i64] = &[
+    0x24, // Return
+    0x30, // Tab
+    0x35, // Escape
+    0x4C, // Enter
+    0x73, // Home
+    0x74, // Page Up
+    0x75, // Forward Delete
+    0x77, // End
+    0x79, // Page Down
+    0x7B, // Left
+    0x7C, // Right
+    0x7D, // Down
+    0x7E, // Up
+];
+
+// Modifier flag masks (CGEventFlags)
+const K_CG_EVENT_FLAG_MASK_SHIFT: u64 = 0x0002_0000;
+const K_CG_EVENT_FLAG_MASK_CONTROL: u64 = 0x0004_0000;
+const K_CG_EVENT_FLAG_MASK_ALTERNATE: u64 = 0x0008_0000;
+const K_CG_EVENT_FLAG_MASK_COMMAND: u64 = 0x0010_0000;
+const MODIFIER_MASK: u64 = K_CG_EVENT_FLAG_MASK_SHIFT
+    | K_CG_EVENT_FLAG_MASK_CONTROL
+    | K_CG_EVENT_FLAG_MASK_ALTERNATE
+    | K_CG_EVENT_FLAG_MASK_COMMAND;
+
+thread_local! {
+    // Only the tap thread touches it
+    static MATCHER: RefCell<TriggerMatcher> = RefCell::new(TriggerMatcher::default());
+    // Keycode of the trigger key swallowed last, so its autorepeats are swallowed too
+    static HELD_TRIGGER: Cell<Option<i64>> = const { Cell::new(None) };
+    // Modifier mode: when the trigger modifier went down with nothing else held
+    static MODIFIER_DOWN: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Classify a key-down for the trigger matcher
+unsafe fn typed_key(event: CGEventRef, keycode: i64, modifiers: u64) -> TypedKey {
+    // Shortcuts and navigation aren't typing
+    let shortcut = modifiers & (K_CG_EVENT_FLAG_MASK_CONTROL | K_CG_EVENT_FLAG_MASK_COMMAND);
+    if shortcut != 0 {
+        return TypedKey::Reset;
+    }
+    match keycode {
+        VK_RETURN | VK_ENTER => TypedKey::Return,
+        VK_BACKSPACE => TypedKey::Backspace,
+        _ if RESET_KEYCODES.contains(&keycode) => TypedKey::Reset,
+        _ => {
+            let mut buf = [0u16; 4];
+            let mut len = 0;
+            CGEventKeyboardGetUnicodeString(event, buf.len(), &mut len, buf.as_mut_ptr());
+            TypedKey::Text(String::from_utf16_lossy(&buf[..len.min(buf.len())]))
+        }
+    }
+}
+
+// Marks the keystrokes we post ourselves ("PrOS"), which the tap lets straight through
+const SYNTHETIC_EVENT_TAG: i64 = 0x5072_4F53;
+
+/// Tag a keystroke we are about to post, so injected text (which may contain "/") or erasing a
+/// trigger can never complete another trigger
+pub(crate) fn mark_synthetic(event: &CGEvent) {
+    event.set_integer_value_field(K_CG_EVENT_SOURCE_USER_DATA, SYNTHETIC_EVENT_TAG);
+}
+
+/// Delete the sequence and command, or the abbreviation, typed before the key that ended them
+fn erase_typed(pid: i32, count: usize) {
+    let Ok(source) = CGEventSource::new(CGEventSourceStateID::HIDSystemState) else {
+        return;
+    };
+
+    // Posted to the app itself, so it lands even after the overlay takes focus
+    for _ in 0..count {
+        for key_down in [true, false] {
+            if let Ok(event) =
+                CGEvent::new_keyboard_event(source.clone(), VK_BACKSPACE as u16, key_down)
+            {
+                mark_synthetic(&event);
+                event.post_to_pid(pid);
+            }
+        }
+    }
+}
+
+/// Type a key into an app after all (hold mode, when the hold key was only tapped)
+fn replay_key(pid: i32, keycode: i64) {
+    let Ok(source) = CGEventSource::new(CGEventSourceStateID::HIDSystemState) else {
+        return;
+    };
+
+    // Posted to the app rather than the HID stream, so the tap doesn't see it again
+    for key_down in [true, false] {
+        if let Ok(event) = CGEvent::new_keyboard_event(source.clone(), keycode as u16, key_down) {
+            mark_synthetic(&event);
+            event.post_to_pid(pid);
+        }
+    }
+}
+
+/// Hold mode: the hold key press in progress, shared with the timer that fires it
+#[derive(Default)]
+struct HoldState {
+    /// Which press is down, if the hold key is
+    pressed: Mutex<Option<u64>>,
+    presses: AtomicU64,
+    /// The press in progress already opened the overlay, so releasing it types nothing
+    fired: AtomicBool,
+}
+
+/// Shared with the tap callback through user_info; lives as long as the tap thread
+struct TapContext {
+    sink: TriggerSink,
+    hold: Arc<HoldState>,
+    status: Option<StatusReport>,
+    stopping: AtomicBool,
+    /// The tap only observes, so keys can't be swallowed
+    listen_only: bool,
+    /// Last seen state of secure keyboard entry
+    secure_input: AtomicBool,
+    /// Set once the tap exists, so the callback can switch it back on
+    tap: AtomicPtr<c_void>,
+    recoveries: AtomicU32,
+}
+
+/// macOS disables a tap whose callback is slow or after some user input; turn it straight back on
+unsafe fn reenable_tap(context: &TapContext, event_type: u32) {
+    let tap = context.tap.load(Ordering::SeqCst);
+    if tap.is_null() || context.stopping.load(Ordering::SeqCst) {
+        return;
+    }
+
+    // Revoking Accessibility access also disables the tap, and re-enabling can't bring it back
+    if !crate::permissions::Permission::Accessibility.granted() {
+        eprintln!("[ERROR] Event tap disabled, Accessibility permission lost");
+        if let Some(report) = &context.status {
+            report(MonitorEvent::PermissionLost);
+        }
+        return;
+    }
+
+    let reason = if event_type == K_CG_EVENT_TAP_DISABLED_BY_TIMEOUT {
+        "timeout"
+    } else {
+        "user_input"
+    };
+    CGEventTapEnable(tap, true);
+    let recoveries = context.recoveries.fetch_add(1, Ordering::SeqCst) + 1;
+    eprintln!(
+        "[ERROR] Event tap disabled by {}, re-enabled ({} so far)",
+        reason, recoveries
+    );
+
+    if let Some(report) = &context.status {
+        report(MonitorEvent::Reenabled(MonitorHealth {
+            reason: reason.to_string(),
+            recoveries,
+        }));
+    }
+}
+
+/// Whether secure keyboard entry (a password prompt) is on, reporting when it changes
+unsafe fn secure_input(context: &TapContext) -> bool {
+    let active = crate::secure_input::enabled();
+    if context.secure_input.swap(active, Ordering::SeqCst) != active {
+        eprintln!("[DEBUG] Secure input {}", if active { "on" } else { "off" });
+        // Nothing typed into the password field may complete a sequence afterwards, and what
+        // was typed before it doesn't lead up to the next trigger
+        MATCHER.with(|matcher| matcher.borrow_mut().clear());
+        crate::typing_context::focus_changed();
+        if let Some(report) = &context.status {
+            report(MonitorEvent::SecureInput(active));
+        }
+    }
+    active
+}
+
+/// Whether the frontmost app or the user's Focus rules out triggering right now
+pub(crate) fn triggers_held_back() -> bool {
+    // Never trigger inside password managers, banking apps, etc.
+    if crate::app_blocklist::frontmost_blocked() {
+        eprintln!("[DEBUG] Frontmost app is blocklisted, ignoring trigger");
+        return true;
+    }
+
+    // Apps the user excluded from (or didn't include in) the trigger filter
+    let bundle_id = crate::frontmost_app::frontmost_app().and_then(|app| app.bundle_id);
+    if keystroke_monitor::app_filtered(bundle_id.as_deref()) {
+        eprintln!("[DEBUG] Frontmost app is filtered out, ignoring trigger");
+        return true;
+    }
+
+    // Stay out of the way during a configured Focus, a presentation, or a full-screen game
+    if crate::focus_mode::triggers_paused() {
+        eprintln!("[DEBUG] Triggers paused by Focus / presenting");
+        return true;
+    }
+    false
+}
+
+/// Hold mode: hold back the hold key, open the overlay once it has been held long enough, and
+/// type it after all if it was only tapped; returns whether the event was the hold key's
+unsafe fn handle_hold(context: &TapContext, event_type: u32, event: CGEventRef) -> bool {
+    let Some((hold_key, hold_time)) = keystroke_monitor::hold_trigger() else {
+        return false;
+    };
+    let keycode = CGEventGetIntegerValueField(event, K_CG_KEYBOARD_EVENT_KEYCODE);
+    if crate::keyboard_layout::us_keycode(keycode) != hold_key {
+        return false;
+    }
+    let hold = &context.hold;
+    let Ok(mut pressed) = hold.pressed.lock() else {
+        return false;
+    };
+
+    if event_type == K_CG_EVENT_KEY_UP {
+        // A press we let through (e.g. with Shift held) is released as usual
+        if pressed.take().is_none() {
+            return false;
+        }
+        if !hold.fired.load(Ordering::SeqCst) && !context.listen_only {
+            if let Some(app) = crate::frontmost_app::frontmost_app() {
+                replay_key(app.pid, keycode);
+            }
+        }
+        return true;
+    }
+
+    // Autorepeat while the key is held
+    if pressed.is_some() {
+        return true;
+    }
+    let autorepeat = CGEventGetIntegerValueField(event, K_CG_KEYBOARD_EVENT_AUTOREPEAT) != 0;
+    if autorepeat || CGEventGetFlags(event) & MODIFIER_MASK != 0 {
+        return false;
+    }
+
+    let press = hold.presses.fetch_add(1, Ordering::SeqCst);
+    *pressed = Some(press);
+    hold.fired.store(false, Ordering::SeqCst);
+
+    let hold = hold.clone();
+    let sink = context.sink.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(hold_time);
+        {
+            // Released (or pressed again) in the meantime: it was a tap
+            let Ok(pressed) = hold.pressed.lock() else {
+                return;
+            };
+            if *pressed != Some(press) {
+                return;
+            }
+            hold.fired.store(true, Ordering::SeqCst);
+        }
+        if !triggers_held_back() {
+            sink(Trigger::Slash);
+        }
+    });
+    true
+}
+
+/// Modifier mode: open the overlay when the trigger modifier is pressed and released on its own,
+/// from kCGEventFlagsChanged since modifiers never send a key-down
+unsafe fn handle_modifier(context: &TapContext, event: CGEventRef) {
+    let Some(trigger_flags) = keystroke_monitor::modifier_trigger() else {
+        return;
+    };
+    let flags = CGEventGetFlags(event) & (MODIFIER_MASK | crate::hotkey::FLAG_FN);
+
+    if flags & !trigger_flags != 0 {
+        // Some other modifier joined in, so it's a shortcut
+        MODIFIER_DOWN.set(None);
+    } else if flags == trigger_flags {
+        // A Hyper key's modifiers arrive one flags change at a time
+        if MODIFIER_DOWN.get().is_none() {
+            MODIFIER_DOWN.set(Some(Instant::now()));
+        }
+    } else if flags == 0 {
+        let tapped = MODIFIER_DOWN
+            .take()
+            .is_some_and(|down| down.elapsed() <= MODIFIER_TAP_TIME);
+        if tapped && !triggers_held_back() {
+            (context.sink)(Trigger::Slash);
+        }
+    }
+}
+
+/// Mouse triggers: a triple-click or a middle-click opens the overlay with the selection, once the
+/// app has had the click
+unsafe fn handle_click(context: &TapContext, event_type: u32, event: CGEventRef) {
+    // A click moves the caret, so what was typed before it no longer leads up to a trigger
+    if event_type == K_CG_EVENT_LEFT_MOUSE_DOWN {
+        MATCHER.with(|matcher| matcher.borrow_mut().clear());
+        crate::typing_context::focus_changed();
+        return;
+    }
+
+    let triggers = keystroke_monitor::mouse_triggers();
+    let clicked = match event_type {
+        K_CG_EVENT_LEFT_MOUSE_UP => {
+            triggers.triple_click
+                && CGEventGetIntegerValueField(event, K_CG_MOUSE_EVENT_CLICK_STATE) == 3
+        }
+        K_CG_EVENT_OTHER_MOUSE_DOWN => {
+            triggers.middle_click
+                && CGEventGetIntegerValueField(event, K_CG_MOUSE_EVENT_BUTTON_NUMBER)
+                    == MIDDLE_BUTTON
+        }
+        _ => false,
+    };
+    if !clicked {
+        return;
+    }
+
+    let sink = context.sink.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(SELECTION_SETTLE_DELAY);
+        if !triggers_held_back() {
+            sink(Trigger::Selection);
+        }
+    });
+}
+
+unsafe extern "C" fn event_tap_callback(
+    _proxy: *mut c_void,
+    event_type: u32,
+    event: CGEventRef,
+    user_info: *mut c_void,
+) -> CGEventRef {
+    let context = &*(user_info as *const TapContext);
+    if event_type == K_CG_EVENT_TAP_DISABLED_BY_TIMEOUT
+        || event_type == K_CG_EVENT_TAP_DISABLED_BY_USER_INPUT
+    {
+        reenable_tap(context, event_type);
+        return event;
+    }
+
+    // Our own paste and erase keystrokes
+    if CGEventGetIntegerValueField(event, K_CG_EVENT_SOURCE_USER_DATA) == SYNTHETIC_EVENT_TAG {
+        return event;
+    }
+
+    // Modifiers always reach the app too
+    if event_type == K_CG_EVENT_FLAGS_CHANGED {
+        if !secure_input(context) {
+            handle_modifier(context, event);
+        }
+        return event;
+    }
+
+    // Anything pressed with the trigger modifier held makes it part of a shortcut
+    if event_type == K_CG_EVENT_KEY_DOWN || event_type == K_CG_EVENT_LEFT_MOUSE_DOWN {
+        MODIFIER_DOWN.set(None);
+    }
+
+    // Clicks always reach the app
+    if event_type == K_CG_EVENT_LEFT_MOUSE_DOWN
+        || event_type == K_CG_EVENT_LEFT_MOUSE_UP
+        || event_type == K_CG_EVENT_OTHER_MOUSE_DOWN
+    {
+        handle_click(context, event_type, event);
+        return event;
+    }
+
+    if event_type == K_CG_EVENT_KEY_DOWN || event_type == K_CG_EVENT_KEY_UP {
+        // Never look at keystrokes, let alone trigger, over a password prompt
+        if secure_input(context) {
+            return event;
+        }
+        // A listen-only tap can't hold the key back, so it types as well as triggering
+        if handle_hold(context, event_type, event) && !context.listen_only {
+            return std::ptr::null_mut();
+        }
+    }
+
+    if event_type == K_CG_EVENT_KEY_DOWN {
+        let keycode = CGEventGetIntegerValueField(event, K_CG_KEYBOARD_EVENT_KEYCODE);
+        // Keep per-keystroke work minimal on battery
+        if !crate::power::throttled() {
+            eprintln!("[DEBUG] Key pressed: keycode={}", keycode);
+        }
+
+        // Held down, a swallowed trigger key would otherwise reach the app on its repeats
+        let repeat = CGEventGetIntegerValueField(event, K_CG_KEYBOARD_EVENT_AUTOREPEAT) != 0;
+        if repeat && HELD_TRIGGER.get() == Some(keycode) && !context.listen_only {
+            return std::ptr::null_mut();
+        }
+        if !repeat {
+            HELD_TRIGGER.set(None);
+        }
+
+        let modifiers = CGEventGetFlags(event) & MODIFIER_MASK;
+        let press = KeyPress {
+            key: typed_key(event, keycode, modifiers),
+            // Chords name keys by what they type, so match on where that sits on US ANSI
+            chord: Some(Chord {
+                keycode: crate::keyboard_layout::us_keycode(keycode),
+                modifiers,
+            }),
+            repeat,
+            at: Instant::now(),
+        };
+        let matched =
+            MATCHER.with(|matcher| keystroke_monitor::feed(&mut matcher.borrow_mut(), press));
+
+        if let Some((trigger, mut typed_len)) = matched {
+            if triggers_held_back() {
+                return event;
+            }
+
+            // Without swallowing, the key ending a command or abbreviation reached the field too
+            if context.listen_only && typed_len > 0 {
+                typed_len += 1;
+            }
+
+            // The overlay may be frontmost by the time the sink returns
+            let target_pid = (typed_len > 0)
+                .then(crate::frontmost_app::frontmost_app)
+                .flatten()
+                .map(|app| app.pid);
+
+            // Snippets and actions write right away, racing the backspaces; only the overlay
+            // waits long enough to check they landed
+            let opens_overlay = matches!(
+                trigger,
+                Trigger::Slash | Trigger::Command(_) | Trigger::Named { .. }
+            );
+
+            // Return null to suppress the trigger keystroke (a listen-only tap ignores it anyway)
+            if (context.sink)(trigger) {
+                if let Some(pid) = target_pid {
+                    if opens_overlay {
+                        crate::text_injector::remember_trigger_text(typed_len);
+                    }
+                    erase_typed(pid, typed_len);
+                }
+                if !context.listen_only {
+                    HELD_TRIGGER.set(Some(keycode));
+                    return std::ptr::null_mut();
+                }
+            }
+        }
+    }
+
+    // Pass through all other events
+    event
+}
+
+/// CFRunLoopStop may be called from any thread
+struct RunLoopHandle(CFRunLoop);
+
+unsafe impl Send for RunLoopHandle {}
+
+/// The tap thread: owns the tap, its run loop source, and the callback context
+struct MonitorActor {
+    context: Arc<TapContext>,
+    run_loop: RunLoopHandle,
+    thread: JoinHandle<()>,
+}
+
+impl MonitorActor {
+    /// Create the tap on a new thread, returning once it is enabled (or failed)
+    fn spawn(sink: TriggerSink, status: Option<StatusReport>) -> Result<Self, PromptOsError> {
+        let context = Arc::new(TapContext {
+            sink,
+            hold: Arc::default(),
+            status,
+            stopping: AtomicBool::new(false),
+            listen_only: keystroke_monitor::listen_only(),
+            secure_input: AtomicBool::new(false),
+            tap: AtomicPtr::new(std::ptr::null_mut()),
+            recoveries: AtomicU32::new(0),
+        });
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        let thread_context = context.clone();
+        let thread = std::thread::spawn(move || unsafe { run_tap(thread_context, ready_tx) });
+
+        match ready_rx.recv() {
+            Ok(Ok(run_loop)) => Ok(Self {
+                context,
+                run_loop,
+                thread,
+            }),
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => Err("Event monitor thread exited during startup".into()),
+        }
+    }
+
+    /// Stop the run loop and wait for the tap to be torn down
+    fn shutdown(self) {
+        self.context.stopping.store(true, Ordering::SeqCst);
+        self.run_loop.0.stop();
+        if self.thread.join().is_err() {
+            eprintln!("[ERROR] Event monitor thread panicked");
+        }
+    }
+}
+
+/// Body of the tap thread; context outlives the tap because it is only dropped on return
+unsafe fn run_tap(
+    context: Arc<TapContext>,
+    ready: mpsc::Sender<Result<RunLoopHandle, PromptOsError>>,
+) {
+    eprintln!("[DEBUG] Event monitor thread started");
+
+    // Key-ups only matter to hold mode, modifier changes to modifier mode, and clicks to mouse
+    // triggers and the matcher
+    let event_mask: u64 = (1 << K_CG_EVENT_KEY_DOWN)
+        | (1 << K_CG_EVENT_KEY_UP)
+        | (1 << K_CG_EVENT_FLAGS_CHANGED)
+        | (1 << K_CG_EVENT_LEFT_MOUSE_DOWN)
+        | (1 << K_CG_EVENT_LEFT_MOUSE_UP)
+        | (1 << K_CG_EVENT_OTHER_MOUSE_DOWN);
+
+    // Create the event tap
+    let options = if context.listen_only {
+        K_CG_EVENT_TAP_OPTION_LISTEN_ONLY
+    } else {
+        K_CG_EVENT_TAP_OPTION_DEFAULT
+    };
+    let tap = CGEventTapCreate(
+        K_CG_EVENT_TAP_LOCATION_HID,
+        K_CG_EVENT_TAP_HEAD_INSERT,
+        options,
+        event_mask,
+        event_tap_callback,
+        Arc::as_ptr(&context) as *mut c_void,
+    );
+
+    if tap.is_null() {
+        eprintln!("[ERROR] Failed to create event tap - NO ACCESSIBILITY PERMISSION!");
+        eprintln!("[ERROR] Grant Accessibility permission to: target/debug/prompt-os");
+        let _ = ready.send(Err(PromptOsError::NoPermission(
+            "Failed to create event tap. Grant Accessibility access in System Settings."
+                .to_string(),
+        )));
+        return;
+    }
+
+    eprintln!("[DEBUG] Event tap created successfully!");
+
+    // Wrap in CFMachPort to create run loop source
+    let mach_port = CFMachPort::wrap_under_create_rule(tap as *mut _);
+    let Ok(run_loop_source) = mach_port.create_runloop_source(0) else {
+        let _ = ready.send(Err("Failed to create run loop source".into()));
+        return;
+    };
+
+    // Add to current run loop
+    let run_loop = CFRunLoop::get_current();
+    run_loop.add_source(&run_loop_source, kCFRunLoopCommonModes);
+
+    // Enable the tap
+    context.tap.store(tap, Ordering::SeqCst);
+    CGEventTapEnable(tap, true);
+    let _ = ready.send(Ok(RunLoopHandle(run_loop.clone())));
+    eprintln!("[DEBUG] Event tap enabled, entering run loop...");
+
+    // Bounded runs, so a stop that lands before the loop starts is still seen
+    while !context.stopping.load(Ordering::SeqCst) {
+        CFRunLoop::run_in_mode(kCFRunLoopDefaultMode, RUN_LOOP_SLICE, false);
+        // Gone for good (e.g. the window server restarted); the watchdog starts a new tap once
+        // this thread exits
+        if !CFMachPortIsValid(tap) {
+            eprintln!("[ERROR] Event tap invalidated");
+            break;
+        }
+    }
+
+    CGEventTapEnable(tap, false);
+    run_loop.remove_source(&run_loop_source, kCFRunLoopCommonModes);
+    CFMachPortInvalidate(tap);
+    eprintln!("[DEBUG] Event tap removed");
+}
+
+/// The CGEvent tap behind the pipeline's Monitor trait, kept in Tauri managed state
+#[derive(Default)]
+pub struct EventTapMonitor {
+    actor: Mutex<Option<MonitorActor>>,
+    status: Mutex<Option<StatusReport>>,
+}
+
+impl EventTapMonitor {
+    /// Where to report re-enables and secure input changes; applies from the next start
+    pub fn on_status(&self, report: StatusReport) {
+        if let Ok(mut status) = self.status.lock() {
+            *status = Some(report);
+        }
+    }
+}
+
+impl Monitor for EventTapMonitor {
+    /// Starting again replaces the running tap
+    fn start(&self, sink: TriggerSink) -> Result<(), PromptOsError> {
+        let mut guard = self
+            .actor
+            .lock()
+            .map_err(|_| "Failed to lock event monitor".to_string())?;
+        if let Some(actor) = guard.take() {
+            actor.shutdown();
+        }
+        let status = self.status.lock().ok().and_then(|status| status.clone());
+        *guard = Some(MonitorActor::spawn(sink, status)?);
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), PromptOsError> {
+        let actor = self
+            .actor
+            .lock()
+            .map_err(|_| "Failed to lock event monitor".to_string())?
+            .take();
+        if let Some(actor) = actor {
+            actor.shutdown();
+        }
+        Ok(())
+    }
+
+    fn died(&self) -> bool {
+        self.actor.lock().is_ok_and(|actor| {
+            actor
+                .as_ref()
+                .is_some_and(|actor| actor.thread.is_finished())
+        })
+    }
+}
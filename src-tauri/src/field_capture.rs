@@ -0,0 +1,250 @@
+// Screenshots of the focused field or its window, for prompts about what is on screen
+// Composites every on-screen window except Prompt OS's own, so the overlay never hides the field
+
+use crate::error::PromptOsError;
+use crate::objc_util::cf_release;
+use crate::permissions::{require, Permission};
+use crate::text_field_detector::{
+    copy_attribute, copy_focused_element, element_frame, is_secure_field, TextFieldBounds,
+};
+use accessibility_sys::AXUIElementRef;
+use cocoa::base::{id, nil};
+use core_foundation::array::{CFArrayCreate, CFArrayGetCount, CFArrayGetValueAtIndex, CFArrayRef};
+use core_foundation::base::{CFIndex, CFType, CFTypeRef, TCFType};
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+use core_foundation::number::CFNumber;
+use core_foundation::string::CFString;
+use objc::msg_send;
+use objc::sel;
+use objc::sel_impl;
+use std::ffi::{c_void, CStr};
+use std::ptr;
+
+// CGWindowListOption: on-screen windows, without the desktop picture and icons
+const WINDOW_LIST_ON_SCREEN_ONLY: u32 = 1 << 0;
+const WINDOW_LIST_EXCLUDE_DESKTOP: u32 = 1 << 4;
+// CGWindowImageOption: full Retina resolution
+const WINDOW_IMAGE_BEST_RESOLUTION: u32 = 1 << 3;
+// NSBitmapImageFileTypePNG
+const BITMAP_FILE_TYPE_PNG: usize = 4;
+
+pub(crate) type CGImageRef = *mut c_void;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct CGPoint {
+    x: f64,
+    y: f64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct CGSize {
+    width: f64,
+    height: f64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct CGRect {
+    origin: CGPoint,
+    size: CGSize,
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGWindowListCopyWindowInfo(option: u32, relative_to_window: u32) -> CFArrayRef;
+    fn CGWindowListCreateImageFromArray(
+        bounds: CGRect,
+        window_array: CFArrayRef,
+        image_option: u32,
+    ) -> CGImageRef;
+    fn CGImageGetWidth(image: CGImageRef) -> usize;
+    fn CGImageGetHeight(image: CGImageRef) -> usize;
+    fn CGImageRelease(image: CGImageRef);
+}
+
+/// What to capture around the focused element
+#[derive(serde::Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureRegion {
+    #[default]
+    Field,
+    /// The window containing the field, e.g. a whole form
+    Window,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct FieldScreenshot {
+    /// PNG image, base64-encoded
+    pub png_base64: String,
+    /// Captured area in screen points
+    pub bounds: TextFieldBounds,
+    /// Image size in pixels (points times the screen's scale factor)
+    pub pixel_width: usize,
+    pub pixel_height: usize,
+}
+
+/// Capture the focused field (or its window) of the app being written to; needs Screen Recording
+#[tauri::command]
+pub fn capture_field_screenshot(
+    region: Option<CaptureRegion>,
+) -> Result<FieldScreenshot, PromptOsError> {
+    let bounds = capture_target(region.unwrap_or_default())?;
+
+    unsafe {
+        let image = capture_image(&bounds)?;
+        let pixel_width = CGImageGetWidth(image);
+        let pixel_height = CGImageGetHeight(image);
+        let png_base64 = encode_png_base64(image);
+        CGImageRelease(image);
+
+        Ok(FieldScreenshot {
+            png_base64: png_base64.ok_or("Failed to encode the capture as PNG")?,
+            bounds,
+            pixel_width,
+            pixel_height,
+        })
+    }
+}
+
+/// Where to capture in the app being written to, once permissions, secure input, and the
+/// blocklist allow it
+pub(crate) fn capture_target(region: CaptureRegion) -> Result<TextFieldBounds, PromptOsError> {
+    require(Permission::ScreenRecording)?;
+    require(Permission::Accessibility)?;
+    if crate::secure_input::enabled() {
+        return Err(PromptOsError::SecureField);
+    }
+
+    let app = crate::context::write_target_app().ok_or(PromptOsError::NoFocus)?;
+    if app
+        .bundle_id
+        .as_deref()
+        .is_some_and(crate::app_blocklist::is_blocked)
+    {
+        return Err(PromptOsError::Blocked(format!(
+            "{} is blocklisted, so it can't be captured",
+            app.name.as_deref().unwrap_or("This app")
+        )));
+    }
+
+    unsafe { target_frame(app.pid, region) }
+}
+
+/// Screen frame of the app's focused element, or of the window containing it
+unsafe fn target_frame(pid: i32, region: CaptureRegion) -> Result<TextFieldBounds, PromptOsError> {
+    let focused = copy_focused_element(Some(pid)).ok_or(PromptOsError::NoFocus)?;
+    let element = focused as AXUIElementRef;
+    if is_secure_field(element) {
+        cf_release(focused);
+        return Err(PromptOsError::SecureField);
+    }
+
+    let frame = match region {
+        CaptureRegion::Field => element_frame(element),
+        CaptureRegion::Window => copy_attribute(element, "AXWindow").and_then(|window| {
+            let frame = element_frame(window as AXUIElementRef);
+            cf_release(window);
+            frame
+        }),
+    };
+    cf_release(focused);
+
+    frame
+        .filter(|frame| frame.width > 0.0 && frame.height > 0.0)
+        .ok_or_else(|| "The focused element has no frame on screen".into())
+}
+
+/// Pixels of a screen area, without this app's windows; release with release_image
+pub(crate) unsafe fn capture_image(bounds: &TextFieldBounds) -> Result<CGImageRef, PromptOsError> {
+    let windows: Vec<*const c_void> = other_apps_windows()
+        .into_iter()
+        .map(|window| window as usize as *const c_void)
+        .collect();
+    // CGWindowIDs are stored as raw values, not CFNumbers
+    let window_array = CFArrayCreate(
+        ptr::null(),
+        windows.as_ptr(),
+        windows.len() as CFIndex,
+        ptr::null(),
+    );
+
+    let rect = CGRect {
+        origin: CGPoint {
+            x: bounds.x,
+            y: bounds.y,
+        },
+        size: CGSize {
+            width: bounds.width,
+            height: bounds.height,
+        },
+    };
+    let image = CGWindowListCreateImageFromArray(rect, window_array, WINDOW_IMAGE_BEST_RESOLUTION);
+    cf_release(window_array as CFTypeRef);
+    if image.is_null() {
+        return Err("Failed to capture the screen".into());
+    }
+    Ok(image)
+}
+
+pub(crate) unsafe fn release_image(image: CGImageRef) {
+    CGImageRelease(image);
+}
+
+/// On-screen windows, front to back, except those of this process
+unsafe fn other_apps_windows() -> Vec<u32> {
+    let info =
+        CGWindowListCopyWindowInfo(WINDOW_LIST_ON_SCREEN_ONLY | WINDOW_LIST_EXCLUDE_DESKTOP, 0);
+    if info.is_null() {
+        return Vec::new();
+    }
+
+    let own_pid = std::process::id() as i64;
+    let owner_key = CFString::from_static_string("kCGWindowOwnerPID");
+    let number_key = CFString::from_static_string("kCGWindowNumber");
+    let number = |window: &CFDictionary<CFString, CFType>, key: &CFString| {
+        window
+            .find(key)
+            .and_then(|value| value.downcast::<CFNumber>())
+            .and_then(|number| number.to_i64())
+    };
+
+    let mut windows = Vec::new();
+    for index in 0..CFArrayGetCount(info) {
+        let window = CFArrayGetValueAtIndex(info, index) as CFDictionaryRef;
+        let window = CFDictionary::<CFString, CFType>::wrap_under_get_rule(window);
+        if number(&window, &owner_key) == Some(own_pid) {
+            continue;
+        }
+        if let Some(id) = number(&window, &number_key) {
+            windows.push(id as u32);
+        }
+    }
+    cf_release(info as CFTypeRef);
+    windows
+}
+
+/// PNG bytes of a CGImage, base64-encoded by Foundation
+unsafe fn encode_png_base64(image: CGImageRef) -> Option<String> {
+    let rep_cls = objc::runtime::Class::get("NSBitmapImageRep")?;
+    let dict_cls = objc::runtime::Class::get("NSDictionary")?;
+
+    let rep: id = msg_send![rep_cls, alloc];
+    let rep: id = msg_send![rep, initWithCGImage: image];
+    if rep == nil {
+        return None;
+    }
+    let properties: id = msg_send![dict_cls, dictionary];
+    let data: id =
+        msg_send![rep, representationUsingType: BITMAP_FILE_TYPE_PNG properties: properties];
+    let encoded: Option<String> = if data == nil {
+        None
+    } else {
+        let base64: id = msg_send![data, base64EncodedStringWithOptions: 0usize];
+        let c_str: *const i8 = msg_send![base64, UTF8String];
+        (!c_str.is_null()).then(|| CStr::from_ptr(c_str).to_string_lossy().into_owned())
+    };
+    let _: () = msg_send![rep, release];
+    encoded
+}
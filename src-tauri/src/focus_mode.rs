@@ -0,0 +1,305 @@
+// Focus / Do Not Disturb awareness
+// Optionally pauses triggers and notifications during a Focus, while presenting, or in full screen
+
+use crate::error::PromptOsError;
+use crate::objc_util::cf_release;
+use crate::text_field_detector::{application_element, copy_attribute};
+use accessibility_sys::*;
+use cocoa::base::id;
+use core_foundation::base::{CFTypeRef, TCFType};
+use core_foundation::boolean::CFBoolean;
+use objc::msg_send;
+use objc::sel;
+use objc::sel_impl;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::image::Image;
+use tauri::{AppHandle, Emitter};
+
+// Written by the system when a Focus is turned on manually or from Control Center
+// (scheduled and automation-triggered Focus modes show up here once active)
+const ASSERTIONS_FILE: &str = "Library/DoNotDisturb/DB/Assertions.json";
+const MODE_CONFIGURATIONS_FILE: &str = "Library/DoNotDisturb/DB/ModeConfigurations.json";
+
+// How soon the tray icon follows a Focus turning on or off
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+// Opacity of the tray icon while triggers are paused
+const PAUSED_ICON_ALPHA: f32 = 0.4;
+
+// Apps whose full-screen windows mean a presentation is running
+const PRESENTATION_BUNDLE_IDS: &[&str] = &[
+    "com.apple.iWork.Keynote",
+    "com.microsoft.Powerpoint",
+    "com.apple.Preview",
+];
+
+// NSApplicationPresentationOptions a full-screen game or slideshow sets for the whole system (an
+// ordinary full-screen window only auto-hides the menu bar and Dock)
+const NS_PRESENTATION_HIDE_DOCK: usize = 1 << 1;
+const NS_PRESENTATION_HIDE_MENU_BAR: usize = 1 << 3;
+
+const SETTINGS_FILE: &str = "focus_pause.json";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct FocusPauseConfig {
+    pub enabled: bool,
+    pub pause_when_presenting: bool,
+    /// Focus names or identifiers (e.g. "Work", "com.apple.focus.work"); empty means any Focus
+    pub focus_modes: Vec<String>,
+    /// Per-app override by bundle id: whether that app in full screen pauses triggers
+    #[serde(default)]
+    pub full_screen_apps: HashMap<String, bool>,
+}
+
+impl Default for FocusPauseConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            pause_when_presenting: true,
+            focus_modes: Vec::new(),
+            full_screen_apps: HashMap::new(),
+        }
+    }
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct FocusState {
+    pub focused: bool,
+    /// Display name of the active Focus, when it could be resolved
+    pub mode: Option<String>,
+    pub mode_identifier: Option<String>,
+    /// A presentation, game, or app set to pause in full screen is frontmost
+    pub presenting: bool,
+    /// Whether triggers and notifications are currently paused
+    pub paused: bool,
+}
+
+static CONFIG: Mutex<Option<FocusPauseConfig>> = Mutex::new(None);
+// Last state seen by the poll thread, so the event tap never reads files or AX itself
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+fn config() -> FocusPauseConfig {
+    CONFIG
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn get_focus_pause_config() -> Result<FocusPauseConfig, PromptOsError> {
+    Ok(config())
+}
+
+#[tauri::command]
+pub fn set_focus_pause_config(config: FocusPauseConfig) -> Result<(), PromptOsError> {
+    apply(config.clone())?;
+    crate::settings::save(SETTINGS_FILE, &config)
+}
+
+fn apply(config: FocusPauseConfig) -> Result<(), PromptOsError> {
+    if !watches_anything(&config) {
+        PAUSED.store(false, Ordering::Relaxed);
+    }
+    *CONFIG
+        .lock()
+        .map_err(|_| "Failed to lock focus config".to_string())? = Some(config);
+    Ok(())
+}
+
+/// Restore the saved focus pause settings (called once during setup)
+pub fn load_settings() {
+    crate::settings::restore(SETTINGS_FILE, apply);
+}
+
+/// Whether anything in the config can pause triggers
+fn watches_anything(config: &FocusPauseConfig) -> bool {
+    config.enabled || config.pause_when_presenting
+}
+
+/// Current Focus / presenting state (re-read on every call, so pausing ends on its own)
+pub fn focus_state() -> FocusState {
+    let config = config();
+    let mode_identifier = active_focus_identifier();
+    let mode = mode_identifier.as_deref().and_then(focus_mode_name);
+    let presenting = is_presenting(&config.full_screen_apps);
+
+    let focus_matches = mode_identifier.as_ref().is_some_and(|identifier| {
+        config.focus_modes.is_empty()
+            || config.focus_modes.iter().any(|wanted| {
+                wanted.eq_ignore_ascii_case(identifier)
+                    || mode
+                        .as_deref()
+                        .is_some_and(|name| wanted.eq_ignore_ascii_case(name))
+            })
+    });
+
+    FocusState {
+        focused: mode_identifier.is_some(),
+        mode,
+        mode_identifier,
+        presenting,
+        paused: (config.enabled && focus_matches) || (config.pause_when_presenting && presenting),
+    }
+}
+
+/// Whether triggers and notifications should be held back, as of the last poll (cheap enough for
+/// the event tap)
+pub fn triggers_paused() -> bool {
+    PAUSED.load(Ordering::Relaxed)
+}
+
+#[tauri::command]
+pub fn get_focus_state() -> Result<FocusState, PromptOsError> {
+    Ok(focus_state())
+}
+
+/// Poll the Focus / presenting state, dim the tray icon while triggers are paused, and emit
+/// "focus-paused" (true or false) when that changes (called once during setup)
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut dimmed = false;
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            // Nothing can pause triggers: skip reading the Focus database and the frontmost window
+            let paused = watches_anything(&config()) && focus_state().paused;
+            PAUSED.store(paused, Ordering::Relaxed);
+            if paused == dimmed {
+                continue;
+            }
+            dimmed = paused;
+            eprintln!(
+                "[DEBUG] Triggers {} by Focus / presenting",
+                if paused { "paused" } else { "resumed" }
+            );
+            set_tray_dimmed(&app, paused);
+            let _ = app.emit("focus-paused", paused);
+        }
+    });
+}
+
+fn set_tray_dimmed(app: &AppHandle, dimmed: bool) {
+    let (Some(tray), Some(icon)) = (app.tray_by_id(crate::TRAY_ID), app.default_window_icon())
+    else {
+        return;
+    };
+    let icon = if dimmed {
+        let rgba = icon
+            .rgba()
+            .chunks_exact(4)
+            .flat_map(|pixel| {
+                let alpha = (pixel[3] as f32 * PAUSED_ICON_ALPHA) as u8;
+                [pixel[0], pixel[1], pixel[2], alpha]
+            })
+            .collect();
+        Image::new_owned(rgba, icon.width(), icon.height())
+    } else {
+        icon.clone()
+    };
+    let _ = tray.set_icon(Some(icon));
+}
+
+fn home_file(relative: &str) -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(relative))
+}
+
+fn read_json(relative: &str) -> Option<Value> {
+    let contents = std::fs::read_to_string(home_file(relative)?).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Mode identifier of the active Focus, if any
+fn active_focus_identifier() -> Option<String> {
+    let assertions = read_json(ASSERTIONS_FILE)?;
+    assertions
+        .get("data")?
+        .as_array()?
+        .iter()
+        .filter_map(|entry| entry.get("storeAssertionRecords")?.as_array())
+        .flatten()
+        .find_map(|record| {
+            record
+                .get("assertionDetails")?
+                .get("assertionDetailsModeIdentifier")?
+                .as_str()
+                .map(str::to_string)
+        })
+}
+
+/// User-visible name for a Focus mode identifier
+fn focus_mode_name(identifier: &str) -> Option<String> {
+    let configurations = read_json(MODE_CONFIGURATIONS_FILE)?;
+    configurations
+        .get("data")?
+        .as_array()?
+        .iter()
+        .find_map(|entry| {
+            entry
+                .get("modeConfigurations")?
+                .get(identifier)?
+                .get("mode")?
+                .get("name")?
+                .as_str()
+                .map(str::to_string)
+        })
+}
+
+/// The frontmost app is presenting or in full screen, unless its override says otherwise
+fn is_presenting(full_screen_apps: &HashMap<String, bool>) -> bool {
+    let Some(app) = crate::frontmost_app::frontmost_app() else {
+        return false;
+    };
+    let bundle_id = app.bundle_id.as_deref().unwrap_or_default();
+    match full_screen_apps.get(bundle_id) {
+        Some(false) => false,
+        Some(true) => system_chrome_hidden() || has_full_screen_window(app.pid),
+        None => {
+            system_chrome_hidden()
+                || (PRESENTATION_BUNDLE_IDS.contains(&bundle_id) && has_full_screen_window(app.pid))
+        }
+    }
+}
+
+/// The active app has hidden the menu bar or Dock outright (games, slideshows)
+fn system_chrome_hidden() -> bool {
+    let Some(app_cls) = objc::runtime::Class::get("NSApplication") else {
+        return false;
+    };
+    unsafe {
+        let ns_app: id = msg_send![app_cls, sharedApplication];
+        let options: usize = msg_send![ns_app, currentSystemPresentationOptions];
+        options & (NS_PRESENTATION_HIDE_DOCK | NS_PRESENTATION_HIDE_MENU_BAR) != 0
+    }
+}
+
+/// The app's focused window is full screen
+fn has_full_screen_window(pid: i32) -> bool {
+    unsafe {
+        let app_element = application_element(pid);
+        if app_element.is_null() {
+            return false;
+        }
+
+        let window = copy_attribute(app_element, "AXFocusedWindow");
+        cf_release(app_element as CFTypeRef);
+        let Some(window) = window else {
+            return false;
+        };
+
+        let full_screen = copy_attribute(window as AXUIElementRef, "AXFullScreen");
+        cf_release(window);
+
+        match full_screen {
+            Some(value) => {
+                let is_true = value == CFBoolean::true_value().as_CFTypeRef();
+                cf_release(value);
+                is_true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,246 @@
+// Focused-field notifications from an AXObserver on the frontmost app
+// Emits "field-focused" / "field-blurred" as focus moves, instead of finding out at trigger time,
+// and "caret-moved" while the overlay is open, so it can follow the caret
+
+use crate::error::PromptOsError;
+use crate::objc_util::cf_release;
+use crate::text_field_detector::{
+    application_element, caret_bounds, copy_focused_element, copy_string_attribute, is_editable,
+    is_secure_field,
+};
+use accessibility_sys::*;
+use core_foundation::base::{CFTypeRef, TCFType};
+use core_foundation::runloop::{kCFRunLoopDefaultMode, CFRunLoop, CFRunLoopSource};
+use core_foundation::string::{CFString, CFStringRef};
+use prompt_os_pipeline::FieldBounds;
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+// How often the observer thread looks for another app coming to the front
+const APP_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+// Caret moves and typing in the observed app, followed only while CARET_TRACKING is set
+const SELECTION_CHANGED: &str = "AXSelectedTextChanged";
+
+// Whether the last focus change landed on a field that can take text
+static FIELD_FOCUSED: AtomicBool = AtomicBool::new(false);
+const SETTINGS_FILE: &str = "caret_tracking.json";
+
+// Set when the overlay opens; cleared once it's found hidden, or by set_caret_tracking
+static CARET_TRACKING: AtomicBool = AtomicBool::new(false);
+// Whether the overlay follows the caret at all; on unless turned off in settings
+static FOLLOW_CARET: AtomicBool = AtomicBool::new(true);
+
+/// Payload of the "field-focused" event
+#[derive(serde::Serialize, Clone)]
+pub struct FieldFocused {
+    pub role: Option<String>,
+    pub bundle_id: Option<String>,
+    pub app_name: Option<String>,
+}
+
+/// Whether a field that can take text has focus, as of the last focus change
+pub fn field_focused() -> bool {
+    FIELD_FOCUSED.load(Ordering::SeqCst)
+}
+
+/// Follow the caret with the overlay from now until it's hidden (called as it opens), unless
+/// that's turned off
+pub fn track_caret() {
+    CARET_TRACKING.store(FOLLOW_CARET.load(Ordering::SeqCst), Ordering::SeqCst);
+}
+
+/// Turn caret following on or off, for the open overlay and every one after it
+#[tauri::command]
+pub fn set_caret_tracking(enabled: bool) -> Result<(), PromptOsError> {
+    FOLLOW_CARET.store(enabled, Ordering::SeqCst);
+    CARET_TRACKING.store(enabled, Ordering::SeqCst);
+    crate::settings::save(SETTINGS_FILE, &enabled)
+}
+
+#[tauri::command]
+pub fn get_caret_tracking() -> Result<bool, PromptOsError> {
+    Ok(FOLLOW_CARET.load(Ordering::SeqCst))
+}
+
+/// Restore the saved setting (called once during setup)
+pub fn load_settings() {
+    crate::settings::restore(SETTINGS_FILE, |enabled| {
+        FOLLOW_CARET.store(enabled, Ordering::SeqCst);
+        Ok(())
+    });
+}
+
+/// A focus observer on one app, added to the observer thread's run loop
+struct AppObserver {
+    observer: AXObserverRef,
+}
+
+impl AppObserver {
+    unsafe fn add(pid: i32, refcon: *mut c_void, run_loop: &CFRunLoop) -> Option<Self> {
+        let mut observer: AXObserverRef = ptr::null_mut();
+        if AXObserverCreate(pid, focus_changed, &mut observer) != 0 || observer.is_null() {
+            return None;
+        }
+
+        let app_element = application_element(pid);
+        let notification = CFString::new("AXFocusedUIElementChanged");
+        let result = AXObserverAddNotification(
+            observer,
+            app_element,
+            notification.as_concrete_TypeRef(),
+            refcon,
+        );
+        if result != 0 {
+            // e.g. an app that doesn't support Accessibility
+            cf_release(app_element as CFTypeRef);
+            cf_release(observer as CFTypeRef);
+            return None;
+        }
+        // Without it the overlay just stays put
+        let selection_changed = CFString::new(SELECTION_CHANGED);
+        AXObserverAddNotification(
+            observer,
+            app_element,
+            selection_changed.as_concrete_TypeRef(),
+            refcon,
+        );
+        cf_release(app_element as CFTypeRef);
+
+        let source = CFRunLoopSource::wrap_under_get_rule(AXObserverGetRunLoopSource(observer));
+        run_loop.add_source(&source, kCFRunLoopDefaultMode);
+        Some(Self { observer })
+    }
+
+    unsafe fn remove(self, run_loop: &CFRunLoop) {
+        let source =
+            CFRunLoopSource::wrap_under_get_rule(AXObserverGetRunLoopSource(self.observer));
+        run_loop.remove_source(&source, kCFRunLoopDefaultMode);
+        cf_release(self.observer as CFTypeRef);
+    }
+}
+
+/// Follow focus in whichever app is frontmost (called once during setup)
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || unsafe { run(app) });
+}
+
+/// Body of the observer thread, which never returns
+unsafe fn run(app: AppHandle) {
+    // Outlives every observer, since the thread never ends
+    let app: &'static AppHandle = Box::leak(Box::new(app));
+    let refcon = app as *const AppHandle as *mut c_void;
+    let run_loop = CFRunLoop::get_current();
+    let own_pid = std::process::id() as i32;
+    let mut current: Option<AppObserver> = None;
+    let mut current_pid = None;
+
+    loop {
+        let frontmost = crate::frontmost_app::frontmost_app().map(|app| app.pid);
+        // Our own overlay or settings coming forward leaves the last app's state as it was
+        let switched = frontmost.filter(|pid| *pid != own_pid && current_pid != Some(*pid));
+        if let Some(pid) = switched {
+            current_pid = Some(pid);
+            if let Some(observer) = current.take() {
+                observer.remove(&run_loop);
+            }
+            current = AppObserver::add(pid, refcon, &run_loop);
+            // A field in the new app may already have focus
+            report_focus(app, pid);
+        }
+
+        // With no source, the run loop would return straight away
+        if current.is_some() {
+            CFRunLoop::run_in_mode(kCFRunLoopDefaultMode, APP_CHECK_INTERVAL, false);
+        } else {
+            std::thread::sleep(APP_CHECK_INTERVAL);
+        }
+    }
+}
+
+unsafe extern "C" fn focus_changed(
+    _observer: AXObserverRef,
+    element: AXUIElementRef,
+    notification: CFStringRef,
+    refcon: *mut c_void,
+) {
+    let app = &*(refcon as *const AppHandle);
+    let mut pid = 0;
+    if element.is_null() || AXUIElementGetPid(element, &mut pid) != 0 {
+        return;
+    }
+
+    if CFString::wrap_under_get_rule(notification) == SELECTION_CHANGED {
+        if CARET_TRACKING.load(Ordering::SeqCst) {
+            report_caret(app, element);
+        }
+    } else {
+        report_focus(app, pid);
+    }
+}
+
+/// Emit "caret-moved" with the caret's new bounds and move the overlay after it; stops tracking
+/// once the overlay has been hidden
+unsafe fn report_caret(app: &AppHandle, element: AXUIElementRef) {
+    let Some(window) = app.get_webview_window("overlay") else {
+        return;
+    };
+    if !window.is_visible().unwrap_or(false) {
+        CARET_TRACKING.store(false, Ordering::SeqCst);
+        return;
+    }
+    let Some(caret) = caret_bounds(element) else {
+        return;
+    };
+
+    let _ = app.emit("caret-moved", caret.clone());
+    crate::overlay_placement::position_overlay(
+        &window,
+        FieldBounds {
+            x: caret.x,
+            y: caret.y,
+            width: caret.width,
+            height: caret.height,
+            display_id: caret.display_id,
+            scale_factor: caret.scale_factor,
+            coordinates: caret.coordinates,
+        },
+    );
+}
+
+/// Emit "field-focused" for a field that can take text, or "field-blurred" once focus leaves one
+unsafe fn report_focus(app: &AppHandle, pid: i32) {
+    // Looked up again rather than taken from the notification, to see into browser web areas
+    let field = copy_focused_element(Some(pid)).and_then(|focused| {
+        let element = focused as AXUIElementRef;
+        let role = copy_string_attribute(element, "AXRole");
+        let takes_text = is_editable(element, role.as_deref()) && !is_secure_field(element);
+        cf_release(focused);
+        takes_text.then_some(role)
+    });
+
+    let focused = field.is_some();
+    if let Some(role) = field {
+        let owner = crate::frontmost_app::running_app(pid);
+        let _ = app.emit(
+            "field-focused",
+            FieldFocused {
+                role,
+                bundle_id: owner.as_ref().and_then(|owner| owner.bundle_id.clone()),
+                app_name: owner.and_then(|owner| owner.name),
+            },
+        );
+    }
+
+    let was_focused = FIELD_FOCUSED.swap(focused, Ordering::SeqCst);
+    if was_focused && !focused {
+        let _ = app.emit("field-blurred", ());
+    }
+    // Only moving between a field and no field shows in the tray
+    if was_focused != focused {
+        crate::keystroke_monitor::update_tray_tooltip(app);
+    }
+}
@@ -0,0 +1,122 @@
+// Frontmost application lookup using NSWorkspace
+// Shared by context collection and anything else that needs to know the target app
+
+use crate::error::PromptOsError;
+
+#[cfg(target_os = "macos")]
+use crate::objc_util::nsstring_to_string;
+#[cfg(target_os = "macos")]
+use cocoa::base::{id, nil};
+#[cfg(target_os = "macos")]
+use objc::msg_send;
+#[cfg(target_os = "macos")]
+use objc::runtime::{BOOL, YES};
+#[cfg(target_os = "macos")]
+use objc::sel;
+#[cfg(target_os = "macos")]
+use objc::sel_impl;
+#[cfg(target_os = "macos")]
+use std::path::PathBuf;
+
+// NSApplicationActivationOptions
+#[cfg(target_os = "macos")]
+const NS_APPLICATION_ACTIVATE_IGNORING_OTHER_APPS: u64 = 1 << 1;
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct FrontmostApp {
+    pub bundle_id: Option<String>,
+    pub name: Option<String>,
+    pub pid: i32,
+}
+
+/// Get the application that currently owns the menu bar
+#[cfg(target_os = "macos")]
+pub fn frontmost_app() -> Option<FrontmostApp> {
+    unsafe {
+        let workspace_cls = objc::runtime::Class::get("NSWorkspace")?;
+        let workspace: id = msg_send![workspace_cls, sharedWorkspace];
+        if workspace == nil {
+            return None;
+        }
+
+        let app: id = msg_send![workspace, frontmostApplication];
+        app_info(app)
+    }
+}
+
+/// Only NSWorkspace is asked so far; elsewhere the target app is unknown, so per-app settings
+/// don't apply
+#[cfg(not(target_os = "macos"))]
+pub fn frontmost_app() -> Option<FrontmostApp> {
+    None
+}
+
+/// A running app by pid, frontmost or not (e.g. the one holding secure input)
+#[cfg(target_os = "macos")]
+pub fn running_app(pid: i32) -> Option<FrontmostApp> {
+    unsafe {
+        let cls = objc::runtime::Class::get("NSRunningApplication")?;
+        let app: id = msg_send![cls, runningApplicationWithProcessIdentifier: pid];
+        app_info(app)
+    }
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn app_info(app: id) -> Option<FrontmostApp> {
+    if app == nil {
+        return None;
+    }
+
+    let bundle_id: id = msg_send![app, bundleIdentifier];
+    let name: id = msg_send![app, localizedName];
+    let pid: i32 = msg_send![app, processIdentifier];
+
+    Some(FrontmostApp {
+        bundle_id: nsstring_to_string(bundle_id),
+        name: nsstring_to_string(name),
+        pid,
+    })
+}
+
+/// Where a running app's bundle is on disk (e.g. to look inside it)
+#[cfg(target_os = "macos")]
+pub fn bundle_path(pid: i32) -> Option<PathBuf> {
+    unsafe {
+        let cls = objc::runtime::Class::get("NSRunningApplication")?;
+        let app: id = msg_send![cls, runningApplicationWithProcessIdentifier: pid];
+        if app == nil {
+            return None;
+        }
+        let url: id = msg_send![app, bundleURL];
+        if url == nil {
+            return None;
+        }
+        let path: id = msg_send![url, path];
+        nsstring_to_string(path).map(PathBuf::from)
+    }
+}
+
+/// Bring an app to the front (e.g. to return to the field an overlay session started from)
+#[cfg(target_os = "macos")]
+pub fn activate_app(pid: i32) -> Result<(), PromptOsError> {
+    unsafe {
+        let cls = objc::runtime::Class::get("NSRunningApplication")
+            .ok_or("Failed to get NSRunningApplication class")?;
+        let app: id = msg_send![cls, runningApplicationWithProcessIdentifier: pid];
+        if app == nil {
+            return Err("Original app is no longer running".into());
+        }
+
+        let activated: BOOL =
+            msg_send![app, activateWithOptions: NS_APPLICATION_ACTIVATE_IGNORING_OTHER_APPS];
+        if activated != YES {
+            return Err("Failed to activate the original app".into());
+        }
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub fn get_frontmost_app() -> Result<FrontmostApp, PromptOsError> {
+    frontmost_app().ok_or_else(|| "Failed to get frontmost application".into())
+}
@@ -0,0 +1,197 @@
+// Fuzzy matching over presets and prompt history using nucleo-matcher
+// Keeps overlay suggestions instant even with tens of thousands of history entries
+
+use crate::error::PromptOsError;
+use crate::history::HistoryEntry;
+use crate::presets::Preset;
+use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_matcher::{Config, Matcher, Utf32Str};
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+const DEFAULT_LIMIT: usize = 10;
+
+// Matcher owns sizeable scratch buffers, so it is reused across calls
+static MATCHER: Mutex<Option<Matcher>> = Mutex::new(None);
+
+#[derive(serde::Serialize, Clone)]
+pub struct FuzzyMatch {
+    /// Index into the candidate list
+    pub index: usize,
+    pub score: u32,
+    /// Matched character (not byte) positions, ascending
+    pub positions: Vec<u32>,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct PromptSuggestion {
+    /// "preset" or "history"
+    pub kind: String,
+    pub text: String,
+    pub label: Option<String>,
+    pub history_id: Option<u64>,
+    pub score: u32,
+    pub positions: Vec<u32>,
+}
+
+/// Rank candidates against a query, best first; ties keep candidate order
+pub fn rank<'a>(
+    candidates: impl IntoIterator<Item = &'a str>,
+    query: &str,
+    limit: usize,
+) -> Vec<FuzzyMatch> {
+    let pattern = Pattern::parse(query, CaseMatching::Smart, Normalization::Smart);
+
+    let mut guard = match MATCHER.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let matcher = guard.get_or_insert_with(|| Matcher::new(Config::DEFAULT));
+
+    let mut buf = Vec::new();
+    let mut scored: Vec<(usize, u32)> = candidates
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| {
+            let score = pattern.score(Utf32Str::new(candidate, &mut buf), matcher)?;
+            Some((index, score))
+        })
+        .collect();
+
+    scored.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    scored.truncate(limit);
+    scored
+        .into_iter()
+        .map(|(index, score)| FuzzyMatch {
+            index,
+            score,
+            positions: Vec::new(),
+        })
+        .collect()
+}
+
+/// Fill in match positions (only computed for the few results that are returned)
+pub fn match_positions(candidate: &str, query: &str) -> Vec<u32> {
+    let pattern = Pattern::parse(query, CaseMatching::Smart, Normalization::Smart);
+
+    let mut guard = match MATCHER.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let matcher = guard.get_or_insert_with(|| Matcher::new(Config::DEFAULT));
+
+    let mut buf = Vec::new();
+    let mut positions = Vec::new();
+    pattern.indices(Utf32Str::new(candidate, &mut buf), matcher, &mut positions);
+    positions.sort_unstable();
+    positions.dedup();
+    positions
+}
+
+/// Suggest presets and past prompts matching what the user has typed so far
+#[tauri::command]
+pub fn fuzzy_search_prompts(
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<PromptSuggestion>, PromptOsError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    let presets = crate::presets::presets();
+
+    // History may be unavailable (e.g. no app data dir); presets still work
+    let suggestions = crate::history::with_entries(|entries| {
+        // Newest first, one entry per distinct prompt
+        let mut seen = HashSet::new();
+        let history: Vec<&HistoryEntry> = entries
+            .iter()
+            .rev()
+            .filter(|e| seen.insert(e.prompt.as_str()))
+            .collect();
+        search(&presets, &history, &query, limit)
+    });
+
+    Ok(suggestions.unwrap_or_else(|_| search(&presets, &[], &query, limit)))
+}
+
+/// Rank an arbitrary candidate list against a query (palette, settings search, ...)
+#[tauri::command]
+pub fn fuzzy_rank(
+    candidates: Vec<String>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<FuzzyMatch>, PromptOsError> {
+    let limit = limit.unwrap_or(candidates.len());
+
+    // Empty query keeps the caller's order
+    if query.trim().is_empty() {
+        return Ok((0..candidates.len().min(limit))
+            .map(|index| FuzzyMatch {
+                index,
+                score: 0,
+                positions: Vec::new(),
+            })
+            .collect());
+    }
+
+    let mut matches = rank(candidates.iter().map(String::as_str), &query, limit);
+    for m in &mut matches {
+        m.positions = match_positions(&candidates[m.index], &query);
+    }
+    Ok(matches)
+}
+
+fn search(
+    presets: &[Preset],
+    history: &[&HistoryEntry],
+    query: &str,
+    limit: usize,
+) -> Vec<PromptSuggestion> {
+    // Empty query: presets, then most recent history
+    if query.trim().is_empty() {
+        return presets
+            .iter()
+            .map(|p| preset_suggestion(p, 0, Vec::new()))
+            .chain(history.iter().map(|e| history_suggestion(e, 0, Vec::new())))
+            .take(limit)
+            .collect();
+    }
+
+    let candidates = presets
+        .iter()
+        .map(|p| p.label.as_str())
+        .chain(history.iter().map(|e| e.prompt.as_str()));
+
+    rank(candidates, query, limit)
+        .into_iter()
+        .map(|m| match presets.get(m.index) {
+            Some(preset) => {
+                preset_suggestion(preset, m.score, match_positions(&preset.label, query))
+            }
+            None => {
+                let entry = history[m.index - presets.len()];
+                history_suggestion(entry, m.score, match_positions(&entry.prompt, query))
+            }
+        })
+        .collect()
+}
+
+fn preset_suggestion(preset: &Preset, score: u32, positions: Vec<u32>) -> PromptSuggestion {
+    PromptSuggestion {
+        kind: "preset".to_string(),
+        text: preset.prompt.clone(),
+        label: Some(preset.label.clone()),
+        history_id: None,
+        score,
+        positions,
+    }
+}
+
+fn history_suggestion(entry: &HistoryEntry, score: u32, positions: Vec<u32>) -> PromptSuggestion {
+    PromptSuggestion {
+        kind: "history".to_string(),
+        text: entry.prompt.clone(),
+        label: None,
+        history_id: Some(entry.id),
+        score,
+        positions,
+    }
+}
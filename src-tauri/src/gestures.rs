@@ -0,0 +1,136 @@
+// Trackpad gesture activation, for users who find typed triggers disruptive
+// An NSEvent global monitor sees gestures made over other apps and opens the overlay at the cursor
+
+use crate::error::PromptOsError;
+use block::ConcreteBlock;
+use cocoa::base::{id, nil};
+use objc::msg_send;
+use objc::sel;
+use objc::sel_impl;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::sync::{Mutex, RwLock};
+use tauri::AppHandle;
+
+// NSEventType values; the matching NSEventMask is 1 << type
+const NS_EVENT_TYPE_QUICK_LOOK: u32 = 33;
+const NS_EVENT_TYPE_PRESSURE: u32 = 34;
+// Pressure stage of a force click (1 is an ordinary click)
+const FORCE_CLICK_STAGE: isize = 2;
+
+const SETTINGS_FILE: &str = "gesture_trigger.json";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Gesture {
+    #[default]
+    Off,
+    /// Press harder after clicking
+    ForceClick,
+    /// Three-finger tap, with "Look up & data detectors" set to it in Trackpad settings
+    ThreeFingerTap,
+}
+
+impl Gesture {
+    fn event_type(self) -> Option<u32> {
+        match self {
+            Gesture::Off => None,
+            Gesture::ForceClick => Some(NS_EVENT_TYPE_PRESSURE),
+            Gesture::ThreeFingerTap => Some(NS_EVENT_TYPE_QUICK_LOOK),
+        }
+    }
+}
+
+/// The object NSEvent returned for the installed monitor, needed to remove it
+struct MonitorHandle(id);
+
+// Only added and removed on the main thread
+unsafe impl Send for MonitorHandle {}
+
+static GESTURE: RwLock<Gesture> = RwLock::new(Gesture::Off);
+static MONITOR: Mutex<Option<MonitorHandle>> = Mutex::new(None);
+// Pressure events stream in while the trackpad is pressed; a force click fires once on reaching
+// its stage
+static PRESSURE_STAGE: AtomicIsize = AtomicIsize::new(0);
+
+/// Whether an event the monitor saw completes the gesture
+unsafe fn completes_gesture(gesture: Gesture, event: id) -> bool {
+    match gesture {
+        Gesture::Off => false,
+        Gesture::ForceClick => {
+            let stage: isize = msg_send![event, stage];
+            let previous = PRESSURE_STAGE.swap(stage, Ordering::SeqCst);
+            stage == FORCE_CLICK_STAGE && previous < FORCE_CLICK_STAGE
+        }
+        Gesture::ThreeFingerTap => true,
+    }
+}
+
+/// Replace the global monitor with one for the gesture (main thread only)
+unsafe fn install(app: AppHandle, gesture: Gesture) {
+    let Ok(mut monitor) = MONITOR.lock() else {
+        return;
+    };
+    let Some(cls) = objc::runtime::Class::get("NSEvent") else {
+        return;
+    };
+    if let Some(MonitorHandle(old)) = monitor.take() {
+        let _: () = msg_send![cls, removeMonitor: old];
+    }
+    let Some(event_type) = gesture.event_type() else {
+        return;
+    };
+
+    let handler = ConcreteBlock::new(move |event: id| {
+        if !unsafe { completes_gesture(gesture, event) } {
+            return;
+        }
+        // Off the main thread, like triggers from the event tap
+        let app = app.clone();
+        std::thread::spawn(move || crate::keystroke_monitor::handle_gesture(&app));
+    });
+    let handler = handler.copy();
+
+    let mask: u64 = 1 << event_type;
+    let handle: id = msg_send![cls, addGlobalMonitorForEventsMatchingMask: mask handler: &*handler];
+    if handle == nil {
+        eprintln!("[ERROR] Failed to add the gesture monitor");
+        return;
+    }
+    PRESSURE_STAGE.store(0, Ordering::SeqCst);
+    *monitor = Some(MonitorHandle(handle));
+}
+
+/// Install the monitor for the configured gesture, replacing the one installed before
+pub fn start(app: &AppHandle) {
+    let gesture = GESTURE.read().map(|gesture| *gesture).unwrap_or_default();
+    let handle = app.clone();
+    let _ = app.run_on_main_thread(move || unsafe { install(handle, gesture) });
+}
+
+#[tauri::command]
+pub fn get_gesture_trigger() -> Result<Gesture, PromptOsError> {
+    GESTURE
+        .read()
+        .map(|gesture| *gesture)
+        .map_err(|_| "Failed to lock gesture trigger".into())
+}
+
+/// Switch the gesture that opens the overlay, or turn gesture activation off
+#[tauri::command]
+pub fn set_gesture_trigger(app: AppHandle, gesture: Gesture) -> Result<(), PromptOsError> {
+    apply(gesture)?;
+    start(&app);
+    crate::settings::save(SETTINGS_FILE, &gesture)
+}
+
+fn apply(gesture: Gesture) -> Result<(), PromptOsError> {
+    *GESTURE
+        .write()
+        .map_err(|_| "Failed to lock gesture trigger".to_string())? = gesture;
+    Ok(())
+}
+
+/// Restore the saved gesture (called once during setup, before the monitor is installed)
+pub fn load_settings() {
+    crate::settings::restore(SETTINGS_FILE, apply);
+}
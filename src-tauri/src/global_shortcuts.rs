@@ -0,0 +1,319 @@
+// Wayland keystroke monitor: the xdg-desktop-portal GlobalShortcuts interface over D-Bus
+// Wayland never shows keystrokes to other clients, so triggers are compositor-bound shortcuts
+// The desktop asks the user to confirm (or rebind) them the first time the session is created
+
+use crate::error::PromptOsError;
+use prompt_os_pipeline::{Monitor, Trigger, TriggerSink};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use zbus::blocking::{proxy, Connection, Proxy};
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+const PORTAL_DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const SHORTCUTS_INTERFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+const SESSION_INTERFACE: &str = "org.freedesktop.portal.Session";
+
+// Shortcut ids registered with the portal
+const OPEN_OVERLAY: &str = "open-overlay";
+const QUICK_TRANSFORM: &str = "quick-transform";
+
+// Suggested bindings; the first activation chord replaces the overlay default when configured
+const DEFAULT_OVERLAY_TRIGGER: &str = "CTRL+ALT+slash";
+const QUICK_TRANSFORM_TRIGGER: &str = "CTRL+ALT+r";
+
+// Response codes on org.freedesktop.portal.Request
+const RESPONSE_SUCCESS: u32 = 0;
+const RESPONSE_CANCELLED: u32 = 1;
+
+static NEXT_TOKEN: AtomicU32 = AtomicU32::new(0);
+
+type Results = HashMap<String, OwnedValue>;
+
+/// Whether this is a Wayland session, where X11 RECORD would only see XWayland apps
+pub fn wayland_session() -> bool {
+    std::env::var("XDG_SESSION_TYPE").is_ok_and(|kind| kind.eq_ignore_ascii_case("wayland"))
+        || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+fn dbus_error(e: zbus::Error) -> PromptOsError {
+    format!("D-Bus error: {}", e).into()
+}
+
+/// A fresh handle token; portal tokens must be valid object path elements
+fn next_token() -> String {
+    let n = NEXT_TOKEN.fetch_add(1, Ordering::SeqCst);
+    format!("prompt_os_{}_{}", std::process::id(), n)
+}
+
+fn portal_proxy<'a>(
+    conn: &Connection,
+    path: String,
+    interface: &'static str,
+) -> Result<Proxy<'a>, PromptOsError> {
+    proxy::Builder::new(conn)
+        .destination(PORTAL_DESTINATION)
+        .and_then(|builder| builder.path(path))
+        .and_then(|builder| builder.interface(interface))
+        .map_err(dbus_error)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .map_err(dbus_error)
+}
+
+/// "cmd+shift+space" in the shortcut notation the portal expects ("LOGO+SHIFT+space")
+fn portal_trigger(hotkey: &str) -> String {
+    hotkey
+        .split('+')
+        .map(|part| match part.trim().to_lowercase().as_str() {
+            "ctrl" | "control" => "CTRL".to_string(),
+            "alt" | "opt" | "option" => "ALT".to_string(),
+            "shift" => "SHIFT".to_string(),
+            "cmd" | "command" => "LOGO".to_string(),
+            "return" => "Return".to_string(),
+            "tab" => "Tab".to_string(),
+            "/" => "slash".to_string(),
+            ";" => "semicolon".to_string(),
+            "," => "comma".to_string(),
+            "." => "period".to_string(),
+            key => key.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+/// Call a portal method that answers through a Request object, and wait for its Response
+fn portal_request<B>(
+    conn: &Connection,
+    portal: &Proxy,
+    method: &str,
+    token: &str,
+    body: &B,
+) -> Result<Results, PromptOsError>
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    let sender = conn
+        .unique_name()
+        .ok_or("D-Bus connection has no unique name")?
+        .trim_start_matches(':')
+        .replace('.', "_");
+    let request = portal_proxy(
+        conn,
+        format!("{}/request/{}/{}", PORTAL_PATH, sender, token),
+        REQUEST_INTERFACE,
+    )?;
+
+    // Subscribe before calling so a fast Response isn't missed
+    let mut responses = request.receive_signal("Response").map_err(dbus_error)?;
+    portal.call_method(method, body).map_err(dbus_error)?;
+
+    let message = responses
+        .next()
+        .ok_or_else(|| format!("Portal closed the {} request", method))?;
+    let (code, results): (u32, Results) = message.body().deserialize().map_err(dbus_error)?;
+    match code {
+        RESPONSE_SUCCESS => Ok(results),
+        RESPONSE_CANCELLED => Err(PromptOsError::NoPermission(
+            "Global shortcuts were not allowed".to_string(),
+        )),
+        _ => Err(format!("Portal {} failed", method).into()),
+    }
+}
+
+/// Create a GlobalShortcuts session and bind the app's shortcuts to it
+fn bind_shortcuts(conn: &Connection, portal: &Proxy) -> Result<String, PromptOsError> {
+    let token = next_token();
+    let options = HashMap::from([
+        ("handle_token", Value::from(token.as_str())),
+        ("session_handle_token", Value::from(next_token())),
+    ]);
+    let results = portal_request(conn, portal, "CreateSession", &token, &(options,))?;
+    let session = match results.get("session_handle").map(|value| &**value) {
+        Some(Value::Str(handle)) => handle.to_string(),
+        Some(Value::ObjectPath(handle)) => handle.to_string(),
+        _ => return Err("Portal returned no session handle".into()),
+    };
+
+    let overlay_trigger = crate::keystroke_monitor::activation_chords()
+        .first()
+        .map(|hotkey| portal_trigger(hotkey))
+        .unwrap_or_else(|| DEFAULT_OVERLAY_TRIGGER.to_string());
+    let shortcuts = vec![
+        (
+            OPEN_OVERLAY,
+            HashMap::from([
+                ("description", Value::from("Open the Prompt OS overlay")),
+                ("preferred_trigger", Value::from(overlay_trigger)),
+            ]),
+        ),
+        (
+            QUICK_TRANSFORM,
+            HashMap::from([
+                ("description", Value::from("Rewrite the selection")),
+                ("preferred_trigger", Value::from(QUICK_TRANSFORM_TRIGGER)),
+            ]),
+        ),
+    ];
+
+    let token = next_token();
+    let session_path = ObjectPath::try_from(session.as_str()).map_err(|e| e.to_string())?;
+    let options = HashMap::from([("handle_token", Value::from(token.as_str()))]);
+    portal_request(
+        conn,
+        portal,
+        "BindShortcuts",
+        &token,
+        &(session_path, shortcuts, "", options),
+    )?;
+    Ok(session)
+}
+
+/// The listener thread and the connection it reads from
+struct MonitorActor {
+    connection: Connection,
+    session: String,
+    stopping: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl MonitorActor {
+    /// Bind the shortcuts on a new thread, returning once they are bound (or failed)
+    fn spawn(sink: TriggerSink) -> Result<Self, PromptOsError> {
+        let stopping = Arc::new(AtomicBool::new(false));
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        let thread_stopping = stopping.clone();
+        let thread = std::thread::spawn(move || run_listener(sink, thread_stopping, ready_tx));
+
+        match ready_rx.recv() {
+            Ok(Ok((connection, session))) => Ok(Self {
+                connection,
+                session,
+                stopping,
+                thread,
+            }),
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => Err("Global shortcuts thread exited during startup".into()),
+        }
+    }
+
+    /// Close the portal session and the connection, which ends the listener's signal stream
+    fn shutdown(self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        match portal_proxy(&self.connection, self.session.clone(), SESSION_INTERFACE) {
+            Ok(session) => {
+                if let Err(e) = session.call_method("Close", &()) {
+                    eprintln!("[ERROR] Failed to close shortcuts session: {}", e);
+                }
+            }
+            Err(e) => eprintln!("[ERROR] Failed to close shortcuts session: {}", e),
+        }
+        if let Err(e) = self.connection.close() {
+            eprintln!("[ERROR] Failed to close D-Bus connection: {}", e);
+        }
+        if self.thread.join().is_err() {
+            eprintln!("[ERROR] Global shortcuts thread panicked");
+        }
+    }
+}
+
+fn run_listener(
+    sink: TriggerSink,
+    stopping: Arc<AtomicBool>,
+    ready: mpsc::Sender<Result<(Connection, String), PromptOsError>>,
+) {
+    eprintln!("[DEBUG] Global shortcuts thread started");
+
+    let setup = Connection::session().map_err(dbus_error).and_then(|conn| {
+        let portal = portal_proxy(&conn, PORTAL_PATH.to_string(), SHORTCUTS_INTERFACE)?;
+        let activations = portal.receive_signal("Activated").map_err(dbus_error)?;
+        let session = bind_shortcuts(&conn, &portal)?;
+        Ok((conn, session, activations))
+    });
+    let (conn, session, activations) = match setup {
+        Ok(setup) => setup,
+        Err(e) => {
+            eprintln!("[ERROR] Failed to bind global shortcuts: {}", e);
+            let _ = ready.send(Err(e));
+            return;
+        }
+    };
+
+    let _ = ready.send(Ok((conn.clone(), session.clone())));
+    eprintln!("[DEBUG] Global shortcuts bound, waiting for activations...");
+
+    for message in activations {
+        if stopping.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok((handle, id, _timestamp, _options)) =
+            message
+                .body()
+                .deserialize::<(OwnedObjectPath, String, u64, Results)>()
+        else {
+            continue;
+        };
+        if handle.as_str() != session {
+            continue;
+        }
+
+        let trigger = match id.as_str() {
+            OPEN_OVERLAY => Trigger::Slash,
+            QUICK_TRANSFORM => Trigger::QuickTransform,
+            _ => continue,
+        };
+        // The compositor already consumed the shortcut, so there is nothing to swallow
+        sink(trigger);
+    }
+
+    eprintln!("[DEBUG] Global shortcuts listener stopped");
+}
+
+/// The GlobalShortcuts portal behind the pipeline's Monitor trait
+#[derive(Default)]
+pub struct GlobalShortcutsMonitor {
+    actor: Mutex<Option<MonitorActor>>,
+}
+
+impl Monitor for GlobalShortcutsMonitor {
+    /// Starting again replaces the running session
+    fn start(&self, sink: TriggerSink) -> Result<(), PromptOsError> {
+        let mut guard = self
+            .actor
+            .lock()
+            .map_err(|_| "Failed to lock global shortcuts".to_string())?;
+        if let Some(actor) = guard.take() {
+            actor.shutdown();
+        }
+        *guard = Some(MonitorActor::spawn(sink)?);
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), PromptOsError> {
+        let actor = self
+            .actor
+            .lock()
+            .map_err(|_| "Failed to lock global shortcuts".to_string())?
+            .take();
+        if let Some(actor) = actor {
+            actor.shutdown();
+        }
+        Ok(())
+    }
+
+    fn died(&self) -> bool {
+        self.actor.lock().is_ok_and(|actor| {
+            actor
+                .as_ref()
+                .is_some_and(|actor| actor.thread.is_finished())
+        })
+    }
+}
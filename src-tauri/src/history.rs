@@ -0,0 +1,172 @@
+// Prompt history of accepted outputs
+// Persisted as JSON lines in the app data directory, cached in memory
+
+use crate::error::PromptOsError;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+// On battery, appends are batched; this many pending entries forces a write anyway
+const MAX_DEFERRED_WRITES: usize = 20;
+// ...and are written at least this often, so a crash loses little
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub id: u64,
+    /// RFC 3339 local timestamp
+    pub timestamp: String,
+    pub prompt: String,
+    pub response: String,
+    pub app_bundle_id: Option<String>,
+    pub app_name: Option<String>,
+}
+
+struct HistoryStore {
+    path: PathBuf,
+    entries: Vec<HistoryEntry>,
+    /// Entries not yet appended to disk
+    pending: Vec<HistoryEntry>,
+}
+
+static HISTORY: Mutex<Option<HistoryStore>> = Mutex::new(None);
+
+/// Load history from the app data directory (called once during setup)
+pub fn init(data_dir: PathBuf) -> Result<(), PromptOsError> {
+    fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+
+    let path = data_dir.join(HISTORY_FILE);
+    let entries = match File::open(&path) {
+        Ok(file) => BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    eprintln!("[DEBUG] Loaded {} history entries", entries.len());
+    *HISTORY
+        .lock()
+        .map_err(|_| "Failed to lock history".to_string())? = Some(HistoryStore {
+        path,
+        entries,
+        pending: Vec::new(),
+    });
+    Ok(())
+}
+
+/// Run a closure over the in-memory history
+pub fn with_entries<T>(f: impl FnOnce(&[HistoryEntry]) -> T) -> Result<T, PromptOsError> {
+    let guard = HISTORY
+        .lock()
+        .map_err(|_| "Failed to lock history".to_string())?;
+    let store = guard.as_ref().ok_or("History not initialized")?;
+    Ok(f(&store.entries))
+}
+
+/// Record an accepted output against the app it was written for
+#[tauri::command]
+pub fn record_history_entry(
+    prompt: String,
+    response: String,
+) -> Result<HistoryEntry, PromptOsError> {
+    let app = crate::context::target_app();
+    let now = chrono::Local::now();
+
+    let mut guard = HISTORY
+        .lock()
+        .map_err(|_| "Failed to lock history".to_string())?;
+    let store = guard.as_mut().ok_or("History not initialized")?;
+
+    let entry = HistoryEntry {
+        id: store.entries.last().map(|e| e.id + 1).unwrap_or(1),
+        timestamp: now.to_rfc3339(),
+        prompt,
+        response,
+        app_bundle_id: app.as_ref().and_then(|app| app.bundle_id.clone()),
+        app_name: app.as_ref().and_then(|app| app.name.clone()),
+    };
+
+    store.entries.push(entry.clone());
+    store.pending.push(entry.clone());
+
+    // Batch disk writes while on battery
+    if !crate::power::throttled() || store.pending.len() >= MAX_DEFERRED_WRITES {
+        write_pending(store)?;
+    }
+    Ok(entry)
+}
+
+fn write_pending(store: &mut HistoryStore) -> Result<(), PromptOsError> {
+    if store.pending.is_empty() {
+        return Ok(());
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&store.path)
+        .map_err(|e| format!("Failed to open history file: {}", e))?;
+    for entry in &store.pending {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| format!("Failed to serialize history entry: {}", e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write history: {}", e))?;
+    }
+
+    store.pending.clear();
+    Ok(())
+}
+
+/// Write any deferred entries to disk (called on a timer and before quitting)
+pub fn flush() -> Result<(), PromptOsError> {
+    let mut guard = HISTORY
+        .lock()
+        .map_err(|_| "Failed to lock history".to_string())?;
+    match guard.as_mut() {
+        Some(store) => write_pending(store),
+        None => Ok(()),
+    }
+}
+
+/// Write deferred entries every FLUSH_INTERVAL (called once during setup)
+pub fn start() {
+    std::thread::spawn(|| loop {
+        std::thread::sleep(FLUSH_INTERVAL);
+        if let Err(e) = flush() {
+            eprintln!("[ERROR] Failed to flush history: {}", e);
+        }
+    });
+}
+
+/// Most recent entries first
+#[tauri::command]
+pub fn get_history(limit: Option<usize>) -> Result<Vec<HistoryEntry>, PromptOsError> {
+    with_entries(|entries| {
+        entries
+            .iter()
+            .rev()
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    })
+}
+
+#[tauri::command]
+pub fn clear_history() -> Result<(), PromptOsError> {
+    let mut guard = HISTORY
+        .lock()
+        .map_err(|_| "Failed to lock history".to_string())?;
+    let store = guard.as_mut().ok_or("History not initialized")?;
+
+    if store.path.exists() {
+        fs::remove_file(&store.path).map_err(|e| format!("Failed to delete history: {}", e))?;
+    }
+    store.entries.clear();
+    store.pending.clear();
+    Ok(())
+}
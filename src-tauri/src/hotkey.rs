@@ -0,0 +1,155 @@
+// Key chord parsing shared by user actions and chord activation
+// Turns "cmd+shift+space" into the keycode and CGEventFlags the event tap sees
+
+use crate::error::PromptOsError;
+pub use prompt_os_pipeline::Chord;
+
+// CGEventFlags, as reported by the keystroke monitor
+pub(crate) const FLAG_SHIFT: u64 = 0x0002_0000;
+pub(crate) const FLAG_CONTROL: u64 = 0x0004_0000;
+pub(crate) const FLAG_ALTERNATE: u64 = 0x0008_0000;
+pub(crate) const FLAG_COMMAND: u64 = 0x0010_0000;
+// Fn, or Globe; also set on arrow and function keys
+#[cfg(target_os = "macos")]
+pub(crate) const FLAG_FN: u64 = 0x0080_0000;
+// Caps Lock remapped to every modifier at once (Karabiner and similar)
+pub(crate) const FLAG_HYPER: u64 = FLAG_CONTROL | FLAG_ALTERNATE | FLAG_SHIFT | FLAG_COMMAND;
+
+/// Ctrl+Option+R ("rewrite"), the built-in quick transform; reserved for it
+pub const QUICK_TRANSFORM: Chord = Chord {
+    keycode: 0x0F,
+    modifiers: FLAG_CONTROL | FLAG_ALTERNATE,
+};
+
+// ANSI virtual keycodes for the keys a chord may use; the event tap maps other layouts onto them
+const KEYCODES: &[(&str, i64)] = &[
+    ("a", 0x00),
+    ("s", 0x01),
+    ("d", 0x02),
+    ("f", 0x03),
+    ("h", 0x04),
+    ("g", 0x05),
+    ("z", 0x06),
+    ("x", 0x07),
+    ("c", 0x08),
+    ("v", 0x09),
+    ("b", 0x0B),
+    ("q", 0x0C),
+    ("w", 0x0D),
+    ("e", 0x0E),
+    ("r", 0x0F),
+    ("y", 0x10),
+    ("t", 0x11),
+    ("1", 0x12),
+    ("2", 0x13),
+    ("3", 0x14),
+    ("4", 0x15),
+    ("6", 0x16),
+    ("5", 0x17),
+    ("9", 0x19),
+    ("7", 0x1A),
+    ("8", 0x1C),
+    ("0", 0x1D),
+    ("o", 0x1F),
+    ("u", 0x20),
+    ("i", 0x22),
+    ("p", 0x23),
+    ("l", 0x25),
+    ("j", 0x26),
+    ("k", 0x28),
+    (";", 0x29),
+    (",", 0x2B),
+    ("/", 0x2C),
+    ("n", 0x2D),
+    ("m", 0x2E),
+    (".", 0x2F),
+    ("return", 0x24),
+    ("tab", 0x30),
+    ("space", 0x31),
+];
+
+/// Keycode of a single key by name, e.g. "/" or "space"
+pub fn keycode(key: &str) -> Option<i64> {
+    KEYCODES
+        .iter()
+        .find(|(name, _)| *name == key)
+        .map(|(_, code)| *code)
+}
+
+/// Name of the key with this keycode, the reverse of keycode
+#[cfg(target_os = "macos")]
+pub fn key_name(keycode: i64) -> Option<&'static str> {
+    KEYCODES
+        .iter()
+        .find(|(_, code)| *code == keycode)
+        .map(|(name, _)| *name)
+}
+
+/// Names of the modifiers set in CGEventFlags, in chord order, e.g. ["ctrl", "cmd"]
+#[cfg(target_os = "macos")]
+pub fn modifier_names(flags: u64) -> Vec<&'static str> {
+    [
+        (FLAG_CONTROL, "ctrl"),
+        (FLAG_ALTERNATE, "alt"),
+        (FLAG_SHIFT, "shift"),
+        (FLAG_COMMAND, "cmd"),
+        (FLAG_FN, "fn"),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| flags & flag != 0)
+    .map(|(_, name)| name)
+    .collect()
+}
+
+/// Parse "ctrl+alt+p" style chords; at least one of Control, Option, or Command is required
+pub fn parse(hotkey: &str) -> Result<Chord, PromptOsError> {
+    let mut modifiers = 0;
+    let mut key_code = None;
+
+    for part in hotkey.split('+').map(|part| part.trim().to_lowercase()) {
+        match part.as_str() {
+            "ctrl" | "control" => modifiers |= FLAG_CONTROL,
+            "alt" | "opt" | "option" => modifiers |= FLAG_ALTERNATE,
+            "shift" => modifiers |= FLAG_SHIFT,
+            "cmd" | "command" => modifiers |= FLAG_COMMAND,
+            "hyper" => modifiers |= FLAG_HYPER,
+            key => {
+                let code = keycode(key);
+                if key_code.is_some() || code.is_none() {
+                    return Err(format!("Unsupported hotkey \"{}\"", hotkey).into());
+                }
+                key_code = code;
+            }
+        }
+    }
+
+    let keycode = key_code.ok_or_else(|| format!("Hotkey \"{}\" has no key", hotkey))?;
+    if modifiers & (FLAG_CONTROL | FLAG_ALTERNATE | FLAG_COMMAND) == 0 {
+        return Err(format!("Hotkey \"{}\" needs Control, Option, or Command", hotkey).into());
+    }
+
+    let chord = Chord { keycode, modifiers };
+    if chord == QUICK_TRANSFORM {
+        return Err(format!("Hotkey \"{}\" is reserved for the quick transform", hotkey).into());
+    }
+    Ok(chord)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hotkeys_need_a_key_and_a_command_modifier() {
+        let chord = parse("Ctrl + Alt + P").unwrap();
+        assert_eq!(chord, parse("alt+ctrl+p").unwrap());
+        assert_eq!(chord.keycode, keycode("p").unwrap());
+
+        assert!(parse("shift+p").is_err());
+        assert!(parse("ctrl+alt").is_err());
+        assert!(parse("ctrl+p+q").is_err());
+        assert!(parse("ctrl+f13").is_err());
+        // Reserved for the quick transform
+        assert!(parse("ctrl+alt+r").is_err());
+    }
+}
@@ -0,0 +1,207 @@
+// Undo for the last injection into each field
+// Remembers where a response landed and what it replaced, so an accidental insertion can be reverted
+
+use crate::error::PromptOsError;
+use crate::permissions::{require, Permission};
+use crate::text_field_detector::{
+    copy_focused_element, copy_string_attribute, select_range, selection_range, string_for_range,
+};
+use accessibility_sys::AXUIElementRef;
+use core_foundation::base::{CFEqual, CFRetain, CFTypeRef};
+use core_graphics::event::{CGEvent, CGEventFlags, CGEventTapLocation, CGKeyCode};
+use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+use std::sync::Mutex;
+
+// Fields whose last injection can still be undone; older ones are forgotten
+const MAX_TRACKED_FIELDS: usize = 10;
+const Z_KEYCODE: CGKeyCode = 0x06;
+
+/// How the text got in, and so how it comes back out
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum InjectionMethod {
+    /// Written through AXSelectedText; undone by selecting it and writing the old text back
+    Accessibility,
+    /// Pasted or typed; the app's own Cmd+Z undoes it
+    Keystrokes,
+}
+
+/// The field's selection just before an injection
+pub(crate) struct Snapshot {
+    /// UTF-16 offset
+    location: usize,
+    replaced: String,
+}
+
+/// The last injection into a field (retained AXUIElement, stored as usize for thread safety)
+struct Injection {
+    element: usize,
+    pid: i32,
+    location: usize,
+    inserted: String,
+    replaced: String,
+    method: InjectionMethod,
+}
+
+impl Drop for Injection {
+    fn drop(&mut self) {
+        unsafe { core_foundation::base::CFRelease(self.element as CFTypeRef) };
+    }
+}
+
+static INJECTIONS: Mutex<Vec<Injection>> = Mutex::new(Vec::new());
+
+/// Read what an injection into the element is about to replace; None when it has no readable
+/// selection, and the injection then can't be undone
+pub(crate) unsafe fn snapshot(element: AXUIElementRef) -> Option<Snapshot> {
+    let (location, length) = selection_range(element)?;
+    let replaced = if length == 0 {
+        String::new()
+    } else {
+        copy_string_attribute(element, "AXSelectedText")
+            .or_else(|| text_in_range(element, location, length))?
+    };
+    Some(Snapshot { location, replaced })
+}
+
+/// Remember an injection into the element as the one to undo there
+///
+/// Text written right after the previous injection (a streamed response's next batch) extends it,
+/// so undo takes the whole response back out.
+pub(crate) unsafe fn record(
+    element: AXUIElementRef,
+    pid: i32,
+    snapshot: Snapshot,
+    inserted: &str,
+    method: InjectionMethod,
+) {
+    let Ok(mut injections) = INJECTIONS.lock() else {
+        return;
+    };
+    let previous = injections
+        .iter()
+        .position(|injection| CFEqual(injection.element as CFTypeRef, element as CFTypeRef) != 0)
+        .map(|index| injections.remove(index));
+
+    if let Some(mut previous) = previous {
+        let end = previous.location + previous.inserted.encode_utf16().count();
+        if previous.method == method && snapshot.replaced.is_empty() && snapshot.location == end {
+            previous.inserted.push_str(inserted);
+            injections.push(previous);
+            return;
+        }
+    }
+
+    injections.push(Injection {
+        element: CFRetain(element as CFTypeRef) as usize,
+        pid,
+        location: snapshot.location,
+        inserted: inserted.to_string(),
+        replaced: snapshot.replaced,
+        method,
+    });
+    if injections.len() > MAX_TRACKED_FIELDS {
+        injections.remove(0);
+    }
+}
+
+/// Point the element's last injection at text written over it in place (a streamed response the
+/// content filter corrected), so undo takes out what's actually there
+pub(crate) unsafe fn replace_inserted(element: AXUIElementRef, location: usize, inserted: &str) {
+    let Ok(mut injections) = INJECTIONS.lock() else {
+        return;
+    };
+    let injection = injections.iter_mut().find(|injection| {
+        injection.location == location
+            && CFEqual(injection.element as CFTypeRef, element as CFTypeRef) != 0
+    });
+    if let Some(injection) = injection {
+        injection.inserted = inserted.to_string();
+    }
+}
+
+/// Revert the last injection into the focused field of the app being written to, as long as the
+/// inserted text is still there untouched
+#[tauri::command]
+pub fn undo_last_injection() -> Result<(), PromptOsError> {
+    require(Permission::Accessibility)?;
+    if crate::secure_input::enabled() {
+        return Err(PromptOsError::SecureField);
+    }
+    let app = crate::context::write_target_app().ok_or(PromptOsError::NoFocus)?;
+
+    let mut injections = INJECTIONS
+        .lock()
+        .map_err(|_| "Failed to lock injections".to_string())?;
+    unsafe {
+        let focused = copy_focused_element(Some(app.pid)).ok_or(PromptOsError::NoFocus)?;
+        let index = injections
+            .iter()
+            .position(|injection| CFEqual(injection.element as CFTypeRef, focused) != 0);
+        core_foundation::base::CFRelease(focused);
+        let index = index.ok_or("Nothing Prompt OS inserted into this field is left to undo")?;
+
+        let injection = &injections[index];
+        let element = injection.element as AXUIElementRef;
+        let length = injection.inserted.encode_utf16().count();
+
+        // Anything typed or edited since would be undone (or overwritten) along with it
+        let current = text_in_range(element, injection.location, length);
+        if current.as_deref() != Some(injection.inserted.as_str()) {
+            injections.remove(index);
+            return Err("The field has changed since the text was inserted; undo it there".into());
+        }
+
+        let result = match injection.method {
+            InjectionMethod::Accessibility => {
+                if !select_range(element, injection.location, length) {
+                    return Err("Failed to select the inserted text".into());
+                }
+                crate::text_injector::set_selected_text(element, &injection.replaced)
+            }
+            InjectionMethod::Keystrokes => {
+                // Cmd+Z goes to the frontmost app, which must be the one pasted into
+                let frontmost = crate::frontmost_app::frontmost_app().map(|app| app.pid);
+                if frontmost != Some(injection.pid) {
+                    return Err("Switch back to the field to undo what was pasted into it".into());
+                }
+                post_undo()
+            }
+        };
+        if result.is_ok() {
+            injections.remove(index);
+        }
+        result
+    }
+}
+
+/// Part of the element's value by UTF-16 range, sliced out of AXValue in apps without
+/// AXStringForRange
+pub(crate) unsafe fn text_in_range(
+    element: AXUIElementRef,
+    location: usize,
+    length: usize,
+) -> Option<String> {
+    string_for_range(element, location, length).or_else(|| {
+        let value: Vec<u16> = copy_string_attribute(element, "AXValue")?
+            .encode_utf16()
+            .collect();
+        value
+            .get(location..location + length)
+            .map(String::from_utf16_lossy)
+    })
+}
+
+/// Press Cmd+Z in the frontmost app
+fn post_undo() -> Result<(), PromptOsError> {
+    let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState)
+        .map_err(|_| "Failed to create event source".to_string())?;
+    for key_down in [true, false] {
+        let event = CGEvent::new_keyboard_event(source.clone(), Z_KEYCODE, key_down)
+            .map_err(|_| "Failed to create keyboard event".to_string())?;
+        event.set_flags(CGEventFlags::CGEventFlagCommand);
+        // The trigger monitor must not react to our own Cmd+Z
+        crate::event_tap::mark_synthetic(&event);
+        event.post(CGEventTapLocation::HID);
+    }
+    Ok(())
+}
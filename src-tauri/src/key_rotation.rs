@@ -0,0 +1,193 @@
+// Rotation and failover across stored API keys
+// Orders keys per request (round-robin or primary-first) and tracks per-key usage
+
+use crate::error::PromptOsError;
+use crate::keychain::StoredKey;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// A key that hit a 429 or 5xx is tried last until this passes
+const COOLDOWN: Duration = Duration::from_secs(60);
+
+const SETTINGS_FILE: &str = "key_rotation.json";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationMode {
+    /// Spread requests evenly across keys
+    RoundRobin,
+    /// Always start with the primary key; others only after a 429 or 5xx
+    Failover,
+}
+
+#[derive(serde::Serialize, Clone, Default)]
+pub struct KeyUsage {
+    pub requests: u64,
+    pub successes: u64,
+    pub rate_limited: u64,
+    pub server_errors: u64,
+    pub other_errors: u64,
+    /// RFC 3339
+    pub last_used: Option<String>,
+    pub cooling_down: bool,
+}
+
+#[derive(Default)]
+struct KeyState {
+    usage: KeyUsage,
+    cooldown_until: Option<Instant>,
+}
+
+struct RotationState {
+    mode: RotationMode,
+    cursor: usize,
+    keys: BTreeMap<String, KeyState>,
+}
+
+static STATE: Mutex<RotationState> = Mutex::new(RotationState {
+    mode: RotationMode::RoundRobin,
+    cursor: 0,
+    keys: BTreeMap::new(),
+});
+
+/// 429 and 5xx responses move on to the next key
+pub fn should_fail_over(status: u16) -> bool {
+    status == 429 || status >= 500
+}
+
+/// Keys to try for one request, in order; cooling-down keys go last
+pub fn candidates() -> Result<Vec<StoredKey>, PromptOsError> {
+    let mut keys = crate::keychain::all_keys()?;
+    if keys.is_empty() {
+        return Err(PromptOsError::ProviderAuth(
+            "No API key configured. Add it in Settings.".to_string(),
+        ));
+    }
+
+    let mut state = STATE
+        .lock()
+        .map_err(|_| "Failed to lock key rotation".to_string())?;
+
+    if state.mode == RotationMode::RoundRobin {
+        let start = state.cursor % keys.len();
+        keys.rotate_left(start);
+        state.cursor = state.cursor.wrapping_add(1);
+    }
+
+    let now = Instant::now();
+    let cooling = |key: &StoredKey| {
+        state
+            .keys
+            .get(&key.id)
+            .and_then(|entry| entry.cooldown_until)
+            .is_some_and(|until| until > now)
+    };
+    // Stable sort keeps the rotation order within each group
+    keys.sort_by_key(cooling);
+    Ok(keys)
+}
+
+/// Record the outcome of a request made with a key (None: no response at all)
+pub fn record(id: &str, status: Option<u16>) {
+    let Ok(mut state) = STATE.lock() else {
+        return;
+    };
+
+    let entry = state.keys.entry(id.to_string()).or_default();
+    entry.usage.requests += 1;
+    entry.usage.last_used = Some(chrono::Utc::now().to_rfc3339());
+
+    match status {
+        Some(status) if (200..300).contains(&status) => {
+            entry.usage.successes += 1;
+            entry.cooldown_until = None;
+        }
+        Some(429) => {
+            entry.usage.rate_limited += 1;
+            entry.cooldown_until = Some(Instant::now() + COOLDOWN);
+        }
+        Some(status) if status >= 500 => {
+            entry.usage.server_errors += 1;
+            entry.cooldown_until = Some(Instant::now() + COOLDOWN);
+        }
+        _ => entry.usage.other_errors += 1,
+    }
+}
+
+/// Usage counters for one key
+pub fn usage(id: &str) -> KeyUsage {
+    let Ok(state) = STATE.lock() else {
+        return KeyUsage::default();
+    };
+
+    state
+        .keys
+        .get(id)
+        .map(|entry| KeyUsage {
+            cooling_down: entry
+                .cooldown_until
+                .is_some_and(|until| until > Instant::now()),
+            ..entry.usage.clone()
+        })
+        .unwrap_or_default()
+}
+
+/// Drop the counters for a removed key
+pub fn forget(id: &str) {
+    if let Ok(mut state) = STATE.lock() {
+        state.keys.remove(id);
+    }
+}
+
+#[tauri::command]
+pub fn get_key_rotation_mode() -> Result<RotationMode, PromptOsError> {
+    let state = STATE
+        .lock()
+        .map_err(|_| "Failed to lock key rotation".to_string())?;
+    Ok(state.mode)
+}
+
+#[tauri::command]
+pub fn set_key_rotation_mode(mode: RotationMode) -> Result<(), PromptOsError> {
+    apply(mode)?;
+    crate::settings::save(SETTINGS_FILE, &mode)
+}
+
+fn apply(mode: RotationMode) -> Result<(), PromptOsError> {
+    let mut state = STATE
+        .lock()
+        .map_err(|_| "Failed to lock key rotation".to_string())?;
+    state.mode = mode;
+    state.cursor = 0;
+    Ok(())
+}
+
+/// Restore the saved rotation mode (called once during setup)
+pub fn load_settings() {
+    crate::settings::restore(SETTINGS_FILE, apply);
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct RotatingKey {
+    pub id: String,
+    pub key: String,
+}
+
+/// Ordered keys for the webview's streaming client, which reports back via report_api_key_result
+#[tauri::command]
+pub fn acquire_api_keys() -> Result<Vec<RotatingKey>, PromptOsError> {
+    Ok(candidates()?
+        .into_iter()
+        .map(|stored| RotatingKey {
+            id: stored.id,
+            key: stored.key,
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn report_api_key_result(id: String, status: Option<u16>) -> Result<(), PromptOsError> {
+    record(&id, status);
+    Ok(())
+}
@@ -0,0 +1,425 @@
+// Windows keystroke monitor: a low-level keyboard hook (WH_KEYBOARD_LL) on its own thread
+// Mirrors event_tap: typed trigger sequences and the quick-transform hotkey (Ctrl+Alt+R)
+// Chord activation and action hotkeys use macOS keycodes and are not matched here yet
+
+use crate::error::PromptOsError;
+use crate::keystroke_monitor;
+use prompt_os_pipeline::{KeyPress, Monitor, Trigger, TriggerMatcher, TriggerSink, TypedKey};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Instant;
+use windows_sys::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows_sys::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows_sys::Win32::System::SystemInformation::GetTickCount;
+use windows_sys::Win32::System::Threading::GetCurrentThreadId;
+use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+    GetKeyState, GetKeyboardState, SendInput, ToUnicode, INPUT, INPUT_0, INPUT_KEYBOARD,
+    KEYBDINPUT, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE, VK_BACK, VK_CONTROL, VK_DELETE, VK_DOWN,
+    VK_END, VK_ESCAPE, VK_HOME, VK_LEFT, VK_LWIN, VK_MENU, VK_NEXT, VK_PRIOR, VK_RETURN, VK_RIGHT,
+    VK_RWIN, VK_SHIFT, VK_TAB, VK_UP,
+};
+use windows_sys::Win32::UI::Input::{RegisterRawInputDevices, RAWINPUTDEVICE, RIDEV_INPUTSINK};
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+    CallNextHookEx, CreateWindowExW, DestroyWindow, DispatchMessageW, GetMessageW, PeekMessageW,
+    PostThreadMessageW, SetWindowsHookExW, UnhookWindowsHookEx, HWND_MESSAGE, KBDLLHOOKSTRUCT,
+    LLKHF_INJECTED, MSG, PM_NOREMOVE, WH_KEYBOARD_LL, WM_INPUT, WM_KEYDOWN, WM_QUIT, WM_SYSKEYDOWN,
+    WM_USER,
+};
+
+// Quick-transform hotkey: Ctrl+Alt+R, matching Ctrl+Option+R on macOS
+const VK_R: u16 = 0x52;
+
+// Keys that move the caret or leave the field, so earlier keystrokes no longer lead up to it
+const RESET_KEYS: &[u16] = &[
+    VK_RETURN, VK_TAB, VK_ESCAPE, VK_HOME, VK_END, VK_PRIOR, VK_NEXT, VK_DELETE, VK_LEFT, VK_RIGHT,
+    VK_UP, VK_DOWN,
+];
+
+// ToUnicode flag: don't disturb dead-key state for the focused app
+const TO_UNICODE_NO_STATE_CHANGE: u32 = 0x4;
+
+// Raw input usage for keyboards (generic desktop page)
+const HID_USAGE_PAGE_GENERIC: u16 = 0x01;
+const HID_USAGE_GENERIC_KEYBOARD: u16 = 0x06;
+// Windows removes a hook that doesn't return in time without telling us; keyboard input this long
+// after the hook last saw any means it is gone
+const HOOK_SILENCE_MS: i32 = 1000;
+
+thread_local! {
+    // Low-level hooks run on the installing thread, so neither needs a lock
+    static WORKER: RefCell<Option<mpsc::Sender<PendingTrigger>>> = const { RefCell::new(None) };
+    static MATCHER: RefCell<TriggerMatcher> = RefCell::new(TriggerMatcher::default());
+}
+
+// Tick counts of the last key event the hook saw and the last one raw input saw
+static LAST_HOOK_EVENT: AtomicU32 = AtomicU32::new(0);
+static LAST_RAW_INPUT: AtomicU32 = AtomicU32::new(0);
+
+/// A trigger the hook matched, for the worker thread to hand to the sink
+struct PendingTrigger {
+    trigger: Trigger,
+    /// Characters to erase once the sink takes it
+    typed_len: usize,
+    /// The swallowed key, given back to the field if the sink turns the trigger down
+    swallowed: Option<TypedKey>,
+}
+
+fn key_down(vk: u16) -> bool {
+    unsafe { GetKeyState(vk as i32) < 0 }
+}
+
+/// Classify a key-down for the trigger matcher
+unsafe fn typed_key(info: &KBDLLHOOKSTRUCT) -> TypedKey {
+    let vk = info.vkCode as u16;
+
+    // Shortcuts and navigation aren't typing
+    let shortcut =
+        (key_down(VK_CONTROL) && !key_down(VK_MENU)) || key_down(VK_LWIN) || key_down(VK_RWIN);
+    if shortcut {
+        return TypedKey::Reset;
+    }
+    match vk {
+        VK_RETURN => return TypedKey::Return,
+        VK_BACK => return TypedKey::Backspace,
+        _ if RESET_KEYS.contains(&vk) => return TypedKey::Reset,
+        _ => {}
+    }
+
+    let mut state = [0u8; 256];
+    GetKeyboardState(state.as_mut_ptr());
+    // The hook runs before the system updates its own state for this key
+    for modifier in [VK_SHIFT, VK_CONTROL, VK_MENU] {
+        state[modifier as usize] = if key_down(modifier) { 0x80 } else { 0 };
+    }
+
+    let mut buf = [0u16; 4];
+    let len = ToUnicode(
+        info.vkCode,
+        info.scanCode,
+        state.as_ptr(),
+        buf.as_mut_ptr(),
+        buf.len() as i32,
+        TO_UNICODE_NO_STATE_CHANGE,
+    );
+    TypedKey::Text(String::from_utf16_lossy(&buf[..len.max(0) as usize]))
+}
+
+fn key_input(vk: u16, scan: u16, flags: u32) -> INPUT {
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: scan,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    }
+}
+
+fn send_inputs(inputs: &[INPUT]) {
+    unsafe {
+        SendInput(
+            inputs.len() as u32,
+            inputs.as_ptr(),
+            std::mem::size_of::<INPUT>() as i32,
+        );
+    }
+}
+
+/// Delete the sequence and command, or the abbreviation, typed before the key that ended them
+fn erase_typed(count: usize) {
+    let inputs: Vec<INPUT> = (0..count)
+        .flat_map(|_| {
+            [
+                key_input(VK_BACK, 0, 0),
+                key_input(VK_BACK, 0, KEYEVENTF_KEYUP),
+            ]
+        })
+        .collect();
+    send_inputs(&inputs);
+}
+
+/// Type a swallowed key after all (the hook lets injected input through)
+fn give_back(key: TypedKey) {
+    let inputs: Vec<INPUT> = match key {
+        TypedKey::Text(text) => text
+            .encode_utf16()
+            .flat_map(|unit| {
+                [
+                    key_input(0, unit, KEYEVENTF_UNICODE),
+                    key_input(0, unit, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP),
+                ]
+            })
+            .collect(),
+        TypedKey::Return => vec![
+            key_input(VK_RETURN, 0, 0),
+            key_input(VK_RETURN, 0, KEYEVENTF_KEYUP),
+        ],
+        TypedKey::Backspace | TypedKey::Reset => Vec::new(),
+    };
+    send_inputs(&inputs);
+}
+
+/// Hand triggers to the sink off the hook thread, until the hook thread drops its sender
+fn deliver_triggers(sink: TriggerSink, pending: mpsc::Receiver<PendingTrigger>) {
+    for PendingTrigger {
+        trigger,
+        typed_len,
+        swallowed,
+    } in pending
+    {
+        if sink(trigger) {
+            if typed_len > 0 {
+                erase_typed(typed_len);
+            }
+        } else if let Some(key) = swallowed {
+            give_back(key);
+        }
+    }
+}
+
+unsafe extern "system" fn keyboard_hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let message = wparam as u32;
+    // Any key event shows the hook is still installed
+    if code >= 0 {
+        let info = &*(lparam as *const KBDLLHOOKSTRUCT);
+        LAST_HOOK_EVENT.store(info.time, Ordering::Relaxed);
+    }
+    if code >= 0 && (message == WM_KEYDOWN || message == WM_SYSKEYDOWN) {
+        let info = &*(lparam as *const KBDLLHOOKSTRUCT);
+
+        // Our own backspaces (and other synthetic input) pass straight through
+        if info.flags & LLKHF_INJECTED == 0 {
+            let quick_transform = info.vkCode as u16 == VK_R
+                && key_down(VK_CONTROL)
+                && key_down(VK_MENU)
+                && !key_down(VK_SHIFT);
+
+            let mut typed_len = 0;
+            let mut typed = None;
+            let trigger = if quick_transform {
+                Some(Trigger::QuickTransform)
+            } else {
+                let key = typed_key(info);
+                typed = Some(key.clone());
+                let press = KeyPress {
+                    key,
+                    chord: None,
+                    repeat: false,
+                    at: Instant::now(),
+                };
+                MATCHER
+                    .with(|matcher| keystroke_monitor::feed(&mut matcher.borrow_mut(), press))
+                    .map(|(trigger, len)| {
+                        typed_len = len;
+                        trigger
+                    })
+            };
+
+            if let Some(trigger) = trigger {
+                let listen_only = keystroke_monitor::listen_only();
+                // Without swallowing, the key that ended a command or abbreviation reaches the field
+                if listen_only && typed_len > 0 {
+                    typed_len += 1;
+                }
+
+                // The sink can take a while (it may open the overlay), longer than Windows waits
+                // for a hook, so it runs on the worker thread and the key is swallowed up front
+                let pending = PendingTrigger {
+                    trigger,
+                    typed_len,
+                    swallowed: if listen_only { None } else { typed },
+                };
+                let sent = WORKER.with(|worker| {
+                    worker
+                        .borrow()
+                        .as_ref()
+                        .is_some_and(|worker| worker.send(pending).is_ok())
+                });
+                // A non-zero return swallows the key
+                if sent && !listen_only {
+                    return 1;
+                }
+            }
+        }
+    }
+
+    CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam)
+}
+
+/// The hook thread: installs the hook and pumps messages until WM_QUIT
+struct MonitorActor {
+    thread_id: u32,
+    thread: JoinHandle<()>,
+}
+
+impl MonitorActor {
+    /// Install the hook on a new thread, returning once it is installed (or failed)
+    fn spawn(sink: TriggerSink) -> Result<Self, PromptOsError> {
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let thread = std::thread::spawn(move || unsafe { run_hook(sink, ready_tx) });
+
+        match ready_rx.recv() {
+            Ok(Ok(thread_id)) => Ok(Self { thread_id, thread }),
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => Err("Keyboard hook thread exited during startup".into()),
+        }
+    }
+
+    /// End the message loop and wait for the hook to be removed
+    fn shutdown(self) {
+        unsafe {
+            PostThreadMessageW(self.thread_id, WM_QUIT, 0, 0);
+        }
+        if self.thread.join().is_err() {
+            eprintln!("[ERROR] Keyboard hook thread panicked");
+        }
+    }
+}
+
+unsafe fn run_hook(sink: TriggerSink, ready: mpsc::Sender<Result<u32, PromptOsError>>) {
+    eprintln!("[DEBUG] Keyboard hook thread started");
+    let (worker_tx, worker_rx) = mpsc::channel();
+    let worker = std::thread::spawn(move || deliver_triggers(sink, worker_rx));
+    WORKER.with(|slot| *slot.borrow_mut() = Some(worker_tx));
+    let now = GetTickCount();
+    LAST_HOOK_EVENT.store(now, Ordering::Relaxed);
+    LAST_RAW_INPUT.store(now, Ordering::Relaxed);
+
+    let hook = SetWindowsHookExW(
+        WH_KEYBOARD_LL,
+        Some(keyboard_hook_proc),
+        GetModuleHandleW(std::ptr::null()),
+        0,
+    );
+    if hook.is_null() {
+        eprintln!("[ERROR] Failed to install keyboard hook");
+        WORKER.with(|slot| *slot.borrow_mut() = None);
+        let _ = worker.join();
+        let _ = ready.send(Err("Failed to install keyboard hook".into()));
+        return;
+    }
+    let raw_input_window = watch_raw_input();
+
+    // Make sure the thread has a message queue before anyone posts WM_QUIT to it
+    let mut msg: MSG = std::mem::zeroed();
+    PeekMessageW(
+        &mut msg,
+        std::ptr::null_mut(),
+        WM_USER,
+        WM_USER,
+        PM_NOREMOVE,
+    );
+
+    let _ = ready.send(Ok(GetCurrentThreadId()));
+    eprintln!("[DEBUG] Keyboard hook installed, entering message loop...");
+
+    // GetMessageW returns 0 on WM_QUIT and -1 on error
+    while GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) > 0 {
+        if msg.message == WM_INPUT {
+            LAST_RAW_INPUT.store(msg.time, Ordering::Relaxed);
+        }
+        // Lets the window clean up after raw input
+        DispatchMessageW(&msg);
+    }
+
+    UnhookWindowsHookEx(hook);
+    if !raw_input_window.is_null() {
+        DestroyWindow(raw_input_window);
+    }
+    // Triggers already handed over are still delivered
+    WORKER.with(|slot| *slot.borrow_mut() = None);
+    if worker.join().is_err() {
+        eprintln!("[ERROR] Keyboard trigger worker panicked");
+    }
+    eprintln!("[DEBUG] Keyboard hook removed");
+}
+
+/// Have keyboard raw input posted to this thread, which sees keys even after Windows has removed
+/// the hook; returns the message-only window it goes to (null if it couldn't be set up)
+unsafe fn watch_raw_input() -> HWND {
+    let class: Vec<u16> = "STATIC\0".encode_utf16().collect();
+    let window = CreateWindowExW(
+        0,
+        class.as_ptr(),
+        std::ptr::null(),
+        0,
+        0,
+        0,
+        0,
+        0,
+        HWND_MESSAGE,
+        std::ptr::null_mut(),
+        GetModuleHandleW(std::ptr::null()),
+        std::ptr::null(),
+    );
+    if window.is_null() {
+        eprintln!("[ERROR] Failed to create the raw input window");
+        return window;
+    }
+
+    let device = RAWINPUTDEVICE {
+        usUsagePage: HID_USAGE_PAGE_GENERIC,
+        usUsage: HID_USAGE_GENERIC_KEYBOARD,
+        dwFlags: RIDEV_INPUTSINK,
+        hwndTarget: window,
+    };
+    if RegisterRawInputDevices(&device, 1, std::mem::size_of::<RAWINPUTDEVICE>() as u32) == 0 {
+        eprintln!("[ERROR] Failed to register for keyboard raw input");
+    }
+    window
+}
+
+/// The low-level keyboard hook behind the pipeline's Monitor trait, kept in Tauri managed state
+#[derive(Default)]
+pub struct KeyboardHookMonitor {
+    actor: Mutex<Option<MonitorActor>>,
+}
+
+impl Monitor for KeyboardHookMonitor {
+    /// Starting again replaces the running hook
+    fn start(&self, sink: TriggerSink) -> Result<(), PromptOsError> {
+        let mut guard = self
+            .actor
+            .lock()
+            .map_err(|_| "Failed to lock keyboard hook".to_string())?;
+        if let Some(actor) = guard.take() {
+            actor.shutdown();
+        }
+        *guard = Some(MonitorActor::spawn(sink)?);
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), PromptOsError> {
+        let actor = self
+            .actor
+            .lock()
+            .map_err(|_| "Failed to lock keyboard hook".to_string())?
+            .take();
+        if let Some(actor) = actor {
+            actor.shutdown();
+        }
+        Ok(())
+    }
+
+    fn died(&self) -> bool {
+        self.actor.lock().is_ok_and(|actor| {
+            actor.as_ref().is_some_and(|actor| {
+                // Tick counts wrap, so compare their difference
+                let silence = LAST_RAW_INPUT
+                    .load(Ordering::Relaxed)
+                    .wrapping_sub(LAST_HOOK_EVENT.load(Ordering::Relaxed))
+                    as i32;
+                actor.thread.is_finished() || silence > HOOK_SILENCE_MS
+            })
+        })
+    }
+}
@@ -0,0 +1,177 @@
+// Keyboard layout awareness for chords and the hold key, which are configured by key ("cmd+/")
+// Keycodes are physical positions: 0x2C types "/" on US ANSI but "!" on AZERTY and "-" on QWERTZ
+
+use std::ffi::c_void;
+use std::sync::RwLock;
+
+#[link(name = "Carbon", kind = "framework")]
+extern "C" {
+    fn TISCopyCurrentKeyboardLayoutInputSource() -> *mut c_void;
+    fn TISGetInputSourceProperty(source: *mut c_void, key: *const c_void) -> *const c_void;
+    fn UCKeyTranslate(
+        key_layout: *const c_void,
+        virtual_key_code: u16,
+        key_action: u16,
+        modifier_key_state: u32,
+        keyboard_type: u32,
+        key_translate_options: u32,
+        dead_key_state: *mut u32,
+        max_string_length: usize,
+        actual_string_length: *mut usize,
+        unicode_string: *mut u16,
+    ) -> i32;
+    fn LMGetKbdType() -> u8;
+
+    static kTISPropertyUnicodeKeyLayoutData: *const c_void;
+    static kTISNotifySelectedKeyboardInputSourceChanged: *const c_void;
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFDataGetBytePtr(data: *const c_void) -> *const u8;
+    fn CFRelease(cf: *const c_void);
+    fn CFNotificationCenterGetDistributedCenter() -> *mut c_void;
+    fn CFNotificationCenterAddObserver(
+        center: *mut c_void,
+        observer: *const c_void,
+        callback: CFNotificationCallback,
+        name: *const c_void,
+        object: *const c_void,
+        suspension_behavior: isize,
+    );
+}
+
+type CFNotificationCallback = extern "C" fn(
+    center: *mut c_void,
+    observer: *mut c_void,
+    name: *const c_void,
+    object: *const c_void,
+    user_info: *const c_void,
+);
+
+const K_UC_KEY_ACTION_DISPLAY: u16 = 3;
+const K_UC_KEY_TRANSLATE_NO_DEAD_KEYS: u32 = 1;
+// Shift in UCKeyTranslate's modifier state (Carbon's shiftKey >> 8)
+const SHIFT_KEY_STATE: u32 = 0x02;
+const CF_NOTIFICATION_DELIVER_IMMEDIATELY: isize = 4;
+// Virtual keycodes run from 0 to 0x7F
+const KEYCODE_COUNT: u16 = 128;
+// A key that types something no chord can name, so it matches none
+const NO_KEY: i64 = -1;
+
+// US ANSI keycode of the key each physical keycode types on the current layout; empty until loaded
+static US_KEYCODES: RwLock<Vec<i64>> = RwLock::new(Vec::new());
+
+/// The single character a key types with these modifiers, ignoring dead keys
+unsafe fn translate(
+    layout: *const c_void,
+    keycode: u16,
+    modifiers: u32,
+    kbd_type: u32,
+) -> Option<char> {
+    let mut dead_key_state = 0;
+    let mut buf = [0u16; 4];
+    let mut len = 0;
+    let status = UCKeyTranslate(
+        layout,
+        keycode,
+        K_UC_KEY_ACTION_DISPLAY,
+        modifiers,
+        kbd_type,
+        K_UC_KEY_TRANSLATE_NO_DEAD_KEYS,
+        &mut dead_key_state,
+        buf.len(),
+        &mut len,
+        buf.as_mut_ptr(),
+    );
+    if status != 0 {
+        return None;
+    }
+    let mut chars = char::decode_utf16(buf[..len.min(buf.len())].iter().copied());
+    match (chars.next(), chars.next()) {
+        (Some(Ok(c)), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// Where the key a physical keycode types sits on US ANSI
+unsafe fn us_keycode_on(layout: *const c_void, keycode: u16, kbd_type: u32) -> i64 {
+    let printable = |modifiers| {
+        translate(layout, keycode, modifiers, kbd_type)
+            .filter(|c| !c.is_control() && !c.is_whitespace())
+    };
+    // Space, Return, Tab, and the like stay where they are on every layout
+    let Some(base) = printable(0) else {
+        return keycode as i64;
+    };
+
+    // Digits are shifted on AZERTY, so "cmd+1" means the key with "1" on it
+    [Some(base), printable(SHIFT_KEY_STATE)]
+        .into_iter()
+        .flatten()
+        .find_map(|c| crate::hotkey::keycode(&c.to_lowercase().to_string()))
+        .unwrap_or(NO_KEY)
+}
+
+/// Rebuild the keycode table for the layout now selected (main thread only)
+unsafe fn load() {
+    let source = TISCopyCurrentKeyboardLayoutInputSource();
+    if source.is_null() {
+        return;
+    }
+
+    let data = TISGetInputSourceProperty(source, kTISPropertyUnicodeKeyLayoutData);
+    let table = if data.is_null() {
+        Vec::new()
+    } else {
+        let layout = CFDataGetBytePtr(data) as *const c_void;
+        let kbd_type = LMGetKbdType() as u32;
+        (0..KEYCODE_COUNT)
+            .map(|keycode| us_keycode_on(layout, keycode, kbd_type))
+            .collect()
+    };
+    CFRelease(source);
+
+    if let Ok(mut current) = US_KEYCODES.write() {
+        *current = table;
+    }
+}
+
+extern "C" fn input_source_changed(
+    _center: *mut c_void,
+    _observer: *mut c_void,
+    _name: *const c_void,
+    _object: *const c_void,
+    _user_info: *const c_void,
+) {
+    eprintln!("[DEBUG] Keyboard layout changed");
+    unsafe { load() };
+}
+
+/// Load the current layout and follow layout switches (called once during setup, on the main
+/// thread, where the notifications are delivered too)
+pub fn start() {
+    unsafe {
+        load();
+        CFNotificationCenterAddObserver(
+            CFNotificationCenterGetDistributedCenter(),
+            std::ptr::null(),
+            input_source_changed,
+            kTISNotifySelectedKeyboardInputSourceChanged,
+            std::ptr::null(),
+            CF_NOTIFICATION_DELIVER_IMMEDIATELY,
+        );
+    }
+}
+
+/// The US ANSI keycode chords are parsed into, for a key pressed on the current layout
+pub fn us_keycode(keycode: i64) -> i64 {
+    let Ok(index) = usize::try_from(keycode) else {
+        return keycode;
+    };
+    US_KEYCODES
+        .read()
+        .ok()
+        .and_then(|table| table.get(index).copied())
+        .unwrap_or(keycode)
+}
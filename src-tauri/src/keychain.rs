@@ -1,53 +1,202 @@
-// Keychain access for storing Gemini API key
-// Uses macOS Keychain via security-framework crate
+// Keychain access for storing Gemini API keys
+// Uses macOS Keychain via security-framework crate (no other key store yet)
 
-use security_framework::passwords::{delete_generic_password, get_generic_password, set_generic_password};
+use crate::error::PromptOsError;
 
+#[cfg(target_os = "macos")]
+use security_framework::passwords::{
+    delete_generic_password, get_generic_password, set_generic_password,
+};
+
+#[cfg(target_os = "macos")]
 const SERVICE: &str = "com.promptos.gemini-api-key";
 const ACCOUNT: &str = "default";
+// Additional keys for rotation, stored together as one JSON item
+const POOL_ACCOUNT: &str = "pool";
+
+/// A key from the rotation pool; the primary key has id "default"
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct StoredKey {
+    pub id: String,
+    pub label: String,
+    pub key: String,
+}
+
+/// What the key list shows: only a hint of each key (the streaming client still gets the keys
+/// themselves, from key_rotation::acquire_api_keys)
+#[derive(serde::Serialize, Clone)]
+pub struct ApiKeyInfo {
+    pub id: String,
+    pub label: String,
+    /// Last four characters only
+    pub hint: String,
+    pub usage: crate::key_rotation::KeyUsage,
+}
 
 #[tauri::command]
-pub fn store_api_key(key: String) -> Result<(), String> {
+pub fn store_api_key(key: String) -> Result<(), PromptOsError> {
     // Delete existing entry first (ignore if not found)
-    let _ = delete_generic_password(SERVICE, ACCOUNT);
+    let _ = delete_item(ACCOUNT);
 
     // Store the new key
-    set_generic_password(SERVICE, ACCOUNT, key.as_bytes())
-        .map_err(|e| format!("Failed to store API key: {}", e))
+    write_item(ACCOUNT, key.as_bytes())
+        .map_err(|e| format!("Failed to store API key: {}", e).into())
 }
 
 #[tauri::command]
-pub fn retrieve_api_key() -> Result<Option<String>, String> {
-    match get_generic_password(SERVICE, ACCOUNT) {
-        Ok(password_bytes) => {
+pub fn retrieve_api_key() -> Result<Option<String>, PromptOsError> {
+    match read_item(ACCOUNT) {
+        Ok(Some(password_bytes)) => {
             let key = String::from_utf8(password_bytes)
                 .map_err(|e| format!("Invalid UTF-8 in stored key: {}", e))?;
             Ok(Some(key))
         }
-        Err(e) => {
-            // Check if it's a "not found" error
-            let error_string = e.to_string();
-            if error_string.contains("not found") || error_string.contains("SecItemNotFound") || error_string.contains("-25300") {
-                Ok(None)
-            } else {
-                Err(format!("Failed to retrieve API key: {}", e))
-            }
-        }
+        Ok(None) => Ok(None),
+        Err(e) => Err(format!("Failed to retrieve API key: {}", e).into()),
     }
 }
 
 #[tauri::command]
-pub fn delete_api_key() -> Result<(), String> {
-    match delete_generic_password(SERVICE, ACCOUNT) {
-        Ok(()) => Ok(()),
-        Err(e) => {
-            // Ignore "not found" errors
-            let error_string = e.to_string();
-            if error_string.contains("not found") || error_string.contains("SecItemNotFound") || error_string.contains("-25300") {
-                Ok(())
-            } else {
-                Err(format!("Failed to delete API key: {}", e))
-            }
-        }
+pub fn delete_api_key() -> Result<(), PromptOsError> {
+    delete_item(ACCOUNT).map_err(|e| format!("Failed to delete API key: {}", e).into())
+}
+
+/// The keychain item for an account, or None if there isn't one
+#[cfg(target_os = "macos")]
+fn read_item(account: &str) -> Result<Option<Vec<u8>>, String> {
+    match get_generic_password(SERVICE, account) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if is_not_found(&e) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Create or overwrite the keychain item for an account
+#[cfg(target_os = "macos")]
+fn write_item(account: &str, bytes: &[u8]) -> Result<(), String> {
+    set_generic_password(SERVICE, account, bytes).map_err(|e| e.to_string())
+}
+
+/// Delete the keychain item for an account; one that isn't there counts as deleted
+#[cfg(target_os = "macos")]
+fn delete_item(account: &str) -> Result<(), String> {
+    match delete_generic_password(SERVICE, account) {
+        Err(e) if !is_not_found(&e) => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn is_not_found(e: &security_framework::base::Error) -> bool {
+    let error_string = e.to_string();
+    error_string.contains("not found")
+        || error_string.contains("SecItemNotFound")
+        || error_string.contains("-25300")
+}
+
+// Only the macOS keychain is wired up so far; elsewhere no key is stored
+#[cfg(not(target_os = "macos"))]
+fn read_item(_account: &str) -> Result<Option<Vec<u8>>, String> {
+    Ok(None)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn write_item(_account: &str, _bytes: &[u8]) -> Result<(), String> {
+    Err("no secure key store on this platform yet".to_string())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn delete_item(_account: &str) -> Result<(), String> {
+    Ok(())
+}
+
+fn load_pool() -> Result<Vec<StoredKey>, PromptOsError> {
+    match read_item(POOL_ACCOUNT) {
+        Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+            .map_err(|e| format!("Invalid key pool in keychain: {}", e).into()),
+        Ok(None) => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to retrieve key pool: {}", e).into()),
+    }
+}
+
+fn save_pool(pool: &[StoredKey]) -> Result<(), PromptOsError> {
+    // Overwritten in place; deleting first would lose every pooled key if the write then failed
+    if pool.is_empty() {
+        return delete_item(POOL_ACCOUNT)
+            .map_err(|e| format!("Failed to clear key pool: {}", e).into());
+    }
+
+    let bytes =
+        serde_json::to_vec(pool).map_err(|e| format!("Failed to encode key pool: {}", e))?;
+    write_item(POOL_ACCOUNT, &bytes).map_err(|e| format!("Failed to store key pool: {}", e).into())
+}
+
+/// Every configured key, primary first
+pub fn all_keys() -> Result<Vec<StoredKey>, PromptOsError> {
+    let mut keys = Vec::new();
+    if let Some(key) = retrieve_api_key()? {
+        keys.push(StoredKey {
+            id: ACCOUNT.to_string(),
+            label: "Primary".to_string(),
+            key,
+        });
+    }
+    keys.extend(load_pool()?);
+    Ok(keys)
+}
+
+fn key_info(stored: &StoredKey) -> ApiKeyInfo {
+    let tail: String = stored.key.chars().rev().take(4).collect();
+    ApiKeyInfo {
+        id: stored.id.clone(),
+        label: stored.label.clone(),
+        hint: tail.chars().rev().collect(),
+        usage: crate::key_rotation::usage(&stored.id),
+    }
+}
+
+#[tauri::command]
+pub fn list_api_keys() -> Result<Vec<ApiKeyInfo>, PromptOsError> {
+    Ok(all_keys()?.iter().map(key_info).collect())
+}
+
+/// Add a key to the rotation pool alongside the primary key
+#[tauri::command]
+pub fn add_api_key(label: String, key: String) -> Result<ApiKeyInfo, PromptOsError> {
+    let key = key.trim().to_string();
+    if key.is_empty() {
+        return Err("API key is empty".into());
+    }
+
+    let mut pool = load_pool()?;
+    if pool.iter().any(|stored| stored.key == key) || retrieve_api_key()?.as_ref() == Some(&key) {
+        return Err("That API key is already stored".into());
+    }
+
+    let stored = StoredKey {
+        id: format!("key-{}", chrono::Utc::now().timestamp_millis()),
+        label: if label.trim().is_empty() {
+            format!("Key {}", pool.len() + 2)
+        } else {
+            label.trim().to_string()
+        },
+        key,
+    };
+    pool.push(stored.clone());
+    save_pool(&pool)?;
+    Ok(key_info(&stored))
+}
+
+/// Remove a pooled key ("default" removes the primary key)
+#[tauri::command]
+pub fn remove_api_key(id: String) -> Result<(), PromptOsError> {
+    if id == ACCOUNT {
+        delete_api_key()?;
+    } else {
+        let mut pool = load_pool()?;
+        pool.retain(|stored| stored.id != id);
+        save_pool(&pool)?;
     }
+    crate::key_rotation::forget(&id);
+    Ok(())
 }
@@ -1,237 +1,1383 @@
-// Keystroke monitoring using macOS CGEvent tap
-// Detects "/" key press and emits trigger-detected event
+// Keystroke monitoring: what happens when a platform monitor reports a trigger
+// Monitors: macOS event tap, Windows keyboard hook, X11 RECORD or the Wayland shortcuts portal
+// Also handles the quick-transform hotkey, which rewrites the selection in place, snippets,
+// abbreviations like ":sig" that are replaced with their expansion as soon as they are typed,
+// named triggers, which open the overlay routed to an action, and mouse and hotkey triggers on a
+// selection
 
-use core_foundation::base::TCFType;
-use core_foundation::mach_port::CFMachPort;
-use core_foundation::runloop::{kCFRunLoopCommonModes, CFRunLoop};
-use std::ffi::c_void;
-use std::sync::Mutex;
-use tauri::{AppHandle, Emitter, Manager};
+use crate::error::PromptOsError;
+use crate::hotkey::Chord;
+use prompt_os_pipeline::{
+    Debouncer, FieldBounds, KeyPress, MatcherConfig, Monitor, OverlayRoute, PlacementMode, Trigger,
+    TriggerHandler, TriggerMatcher, TriggerSink, MAX_TYPED_CHARS,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
 
 #[cfg(target_os = "macos")]
 use cocoa::appkit::NSWindow;
 #[cfg(target_os = "macos")]
-use cocoa::base::id;
-#[cfg(target_os = "macos")]
 use objc::msg_send;
 #[cfg(target_os = "macos")]
 use objc::sel;
 #[cfg(target_os = "macos")]
 use objc::sel_impl;
 
-// FFI declarations for CGEvent APIs
-#[link(name = "ApplicationServices", kind = "framework")]
-extern "C" {
-    fn CGEventTapCreate(
-        tap: u32,
-        place: u32,
-        options: u32,
-        eventsOfInterest: u64,
-        callback: CGEventTapCallBack,
-        userInfo: *mut c_void,
-    ) -> CFMachPortRef;
-
-    fn CGEventTapEnable(tap: CFMachPortRef, enable: bool);
-    fn CGEventGetIntegerValueField(event: CGEventRef, field: u32) -> i64;
-}
-
-type CFMachPortRef = *mut c_void;
-type CGEventRef = *mut c_void;
-type CGEventTapCallBack = unsafe extern "C" fn(
-    proxy: *mut c_void,
-    event_type: u32,
-    event: CGEventRef,
-    user_info: *mut c_void,
-) -> CGEventRef;
-
-// Event type constants
-const K_CG_EVENT_KEY_DOWN: u32 = 10;
-const K_CG_EVENT_TAP_LOCATION_HID: u32 = 0;
-const K_CG_EVENT_TAP_HEAD_INSERT: u32 = 0;
-const K_CG_EVENT_TAP_OPTION_DEFAULT: u32 = 0;
-const K_CG_KEYBOARD_EVENT_KEYCODE: u32 = 9;
-
-// "/" key virtual keycode on macOS
-const VK_SLASH: i64 = 0x2C;
-
-// CGPoint for mouse position
-#[cfg(target_os = "macos")]
-#[repr(C)]
-#[derive(Copy, Clone)]
-struct NSPoint {
-    x: f64,
-    y: f64,
-}
-
-#[cfg(target_os = "macos")]
-#[repr(C)]
-#[derive(Copy, Clone)]
-struct CGRect {
-    origin: NSPoint,
-    size: NSPoint, // Using NSPoint for size (width, height)
-}
-
-// Static storage - using raw pointer for thread safety
-static EVENT_TAP_REF: Mutex<Option<usize>> = Mutex::new(None);
-static APP_HANDLE: Mutex<Option<AppHandle>> = Mutex::new(None);
-
-unsafe extern "C" fn event_tap_callback(
-    _proxy: *mut c_void,
-    event_type: u32,
-    event: CGEventRef,
-    _user_info: *mut c_void,
-) -> CGEventRef {
-    if event_type == K_CG_EVENT_KEY_DOWN {
-        let keycode = CGEventGetIntegerValueField(event, K_CG_KEYBOARD_EVENT_KEYCODE);
-        eprintln!("[DEBUG] Key pressed: keycode={}", keycode);
-
-        if keycode == VK_SLASH {
-            eprintln!("[DEBUG] SLASH detected! Checking for text field...");
-
-            // Get focused text field bounds
-            match crate::text_field_detector::get_focused_text_field_bounds() {
-                Ok(bounds) => {
-                    eprintln!("[DEBUG] Text field bounds: x={}, y={}, w={}, h={}",
-                        bounds.x, bounds.y, bounds.width, bounds.height);
-
-                    // Emit Tauri event with bounds and show overlay
-                    if let Ok(guard) = APP_HANDLE.lock() {
-                        if let Some(app) = guard.as_ref() {
-                            eprintln!("[DEBUG] Emitting trigger-detected event");
-                            let _ = app.emit("trigger-detected", bounds);
-
-                            // Show and focus the overlay window
-                            if let Some(window) = app.get_webview_window("overlay") {
-                                eprintln!("[DEBUG] Showing overlay window");
-                                
-                                // Position at cursor first
-                                #[cfg(target_os = "macos")]
-                                {
-                                    let cls = objc::runtime::Class::get("NSEvent").unwrap();
-                                    let mouse_loc: NSPoint = msg_send![cls, mouseLocation];
-                                    
-                                    let screen_cls = objc::runtime::Class::get("NSScreen").unwrap();
-                                    let main_screen: id = msg_send![screen_cls, mainScreen];
-                                    let frame: CGRect = msg_send![main_screen, frame];
-                                    let screen_height = frame.size.y;
-                                    
-                                    let y = screen_height - mouse_loc.y;
-                                    eprintln!("[DEBUG] Positioning at: x={}, y={}", mouse_loc.x, y);
-                                    
-                                    let _ = window.set_position(tauri::Position::Physical(
-                                        tauri::PhysicalPosition {
-                                            x: mouse_loc.x as i32,
-                                            y: y as i32,
-                                        }
-                                    ));
-                                }
-                                
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                                
-                                eprintln!("[DEBUG] Window shown, is_visible: {:?}", window.is_visible());
-                            }
-
-                            // Return null to suppress the "/" keystroke
-                            return std::ptr::null_mut();
-                        } else {
-                            eprintln!("[DEBUG] App handle is None");
-                        }
-                    } else {
-                        eprintln!("[DEBUG] Failed to lock APP_HANDLE");
+/// The monitor for this platform, kept in Tauri managed state
+#[cfg(target_os = "macos")]
+pub use crate::event_tap::EventTapMonitor as PlatformMonitor;
+#[cfg(target_os = "windows")]
+pub use crate::keyboard_hook::KeyboardHookMonitor as PlatformMonitor;
+
+/// Linux picks per session: the GlobalShortcuts portal on Wayland, X11 RECORD otherwise
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+pub struct PlatformMonitor {
+    x11: crate::x11_record::XRecordMonitor,
+    wayland: crate::global_shortcuts::GlobalShortcutsMonitor,
+}
+
+#[cfg(target_os = "linux")]
+impl Monitor for PlatformMonitor {
+    fn start(&self, sink: prompt_os_pipeline::TriggerSink) -> Result<(), PromptOsError> {
+        if crate::global_shortcuts::wayland_session() {
+            self.wayland.start(sink)
+        } else {
+            self.x11.start(sink)
+        }
+    }
+
+    fn stop(&self) -> Result<(), PromptOsError> {
+        self.wayland.stop()?;
+        self.x11.stop()
+    }
+
+    fn died(&self) -> bool {
+        self.wayland.died() || self.x11.died()
+    }
+}
+
+// Typed sequences that open the overlay; a single "/" unless configured otherwise
+const DEFAULT_TRIGGER_SEQUENCE: &str = "/";
+const MAX_SEQUENCE_CHARS: usize = 8;
+// As much typing as the matcher keeps
+const MAX_ABBREVIATION_CHARS: usize = MAX_TYPED_CHARS;
+// Hold mode: which key, and how long it is held before the overlay opens instead of it typing
+#[cfg(target_os = "macos")]
+const DEFAULT_HOLD_KEY: &str = "/";
+#[cfg(target_os = "macos")]
+const DEFAULT_HOLD_MS: u64 = 400;
+const HOLD_MS_RANGE: std::ops::RangeInclusive<u64> = 150..=2000;
+// A trigger this soon after the previous one is a bounce or a double press, not a second request
+const DEFAULT_DEBOUNCE_MS: u64 = 300;
+const MAX_DEBOUNCE_MS: u64 = 2000;
+// Lets the monitor's backspaces over the abbreviation land before the expansion is inserted
+const SNIPPET_INJECT_DELAY: Duration = Duration::from_millis(30);
+// How often the watchdog checks that a running monitor is still alive
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(5);
+// Saved settings, one file each
+const TRIGGER_SEQUENCES_FILE: &str = "trigger_sequences.json";
+const ACTIVATION_FILE: &str = "activation.json";
+const APP_FILTER_FILE: &str = "trigger_app_filter.json";
+const SNIPPETS_FILE: &str = "snippets.json";
+const LISTEN_ONLY_FILE: &str = "listen_only.json";
+const NAMED_TRIGGERS_FILE: &str = "named_triggers.json";
+const MOUSE_TRIGGERS_FILE: &str = "mouse_triggers.json";
+const SELECTION_HOTKEY_FILE: &str = "selection_hotkey.json";
+const PLACEMENT_FILE: &str = "overlay_placement.json";
+
+/// Payload of the "monitor-tap-disabled" event: the OS switched the monitor off and it was
+/// re-enabled
+#[cfg(target_os = "macos")]
+#[derive(serde::Serialize, Clone)]
+pub struct MonitorHealth {
+    /// "timeout" (the callback was too slow) or "user_input"
+    pub reason: String,
+    /// Re-enables since the monitor started
+    pub recoveries: u32,
+}
+
+/// Monitor state changes the frontend hears about
+#[cfg(target_os = "macos")]
+pub enum MonitorEvent {
+    /// Emitted as "monitor-tap-disabled"
+    Reenabled(MonitorHealth),
+    /// Accessibility access was revoked, so the tap stays off; emitted as "monitor-permission-lost"
+    PermissionLost,
+    /// Emitted as "secure-input-active" when secure keyboard entry turns on (true) or off (false)
+    SecureInput(bool),
+}
+
+#[cfg(target_os = "macos")]
+pub type StatusReport = Arc<dyn Fn(MonitorEvent) + Send + Sync>;
+
+/// Payload of the "monitor-restarted" event: the monitor died and the watchdog started it again
+#[derive(serde::Serialize, Clone)]
+pub struct MonitorRestart {
+    /// Restarts since launch
+    pub restarts: u32,
+}
+
+/// Where monitoring stands, for windows opened after the status event went out
+#[derive(serde::Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MonitorState {
+    Stopped,
+    Running,
+    /// Start failed; "monitor-error" carried the reason
+    Failed,
+    #[cfg(target_os = "macos")]
+    PermissionLost,
+    /// No event tap, only global shortcuts; "monitor-fallback" carried the reason and shortcuts
+    #[cfg(target_os = "macos")]
+    ShortcutsOnly,
+}
+
+impl MonitorState {
+    fn tooltip(self) -> &'static str {
+        match self {
+            MonitorState::Stopped => "Prompt OS (paused)",
+            MonitorState::Running => "Prompt OS",
+            MonitorState::Failed => "Prompt OS: triggers unavailable",
+            #[cfg(target_os = "macos")]
+            MonitorState::PermissionLost => "Prompt OS: Accessibility access lost",
+            #[cfg(target_os = "macos")]
+            MonitorState::ShortcutsOnly => "Prompt OS: shortcuts only (no Accessibility access)",
+        }
+    }
+}
+
+static MONITOR_STATE: RwLock<MonitorState> = RwLock::new(MonitorState::Stopped);
+
+// Every trigger comes from a global shortcut, so none of them was typed into a field
+static SHORTCUTS_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Payload of the "trigger-detected" event
+#[derive(serde::Serialize, Clone)]
+struct TriggerDetected {
+    #[serde(flatten)]
+    bounds: FieldBounds,
+    #[serde(flatten)]
+    route: OverlayRoute,
+    /// Kind of trigger that opened the overlay, e.g. "slash" or "named"
+    trigger_id: &'static str,
+    /// Modifier keys held as it fired, e.g. ["cmd", "shift"]
+    modifiers: Vec<&'static str>,
+    /// App the overlay was opened over
+    bundle_id: Option<String>,
+    /// Light or dark UI around the caret, so the overlay opens in a matching theme (other
+    /// platforms' overlays keep the dark theme)
+    #[cfg(target_os = "macos")]
+    appearance: Option<crate::appearance::TargetAppearance>,
+    /// Frame of the window the field is in, which the overlay docks to in window-edge placement
+    window: Option<crate::text_field_detector::TextFieldBounds>,
+    /// When it fired (RFC 3339)
+    timestamp: String,
+}
+
+static TRIGGER_SEQUENCES: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Text typed in place of an abbreviation
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Snippet {
+    /// e.g. ":sig"; matched anywhere, even mid-word
+    pub abbreviation: String,
+    pub expansion: String,
+}
+
+static SNIPPETS: RwLock<Vec<Snippet>> = RwLock::new(Vec::new());
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NamedTriggerKind {
+    /// A chord like "cmd+shift+t"
+    Chord,
+    /// Text like "::fix", matched like a snippet abbreviation
+    Typed,
+}
+
+/// A user-configured trigger; the overlay it opens is routed to its action
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct NamedTrigger {
+    /// Shown in settings and sent with the trigger-detected event
+    pub name: String,
+    pub kind: NamedTriggerKind,
+    pub keys: String,
+    /// User action id; None opens the plain prompt overlay
+    pub action: Option<String>,
+}
+
+static NAMED_TRIGGERS: RwLock<Vec<NamedTrigger>> = RwLock::new(Vec::new());
+
+/// Clicks that open the overlay pre-filled with the selected text (macOS only); both off by default
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Default)]
+pub struct MouseTriggers {
+    /// Triple-click, e.g. to select a paragraph
+    pub triple_click: bool,
+    /// Middle-click on an existing selection
+    pub middle_click: bool,
+}
+
+static MOUSE_TRIGGERS: RwLock<MouseTriggers> = RwLock::new(MouseTriggers {
+    triple_click: false,
+    middle_click: false,
+});
+
+// Chord that opens the overlay with the selection, to rewrite it without retyping; none unless set
+static SELECTION_HOTKEY: RwLock<Option<(String, Chord)>> = RwLock::new(None);
+
+static PLACEMENT_MODE: RwLock<PlacementMode> = RwLock::new(PlacementMode::Caret);
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivationMode {
+    /// Typing a trigger sequence opens the overlay
+    Sequence,
+    /// Only a modifier chord (e.g. Cmd+Shift+Space) opens it; typing is never intercepted
+    Chord,
+    /// Holding a key (e.g. "/") opens it; a quick tap types the key as usual (macOS only)
+    Hold,
+    /// Tapping a modifier on its own (Fn / Globe, or a Hyper key) opens it (macOS only)
+    Modifier,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ModifierKey {
+    /// Fn, or Globe on newer keyboards (with its Keyboard setting at "Do Nothing")
+    #[default]
+    Fn,
+    /// Control, Option, Shift, and Command at once, e.g. Caps Lock remapped with Karabiner
+    Hyper,
+}
+
+impl ModifierKey {
+    /// The CGEventFlags the key sets while held
+    #[cfg(target_os = "macos")]
+    fn flags(self) -> u64 {
+        match self {
+            ModifierKey::Fn => crate::hotkey::FLAG_FN,
+            ModifierKey::Hyper => crate::hotkey::FLAG_HYPER,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct ActivationConfig {
+    pub mode: ActivationMode,
+    /// e.g. "cmd+shift+space"; used in chord mode
+    pub chords: Vec<String>,
+    /// Keys typed further apart than this don't build up a sequence, command, or abbreviation
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Hold mode: the key to hold, "/" unless set
+    #[serde(default)]
+    pub hold_key: Option<String>,
+    /// Hold mode: how long to hold it, 400 ms unless set
+    #[serde(default)]
+    pub hold_ms: Option<u64>,
+    /// Modifier mode: the modifier to tap, Fn unless set
+    #[serde(default)]
+    pub modifier_key: Option<ModifierKey>,
+    /// Triggers this soon after the previous one are ignored, 300 ms unless set (0 turns it off)
+    #[serde(default)]
+    pub debounce_ms: Option<u64>,
+}
+
+struct Activation {
+    mode: ActivationMode,
+    chords: Vec<(String, Chord)>,
+    timeout_ms: Option<u64>,
+    hold_key: Option<(String, i64)>,
+    hold_ms: Option<u64>,
+    modifier_key: Option<ModifierKey>,
+    debounce_ms: Option<u64>,
+}
+
+static ACTIVATION: RwLock<Activation> = RwLock::new(Activation {
+    mode: ActivationMode::Sequence,
+    chords: Vec::new(),
+    timeout_ms: None,
+    hold_key: None,
+    hold_ms: None,
+    modifier_key: None,
+    debounce_ms: None,
+});
+
+// When the last trigger arrived, debounced or not
+static DEBOUNCER: Debouncer = Debouncer::new();
+
+// Label of our own window with keyboard focus; typing into the overlay or settings never triggers
+static FOCUSED_WINDOW: RwLock<Option<String>> = RwLock::new(None);
+
+// Hotkeys declared by user actions, as loaded from the actions directory
+static ACTION_HOTKEYS: RwLock<Vec<(Chord, String)>> = RwLock::new(Vec::new());
+
+// What every platform monitor's matcher looks for, rebuilt from the settings above when they change
+static MATCHER_CONFIG: LazyLock<RwLock<MatcherConfig>> =
+    LazyLock::new(|| RwLock::new(matcher_config()));
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AppFilterMode {
+    /// Trigger everywhere except the listed apps
+    Deny,
+    /// Trigger only inside the listed apps
+    Allow,
+}
+
+/// Per-app trigger filter, on top of the sensitive-app blocklist (e.g. games, terminals)
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct AppFilter {
+    pub mode: AppFilterMode,
+    /// Bundle ids, or prefixes ending in '.'
+    pub apps: Vec<String>,
+}
+
+// Never swallow keys: the event tap is created listen-only and trigger keys reach the app too
+static LISTEN_ONLY: AtomicBool = AtomicBool::new(false);
+
+static APP_FILTER: RwLock<AppFilter> = RwLock::new(AppFilter {
+    mode: AppFilterMode::Deny,
+    apps: Vec::new(),
+});
+
+/// Whether the filter keeps triggers out of an app; an unidentified app only passes a deny list
+#[cfg(target_os = "macos")]
+pub(crate) fn app_filtered(bundle_id: Option<&str>) -> bool {
+    let Ok(filter) = APP_FILTER.read() else {
+        return false;
+    };
+    let listed = bundle_id.is_some_and(|bundle_id| {
+        filter.apps.iter().any(|entry| {
+            if entry.ends_with('.') {
+                bundle_id.starts_with(entry.as_str())
+            } else {
+                bundle_id == entry
+            }
+        })
+    });
+    match filter.mode {
+        AppFilterMode::Deny => listed,
+        AppFilterMode::Allow => !listed,
+    }
+}
+
+/// Read when a monitor starts; an event tap's options are fixed once it is created
+pub(crate) fn listen_only() -> bool {
+    LISTEN_ONLY.load(Ordering::Relaxed)
+}
+
+/// Configured chords whatever the mode; Wayland binds the first as its overlay shortcut
+#[cfg(target_os = "linux")]
+pub(crate) fn activation_chords() -> Vec<String> {
+    ACTIVATION
+        .read()
+        .map(|activation| {
+            activation
+                .chords
+                .iter()
+                .map(|(hotkey, _)| hotkey.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn matcher_config() -> MatcherConfig {
+    // Built-in quick transform first, then action hotkeys, the selection hotkey, named triggers,
+    // and activation chords
+    let mut chords = vec![(crate::hotkey::QUICK_TRANSFORM, Trigger::QuickTransform)];
+    if let Ok(hotkeys) = ACTION_HOTKEYS.read() {
+        chords.extend(
+            hotkeys
+                .iter()
+                .map(|(chord, id)| (*chord, Trigger::Action(id.clone()))),
+        );
+    }
+    if let Ok(Some((_, chord))) = SELECTION_HOTKEY.read().as_deref() {
+        chords.push((*chord, Trigger::Selection));
+    }
+
+    let mut typed = Vec::new();
+    if let Ok(named) = NAMED_TRIGGERS.read() {
+        for named in named.iter() {
+            let trigger = Trigger::Named {
+                name: named.name.clone(),
+                action: named.action.clone(),
+            };
+            match named.kind {
+                // Parsed when it was set, so this can't fail
+                NamedTriggerKind::Chord => {
+                    if let Ok(chord) = crate::hotkey::parse(&named.keys) {
+                        chords.push((chord, trigger));
                     }
                 }
-                Err(e) => {
-                    eprintln!("[DEBUG] No text field found: {}", e);
+                NamedTriggerKind::Typed => typed.push((named.keys.clone(), trigger)),
+            }
+        }
+    }
+
+    let mut sequences = Vec::new();
+    let mut timeout = None;
+    if let Ok(activation) = ACTIVATION.read() {
+        match activation.mode {
+            ActivationMode::Sequence => {
+                sequences = TRIGGER_SEQUENCES
+                    .read()
+                    .map(|sequences| sequences.clone())
+                    .unwrap_or_default();
+                if sequences.is_empty() {
+                    sequences.push(DEFAULT_TRIGGER_SEQUENCE.to_string());
                 }
             }
+            ActivationMode::Chord => chords.extend(
+                activation
+                    .chords
+                    .iter()
+                    .map(|(_, chord)| (*chord, Trigger::Slash)),
+            ),
+            // The event tap times the hold and watches modifiers itself; see hold_trigger and
+            // modifier_trigger
+            ActivationMode::Hold | ActivationMode::Modifier => {}
         }
+        timeout = activation.timeout_ms.map(Duration::from_millis);
+    }
+
+    let snippets = SNIPPETS
+        .read()
+        .map(|snippets| {
+            snippets
+                .iter()
+                .map(|snippet| (snippet.abbreviation.clone(), snippet.expansion.clone()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    MatcherConfig {
+        chords,
+        sequences,
+        snippets,
+        typed,
+        timeout,
+    }
+}
+
+/// Pick up changed settings in every monitor's matcher
+fn refresh_matcher() {
+    let config = matcher_config();
+    if let Ok(mut current) = MATCHER_CONFIG.write() {
+        *current = config;
+    }
+}
+
+/// Called by actions whenever the actions directory is reloaded
+pub(crate) fn set_action_hotkeys(hotkeys: Vec<(Chord, String)>) {
+    if let Ok(mut current) = ACTION_HOTKEYS.write() {
+        *current = hotkeys;
+    }
+    refresh_matcher();
+}
+
+/// Hold mode's key and hold time, unless another mode is on or our own window has the keys
+#[cfg(target_os = "macos")]
+pub(crate) fn hold_trigger() -> Option<(i64, Duration)> {
+    if FOCUSED_WINDOW.read().is_ok_and(|window| window.is_some()) {
+        return None;
     }
+    let activation = ACTIVATION.read().ok()?;
+    if activation.mode != ActivationMode::Hold {
+        return None;
+    }
+    let keycode = match &activation.hold_key {
+        Some((_, keycode)) => *keycode,
+        None => crate::hotkey::keycode(DEFAULT_HOLD_KEY)?,
+    };
+    let hold_ms = activation.hold_ms.unwrap_or(DEFAULT_HOLD_MS);
+    Some((keycode, Duration::from_millis(hold_ms)))
+}
+
+/// Modifier mode's CGEventFlags, unless another mode is on or our own window has the keys
+#[cfg(target_os = "macos")]
+pub(crate) fn modifier_trigger() -> Option<u64> {
+    if FOCUSED_WINDOW.read().is_ok_and(|window| window.is_some()) {
+        return None;
+    }
+    let activation = ACTIVATION.read().ok()?;
+    if activation.mode != ActivationMode::Modifier {
+        return None;
+    }
+    Some(activation.modifier_key.unwrap_or_default().flags())
+}
+
+/// Enabled mouse triggers; none while our own window has focus
+#[cfg(target_os = "macos")]
+pub(crate) fn mouse_triggers() -> MouseTriggers {
+    if FOCUSED_WINDOW.read().is_ok_and(|window| window.is_some()) {
+        return MouseTriggers::default();
+    }
+    MOUSE_TRIGGERS
+        .read()
+        .map(|triggers| *triggers)
+        .unwrap_or_default()
+}
+
+/// Track focus across our windows (from the window event handler); focus moving between two of
+/// them can report the gain before the loss
+pub(crate) fn window_focus_changed(label: &str, focused: bool) {
+    if let Ok(mut window) = FOCUSED_WINDOW.write() {
+        if focused {
+            *window = Some(label.to_string());
+        } else if window.as_deref() == Some(label) {
+            *window = None;
+        }
+    }
+}
+
+/// Run a platform monitor's key-down through its matcher with the current settings
+pub(crate) fn feed(matcher: &mut TriggerMatcher, press: KeyPress) -> Option<(Trigger, usize)> {
+    // Our own window has the keys, so a "/" there would only re-open the overlay over itself
+    if FOCUSED_WINDOW.read().is_ok_and(|window| window.is_some()) {
+        matcher.clear();
+        return None;
+    }
+
+    let config = MATCHER_CONFIG.read().ok()?;
+    let key = press.key.clone();
+    let matched = matcher.feed(press, &config);
+    crate::typing_context::record(&key, matched.as_ref().map(|(_, typed_len)| *typed_len));
+    matched
+}
+
+/// Whether a trigger follows the last one too closely to be meant
+fn debounced(trigger: &Trigger) -> bool {
+    let debounce_ms = ACTIVATION
+        .read()
+        .ok()
+        .and_then(|activation| activation.debounce_ms)
+        .unwrap_or(DEFAULT_DEBOUNCE_MS);
+    DEBOUNCER.debounced(trigger, Duration::from_millis(debounce_ms))
+}
+
+/// How the app reacts to each trigger from the platform monitor
+struct AppTriggers(AppHandle);
+
+impl TriggerHandler for AppTriggers {
+    fn debounced(&self, trigger: &Trigger) -> bool {
+        let debounced = debounced(trigger);
+        if debounced {
+            eprintln!("[DEBUG] Trigger debounced");
+        }
+        debounced
+    }
+
+    fn permitted(&self) -> bool {
+        #[cfg(target_os = "macos")]
+        return crate::permissions::preflight(&self.0).is_ok();
+        // UI Automation and AT-SPI don't need a permission granted first
+        #[cfg(not(target_os = "macos"))]
+        true
+    }
+
+    fn clipboard_only(&self, trigger: Trigger) -> bool {
+        handle_clipboard_only_trigger(&self.0, trigger)
+    }
+
+    fn quick_transform(&self, action: Option<String>) -> bool {
+        match &action {
+            Some(id) => {
+                eprintln!("[DEBUG] Action hotkey detected: {}", id);
+                crate::telemetry::record(crate::telemetry::EVENT_TRIGGER_ACTION);
+            }
+            None => {
+                eprintln!("[DEBUG] Quick-transform hotkey detected");
+                crate::telemetry::record(crate::telemetry::EVENT_TRIGGER_QUICK_TRANSFORM);
+            }
+        }
+
+        // Network call happens off the tap thread so input never stalls
+        let app = self.0.clone();
+        std::thread::spawn(move || run_quick_transform(&app, action.as_deref()));
+
+        // Suppress the hotkey so it doesn't reach the focused app
+        true
+    }
+
+    fn open_overlay(&self, trigger_id: &'static str, route: OverlayRoute) -> bool {
+        if let Some(name) = &route.trigger {
+            eprintln!("[DEBUG] Named trigger detected: {}", name);
+        }
+        unsafe { show_overlay(&self.0, trigger_id, route) }
+    }
+
+    fn open_selection_overlay(&self) -> bool {
+        unsafe { show_selection_overlay(&self.0) }
+    }
+
+    fn expand_snippet(&self, expansion: String) -> bool {
+        expand_snippet(expansion)
+    }
+}
+
+/// Gesture activation: open the overlay at the mouse cursor, over a text field or not
+#[cfg(target_os = "macos")]
+pub(crate) fn handle_gesture(app: &AppHandle) {
+    if crate::event_tap::triggers_held_back() || debounced(&Trigger::Slash) {
+        return;
+    }
+    eprintln!("[DEBUG] Gesture trigger detected");
+
+    let cursor = match crate::text_field_detector::get_cursor_position() {
+        Ok(cursor) => cursor,
+        Err(e) => {
+            eprintln!("[ERROR] No cursor position for overlay: {}", e);
+            return;
+        }
+    };
+    crate::telemetry::record(crate::telemetry::EVENT_TRIGGER_GESTURE);
+    let bounds = FieldBounds {
+        x: cursor.x,
+        y: cursor.y,
+        width: cursor.width,
+        height: cursor.height,
+        display_id: cursor.display_id,
+        scale_factor: cursor.scale_factor,
+        coordinates: cursor.coordinates,
+    };
+    unsafe { present_overlay(app, bounds, "gesture", OverlayRoute::default()) };
+}
+
+/// Clipboard-only mode: the focused field can't be verified, read, or written
+fn handle_clipboard_only_trigger(app: &AppHandle, trigger: Trigger) -> bool {
+    let shortcuts_only = SHORTCUTS_ONLY.load(Ordering::Relaxed);
+    let trigger_id = trigger.id();
+    let route = match trigger {
+        // The hotkey is unambiguous: open the overlay at the cursor and copy the response instead
+        Trigger::QuickTransform => {
+            crate::telemetry::record(crate::telemetry::EVENT_TRIGGER_QUICK_TRANSFORM);
+            OverlayRoute::default()
+        }
+        // So is a fallback shortcut
+        Trigger::Slash if shortcuts_only => {
+            crate::telemetry::record(crate::telemetry::EVENT_TRIGGER_SLASH);
+            OverlayRoute::default()
+        }
+        Trigger::Named { name, action } if shortcuts_only => OverlayRoute {
+            trigger: Some(name),
+            action,
+            ..OverlayRoute::default()
+        },
+        // A bare "/" may not be in a text field at all, so let it through
+        Trigger::Slash | Trigger::Command(_) | Trigger::Named { .. } => return false,
+        // Actions and snippets write to the field in place, and there's no selection to read
+        Trigger::Action(_) | Trigger::Snippet(_) | Trigger::Selection => return false,
+    };
+
+    match crate::text_field_detector::get_cursor_position() {
+        Ok(cursor) => unsafe {
+            present_overlay(
+                app,
+                FieldBounds {
+                    x: cursor.x,
+                    y: cursor.y,
+                    width: cursor.width,
+                    height: cursor.height,
+                    display_id: cursor.display_id,
+                    scale_factor: cursor.scale_factor,
+                    coordinates: cursor.coordinates,
+                },
+                trigger_id,
+                route,
+            )
+        },
+        Err(e) => {
+            eprintln!("[ERROR] No cursor position for overlay: {}", e);
+            false
+        }
+    }
+}
+
+/// Show the overlay if a text field is focused
+unsafe fn show_overlay(app: &AppHandle, trigger_id: &'static str, route: OverlayRoute) -> bool {
+    eprintln!("[DEBUG] Trigger sequence detected! Checking for text field...");
+
+    // Get focused text field bounds
+    let bounds = match crate::backends::pipeline().overlay_target() {
+        Ok((bounds, _)) => bounds,
+        Err(e) => {
+            eprintln!("[DEBUG] No text field found: {}", e);
+            return false;
+        }
+    };
+    eprintln!(
+        "[DEBUG] Text field bounds: x={}, y={}, w={}, h={}",
+        bounds.x, bounds.y, bounds.width, bounds.height
+    );
 
-    // Pass through all other events
-    event
+    crate::telemetry::record(crate::telemetry::EVENT_TRIGGER_SLASH);
+    present_overlay(app, bounds, trigger_id, route)
 }
 
-pub fn start_monitoring(app: AppHandle) -> Result<(), String> {
+/// Show the overlay pre-filled with the selection; a click with nothing selected is just a click
+unsafe fn show_selection_overlay(app: &AppHandle) -> bool {
+    let (bounds, _, selection) = match crate::backends::pipeline().selection_target() {
+        Ok(target) => target,
+        Err(e) => {
+            eprintln!("[DEBUG] No selection to open the overlay with: {}", e);
+            return false;
+        }
+    };
+    eprintln!("[DEBUG] Selection trigger detected");
+
+    crate::telemetry::record(crate::telemetry::EVENT_TRIGGER_SELECTION);
+    let route = OverlayRoute {
+        selection: Some(selection),
+        ..OverlayRoute::default()
+    };
+    present_overlay(app, bounds, Trigger::Selection.id(), route)
+}
+
+/// Modifier keys held right now, which for a chord are the ones it was pressed with
+fn held_modifiers() -> Vec<&'static str> {
+    #[cfg(target_os = "macos")]
+    unsafe {
+        let cls = objc::runtime::Class::get("NSEvent").unwrap();
+        let flags: u64 = msg_send![cls, modifierFlags];
+        crate::hotkey::modifier_names(flags)
+    }
+    // Only the macOS monitor reports modifiers as CGEventFlags
+    #[cfg(not(target_os = "macos"))]
+    Vec::new()
+}
+
+/// Appearance around where the overlay is about to open
+#[cfg(target_os = "macos")]
+fn target_appearance(bounds: &FieldBounds) -> Option<crate::appearance::TargetAppearance> {
+    Some(crate::appearance::appearance_at(
+        bounds.x + bounds.width / 2.0,
+        bounds.y + bounds.height / 2.0,
+    ))
+}
+
+/// Emit trigger-detected and bring up the overlay next to the caret
+unsafe fn present_overlay(
+    app: &AppHandle,
+    bounds: FieldBounds,
+    trigger_id: &'static str,
+    route: OverlayRoute,
+) -> bool {
+    // Remember the target app before the overlay takes focus
+    crate::context::remember_target_app();
+
+    // Emit Tauri event with bounds and show overlay
+    eprintln!("[DEBUG] Emitting trigger-detected event");
+    let detected = TriggerDetected {
+        bounds,
+        route,
+        trigger_id,
+        modifiers: held_modifiers(),
+        bundle_id: crate::context::target_app().and_then(|app| app.bundle_id),
+        #[cfg(target_os = "macos")]
+        appearance: target_appearance(&bounds),
+        window: crate::text_field_detector::focused_window_frame(),
+        timestamp: chrono::Local::now().to_rfc3339(),
+    };
+    let _ = app.emit("trigger-detected", detected);
+
+    // Show and focus the overlay window
+    if let Some(window) = app.get_webview_window("overlay") {
+        eprintln!("[DEBUG] Showing overlay window");
+
+        // Next to the caret, kept on its screen, and following it while the overlay is open
+        #[cfg(target_os = "macos")]
+        {
+            crate::overlay_placement::position_overlay(&window, bounds);
+            crate::focus_observer::track_caret();
+        }
+
+        let _ = window.show();
+        let _ = window.set_focus();
+
+        eprintln!(
+            "[DEBUG] Window shown, is_visible: {:?}",
+            window.is_visible()
+        );
+    }
+
+    true
+}
+
+/// Replace a just-typed abbreviation; the monitor erases it once this returns true
+fn expand_snippet(expansion: String) -> bool {
+    // Outside a text field the abbreviation is just text
+    if let Err(e) = crate::backends::pipeline().overlay_target() {
+        eprintln!("[DEBUG] No text field for snippet: {}", e);
+        return false;
+    }
+    eprintln!("[DEBUG] Snippet abbreviation detected");
+    crate::telemetry::record(crate::telemetry::EVENT_TRIGGER_SNIPPET);
+
+    std::thread::spawn(move || {
+        std::thread::sleep(SNIPPET_INJECT_DELAY);
+        if let Err(e) = crate::backends::pipeline().inject(&expansion) {
+            eprintln!("[ERROR] Snippet expansion failed: {}", e);
+        }
+    });
+    true
+}
+
+/// Grab the selection, run the default quick action (or a user action), and replace it in place
+fn run_quick_transform(app: &AppHandle, action: Option<&str>) {
+    let result = match action {
+        Some(id) => crate::actions::run(id),
+        None => crate::llm::get_quick_action()
+            .and_then(|instruction| crate::backends::pipeline().quick_transform(&instruction)),
+    };
+
+    match result {
+        Ok(_) => eprintln!("[DEBUG] Quick transform complete"),
+        Err(e) => {
+            eprintln!("[ERROR] Quick transform failed: {}", e);
+            crate::telemetry::record(crate::telemetry::EVENT_QUICK_TRANSFORM_FAILED);
+            let _ = app.emit("quick-transform-failed", e);
+        }
+    }
+}
+
+/// Record a state change, mirror it in the tray tooltip, and emit its event
+fn report_state<S: serde::Serialize + Clone>(
+    app: &AppHandle,
+    state: MonitorState,
+    event: &str,
+    payload: S,
+) {
+    if let Ok(mut current) = MONITOR_STATE.write() {
+        *current = state;
+    }
+    update_tray_tooltip(app);
+    let _ = app.emit(event, payload);
+}
+
+/// Mirror the monitor state, and whether the focused field can take text, in the tray tooltip
+pub(crate) fn update_tray_tooltip(app: &AppHandle) {
+    let state = MONITOR_STATE
+        .read()
+        .map(|state| *state)
+        .unwrap_or(MonitorState::Stopped);
+    // Only macOS follows focus into other apps' fields so far
+    #[cfg(target_os = "macos")]
+    let field_focused = crate::focus_observer::field_focused();
+    #[cfg(not(target_os = "macos"))]
+    let field_focused = false;
+    let tooltip = if state == MonitorState::Running && field_focused {
+        "Prompt OS: ready to write here"
+    } else {
+        state.tooltip()
+    };
+    if let Some(tray) = app.tray_by_id(crate::TRAY_ID) {
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
+
+pub fn start_monitoring(app: AppHandle) -> Result<(), PromptOsError> {
     eprintln!("[DEBUG] start_monitoring called");
 
-    // Store app handle for event emission
-    *APP_HANDLE.lock().unwrap() = Some(app);
-
-    // Spawn background thread for event monitoring
-    std::thread::spawn(|| {
-        unsafe {
-            eprintln!("[DEBUG] Event monitor thread started");
-
-            // Event mask for keyDown events
-            let event_mask: u64 = 1 << K_CG_EVENT_KEY_DOWN;
-
-            // Create the event tap
-            let tap = CGEventTapCreate(
-                K_CG_EVENT_TAP_LOCATION_HID,
-                K_CG_EVENT_TAP_HEAD_INSERT,
-                K_CG_EVENT_TAP_OPTION_DEFAULT,
-                event_mask,
-                event_tap_callback,
-                std::ptr::null_mut(),
+    let monitor = app.state::<PlatformMonitor>();
+    #[cfg(target_os = "macos")]
+    {
+        let handle = app.clone();
+        monitor.on_status(Arc::new(move |event| {
+            let _ = match event {
+                MonitorEvent::Reenabled(health) => handle.emit("monitor-tap-disabled", health),
+                MonitorEvent::PermissionLost => {
+                    report_state(
+                        &handle,
+                        MonitorState::PermissionLost,
+                        "monitor-permission-lost",
+                        (),
+                    );
+                    Ok(())
+                }
+                MonitorEvent::SecureInput(active) => {
+                    // A password prompt just came up; don't leave the overlay over it
+                    if let Some(window) = handle.get_webview_window("overlay").filter(|_| active) {
+                        let _ = window.hide();
+                    }
+                    handle.emit("secure-input-active", active)
+                }
+            };
+        }));
+    }
+
+    let handler = AppTriggers(app.clone());
+    let sink: TriggerSink =
+        Arc::new(move |trigger| prompt_os_pipeline::dispatch(&handler, trigger));
+    let result = monitor.start(sink.clone());
+
+    // Without the tap, global shortcuts keep the overlay reachable
+    #[cfg(target_os = "macos")]
+    {
+        let fallback = match &result {
+            Ok(()) => {
+                crate::shortcut_fallback::stop(&app);
+                None
+            }
+            Err(e) => match crate::shortcut_fallback::start(&app, matcher_config().chords, sink) {
+                Ok(shortcuts) => Some(crate::shortcut_fallback::ShortcutFallback {
+                    reason: e.to_string(),
+                    shortcuts,
+                }),
+                Err(fallback_error) => {
+                    eprintln!("[ERROR] Shortcut fallback failed: {}", fallback_error);
+                    None
+                }
+            },
+        };
+        SHORTCUTS_ONLY.store(fallback.is_some(), Ordering::Relaxed);
+        if let Some(fallback) = fallback {
+            report_state(
+                &app,
+                MonitorState::ShortcutsOnly,
+                "monitor-fallback",
+                fallback,
             );
+            return Ok(());
+        }
+    }
+
+    match &result {
+        Ok(()) => report_state(&app, MonitorState::Running, "monitor-started", ()),
+        Err(e) => report_state(&app, MonitorState::Failed, "monitor-error", e),
+    }
+    result
+}
+
+/// Restore the saved trigger settings (called once during setup, before monitoring starts)
+pub fn load_settings() {
+    crate::settings::restore(TRIGGER_SEQUENCES_FILE, apply_trigger_sequences);
+    crate::settings::restore(ACTIVATION_FILE, apply_activation_config);
+    crate::settings::restore(APP_FILTER_FILE, |filter| {
+        apply_trigger_app_filter(filter).map(|_| ())
+    });
+    crate::settings::restore(SNIPPETS_FILE, apply_snippets);
+    crate::settings::restore(LISTEN_ONLY_FILE, |enabled| {
+        LISTEN_ONLY.store(enabled, Ordering::Relaxed);
+        Ok(())
+    });
+    crate::settings::restore(NAMED_TRIGGERS_FILE, apply_named_triggers);
+    crate::settings::restore(MOUSE_TRIGGERS_FILE, apply_mouse_triggers);
+    crate::settings::restore(SELECTION_HOTKEY_FILE, apply_selection_hotkey);
+    crate::settings::restore(PLACEMENT_FILE, apply_overlay_placement);
+}
+
+/// Start the monitor again whenever it dies while it should be running (called once during
+/// setup); a monitor that was stopped, failed, or lost its permission is left alone
+pub fn start_watchdog(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut restarts = 0;
+        loop {
+            std::thread::sleep(WATCHDOG_INTERVAL);
+            let running = MONITOR_STATE
+                .read()
+                .is_ok_and(|state| *state == MonitorState::Running);
+            if !running || !app.state::<PlatformMonitor>().died() {
+                continue;
+            }
 
-            if tap.is_null() {
-                eprintln!("[ERROR] Failed to create event tap - NO ACCESSIBILITY PERMISSION!");
-                eprintln!("[ERROR] Grant Accessibility permission to: target/debug/prompt-os");
-                return;
+            restarts += 1;
+            eprintln!(
+                "[ERROR] Keystroke monitor died, restarting ({} so far)",
+                restarts
+            );
+            // A failed restart reports "monitor-error" and leaves the monitor stopped
+            if start_monitoring(app.clone()).is_ok() {
+                let _ = app.emit("monitor-restarted", MonitorRestart { restarts });
             }
+        }
+    });
+}
 
-            eprintln!("[DEBUG] Event tap created successfully!");
+#[tauri::command]
+pub fn get_trigger_sequences() -> Result<Vec<String>, PromptOsError> {
+    let sequences = TRIGGER_SEQUENCES
+        .read()
+        .map_err(|_| "Failed to lock trigger sequences".to_string())?;
+    if sequences.is_empty() {
+        return Ok(vec![DEFAULT_TRIGGER_SEQUENCE.to_string()]);
+    }
+    Ok(sequences.clone())
+}
 
-            // Store for cleanup (as usize for thread safety)
-            *EVENT_TAP_REF.lock().unwrap() = Some(tap as usize);
+/// Replace the typed sequences that open the overlay (e.g. "//" or "::ai"); empty restores "/"
+#[tauri::command]
+pub fn set_trigger_sequences(sequences: Vec<String>) -> Result<(), PromptOsError> {
+    apply_trigger_sequences(sequences.clone())?;
+    crate::settings::save(TRIGGER_SEQUENCES_FILE, &sequences)
+}
 
-            // Wrap in CFMachPort to create run loop source
-            let mach_port = CFMachPort::wrap_under_create_rule(tap as *mut _);
+fn apply_trigger_sequences(sequences: Vec<String>) -> Result<(), PromptOsError> {
+    for sequence in &sequences {
+        let chars = sequence.chars().count();
+        if sequence.trim().is_empty() || chars > MAX_SEQUENCE_CHARS {
+            return Err(format!(
+                "Trigger sequence \"{}\" must be 1 to {} characters",
+                sequence, MAX_SEQUENCE_CHARS
+            )
+            .into());
+        }
+    }
 
-            // Create run loop source
-            let run_loop_source = mach_port
-                .create_runloop_source(0)
-                .expect("Failed to create run loop source");
+    *TRIGGER_SEQUENCES
+        .write()
+        .map_err(|_| "Failed to lock trigger sequences".to_string())? = sequences;
+    refresh_matcher();
+    Ok(())
+}
 
-            // Add to current run loop
-            let run_loop = CFRunLoop::get_current();
-            run_loop.add_source(&run_loop_source, kCFRunLoopCommonModes);
+#[tauri::command]
+pub fn get_activation_config() -> Result<ActivationConfig, PromptOsError> {
+    let activation = ACTIVATION
+        .read()
+        .map_err(|_| "Failed to lock activation config".to_string())?;
+    Ok(ActivationConfig {
+        mode: activation.mode,
+        chords: activation
+            .chords
+            .iter()
+            .map(|(hotkey, _)| hotkey.clone())
+            .collect(),
+        timeout_ms: activation.timeout_ms,
+        hold_key: activation.hold_key.as_ref().map(|(key, _)| key.clone()),
+        hold_ms: activation.hold_ms,
+        modifier_key: activation.modifier_key,
+        debounce_ms: activation.debounce_ms,
+    })
+}
 
-            // Enable the tap
-            CGEventTapEnable(tap, true);
-            eprintln!("[DEBUG] Event tap enabled, entering run loop...");
+/// Switch activation mode, and set the typing timeout and the trigger debounce
+#[tauri::command]
+pub fn set_activation_config(config: ActivationConfig) -> Result<(), PromptOsError> {
+    apply_activation_config(config.clone())?;
+    crate::settings::save(ACTIVATION_FILE, &config)
+}
 
-            // Run the event loop (blocks this thread)
-            CFRunLoop::run_current();
+fn apply_activation_config(config: ActivationConfig) -> Result<(), PromptOsError> {
+    let chords = config
+        .chords
+        .iter()
+        .map(|hotkey| crate::hotkey::parse(hotkey).map(|chord| (hotkey.clone(), chord)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if config.mode == ActivationMode::Chord && chords.is_empty() {
+        return Err("Chord mode needs at least one chord".into());
+    }
+    let hold_key = match config.hold_key {
+        Some(key) => {
+            let keycode = crate::hotkey::keycode(&key.trim().to_lowercase())
+                .ok_or_else(|| format!("Unsupported hold key \"{}\"", key))?;
+            Some((key, keycode))
         }
-    });
+        None => None,
+    };
+    if let Some(hold_ms) = config.hold_ms.filter(|ms| !HOLD_MS_RANGE.contains(ms)) {
+        return Err(format!(
+            "Hold time {} ms must be {} to {} ms",
+            hold_ms,
+            HOLD_MS_RANGE.start(),
+            HOLD_MS_RANGE.end()
+        )
+        .into());
+    }
+    if let Some(debounce_ms) = config.debounce_ms.filter(|ms| *ms > MAX_DEBOUNCE_MS) {
+        return Err(format!(
+            "Debounce {} ms must be at most {} ms",
+            debounce_ms, MAX_DEBOUNCE_MS
+        )
+        .into());
+    }
 
+    *ACTIVATION
+        .write()
+        .map_err(|_| "Failed to lock activation config".to_string())? = Activation {
+        mode: config.mode,
+        chords,
+        timeout_ms: config.timeout_ms,
+        hold_key,
+        hold_ms: config.hold_ms,
+        modifier_key: config.modifier_key,
+        debounce_ms: config.debounce_ms,
+    };
+    refresh_matcher();
     Ok(())
 }
 
 #[tauri::command]
-pub fn start_monitoring_command(app: AppHandle) -> Result<(), String> {
-    start_monitoring(app)
+pub fn get_trigger_app_filter() -> Result<AppFilter, PromptOsError> {
+    APP_FILTER
+        .read()
+        .map(|filter| filter.clone())
+        .map_err(|_| "Failed to lock app filter".into())
 }
 
+/// Replace the per-app filter; an empty allow list means triggers never fire
 #[tauri::command]
-pub fn stop_monitoring() -> Result<(), String> {
-    eprintln!("[DEBUG] stop_monitoring called");
+pub fn set_trigger_app_filter(filter: AppFilter) -> Result<AppFilter, PromptOsError> {
+    let filter = apply_trigger_app_filter(filter)?;
+    crate::settings::save(APP_FILTER_FILE, &filter)?;
+    Ok(filter)
+}
+
+/// Trim and dedupe the app list, then install the filter; returns what was installed
+fn apply_trigger_app_filter(filter: AppFilter) -> Result<AppFilter, PromptOsError> {
+    let mut apps: Vec<String> = Vec::new();
+    for app in filter.apps {
+        let app = app.trim().to_string();
+        if !app.is_empty() && !apps.contains(&app) {
+            apps.push(app);
+        }
+    }
+
+    let filter = AppFilter {
+        mode: filter.mode,
+        apps,
+    };
+    *APP_FILTER
+        .write()
+        .map_err(|_| "Failed to lock app filter".to_string())? = filter.clone();
+    Ok(filter)
+}
+
+/// Takes effect the next time monitoring starts
+#[tauri::command]
+pub fn set_listen_only(enabled: bool) -> Result<(), PromptOsError> {
+    LISTEN_ONLY.store(enabled, Ordering::Relaxed);
+    crate::settings::save(LISTEN_ONLY_FILE, &enabled)
+}
+
+#[tauri::command]
+pub fn get_listen_only() -> Result<bool, PromptOsError> {
+    Ok(listen_only())
+}
+
+#[tauri::command]
+pub fn get_mouse_triggers() -> Result<MouseTriggers, PromptOsError> {
+    MOUSE_TRIGGERS
+        .read()
+        .map(|triggers| *triggers)
+        .map_err(|_| "Failed to lock mouse triggers".into())
+}
+
+#[tauri::command]
+pub fn set_mouse_triggers(triggers: MouseTriggers) -> Result<(), PromptOsError> {
+    apply_mouse_triggers(triggers)?;
+    crate::settings::save(MOUSE_TRIGGERS_FILE, &triggers)
+}
+
+fn apply_mouse_triggers(triggers: MouseTriggers) -> Result<(), PromptOsError> {
+    *MOUSE_TRIGGERS
+        .write()
+        .map_err(|_| "Failed to lock mouse triggers".to_string())? = triggers;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_selection_hotkey() -> Result<Option<String>, PromptOsError> {
+    SELECTION_HOTKEY
+        .read()
+        .map(|hotkey| hotkey.as_ref().map(|(hotkey, _)| hotkey.clone()))
+        .map_err(|_| "Failed to lock selection hotkey".into())
+}
+
+/// Set or (with None) clear the selection hotkey; with nothing selected it reaches the app as usual
+#[tauri::command]
+pub fn set_selection_hotkey(hotkey: Option<String>) -> Result<(), PromptOsError> {
+    apply_selection_hotkey(hotkey.clone())?;
+    crate::settings::save(SELECTION_HOTKEY_FILE, &hotkey)
+}
+
+fn apply_selection_hotkey(hotkey: Option<String>) -> Result<(), PromptOsError> {
+    let hotkey = match hotkey {
+        Some(hotkey) => {
+            let chord = crate::hotkey::parse(&hotkey)?;
+            Some((hotkey, chord))
+        }
+        None => None,
+    };
+
+    *SELECTION_HOTKEY
+        .write()
+        .map_err(|_| "Failed to lock selection hotkey".to_string())? = hotkey;
+    refresh_matcher();
+    Ok(())
+}
+
+/// What the overlay is anchored to when it opens
+pub fn placement_mode() -> PlacementMode {
+    PLACEMENT_MODE.read().map(|mode| *mode).unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn get_overlay_placement() -> Result<PlacementMode, PromptOsError> {
+    Ok(placement_mode())
+}
+
+/// Open the overlay next to the caret, or docked to the side of the focused window (macOS)
+#[tauri::command]
+pub fn set_overlay_placement(mode: PlacementMode) -> Result<(), PromptOsError> {
+    apply_overlay_placement(mode)?;
+    crate::settings::save(PLACEMENT_FILE, &mode)
+}
+
+fn apply_overlay_placement(mode: PlacementMode) -> Result<(), PromptOsError> {
+    *PLACEMENT_MODE
+        .write()
+        .map_err(|_| "Failed to lock overlay placement".to_string())? = mode;
+    Ok(())
+}
 
-    // Stop the event tap
-    if let Ok(mut guard) = EVENT_TAP_REF.lock() {
-        if let Some(tap_addr) = guard.take() {
-            unsafe {
-                let tap = tap_addr as CFMachPortRef;
-                CGEventTapEnable(tap, false);
+#[tauri::command]
+pub fn get_snippets() -> Result<Vec<Snippet>, PromptOsError> {
+    SNIPPETS
+        .read()
+        .map(|snippets| snippets.clone())
+        .map_err(|_| "Failed to lock snippets".into())
+}
+
+/// Replace the snippet registry; abbreviations must be unique, at most 32 characters, and not
+/// contain one another
+#[tauri::command]
+pub fn set_snippets(snippets: Vec<Snippet>) -> Result<(), PromptOsError> {
+    apply_snippets(snippets.clone())?;
+    crate::settings::save(SNIPPETS_FILE, &snippets)
+}
+
+fn apply_snippets(snippets: Vec<Snippet>) -> Result<(), PromptOsError> {
+    for (i, snippet) in snippets.iter().enumerate() {
+        let chars = snippet.abbreviation.chars().count();
+        if snippet.abbreviation.trim().is_empty() || chars > MAX_ABBREVIATION_CHARS {
+            return Err(format!(
+                "Abbreviation \"{}\" must be 1 to {} characters",
+                snippet.abbreviation, MAX_ABBREVIATION_CHARS
+            )
+            .into());
+        }
+        if snippets[..i]
+            .iter()
+            .any(|other| other.abbreviation == snippet.abbreviation)
+        {
+            return Err(format!("Abbreviation \"{}\" is used twice", snippet.abbreviation).into());
+        }
+    }
+    // Expansion fires as soon as an abbreviation is complete, so ":sig" would hide ":sig2"
+    for snippet in &snippets {
+        let mut before_last = snippet.abbreviation.chars();
+        before_last.next_back();
+        let before_last = before_last.as_str();
+        if let Some(other) = snippets
+            .iter()
+            .find(|other| before_last.contains(other.abbreviation.as_str()))
+        {
+            return Err(format!(
+                "Abbreviation \"{}\" could never be typed past \"{}\"",
+                snippet.abbreviation, other.abbreviation
+            )
+            .into());
+        }
+    }
+
+    *SNIPPETS
+        .write()
+        .map_err(|_| "Failed to lock snippets".to_string())? = snippets;
+    refresh_matcher();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_named_triggers() -> Result<Vec<NamedTrigger>, PromptOsError> {
+    NAMED_TRIGGERS
+        .read()
+        .map(|named| named.clone())
+        .map_err(|_| "Failed to lock named triggers".into())
+}
+
+/// Replace the named trigger registry; names and keys must be unique, chords must parse, and typed
+/// keys follow the snippet abbreviation limit
+#[tauri::command]
+pub fn set_named_triggers(triggers: Vec<NamedTrigger>) -> Result<(), PromptOsError> {
+    apply_named_triggers(triggers.clone())?;
+    crate::settings::save(NAMED_TRIGGERS_FILE, &triggers)
+}
+
+fn apply_named_triggers(triggers: Vec<NamedTrigger>) -> Result<(), PromptOsError> {
+    for (i, named) in triggers.iter().enumerate() {
+        if named.name.trim().is_empty() {
+            return Err("Every trigger needs a name".into());
+        }
+        match named.kind {
+            NamedTriggerKind::Chord => {
+                crate::hotkey::parse(&named.keys)?;
+            }
+            NamedTriggerKind::Typed => {
+                let chars = named.keys.chars().count();
+                if named.keys.trim().is_empty() || chars > MAX_ABBREVIATION_CHARS {
+                    return Err(format!(
+                        "Trigger \"{}\" must be 1 to {} characters",
+                        named.keys, MAX_ABBREVIATION_CHARS
+                    )
+                    .into());
+                }
+            }
+        }
+        for other in &triggers[..i] {
+            if other.name == named.name {
+                return Err(format!("Trigger name \"{}\" is used twice", named.name).into());
+            }
+            let same_keys = match named.kind {
+                // "shift+cmd+t" is the same chord as "cmd+shift+t"
+                NamedTriggerKind::Chord => {
+                    crate::hotkey::parse(&other.keys).ok() == crate::hotkey::parse(&named.keys).ok()
+                }
+                NamedTriggerKind::Typed => other.keys == named.keys,
+            };
+            if other.kind == named.kind && same_keys {
+                return Err(format!("Trigger \"{}\" is used twice", named.keys).into());
             }
         }
     }
 
-    // Clear app handle
-    *APP_HANDLE.lock().unwrap() = None;
+    *NAMED_TRIGGERS
+        .write()
+        .map_err(|_| "Failed to lock named triggers".to_string())? = triggers;
+    refresh_matcher();
+    Ok(())
+}
+
+/// Start, or restart with the current configuration; a running monitor is stopped and joined first
+#[tauri::command]
+pub fn start_monitoring_command(app: AppHandle) -> Result<(), PromptOsError> {
+    start_monitoring(app)
+}
 
+#[tauri::command]
+pub fn stop_monitoring(
+    app: AppHandle,
+    monitor: State<'_, PlatformMonitor>,
+) -> Result<(), PromptOsError> {
+    eprintln!("[DEBUG] stop_monitoring called");
+    monitor.stop()?;
+    #[cfg(target_os = "macos")]
+    {
+        crate::shortcut_fallback::stop(&app);
+        SHORTCUTS_ONLY.store(false, Ordering::Relaxed);
+    }
+    report_state(&app, MonitorState::Stopped, "monitor-stopped", ());
     Ok(())
 }
+
+#[tauri::command]
+pub fn get_monitor_state() -> Result<MonitorState, PromptOsError> {
+    MONITOR_STATE
+        .read()
+        .map(|state| *state)
+        .map_err(|_| "Failed to lock monitor state".into())
+}
@@ -0,0 +1,203 @@
+// Language detection using NLLanguageRecognizer
+// Powers the auto-language response mode and translate quick actions
+
+use crate::error::PromptOsError;
+use crate::objc_util::nsstring_to_string;
+use cocoa::base::{id, nil};
+use cocoa::foundation::NSString;
+use objc::msg_send;
+use objc::sel;
+use objc::sel_impl;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[link(name = "NaturalLanguage", kind = "framework")]
+extern "C" {}
+
+// Below this the recognizer is mostly guessing (short or mixed text)
+const MIN_CONFIDENCE: f64 = 0.5;
+
+// Enough text for a stable guess without scanning huge documents
+const MAX_DETECTION_CHARS: usize = 2000;
+
+// Built-in targets for the "translate selection to X" quick actions
+const TRANSLATION_LANGUAGES: &[(&str, &str)] = &[
+    ("en", "English"),
+    ("ja", "Japanese"),
+    ("zh-Hans", "Chinese (Simplified)"),
+    ("ko", "Korean"),
+    ("es", "Spanish"),
+    ("fr", "French"),
+    ("de", "German"),
+    ("pt", "Portuguese"),
+];
+
+const SETTINGS_FILE: &str = "auto_language.json";
+
+static AUTO_LANGUAGE: AtomicBool = AtomicBool::new(false);
+
+#[derive(serde::Serialize, Clone)]
+pub struct DetectedLanguage {
+    /// BCP-47 code, e.g. "ja"
+    pub code: String,
+    /// English name, e.g. "Japanese"
+    pub name: String,
+    pub confidence: f64,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct TranslationLanguage {
+    pub code: String,
+    pub name: String,
+}
+
+/// Detect the dominant language of a piece of text
+pub fn detect_language(text: &str) -> Option<DetectedLanguage> {
+    let text: String = text.chars().take(MAX_DETECTION_CHARS).collect();
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    unsafe {
+        let cls = objc::runtime::Class::get("NLLanguageRecognizer")?;
+        let recognizer: id = msg_send![cls, new];
+        if recognizer == nil {
+            return None;
+        }
+
+        let ns_text = NSString::alloc(nil).init_str(&text);
+        let _: () = msg_send![recognizer, processString: ns_text];
+
+        let language: id = msg_send![recognizer, dominantLanguage];
+        let code = nsstring_to_string(language);
+
+        let confidence = if language != nil {
+            let hypotheses: id = msg_send![recognizer, languageHypothesesWithMaximum: 1usize];
+            let score: id = if hypotheses != nil {
+                msg_send![hypotheses, objectForKey: language]
+            } else {
+                nil
+            };
+            if score != nil {
+                msg_send![score, doubleValue]
+            } else {
+                0.0
+            }
+        } else {
+            0.0
+        };
+
+        let _: () = msg_send![recognizer, release];
+
+        let code = code.filter(|code| code != "und")?;
+        let name = language_name(&code).unwrap_or_else(|| code.clone());
+        Some(DetectedLanguage {
+            code,
+            name,
+            confidence,
+        })
+    }
+}
+
+/// English display name for a language code
+fn language_name(code: &str) -> Option<String> {
+    unsafe {
+        let cls = objc::runtime::Class::get("NSLocale")?;
+        let identifier = NSString::alloc(nil).init_str("en");
+        let locale: id = msg_send![cls, localeWithLocaleIdentifier: identifier];
+        if locale == nil {
+            return None;
+        }
+
+        let ns_code = NSString::alloc(nil).init_str(code);
+        let name: id = msg_send![locale, localizedStringForLanguageCode: ns_code];
+        nsstring_to_string(name)
+    }
+}
+
+#[tauri::command]
+pub fn detect_text_language(text: String) -> Result<Option<DetectedLanguage>, PromptOsError> {
+    Ok(detect_language(&text))
+}
+
+/// Detect the language of the target field's selection, or its whole value
+pub fn detect_field_language(pid: Option<i32>) -> Option<DetectedLanguage> {
+    let (selection, value) = crate::text_field_detector::read_field_text(pid);
+    let text = selection
+        .filter(|s| !s.trim().is_empty())
+        .or(value)
+        .filter(|s| !s.trim().is_empty())?;
+
+    detect_confident_language(&text)
+}
+
+/// Language of text, only when the recognizer is reasonably sure of it
+pub fn detect_confident_language(text: &str) -> Option<DetectedLanguage> {
+    detect_language(text).filter(|language| language.confidence >= MIN_CONFIDENCE)
+}
+
+#[tauri::command]
+pub fn get_field_language() -> Result<Option<DetectedLanguage>, PromptOsError> {
+    let pid = crate::context::target_app().map(|app| app.pid);
+    Ok(detect_field_language(pid))
+}
+
+pub fn auto_language_enabled() -> bool {
+    AUTO_LANGUAGE.load(Ordering::Relaxed)
+}
+
+/// When on, the model is told to respond in the field's detected language
+#[tauri::command]
+pub fn set_auto_language_mode(enabled: bool) -> Result<(), PromptOsError> {
+    AUTO_LANGUAGE.store(enabled, Ordering::Relaxed);
+    crate::settings::save(SETTINGS_FILE, &enabled)
+}
+
+/// Restore the saved mode (called once during setup)
+pub fn load_settings() {
+    crate::settings::restore(SETTINGS_FILE, |enabled| {
+        AUTO_LANGUAGE.store(enabled, Ordering::Relaxed);
+        Ok(())
+    });
+}
+
+#[tauri::command]
+pub fn get_auto_language_mode() -> Result<bool, PromptOsError> {
+    Ok(auto_language_enabled())
+}
+
+#[tauri::command]
+pub fn list_translation_languages() -> Result<Vec<TranslationLanguage>, PromptOsError> {
+    Ok(TRANSLATION_LANGUAGES
+        .iter()
+        .map(|(code, name)| TranslationLanguage {
+            code: code.to_string(),
+            name: name.to_string(),
+        })
+        .collect())
+}
+
+/// Translate text into a target language (code like "ja" or a name like "Japanese")
+#[tauri::command]
+pub async fn translate_text(
+    text: String,
+    target_language: String,
+) -> Result<String, PromptOsError> {
+    // The model call can take up to its timeout; keep it off the main thread
+    tauri::async_runtime::spawn_blocking(move || translate(text, target_language))
+        .await
+        .map_err(|e| format!("Translation failed: {}", e))?
+}
+
+fn translate(text: String, target_language: String) -> Result<String, PromptOsError> {
+    if text.trim().is_empty() {
+        return Err("Nothing to translate".into());
+    }
+
+    let name = TRANSLATION_LANGUAGES
+        .iter()
+        .find(|(code, _)| code.eq_ignore_ascii_case(&target_language))
+        .map(|(_, name)| name.to_string())
+        .unwrap_or(target_language);
+
+    crate::llm::translate(&text, &name)
+}
@@ -1,7 +1,91 @@
+mod actions;
+mod app_blocklist;
+#[cfg(target_os = "macos")]
+mod appearance;
+#[cfg(target_os = "linux")]
+mod atspi_detector;
+mod backends;
+#[cfg(target_os = "macos")]
+mod benchmark;
+#[cfg(target_os = "macos")]
+mod calendar_context;
+#[cfg(target_os = "macos")]
+mod clipboard_watcher;
+mod content_filter;
+mod context;
+#[cfg(target_os = "macos")]
+mod document_context;
+#[cfg(target_os = "macos")]
+mod editor_context;
+#[cfg(target_os = "macos")]
+mod electron;
+mod error;
+#[cfg(target_os = "macos")]
+mod field_capture;
+#[cfg(target_os = "macos")]
+mod event_tap;
+#[cfg(target_os = "macos")]
+mod focus_mode;
+#[cfg(target_os = "macos")]
+mod focus_observer;
+mod frontmost_app;
+mod fuzzy;
+#[cfg(target_os = "macos")]
+mod gestures;
+#[cfg(target_os = "linux")]
+mod global_shortcuts;
+mod history;
+mod hotkey;
+#[cfg(target_os = "macos")]
+mod injection_undo;
+mod key_rotation;
 mod keychain;
+#[cfg(target_os = "windows")]
+mod keyboard_hook;
+#[cfg(target_os = "macos")]
+mod keyboard_layout;
 mod keystroke_monitor;
+#[cfg(target_os = "macos")]
+mod language;
+mod llm;
+#[cfg(target_os = "macos")]
+mod mail_context;
+#[cfg(target_os = "macos")]
+mod objc_util;
+#[cfg(target_os = "macos")]
+mod ocr;
+#[cfg(target_os = "macos")]
+mod overlay_placement;
+#[cfg(target_os = "macos")]
+mod permissions;
+mod power;
+mod presets;
+mod quota;
+mod redaction;
+#[cfg(target_os = "macos")]
+mod secure_input;
+#[cfg(target_os = "macos")]
+mod sessions;
+mod settings;
+#[cfg(target_os = "macos")]
+mod shortcut_fallback;
+#[cfg(target_os = "macos")]
+mod speech;
+#[cfg(target_os = "macos")]
+mod spell_checker;
+#[cfg(target_os = "macos")]
+mod stream_injection;
+mod style_memory;
+mod telemetry;
+#[cfg(target_os = "macos")]
 mod text_field_detector;
+#[cfg(target_os = "macos")]
 mod text_injector;
+mod typing_context;
+#[cfg(target_os = "windows")]
+mod uia_detector;
+#[cfg(target_os = "linux")]
+mod x11_record;
 
 use tauri::{
     menu::{Menu, MenuItem},
@@ -9,25 +93,56 @@ use tauri::{
     Manager,
 };
 
+// The detector commands come from UI Automation on Windows and AT-SPI on Linux
+#[cfg(target_os = "linux")]
+use atspi_detector as text_field_detector;
+#[cfg(target_os = "windows")]
+use uia_detector as text_field_detector;
+
+// Lets monitor status changes reach the tray tooltip
+pub(crate) const TRAY_ID: &str = "main";
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_shell::init())
+    let builder = tauri::Builder::default().plugin(tauri_plugin_shell::init());
+    // Fallback shortcuts for when the event tap can't be created
+    #[cfg(target_os = "macos")]
+    let builder = builder.plugin(tauri_plugin_global_shortcut::Builder::new().build());
+
+    builder
         .setup(|app| {
             // Create tray menu
             let quit = MenuItem::with_id(app, "quit", "Quit Prompt OS", true, None::<&str>)?;
             let settings =
                 MenuItem::with_id(app, "settings", "Settings...", true, None::<&str>)?;
+            // The menu bar doesn't take focus, so the field written to is still the focused one
+            #[cfg(target_os = "macos")]
+            let undo = MenuItem::with_id(app, "undo", "Undo Last Insertion", true, None::<&str>)?;
+            // Humanized typing can run for minutes while the field it types into has focus
+            #[cfg(target_os = "macos")]
+            let stop_typing =
+                MenuItem::with_id(app, "stop_typing", "Stop Typing", true, None::<&str>)?;
+            #[cfg(target_os = "macos")]
+            let menu = Menu::with_items(app, &[&undo, &stop_typing, &settings, &quit])?;
+            // Only macOS writes into other apps so far, so elsewhere there is nothing to undo
+            #[cfg(not(target_os = "macos"))]
             let menu = Menu::with_items(app, &[&settings, &quit])?;
 
             // Create tray icon
-            let _tray = TrayIconBuilder::new()
+            let _tray = TrayIconBuilder::with_id(TRAY_ID)
                 .icon(app.default_window_icon().unwrap().clone())
                 .menu(&menu)
                 .on_menu_event(|app, event| match event.id.as_ref() {
-                    "quit" => {
-                        app.exit(0);
+                    // Deferred writes are saved on RunEvent::Exit
+                    "quit" => app.exit(0),
+                    #[cfg(target_os = "macos")]
+                    "undo" => {
+                        if let Err(e) = injection_undo::undo_last_injection() {
+                            eprintln!("[ERROR] Failed to undo the last insertion: {}", e);
+                        }
                     }
+                    #[cfg(target_os = "macos")]
+                    "stop_typing" => text_injector::cancel_typing(),
                     "settings" => {
                         if let Some(window) = app.get_webview_window("settings") {
                             let _ = window.show();
@@ -38,6 +153,76 @@ pub fn run() {
                 })
                 .build(app)?;
 
+            // Load prompt history, the app blocklist, injection strategies, saved settings, and
+            // user actions from the app data directory
+            match app.path().app_data_dir() {
+                Ok(data_dir) => {
+                    if let Err(e) = history::init(data_dir.clone()) {
+                        eprintln!("[ERROR] Failed to load history: {}", e);
+                    }
+                    if let Err(e) = app_blocklist::init(data_dir.clone()) {
+                        eprintln!("[ERROR] Failed to load blocklist: {}", e);
+                    }
+                    #[cfg(target_os = "macos")]
+                    if let Err(e) = text_injector::init(data_dir.clone()) {
+                        eprintln!("[ERROR] Failed to load injection strategies: {}", e);
+                    }
+                    match settings::init(data_dir.clone()) {
+                        Ok(()) => {
+                            redaction::load_settings();
+                            content_filter::load_settings();
+                            style_memory::load_settings();
+                            keystroke_monitor::load_settings();
+                            typing_context::load_settings();
+                            telemetry::load_settings();
+                            key_rotation::load_settings();
+                            #[cfg(target_os = "macos")]
+                            {
+                                language::load_settings();
+                                clipboard_watcher::load_settings(app.handle());
+                                focus_observer::load_settings();
+                                focus_mode::load_settings();
+                                gestures::load_settings();
+                                text_injector::load_settings();
+                            }
+                        }
+                        Err(e) => eprintln!("[ERROR] Failed to load settings: {}", e),
+                    }
+                    if let Err(e) = actions::init(app.handle().clone(), data_dir) {
+                        eprintln!("[ERROR] Failed to load actions: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("[ERROR] No app data directory: {}", e),
+            }
+
+            // Quota updates are pushed to the UI as events
+            quota::init(app.handle().clone());
+
+            // Batches opt-in telemetry in the background
+            telemetry::start();
+
+            // Writes history deferred on battery
+            history::start();
+
+            // Chords and the hold key follow the keyboard layout
+            #[cfg(target_os = "macos")]
+            keyboard_layout::start();
+
+            // Dims the tray icon while a Focus pauses triggers
+            #[cfg(target_os = "macos")]
+            focus_mode::start(app.handle().clone());
+
+            // Pushes "field-focused" / "field-blurred" as focus moves in the frontmost app
+            #[cfg(target_os = "macos")]
+            focus_observer::start(app.handle().clone());
+
+            // Trackpad gesture activation, once one is configured
+            #[cfg(target_os = "macos")]
+            gestures::start(app.handle());
+
+            // The monitor lives in managed state so commands can stop and restart it
+            app.manage(keystroke_monitor::PlatformMonitor::default());
+
             // Start keystroke monitoring on launch
             let app_handle = app.handle().clone();
             eprintln!("[DEBUG] App setup complete, starting keystroke monitor...");
@@ -49,20 +234,223 @@ pub fn run() {
                 }
             });
 
+            // Brings the monitor back if its thread dies
+            keystroke_monitor::start_watchdog(app.handle().clone());
+
             Ok(())
         })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Focused(focused) = event {
+                keystroke_monitor::window_focus_changed(window.label(), *focused);
+            }
+        })
         .invoke_handler(tauri::generate_handler![
+            style_memory::set_style_memory_enabled,
+            style_memory::get_style_memory_enabled,
+            style_memory::get_style_profile,
+            typing_context::get_typing_context_config,
+            typing_context::set_typing_context_config,
+            typing_context::read_typing_context,
+            typing_context::purge_typing_context,
+            telemetry::get_telemetry_config,
+            telemetry::set_telemetry_config,
+            telemetry::preview_telemetry,
+            telemetry::flush_telemetry,
             text_field_detector::get_focused_text_field_bounds,
             text_field_detector::check_accessibility_permission,
             text_field_detector::get_cursor_position,
+            text_field_detector::get_focused_field_info,
+            text_field_detector::get_selected_text,
+            text_field_detector::get_field_content,
+            text_field_detector::get_surrounding_text,
+            #[cfg(target_os = "macos")]
+            field_capture::capture_field_screenshot,
+            #[cfg(target_os = "macos")]
+            ocr::recognize_field_text,
+            #[cfg(target_os = "macos")]
+            focus_observer::set_caret_tracking,
+            #[cfg(target_os = "macos")]
+            focus_observer::get_caret_tracking,
+            #[cfg(target_os = "macos")]
+            appearance::get_target_appearance,
+            #[cfg(target_os = "macos")]
             text_injector::insert_text,
+            #[cfg(target_os = "macos")]
             text_injector::insert_text_via_paste,
+            #[cfg(target_os = "macos")]
+            text_injector::insert_rich_text,
+            #[cfg(target_os = "macos")]
+            text_injector::insert_text_via_typing,
+            #[cfg(target_os = "macos")]
+            text_injector::cancel_typing,
+            #[cfg(target_os = "macos")]
+            text_injector::get_typing_pace,
+            #[cfg(target_os = "macos")]
+            text_injector::set_typing_pace,
+            #[cfg(target_os = "macos")]
+            text_injector::inject_text,
+            #[cfg(target_os = "macos")]
+            text_injector::get_injection_strategies,
+            #[cfg(target_os = "macos")]
+            text_injector::set_app_injection_strategy,
+            #[cfg(target_os = "macos")]
+            text_injector::remove_app_injection_strategy,
+            #[cfg(target_os = "macos")]
+            stream_injection::start_stream_injection,
+            #[cfg(target_os = "macos")]
+            stream_injection::push_chunk,
+            #[cfg(target_os = "macos")]
+            stream_injection::end_stream,
+            #[cfg(target_os = "macos")]
+            injection_undo::undo_last_injection,
+            #[cfg(target_os = "macos")]
+            text_injector::probe_injection_capabilities,
+            #[cfg(target_os = "macos")]
+            text_injector::verify_injection_target,
+            history::record_history_entry,
+            history::get_history,
+            history::clear_history,
             keychain::store_api_key,
             keychain::retrieve_api_key,
             keychain::delete_api_key,
+            keychain::list_api_keys,
+            keychain::add_api_key,
+            keychain::remove_api_key,
+            key_rotation::get_key_rotation_mode,
+            key_rotation::set_key_rotation_mode,
+            key_rotation::acquire_api_keys,
+            key_rotation::report_api_key_result,
             keystroke_monitor::start_monitoring_command,
             keystroke_monitor::stop_monitoring,
+            keystroke_monitor::get_monitor_state,
+            keystroke_monitor::get_trigger_sequences,
+            keystroke_monitor::set_trigger_sequences,
+            keystroke_monitor::get_activation_config,
+            keystroke_monitor::set_activation_config,
+            keystroke_monitor::get_trigger_app_filter,
+            keystroke_monitor::set_trigger_app_filter,
+            keystroke_monitor::get_listen_only,
+            keystroke_monitor::set_listen_only,
+            keystroke_monitor::get_snippets,
+            keystroke_monitor::set_snippets,
+            keystroke_monitor::get_named_triggers,
+            keystroke_monitor::set_named_triggers,
+            keystroke_monitor::get_mouse_triggers,
+            keystroke_monitor::set_mouse_triggers,
+            keystroke_monitor::get_selection_hotkey,
+            keystroke_monitor::set_selection_hotkey,
+            keystroke_monitor::get_overlay_placement,
+            keystroke_monitor::set_overlay_placement,
+            actions::list_actions,
+            actions::reload_actions,
+            app_blocklist::get_blocklist,
+            app_blocklist::add_blocked_app,
+            app_blocklist::remove_blocked_app,
+            app_blocklist::update_blocklist,
+            #[cfg(target_os = "macos")]
+            benchmark::run_benchmark,
+            #[cfg(target_os = "macos")]
+            calendar_context::get_date_context,
+            #[cfg(target_os = "macos")]
+            calendar_context::check_calendar_permission,
+            #[cfg(target_os = "macos")]
+            calendar_context::request_calendar_permission,
+            #[cfg(target_os = "macos")]
+            calendar_context::get_calendar_events,
+            #[cfg(target_os = "macos")]
+            clipboard_watcher::set_clipboard_suggestions_enabled,
+            #[cfg(target_os = "macos")]
+            clipboard_watcher::get_clipboard_suggestions_enabled,
+            content_filter::get_content_filter_config,
+            content_filter::set_content_filter_config,
+            content_filter::filter_response,
+            #[cfg(target_os = "macos")]
+            context::collect_prompt_context,
+            #[cfg(target_os = "macos")]
+            document_context::get_focused_document,
+            #[cfg(target_os = "macos")]
+            focus_mode::get_focus_pause_config,
+            #[cfg(target_os = "macos")]
+            focus_mode::set_focus_pause_config,
+            #[cfg(target_os = "macos")]
+            focus_mode::get_focus_state,
+            frontmost_app::get_frontmost_app,
+            fuzzy::fuzzy_search_prompts,
+            fuzzy::fuzzy_rank,
+            #[cfg(target_os = "macos")]
+            gestures::get_gesture_trigger,
+            #[cfg(target_os = "macos")]
+            gestures::set_gesture_trigger,
+            #[cfg(target_os = "macos")]
+            language::detect_text_language,
+            #[cfg(target_os = "macos")]
+            language::get_field_language,
+            #[cfg(target_os = "macos")]
+            language::set_auto_language_mode,
+            #[cfg(target_os = "macos")]
+            language::get_auto_language_mode,
+            #[cfg(target_os = "macos")]
+            language::list_translation_languages,
+            #[cfg(target_os = "macos")]
+            language::translate_text,
+            llm::get_quick_action,
+            llm::set_quick_action,
+            #[cfg(target_os = "macos")]
+            permissions::get_permission_status,
+            #[cfg(target_os = "macos")]
+            permissions::request_accessibility_permission,
+            #[cfg(target_os = "macos")]
+            permissions::open_permission_settings,
+            power::get_power_status,
+            presets::list_presets,
+            quota::get_quota_status,
+            quota::report_quota_headers,
+            redaction::get_redaction_config,
+            redaction::set_redaction_config,
+            redaction::redact_text,
+            redaction::redact_texts,
+            redaction::restore_redacted,
+            #[cfg(target_os = "macos")]
+            secure_input::check_secure_input,
+            #[cfg(target_os = "macos")]
+            sessions::start_session,
+            #[cfg(target_os = "macos")]
+            sessions::add_session_turn,
+            #[cfg(target_os = "macos")]
+            sessions::export_session,
+            #[cfg(target_os = "macos")]
+            sessions::inject_into_origin,
+            #[cfg(target_os = "macos")]
+            speech::speak_text,
+            #[cfg(target_os = "macos")]
+            speech::pause_speech,
+            #[cfg(target_os = "macos")]
+            speech::resume_speech,
+            #[cfg(target_os = "macos")]
+            speech::stop_speech,
+            #[cfg(target_os = "macos")]
+            speech::get_speech_status,
+            #[cfg(target_os = "macos")]
+            speech::list_speech_voices,
+            #[cfg(target_os = "macos")]
+            spell_checker::check_text,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running Prompt OS");
+        .build(tauri::generate_context!())
+        .expect("error while building Prompt OS")
+        .run(|_app, event| {
+            // However the app quits, keep what was only in memory
+            if let tauri::RunEvent::Exit = event {
+                save_before_exit();
+            }
+        });
+}
+
+/// Write deferred history and unsent telemetry counts
+fn save_before_exit() {
+    if let Err(e) = history::flush() {
+        eprintln!("[ERROR] Failed to flush history: {}", e);
+    }
+    if let Err(e) = telemetry::save_pending() {
+        eprintln!("[ERROR] Failed to save telemetry: {}", e);
+    }
 }
@@ -0,0 +1,259 @@
+// Gemini client for flows that run entirely in Rust
+// Mirrors src/lib/gemini.ts but mostly uses the non-streaming generateContent endpoint
+
+use crate::error::PromptOsError;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[cfg(target_os = "macos")]
+use std::io::{BufRead, BufReader};
+#[cfg(target_os = "macos")]
+use std::time::Instant;
+
+const MODELS_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models";
+const DEFAULT_MODEL: &str = "gemini-3-flash-preview";
+#[cfg(target_os = "macos")]
+const STREAM_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models/gemini-3-flash-preview:streamGenerateContent?alt=sse";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Default action applied by the quick-transform hotkey
+const DEFAULT_QUICK_ACTION: &str = "Fix the spelling and grammar of the following text. \
+Keep the original meaning, tone, formatting, and language. \
+Return only the corrected text with no explanations or quotes.";
+
+static QUICK_ACTION: Mutex<Option<String>> = Mutex::new(None);
+
+/// Per-request overrides (user-defined actions set these)
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
+pub struct GenerationOptions {
+    pub model: Option<String>,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub max_output_tokens: Option<u32>,
+}
+
+/// Send a single prompt to Gemini and return the full response text
+pub fn generate(prompt: &str, system_prompt: Option<&str>) -> Result<String, PromptOsError> {
+    generate_with(prompt, system_prompt, &GenerationOptions::default())
+}
+
+/// generate with a model and sampling parameters other than the defaults
+pub fn generate_with(
+    prompt: &str,
+    system_prompt: Option<&str>,
+    options: &GenerationOptions,
+) -> Result<String, PromptOsError> {
+    // Strip PII before anything leaves the machine; placeholders are restored below
+    let mut replacements = Vec::new();
+    let prompt = crate::redaction::redact_with(prompt, &mut replacements);
+    let system_prompt = system_prompt
+        .map(|system_prompt| crate::redaction::redact_with(system_prompt, &mut replacements));
+
+    let mut body = serde_json::json!({
+        "contents": [{ "parts": [{ "text": prompt }] }],
+    });
+
+    if let Some(system_prompt) = system_prompt {
+        body["systemInstruction"] = serde_json::json!({ "parts": [{ "text": system_prompt }] });
+    }
+
+    let mut generation_config = serde_json::Map::new();
+    if let Some(temperature) = options.temperature {
+        generation_config.insert("temperature".to_string(), temperature.into());
+    }
+    if let Some(top_p) = options.top_p {
+        generation_config.insert("topP".to_string(), top_p.into());
+    }
+    if let Some(max_output_tokens) = options.max_output_tokens {
+        generation_config.insert("maxOutputTokens".to_string(), max_output_tokens.into());
+    }
+    if !generation_config.is_empty() {
+        body["generationConfig"] = generation_config.into();
+    }
+
+    let url = format!(
+        "{}/{}:generateContent",
+        MODELS_URL,
+        options.model.as_deref().unwrap_or(DEFAULT_MODEL)
+    );
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    eprintln!("[DEBUG] Gemini request: {}", url);
+
+    let res = send_with_rotation(&client, &url, &body)?;
+
+    let json: serde_json::Value = res
+        .json()
+        .map_err(|e| PromptOsError::Provider(format!("Invalid Gemini response: {}", e)))?;
+
+    let text = extract_text(&json)
+        .ok_or_else(|| PromptOsError::Provider("Gemini returned no text".to_string()))?;
+    Ok(crate::redaction::restore(&text, &replacements))
+}
+
+/// POST with each stored key in rotation order, failing over on 429 and 5xx
+fn send_with_rotation(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    body: &serde_json::Value,
+) -> Result<reqwest::blocking::Response, PromptOsError> {
+    let keys = crate::key_rotation::candidates()?;
+    let last = keys.len() - 1;
+
+    for (index, stored) in keys.iter().enumerate() {
+        let res = match client
+            .post(url)
+            .header("x-goog-api-key", &stored.key)
+            .json(body)
+            .send()
+        {
+            Ok(res) => res,
+            Err(e) => {
+                crate::key_rotation::record(&stored.id, None);
+                return Err(request_error(e));
+            }
+        };
+
+        let status = res.status().as_u16();
+        let headers = crate::quota::header_pairs(res.headers());
+        crate::key_rotation::record(&stored.id, Some(status));
+        if res.status().is_success() {
+            crate::quota::update(&headers, status, None);
+            return Ok(res);
+        }
+
+        let error_text = res.text().unwrap_or_default();
+        crate::quota::update(&headers, status, Some(&error_text));
+        if index < last && crate::key_rotation::should_fail_over(status) {
+            eprintln!(
+                "[DEBUG] Gemini key \"{}\" returned {}, trying the next key",
+                stored.label, status
+            );
+            continue;
+        }
+        return Err(api_error(status, &error_text));
+    }
+
+    Err(PromptOsError::ProviderAuth(
+        "No API key configured. Add it in Settings.".to_string(),
+    ))
+}
+
+fn api_error(status: u16, error_text: &str) -> PromptOsError {
+    eprintln!("[ERROR] Gemini API error {}: {}", status, error_text);
+    crate::telemetry::record(crate::telemetry::EVENT_LLM_ERROR);
+
+    match status {
+        429 => PromptOsError::RateLimited,
+        401 | 403 => {
+            PromptOsError::ProviderAuth("Invalid API key. Update it in Settings.".to_string())
+        }
+        _ => PromptOsError::Provider(format!("Gemini API error {}: {}", status, error_text)),
+    }
+}
+
+fn request_error(e: reqwest::Error) -> PromptOsError {
+    if e.is_timeout() {
+        PromptOsError::Timeout("Gemini took too long to respond".to_string())
+    } else {
+        PromptOsError::Provider(format!("Gemini request failed: {}", e))
+    }
+}
+
+/// Stream a prompt and time it: (time to first token, total time)
+#[cfg(target_os = "macos")]
+pub fn measure_stream(prompt: &str) -> Result<(Duration, Duration), PromptOsError> {
+    let body = serde_json::json!({
+        "contents": [{ "parts": [{ "text": prompt }] }],
+    });
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let started = Instant::now();
+    let res = send_with_rotation(&client, STREAM_URL, &body)?;
+
+    // Server-sent events: each "data:" line carries a partial response
+    let mut first_token = None;
+    for line in BufReader::new(res).lines() {
+        let line =
+            line.map_err(|e| PromptOsError::Provider(format!("Gemini stream failed: {}", e)))?;
+        let Some(data) = line.strip_prefix("data:") else {
+            continue;
+        };
+        if first_token.is_none() {
+            let has_text = serde_json::from_str::<serde_json::Value>(data.trim())
+                .ok()
+                .and_then(|json| extract_text(&json))
+                .is_some();
+            if has_text {
+                first_token = Some(started.elapsed());
+            }
+        }
+    }
+
+    let total = started.elapsed();
+    let first_token = first_token
+        .ok_or_else(|| PromptOsError::Provider("Gemini returned no text".to_string()))?;
+    Ok((first_token, total))
+}
+
+/// Concatenate the text parts of the first candidate
+fn extract_text(json: &serde_json::Value) -> Option<String> {
+    let parts = json["candidates"][0]["content"]["parts"].as_array()?;
+    let text: String = parts
+        .iter()
+        .filter_map(|part| part["text"].as_str())
+        .collect();
+
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// Run the configured quick action over a piece of text
+#[cfg(target_os = "macos")]
+pub fn quick_transform(text: &str) -> Result<String, PromptOsError> {
+    let instruction = get_quick_action()?;
+    generate(text, Some(&instruction))
+}
+
+/// Translate text, preserving formatting
+#[cfg(target_os = "macos")]
+pub fn translate(text: &str, target_language: &str) -> Result<String, PromptOsError> {
+    let instruction = format!(
+        "Translate the following text into {}. Preserve the formatting, tone, and meaning. \
+Return only the translation with no explanations or quotes.",
+        target_language
+    );
+    generate(text, Some(&instruction))
+}
+
+#[tauri::command]
+pub fn get_quick_action() -> Result<String, PromptOsError> {
+    let guard = QUICK_ACTION
+        .lock()
+        .map_err(|_| "Failed to lock quick action".to_string())?;
+    Ok(guard
+        .clone()
+        .unwrap_or_else(|| DEFAULT_QUICK_ACTION.to_string()))
+}
+
+/// Override the quick action instruction (None restores the default)
+#[tauri::command]
+pub fn set_quick_action(instruction: Option<String>) -> Result<(), PromptOsError> {
+    let instruction = instruction.filter(|s| !s.trim().is_empty());
+    *QUICK_ACTION
+        .lock()
+        .map_err(|_| "Failed to lock quick action".to_string())? = instruction;
+    Ok(())
+}
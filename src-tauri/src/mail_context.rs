@@ -0,0 +1,225 @@
+// Mail thread context extraction for Mail and Outlook
+// Finds the message being replied to via Apple Events, falling back to the AX tree
+
+use crate::error::PromptOsError;
+use crate::frontmost_app::FrontmostApp;
+use crate::objc_util::cf_release;
+use crate::text_field_detector::{application_element, copy_attribute, copy_string_attribute};
+use accessibility_sys::*;
+use core_foundation::array::{
+    CFArrayGetCount, CFArrayGetTypeID, CFArrayGetValueAtIndex, CFArrayRef,
+};
+use core_foundation::base::{CFGetTypeID, CFTypeRef};
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const MAIL_BUNDLE_ID: &str = "com.apple.mail";
+const OUTLOOK_BUNDLE_ID: &str = "com.microsoft.Outlook";
+
+// Keep prompts bounded even for long threads
+const MAX_BODY_CHARS: usize = 8000;
+const MAX_TREE_DEPTH: usize = 40;
+
+// A busy mail app, or the first-run Automation consent prompt, can hold the script indefinitely
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(3);
+const SCRIPT_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+// ASCII unit separator between AppleScript result fields
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+const MAIL_SCRIPT: &str = r#"
+tell application "Mail"
+    set msgs to selection
+    if (count of msgs) is 0 then return ""
+    set m to item 1 of msgs
+    return (sender of m) & (ASCII character 31) & (subject of m) & (ASCII character 31) & (content of m)
+end tell
+"#;
+
+const OUTLOOK_SCRIPT: &str = r#"
+tell application "Microsoft Outlook"
+    set msgs to selected objects
+    if (count of msgs) is 0 then return ""
+    set m to item 1 of msgs
+    set s to sender of m
+    return (name of s) & " <" & (address of s) & ">" & (ASCII character 31) & (subject of m) & (ASCII character 31) & (plain text content of m)
+end tell
+"#;
+
+#[derive(serde::Serialize, Clone)]
+pub struct MailMessage {
+    pub sender: Option<String>,
+    pub subject: Option<String>,
+    pub body: String,
+    /// "apple-events" or "accessibility"
+    pub source: String,
+}
+
+pub fn is_mail_app(bundle_id: &str) -> bool {
+    bundle_id == MAIL_BUNDLE_ID || bundle_id == OUTLOOK_BUNDLE_ID
+}
+
+/// Extract the message the user is replying to, if the app is a supported mail client
+pub fn extract_reply_context(app: &FrontmostApp) -> Option<MailMessage> {
+    let bundle_id = app.bundle_id.as_deref()?;
+    let script = match bundle_id {
+        MAIL_BUNDLE_ID => MAIL_SCRIPT,
+        OUTLOOK_BUNDLE_ID => OUTLOOK_SCRIPT,
+        _ => return None,
+    };
+
+    match run_applescript(script) {
+        Ok(output) => {
+            if let Some(message) = parse_script_output(&output) {
+                return Some(message);
+            }
+            eprintln!("[DEBUG] No selected message in {}", bundle_id);
+        }
+        Err(e) => eprintln!("[DEBUG] Apple Events mail lookup failed: {}", e),
+    }
+
+    // Fallback: scrape visible text from the focused window (includes quoted reply)
+    let body = unsafe { read_window_text(app.pid) }?;
+    Some(MailMessage {
+        sender: None,
+        subject: None,
+        body,
+        source: "accessibility".to_string(),
+    })
+}
+
+/// Run the script, killing it if it hasn't finished within SCRIPT_TIMEOUT
+fn run_applescript(script: &str) -> Result<String, PromptOsError> {
+    let mut child = Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run osascript: {}", e))?;
+    // Drained while waiting, so a long message can't fill the pipe and stall the script
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() >= SCRIPT_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(PromptOsError::Timeout(
+                    "The mail app didn't answer in time".to_string(),
+                ));
+            }
+            Ok(None) => thread::sleep(SCRIPT_POLL_INTERVAL),
+            Err(e) => return Err(format!("Failed to wait for osascript: {}", e).into()),
+        }
+    };
+
+    if !status.success() {
+        let stderr = stderr.join().unwrap_or_default();
+        return Err(String::from_utf8_lossy(&stderr).trim().into());
+    }
+
+    let stdout = stdout.join().unwrap_or_default();
+    Ok(String::from_utf8_lossy(&stdout).trim_end().to_string())
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        bytes
+    })
+}
+
+fn parse_script_output(output: &str) -> Option<MailMessage> {
+    let mut fields = output.splitn(3, FIELD_SEPARATOR);
+    let sender = fields.next()?.trim();
+    let subject = fields.next()?.trim();
+    let body = fields.next()?.trim();
+
+    if body.is_empty() {
+        return None;
+    }
+
+    Some(MailMessage {
+        sender: non_empty(sender),
+        subject: non_empty(subject),
+        body: truncate_chars(body, MAX_BODY_CHARS),
+        source: "apple-events".to_string(),
+    })
+}
+
+/// Collect static text from the app's focused window
+unsafe fn read_window_text(pid: i32) -> Option<String> {
+    let app_element = application_element(pid);
+    if app_element.is_null() {
+        return None;
+    }
+
+    let window = copy_attribute(app_element, "AXFocusedWindow");
+    cf_release(app_element as CFTypeRef);
+    let window = window?;
+
+    let mut text = String::new();
+    collect_text(window as AXUIElementRef, 0, &mut text);
+    cf_release(window);
+
+    let text = text.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(truncate_chars(text, MAX_BODY_CHARS))
+    }
+}
+
+unsafe fn collect_text(element: AXUIElementRef, depth: usize, out: &mut String) {
+    if depth > MAX_TREE_DEPTH || out.len() >= MAX_BODY_CHARS {
+        return;
+    }
+
+    if let Some(role) = copy_string_attribute(element, "AXRole") {
+        if role == "AXStaticText" || role == "AXTextArea" {
+            if let Some(value) = copy_string_attribute(element, "AXValue") {
+                let value = value.trim();
+                if !value.is_empty() {
+                    out.push_str(value);
+                    out.push('\n');
+                }
+            }
+        }
+    }
+
+    let Some(children) = copy_attribute(element, "AXChildren") else {
+        return;
+    };
+
+    if CFGetTypeID(children) == CFArrayGetTypeID() {
+        let count = CFArrayGetCount(children as CFArrayRef);
+        for i in 0..count {
+            // Array elements are borrowed, not owned
+            let child = CFArrayGetValueAtIndex(children as CFArrayRef, i);
+            collect_text(child as AXUIElementRef, depth + 1, out);
+        }
+    }
+
+    cf_release(children);
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s.to_string())
+    }
+}
+
+fn truncate_chars(s: &str, max_chars: usize) -> String {
+    s.chars().take(max_chars).collect()
+}
@@ -0,0 +1,33 @@
+// Conversions between Objective-C / Core Foundation values and Rust ones
+// Shared by the modules that read from AppKit, Foundation, and Accessibility
+
+use cocoa::base::{id, nil};
+use core_foundation::base::CFTypeRef;
+use objc::msg_send;
+use objc::sel;
+use objc::sel_impl;
+
+/// Convert an NSString to a Rust String
+pub(crate) unsafe fn nsstring_to_string(ns_string: id) -> Option<String> {
+    if ns_string == nil {
+        return None;
+    }
+
+    let c_str: *const i8 = msg_send![ns_string, UTF8String];
+    if c_str.is_null() {
+        return None;
+    }
+
+    Some(
+        std::ffi::CStr::from_ptr(c_str)
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+/// Safe CFRelease wrapper
+pub(crate) unsafe fn cf_release(cf: CFTypeRef) {
+    if !cf.is_null() {
+        core_foundation::base::CFRelease(cf);
+    }
+}
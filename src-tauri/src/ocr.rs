@@ -0,0 +1,156 @@
+// Text recognition over a capture of the focused field, for when Accessibility can't read it
+// Canvas-drawn editors and remote desktops expose no AXValue; Vision's reading is approximate
+
+use crate::error::PromptOsError;
+use crate::field_capture::{
+    capture_image, capture_target, release_image, CGImageRef, CaptureRegion,
+};
+use crate::permissions::Permission;
+use cocoa::base::{id, nil, BOOL, NO, YES};
+use objc::msg_send;
+use objc::sel;
+use objc::sel_impl;
+use std::ffi::CStr;
+
+// VNRequestTextRecognitionLevelAccurate
+const RECOGNITION_LEVEL_ACCURATE: isize = 0;
+
+#[link(name = "Vision", kind = "framework")]
+extern "C" {}
+
+/// Text read off the screen rather than from the field itself
+#[derive(serde::Serialize, Clone)]
+pub struct RecognizedText {
+    /// Recognized lines, in Vision's (top to bottom) order
+    pub text: String,
+    /// Mean recognition confidence, 0-1
+    pub confidence: f32,
+    /// Always "ocr", so consumers weigh it below text read through Accessibility
+    pub source: &'static str,
+}
+
+/// OCR the focused field (or its window) of the app being written to; needs Screen Recording
+#[tauri::command]
+pub async fn recognize_field_text(
+    region: Option<CaptureRegion>,
+) -> Result<Option<RecognizedText>, PromptOsError> {
+    // Capture plus accurate-level recognition takes a while; keep it off the main thread
+    tauri::async_runtime::spawn_blocking(move || recognize_field(region))
+        .await
+        .map_err(|e| format!("Text recognition failed: {}", e))?
+}
+
+fn recognize_field(region: Option<CaptureRegion>) -> Result<Option<RecognizedText>, PromptOsError> {
+    let bounds = capture_target(region.unwrap_or_default())?;
+    unsafe {
+        let image = capture_image(&bounds)?;
+        let text = recognize(image);
+        release_image(image);
+        text
+    }
+}
+
+/// Prompt context fallback for a field whose value Accessibility can't read; None unless Screen
+/// Recording was granted and something legible is there
+pub fn recognize_unreadable_field(pid: i32) -> Option<RecognizedText> {
+    if !Permission::ScreenRecording.granted() {
+        return None;
+    }
+    let (selection, value) = crate::text_field_detector::read_field_text(Some(pid));
+    if selection.or(value).is_some_and(|text| !text.is_empty()) {
+        return None;
+    }
+
+    recognize_field(Some(CaptureRegion::Field))
+        .inspect_err(|e| eprintln!("[DEBUG] No OCR fallback: {}", e))
+        .ok()
+        .flatten()
+}
+
+/// Run a Vision text request over an image; None when nothing was recognized
+unsafe fn recognize(image: CGImageRef) -> Result<Option<RecognizedText>, PromptOsError> {
+    let handler_cls =
+        objc::runtime::Class::get("VNImageRequestHandler").ok_or("Vision is unavailable")?;
+    let request_cls =
+        objc::runtime::Class::get("VNRecognizeTextRequest").ok_or("Vision is unavailable")?;
+    let array_cls = objc::runtime::Class::get("NSArray").ok_or("Failed to get NSArray class")?;
+    let dict_cls =
+        objc::runtime::Class::get("NSDictionary").ok_or("Failed to get NSDictionary class")?;
+
+    let options: id = msg_send![dict_cls, dictionary];
+    let handler: id = msg_send![handler_cls, alloc];
+    let handler: id = msg_send![handler, initWithCGImage: image options: options];
+    if handler == nil {
+        return Err("Failed to read the capture for text recognition".into());
+    }
+    let request: id = msg_send![request_cls, alloc];
+    let request: id = msg_send![request, init];
+    if request == nil {
+        let _: () = msg_send![handler, release];
+        return Err("Failed to create a text recognition request".into());
+    }
+    let _: () = msg_send![request, setRecognitionLevel: RECOGNITION_LEVEL_ACCURATE];
+    let _: () = msg_send![request, setUsesLanguageCorrection: YES];
+
+    let requests: id = msg_send![array_cls, arrayWithObject: request];
+    let mut error: id = nil;
+    let performed: BOOL = msg_send![handler, performRequests: requests error: &mut error];
+
+    let lines = if performed == NO {
+        Vec::new()
+    } else {
+        recognized_lines(request)
+    };
+    let _: () = msg_send![request, release];
+    let _: () = msg_send![handler, release];
+    if performed == NO {
+        return Err("Text recognition failed".into());
+    }
+
+    if lines.is_empty() {
+        return Ok(None);
+    }
+    let confidence = lines.iter().map(|(_, c)| c).sum::<f32>() / lines.len() as f32;
+    let text = lines
+        .into_iter()
+        .map(|(line, _)| line)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(Some(RecognizedText {
+        text,
+        confidence,
+        source: "ocr",
+    }))
+}
+
+/// Text and confidence of each observation's best candidate
+unsafe fn recognized_lines(request: id) -> Vec<(String, f32)> {
+    let results: id = msg_send![request, results];
+    if results == nil {
+        return Vec::new();
+    }
+
+    let count: usize = msg_send![results, count];
+    let mut lines = Vec::with_capacity(count);
+    for index in 0..count {
+        let observation: id = msg_send![results, objectAtIndex: index];
+        let candidates: id = msg_send![observation, topCandidates: 1usize];
+        let candidate: id = msg_send![candidates, firstObject];
+        if candidate == nil {
+            continue;
+        }
+
+        let string: id = msg_send![candidate, string];
+        let c_str: *const i8 = msg_send![string, UTF8String];
+        if c_str.is_null() {
+            continue;
+        }
+        let confidence: f32 = msg_send![candidate, confidence];
+        lines.push((
+            CStr::from_ptr(c_str).to_string_lossy().into_owned(),
+            confidence,
+        ));
+    }
+    lines
+}
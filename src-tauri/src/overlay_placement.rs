@@ -0,0 +1,118 @@
+// Positions the overlay window next to the caret, or docked to the focused window's edge
+// Gathers the field, window, and screen geometry for prompt_os_pipeline, then moves the window
+
+use crate::text_field_detector::TextFieldBounds;
+use cocoa::base::{id, nil};
+use objc::msg_send;
+use objc::sel;
+use objc::sel_impl;
+use prompt_os_pipeline::{dock_to_window, place_overlay, FieldBounds, PlacementMode, Rect};
+use tauri::{LogicalPosition, Position, WebviewWindow};
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct NSPoint {
+    x: f64,
+    y: f64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct NSSize {
+    width: f64,
+    height: f64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct NSRect {
+    origin: NSPoint,
+    size: NSSize,
+}
+
+/// Move the overlay next to the caret (or mouse position) the trigger reported, or to the side of
+/// the focused window in window-edge placement
+pub fn position_overlay(window: &WebviewWindow, anchor: FieldBounds) {
+    let caret = Rect::from(anchor);
+    let Some(screen) = visible_frame_at(caret.x, caret.y) else {
+        eprintln!(
+            "[ERROR] No screen at x={}, y={} for the overlay",
+            caret.x, caret.y
+        );
+        return;
+    };
+
+    let (Ok(size), Ok(scale)) = (window.outer_size(), window.scale_factor()) else {
+        return;
+    };
+    let size = size.to_logical::<f64>(scale);
+    let size = (size.width, size.height);
+
+    // Without a window frame (e.g. a mouse trigger over the desktop) it falls back to the caret
+    let docked = match crate::keystroke_monitor::placement_mode() {
+        PlacementMode::Caret => None,
+        PlacementMode::WindowEdge => crate::text_field_detector::focused_window_frame()
+            .map(|frame| dock_to_window(rect(frame), size, screen)),
+    };
+
+    let (x, y) = docked.unwrap_or_else(|| {
+        // The field counts only when the anchor is inside it, not the mouse somewhere else
+        let field = crate::text_field_detector::focused_field_frame()
+            .map(rect)
+            .filter(|field| contains(field, caret.x, caret.y));
+        place_overlay(caret, field, size, screen)
+    });
+    eprintln!("[DEBUG] Positioning overlay at: x={}, y={}", x, y);
+    let _ = window.set_position(Position::Logical(LogicalPosition { x, y }));
+}
+
+fn rect(frame: TextFieldBounds) -> Rect {
+    Rect {
+        x: frame.x,
+        y: frame.y,
+        width: frame.width,
+        height: frame.height,
+    }
+}
+
+fn contains(rect: &Rect, x: f64, y: f64) -> bool {
+    x >= rect.x && x <= rect.right() && y >= rect.y && y <= rect.bottom()
+}
+
+/// Visible frame (without the menu bar and Dock) of the screen containing a point, converted to
+/// global top-left coordinates like the caret
+fn visible_frame_at(x: f64, y: f64) -> Option<Rect> {
+    let screen_cls = objc::runtime::Class::get("NSScreen")?;
+    unsafe {
+        let screens: id = msg_send![screen_cls, screens];
+        let count: usize = msg_send![screens, count];
+        if count == 0 {
+            return None;
+        }
+
+        // Cocoa's y axis starts at the bottom of the first (menu bar) screen
+        let primary: id = msg_send![screens, firstObject];
+        let primary_frame: NSRect = msg_send![primary, frame];
+        let flip = |rect: NSRect| Rect {
+            x: rect.origin.x,
+            y: primary_frame.size.height - rect.origin.y - rect.size.height,
+            width: rect.size.width,
+            height: rect.size.height,
+        };
+
+        let mut fallback = None;
+        for index in 0..count {
+            let screen: id = msg_send![screens, objectAtIndex: index];
+            if screen == nil {
+                continue;
+            }
+            let frame: NSRect = msg_send![screen, frame];
+            let visible: NSRect = msg_send![screen, visibleFrame];
+            if contains(&flip(frame), x, y) {
+                return Some(flip(visible));
+            }
+            fallback.get_or_insert(flip(visible));
+        }
+        fallback
+    }
+}
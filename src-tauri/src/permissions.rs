@@ -0,0 +1,236 @@
+// macOS privacy permissions, re-checked before every trigger
+// A missing grant shows a remediation alert and drops the app into clipboard-only mode
+
+use crate::error::PromptOsError;
+use accessibility_sys::{
+    kAXTrustedCheckOptionPrompt, AXIsProcessTrusted, AXIsProcessTrustedWithOptions,
+};
+use cocoa::base::{id, nil, YES};
+use cocoa::foundation::NSString;
+use core_foundation::base::TCFType;
+use core_foundation::boolean::CFBoolean;
+use core_foundation::dictionary::CFDictionary;
+use core_foundation::string::CFString;
+use objc::msg_send;
+use objc::sel;
+use objc::sel_impl;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGPreflightScreenCaptureAccess() -> bool;
+}
+
+// NSAlert response for the first (default) button
+const NS_ALERT_FIRST_BUTTON_RETURN: isize = 1000;
+
+// After prompting for Accessibility, how long (and how often) to watch for the user granting it
+const GRANT_WATCH_TIME: Duration = Duration::from_secs(120);
+const GRANT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    Accessibility,
+    ScreenRecording,
+}
+
+impl Permission {
+    pub fn granted(self) -> bool {
+        unsafe {
+            match self {
+                Permission::Accessibility => AXIsProcessTrusted(),
+                Permission::ScreenRecording => CGPreflightScreenCaptureAccess(),
+            }
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Permission::Accessibility => "Accessibility",
+            Permission::ScreenRecording => "Screen Recording",
+        }
+    }
+
+    /// Deep link to the matching System Settings > Privacy & Security pane
+    fn settings_url(self) -> &'static str {
+        match self {
+            Permission::Accessibility => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility"
+            }
+            Permission::ScreenRecording => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture"
+            }
+        }
+    }
+}
+
+// Needed to find, read, and write the focused field; Screen Recording joins once a trigger path captures pixels
+const TRIGGER_PERMISSIONS: &[Permission] = &[Permission::Accessibility];
+
+// Set while a trigger permission is missing: the overlay copies instead of injecting
+static CLIPBOARD_ONLY: AtomicBool = AtomicBool::new(false);
+
+#[derive(serde::Serialize, Clone)]
+pub struct PermissionStatus {
+    pub accessibility: bool,
+    pub screen_recording: bool,
+    pub clipboard_only: bool,
+}
+
+fn status() -> PermissionStatus {
+    PermissionStatus {
+        accessibility: Permission::Accessibility.granted(),
+        screen_recording: Permission::ScreenRecording.granted(),
+        clipboard_only: clipboard_only(),
+    }
+}
+
+pub fn clipboard_only() -> bool {
+    CLIPBOARD_ONLY.load(Ordering::Relaxed)
+}
+
+/// Fail with a typed error when a permission hasn't been granted
+pub fn require(permission: Permission) -> Result<(), PromptOsError> {
+    if permission.granted() {
+        Ok(())
+    } else {
+        Err(PromptOsError::NoPermission(format!(
+            "{} permission is required. Enable Prompt OS in System Settings > Privacy & Security > {}.",
+            permission.label(),
+            permission.label()
+        )))
+    }
+}
+
+/// Check trigger permissions; the first miss alerts and switches to clipboard-only mode
+pub fn preflight(app: &AppHandle) -> Result<(), Permission> {
+    let missing = TRIGGER_PERMISSIONS
+        .iter()
+        .copied()
+        .find(|permission| !permission.granted());
+
+    match missing {
+        None => {
+            if CLIPBOARD_ONLY.swap(false, Ordering::Relaxed) {
+                eprintln!("[DEBUG] Permissions restored, leaving clipboard-only mode");
+                let _ = app.emit("permission-status", status());
+            }
+            Ok(())
+        }
+        Some(permission) => {
+            // Alert once per lapse rather than on every keystroke
+            if !CLIPBOARD_ONLY.swap(true, Ordering::Relaxed) {
+                eprintln!(
+                    "[ERROR] {} permission missing, switching to clipboard-only mode",
+                    permission.label()
+                );
+                let _ = app.emit("permission-status", status());
+                show_remediation_alert(app, permission);
+            }
+            Err(permission)
+        }
+    }
+}
+
+/// Native alert with a button that opens the right System Settings pane
+fn show_remediation_alert(app: &AppHandle, permission: Permission) {
+    let result = app.run_on_main_thread(move || unsafe {
+        let Some(alert_cls) = objc::runtime::Class::get("NSAlert") else {
+            return;
+        };
+        let alert: id = msg_send![alert_cls, new];
+        if alert == nil {
+            return;
+        }
+
+        let title = format!("Prompt OS needs {} access", permission.label());
+        let body = format!(
+            "Without it Prompt OS can't read or type into text fields. Until access is restored, \
+responses are copied to the clipboard for you to paste.\n\nEnable Prompt OS under Privacy & \
+Security > {}.",
+            permission.label()
+        );
+        let _: () = msg_send![alert, setMessageText: ns_string(&title)];
+        let _: () = msg_send![alert, setInformativeText: ns_string(&body)];
+        let _: id = msg_send![alert, addButtonWithTitle: ns_string("Open System Settings")];
+        let _: id = msg_send![alert, addButtonWithTitle: ns_string("Use Clipboard Only")];
+
+        // Accessory apps must come forward for the alert to be seen
+        if let Some(app_cls) = objc::runtime::Class::get("NSApplication") {
+            let ns_app: id = msg_send![app_cls, sharedApplication];
+            let _: () = msg_send![ns_app, activateIgnoringOtherApps: YES];
+        }
+
+        let response: isize = msg_send![alert, runModal];
+        let _: () = msg_send![alert, release];
+
+        if response == NS_ALERT_FIRST_BUTTON_RETURN {
+            if let Err(e) = open_settings(permission) {
+                eprintln!("[ERROR] {}", e);
+            }
+        }
+    });
+
+    if let Err(e) = result {
+        eprintln!("[ERROR] Failed to show permission alert: {}", e);
+    }
+}
+
+fn open_settings(permission: Permission) -> Result<(), PromptOsError> {
+    let status = std::process::Command::new("open")
+        .arg(permission.settings_url())
+        .status()
+        .map_err(|e| format!("Failed to open System Settings: {}", e))?;
+    if !status.success() {
+        return Err("Failed to open System Settings".into());
+    }
+    Ok(())
+}
+
+unsafe fn ns_string(s: &str) -> id {
+    NSString::alloc(nil).init_str(s)
+}
+
+#[tauri::command]
+pub fn get_permission_status() -> Result<PermissionStatus, PromptOsError> {
+    Ok(status())
+}
+
+/// Show the system's Accessibility prompt (macOS only shows it while access is missing) and watch
+/// for the grant, emitting "permission-status" once it lands; returns whether access is granted
+#[tauri::command]
+pub fn request_accessibility_permission(app: AppHandle) -> Result<bool, PromptOsError> {
+    let trusted = unsafe {
+        let options = CFDictionary::from_CFType_pairs(&[(
+            CFString::wrap_under_get_rule(kAXTrustedCheckOptionPrompt),
+            CFBoolean::true_value(),
+        )]);
+        AXIsProcessTrustedWithOptions(options.as_concrete_TypeRef())
+    };
+    if trusted {
+        return Ok(true);
+    }
+
+    std::thread::spawn(move || {
+        let started = Instant::now();
+        while started.elapsed() < GRANT_WATCH_TIME {
+            std::thread::sleep(GRANT_POLL_INTERVAL);
+            if Permission::Accessibility.granted() {
+                eprintln!("[DEBUG] Accessibility permission granted");
+                // Leaves clipboard-only mode if the missing grant had switched to it
+                let _ = preflight(&app);
+                let _ = app.emit("permission-status", status());
+                return;
+            }
+        }
+    });
+    Ok(false)
+}
+
+#[tauri::command]
+pub fn open_permission_settings(permission: Permission) -> Result<(), PromptOsError> {
+    open_settings(permission)
+}
@@ -0,0 +1,162 @@
+// Battery-aware throttling of background work
+// Reports the power profile (AC, battery, Low Power Mode) so pollers and writers can back off
+
+use crate::error::PromptOsError;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[cfg(target_os = "macos")]
+use core_foundation::base::{CFTypeRef, TCFType};
+#[cfg(target_os = "macos")]
+use core_foundation::string::{CFString, CFStringRef};
+#[cfg(target_os = "macos")]
+use objc::msg_send;
+#[cfg(target_os = "macos")]
+use objc::runtime::{BOOL, YES};
+#[cfg(target_os = "macos")]
+use objc::sel;
+#[cfg(target_os = "macos")]
+use objc::sel_impl;
+
+#[cfg(target_os = "macos")]
+#[link(name = "IOKit", kind = "framework")]
+extern "C" {
+    fn IOPSCopyPowerSourcesInfo() -> CFTypeRef;
+    fn IOPSGetProvidingPowerSourceType(snapshot: CFTypeRef) -> CFStringRef;
+}
+
+#[cfg(target_os = "macos")]
+const BATTERY_POWER: &str = "Battery Power";
+
+// Power state changes rarely; don't hit IOKit on every keystroke
+const CACHE_TTL: Duration = Duration::from_secs(30);
+
+// Polling intervals are stretched by this factor when throttled
+const THROTTLED_POLL_FACTOR: u32 = 4;
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerProfile {
+    /// On AC power: no throttling
+    Normal,
+    /// On battery: background work is reduced
+    Battery,
+    /// Low Power Mode: background work is reduced the most
+    LowPower,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct PowerStatus {
+    pub profile: PowerProfile,
+    pub on_battery: bool,
+    pub low_power_mode: bool,
+    /// Multiplier applied to background polling intervals
+    pub poll_factor: u32,
+    /// Whether history writes are being batched
+    pub deferring_writes: bool,
+}
+
+static CACHE: Mutex<Option<(Instant, PowerProfile)>> = Mutex::new(None);
+
+/// Current power profile (cached for a short while)
+pub fn power_profile() -> PowerProfile {
+    if let Ok(guard) = CACHE.lock() {
+        if let Some((checked_at, profile)) = *guard {
+            if checked_at.elapsed() < CACHE_TTL {
+                return profile;
+            }
+        }
+    }
+
+    let profile = if low_power_mode() {
+        PowerProfile::LowPower
+    } else if on_battery() {
+        PowerProfile::Battery
+    } else {
+        PowerProfile::Normal
+    };
+
+    if let Ok(mut guard) = CACHE.lock() {
+        *guard = Some((Instant::now(), profile));
+    }
+    profile
+}
+
+/// Whether background work should be reduced
+pub fn throttled() -> bool {
+    power_profile() != PowerProfile::Normal
+}
+
+/// Stretch a background polling interval according to the power profile
+pub fn poll_interval(base: Duration) -> Duration {
+    if throttled() {
+        base * THROTTLED_POLL_FACTOR
+    } else {
+        base
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn on_battery() -> bool {
+    unsafe {
+        let snapshot = IOPSCopyPowerSourcesInfo();
+        if snapshot.is_null() {
+            return false;
+        }
+
+        // Follows the get rule: owned by the snapshot
+        let source = IOPSGetProvidingPowerSourceType(snapshot);
+        let on_battery =
+            !source.is_null() && CFString::wrap_under_get_rule(source).to_string() == BATTERY_POWER;
+
+        core_foundation::base::CFRelease(snapshot);
+        on_battery
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn low_power_mode() -> bool {
+    unsafe {
+        let Some(cls) = objc::runtime::Class::get("NSProcessInfo") else {
+            return false;
+        };
+        let info: cocoa::base::id = msg_send![cls, processInfo];
+
+        // isLowPowerModeEnabled is macOS 12+
+        let responds: BOOL = msg_send![info, respondsToSelector: sel!(isLowPowerModeEnabled)];
+        if responds != YES {
+            return false;
+        }
+        let enabled: BOOL = msg_send![info, isLowPowerModeEnabled];
+        enabled == YES
+    }
+}
+
+// Only IOKit and NSProcessInfo are read so far, so elsewhere nothing is throttled
+#[cfg(not(target_os = "macos"))]
+fn on_battery() -> bool {
+    false
+}
+
+#[cfg(not(target_os = "macos"))]
+fn low_power_mode() -> bool {
+    false
+}
+
+/// Active power profile and what it currently throttles
+///
+/// The event tap stays an active filter even when throttled, since it has to swallow the
+/// trigger key; its per-keystroke work is trimmed instead.
+#[tauri::command]
+pub fn get_power_status() -> Result<PowerStatus, PromptOsError> {
+    let profile = power_profile();
+    let throttled = profile != PowerProfile::Normal;
+
+    Ok(PowerStatus {
+        profile,
+        on_battery: on_battery(),
+        low_power_mode: profile == PowerProfile::LowPower,
+        poll_factor: if throttled { THROTTLED_POLL_FACTOR } else { 1 },
+        deferring_writes: throttled,
+    })
+}
@@ -0,0 +1,77 @@
+// Built-in prompt presets offered as overlay suggestions
+// User actions from the actions directory are listed after them
+
+use crate::error::PromptOsError;
+
+#[derive(serde::Serialize, Clone)]
+pub struct Preset {
+    pub id: String,
+    pub label: String,
+    pub prompt: String,
+}
+
+const BUILTIN_PRESETS: &[(&str, &str, &str)] = &[
+    (
+        "fix",
+        "Fix spelling & grammar",
+        "Fix the spelling and grammar",
+    ),
+    (
+        "formal",
+        "Make more formal",
+        "Rewrite this to sound more formal and professional",
+    ),
+    (
+        "casual",
+        "Make more casual",
+        "Rewrite this to sound more casual and friendly",
+    ),
+    (
+        "shorter",
+        "Make shorter",
+        "Make this shorter and more concise",
+    ),
+    ("longer", "Expand", "Expand this with more detail"),
+    (
+        "summarize",
+        "Summarize",
+        "Summarize this in a few sentences",
+    ),
+    (
+        "bullets",
+        "Convert to bullet points",
+        "Convert this into bullet points",
+    ),
+    ("reply", "Draft a reply", "Draft a reply to this message"),
+    ("explain", "Explain", "Explain this in simple terms"),
+    (
+        "translate-en",
+        "Translate to English",
+        "Translate this into English",
+    ),
+    (
+        "translate-ja",
+        "Translate to Japanese",
+        "Translate this into Japanese",
+    ),
+];
+
+/// Built-in presets followed by the user's actions
+pub fn presets() -> Vec<Preset> {
+    let builtin = BUILTIN_PRESETS.iter().map(|(id, label, prompt)| Preset {
+        id: id.to_string(),
+        label: label.to_string(),
+        prompt: prompt.to_string(),
+    });
+    let actions = crate::actions::actions().into_iter().map(|action| Preset {
+        id: format!("action:{}", action.id),
+        label: action.label,
+        prompt: action.template,
+    });
+    builtin.chain(actions).collect()
+}
+
+#[tauri::command]
+pub fn list_presets() -> Result<Vec<Preset>, PromptOsError> {
+    Ok(presets())
+}
@@ -0,0 +1,208 @@
+// Provider quota feedback
+// Parses rate-limit headers and 429 retry hints so the UI can warn before a hard limit
+
+use crate::error::PromptOsError;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+// Warn once less than this share of the request or token budget is left
+const LOW_REMAINING_FRACTION: f64 = 0.1;
+
+const REMAINING_REQUESTS_HEADERS: &[&str] = &[
+    "x-ratelimit-remaining-requests",
+    "x-ratelimit-remaining",
+    "ratelimit-remaining",
+];
+const LIMIT_REQUESTS_HEADERS: &[&str] = &[
+    "x-ratelimit-limit-requests",
+    "x-ratelimit-limit",
+    "ratelimit-limit",
+];
+const REMAINING_TOKENS_HEADERS: &[&str] = &["x-ratelimit-remaining-tokens"];
+const LIMIT_TOKENS_HEADERS: &[&str] = &["x-ratelimit-limit-tokens"];
+const RESET_HEADERS: &[&str] = &[
+    "x-ratelimit-reset-requests",
+    "x-ratelimit-reset-tokens",
+    "x-ratelimit-reset",
+    "ratelimit-reset",
+];
+
+// Reset values above this are Unix timestamps rather than relative seconds
+const EPOCH_THRESHOLD: f64 = 1_000_000_000.0;
+
+#[derive(serde::Serialize, Clone, Default, Debug)]
+pub struct QuotaStatus {
+    pub remaining_requests: Option<u64>,
+    pub limit_requests: Option<u64>,
+    pub remaining_tokens: Option<u64>,
+    pub limit_tokens: Option<u64>,
+    /// RFC 3339 time the budget resets
+    pub reset_at: Option<String>,
+    pub retry_after_seconds: Option<u64>,
+    /// Last response was a 429
+    pub rate_limited: bool,
+    /// Close to the limit: the UI should warn
+    pub low: bool,
+    pub updated_at: Option<String>,
+}
+
+static STATUS: Mutex<Option<QuotaStatus>> = Mutex::new(None);
+static APP_HANDLE: Mutex<Option<AppHandle>> = Mutex::new(None);
+
+/// Keep a handle for quota-updated events (called once during setup)
+pub fn init(app: AppHandle) {
+    if let Ok(mut guard) = APP_HANDLE.lock() {
+        *guard = Some(app);
+    }
+}
+
+/// Lowercased header name/value pairs from a reqwest response
+pub fn header_pairs(headers: &reqwest::header::HeaderMap) -> HashMap<String, String> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let value = value.to_str().ok()?;
+            Some((name.as_str().to_lowercase(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Update the quota from a provider response; emits quota-updated when anything was learned
+pub fn update(headers: &HashMap<String, String>, status: u16, body: Option<&str>) -> QuotaStatus {
+    let now = chrono::Local::now();
+    let find = |names: &[&str]| names.iter().find_map(|name| headers.get(*name));
+    let number = |names: &[&str]| find(names).and_then(|value| value.trim().parse::<u64>().ok());
+
+    let rate_limited = status == 429;
+    let retry_after_seconds = headers
+        .get("retry-after")
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .or_else(|| body.and_then(retry_delay_from_body));
+
+    let reset_seconds = find(RESET_HEADERS)
+        .and_then(|value| parse_reset(value, now.timestamp()))
+        .or(retry_after_seconds.map(|seconds| seconds as f64));
+
+    let mut quota = QuotaStatus {
+        remaining_requests: number(REMAINING_REQUESTS_HEADERS),
+        limit_requests: number(LIMIT_REQUESTS_HEADERS),
+        remaining_tokens: number(REMAINING_TOKENS_HEADERS),
+        limit_tokens: number(LIMIT_TOKENS_HEADERS),
+        reset_at: reset_seconds.map(|seconds| {
+            (now + chrono::Duration::milliseconds((seconds * 1000.0) as i64)).to_rfc3339()
+        }),
+        retry_after_seconds,
+        rate_limited,
+        low: false,
+        updated_at: Some(now.to_rfc3339()),
+    };
+    quota.low = rate_limited
+        || is_low(quota.remaining_requests, quota.limit_requests)
+        || is_low(quota.remaining_tokens, quota.limit_tokens);
+
+    let learned_anything = rate_limited
+        || quota.remaining_requests.is_some()
+        || quota.remaining_tokens.is_some()
+        || quota.reset_at.is_some();
+    if !learned_anything {
+        return quota;
+    }
+
+    if let Ok(mut guard) = STATUS.lock() {
+        *guard = Some(quota.clone());
+    }
+    if let Ok(guard) = APP_HANDLE.lock() {
+        if let Some(app) = guard.as_ref() {
+            let _ = app.emit("quota-updated", quota.clone());
+        }
+    }
+    quota
+}
+
+fn is_low(remaining: Option<u64>, limit: Option<u64>) -> bool {
+    match (remaining, limit) {
+        (Some(0), _) => true,
+        (Some(remaining), Some(limit)) if limit > 0 => {
+            (remaining as f64) < limit as f64 * LOW_REMAINING_FRACTION
+        }
+        _ => false,
+    }
+}
+
+/// Seconds until reset from "30", "1700000000", or duration strings like "6m0s" / "250ms"
+fn parse_reset(value: &str, now_epoch: i64) -> Option<f64> {
+    let value = value.trim();
+    if let Ok(number) = value.parse::<f64>() {
+        return Some(if number > EPOCH_THRESHOLD {
+            (number - now_epoch as f64).max(0.0)
+        } else {
+            number
+        });
+    }
+    parse_duration(value)
+}
+
+/// Go-style duration ("1h2m3.5s", "20ms") in seconds
+fn parse_duration(value: &str) -> Option<f64> {
+    let mut total = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return None;
+        }
+        let amount: f64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+
+        let (unit, scale) = if rest.starts_with("ms") {
+            ("ms", 0.001)
+        } else if rest.starts_with('h') {
+            ("h", 3600.0)
+        } else if rest.starts_with('m') {
+            ("m", 60.0)
+        } else if rest.starts_with('s') {
+            ("s", 1.0)
+        } else {
+            return None;
+        };
+        total += amount * scale;
+        rest = &rest[unit.len()..];
+    }
+    Some(total)
+}
+
+/// Gemini puts a RetryInfo detail (retryDelay: "23s") in 429 error bodies
+fn retry_delay_from_body(body: &str) -> Option<u64> {
+    let json: serde_json::Value = serde_json::from_str(body).ok()?;
+    json["error"]["details"]
+        .as_array()?
+        .iter()
+        .find_map(|detail| detail["retryDelay"].as_str())
+        .and_then(parse_duration)
+        .map(|seconds| seconds.ceil() as u64)
+}
+
+#[tauri::command]
+pub fn get_quota_status() -> Result<Option<QuotaStatus>, PromptOsError> {
+    let guard = STATUS
+        .lock()
+        .map_err(|_| "Failed to lock quota status".to_string())?;
+    Ok(guard.clone())
+}
+
+/// Feed headers from requests made by the frontend (the overlay streams from the webview)
+#[tauri::command]
+pub fn report_quota_headers(
+    headers: HashMap<String, String>,
+    status: u16,
+    body: Option<String>,
+) -> Result<QuotaStatus, PromptOsError> {
+    let headers = headers
+        .into_iter()
+        .map(|(name, value)| (name.to_lowercase(), value))
+        .collect();
+    Ok(update(&headers, status, body.as_deref()))
+}
@@ -0,0 +1,506 @@
+// PII redaction before requests leave the machine
+// Replaces emails, phone numbers, card numbers, custom patterns, and (optionally) names with placeholders
+
+use crate::error::PromptOsError;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+#[cfg(target_os = "macos")]
+use crate::objc_util::nsstring_to_string;
+#[cfg(target_os = "macos")]
+use block::ConcreteBlock;
+#[cfg(target_os = "macos")]
+use cocoa::base::{id, nil};
+#[cfg(target_os = "macos")]
+use cocoa::foundation::{NSArray, NSRange, NSString};
+#[cfg(target_os = "macos")]
+use objc::msg_send;
+#[cfg(target_os = "macos")]
+use objc::runtime::BOOL;
+#[cfg(target_os = "macos")]
+use objc::sel;
+#[cfg(target_os = "macos")]
+use objc::sel_impl;
+#[cfg(target_os = "macos")]
+use std::sync::Arc;
+
+const EMAIL_PATTERN: &str = r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b";
+// A bare run of digits is as likely an order number, date, or ISBN, so a phone number needs a
+// country code, an area code in parentheses, a trunk 0, or the North American grouping
+const PHONE_PATTERN: &str = concat!(
+    r"\+\d{1,3}(?:[\s.-]?\(\d{1,4}\))?(?:[\s.-]?\d{2,4}){2,4}\b",
+    r"|\(\d{2,4}\)\s?\d{3,4}[\s.-]\d{4}\b",
+    r"|\b0\d{1,4}[\s.-]\d{3,4}[\s.-]\d{4}\b",
+    r"|\b\d{3}[\s.-]\d{3}[\s.-]\d{4}\b",
+);
+const CARD_PATTERN: &str = r"\b(?:\d[ -]?){12,18}\d\b";
+
+// NSLinguisticTagger options: omit whitespace | omit punctuation | join names
+#[cfg(target_os = "macos")]
+const NS_LINGUISTIC_TAGGER_OPTIONS: u64 = 2 | 4 | 16;
+// NSLinguisticTaggerUnitWord
+#[cfg(target_os = "macos")]
+const NS_LINGUISTIC_TAGGER_UNIT_WORD: i64 = 0;
+
+const SETTINGS_FILE: &str = "redaction.json";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct RedactionConfig {
+    pub enabled: bool,
+    pub emails: bool,
+    pub phone_numbers: bool,
+    pub credit_cards: bool,
+    /// Extra regexes; each match becomes a CUSTOM placeholder
+    pub custom_patterns: Vec<String>,
+    /// Person, place, and organization names via NSLinguisticTagger
+    pub named_entities: bool,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            emails: true,
+            phone_numbers: true,
+            credit_cards: true,
+            custom_patterns: Vec::new(),
+            named_entities: false,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct Replacement {
+    pub placeholder: String,
+    pub original: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct RedactedText {
+    pub text: String,
+    pub replacements: Vec<Replacement>,
+}
+
+struct CompiledConfig {
+    config: RedactionConfig,
+    custom: Vec<Regex>,
+}
+
+static CONFIG: Mutex<Option<CompiledConfig>> = Mutex::new(None);
+
+fn builtin(pattern: &'static str, cell: &'static OnceLock<Regex>) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("built-in redaction pattern is valid"))
+}
+
+fn email_regex() -> &'static Regex {
+    static CELL: OnceLock<Regex> = OnceLock::new();
+    builtin(EMAIL_PATTERN, &CELL)
+}
+
+fn phone_regex() -> &'static Regex {
+    static CELL: OnceLock<Regex> = OnceLock::new();
+    builtin(PHONE_PATTERN, &CELL)
+}
+
+fn card_regex() -> &'static Regex {
+    static CELL: OnceLock<Regex> = OnceLock::new();
+    builtin(CARD_PATTERN, &CELL)
+}
+
+#[tauri::command]
+pub fn get_redaction_config() -> Result<RedactionConfig, PromptOsError> {
+    let guard = CONFIG
+        .lock()
+        .map_err(|_| "Failed to lock redaction config".to_string())?;
+    Ok(guard
+        .as_ref()
+        .map(|compiled| compiled.config.clone())
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn set_redaction_config(config: RedactionConfig) -> Result<(), PromptOsError> {
+    apply(config.clone())?;
+    crate::settings::save(SETTINGS_FILE, &config)
+}
+
+/// Restore the saved config (called once during setup)
+pub fn load_settings() {
+    crate::settings::restore(SETTINGS_FILE, apply);
+}
+
+fn apply(config: RedactionConfig) -> Result<(), PromptOsError> {
+    let custom = config
+        .custom_patterns
+        .iter()
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|e| format!("Invalid pattern \"{}\": {}", pattern, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    *CONFIG
+        .lock()
+        .map_err(|_| "Failed to lock redaction config".to_string())? =
+        Some(CompiledConfig { config, custom });
+    Ok(())
+}
+
+/// Redact text using the current config (a no-op when redaction is disabled)
+pub fn redact(text: &str) -> RedactedText {
+    let mut replacements = Vec::new();
+    let text = redact_with(text, &mut replacements);
+    RedactedText { text, replacements }
+}
+
+/// Redact text, sharing placeholders with earlier calls (so prompt and context agree)
+pub fn redact_with(text: &str, replacements: &mut Vec<Replacement>) -> String {
+    let Ok(guard) = CONFIG.lock() else {
+        return text.to_string();
+    };
+    let Some(compiled) = guard.as_ref() else {
+        return text.to_string();
+    };
+    if !compiled.config.enabled {
+        return text.to_string();
+    }
+
+    let config = &compiled.config;
+    let mut spans: Vec<(usize, usize, &str)> = Vec::new();
+
+    if config.emails {
+        spans.extend(
+            email_regex()
+                .find_iter(text)
+                .map(|m| (m.start(), m.end(), "EMAIL")),
+        );
+    }
+    if config.credit_cards {
+        spans.extend(
+            card_regex()
+                .find_iter(text)
+                .filter(|m| luhn_valid(m.as_str()))
+                .map(|m| (m.start(), m.end(), "CARD")),
+        );
+    }
+    if config.phone_numbers {
+        spans.extend(
+            phone_regex()
+                .find_iter(text)
+                .map(|m| (m.start(), m.end(), "PHONE")),
+        );
+    }
+    for regex in &compiled.custom {
+        spans.extend(
+            regex
+                .find_iter(text)
+                .map(|m| (m.start(), m.end(), "CUSTOM")),
+        );
+    }
+    if config.named_entities {
+        spans.extend(unsafe { named_entity_spans(text) });
+    }
+
+    apply_spans(text, spans, replacements)
+}
+
+/// Replace non-overlapping spans (earliest, then longest, wins) with numbered placeholders
+fn apply_spans(
+    text: &str,
+    mut spans: Vec<(usize, usize, &str)>,
+    replacements: &mut Vec<Replacement>,
+) -> String {
+    spans.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+    let mut counters: HashMap<String, usize> = HashMap::new();
+    for replacement in replacements.iter() {
+        if let Some(kind) = placeholder_kind(&replacement.placeholder) {
+            *counters.entry(kind.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut cursor = 0;
+
+    for (start, end, kind) in spans {
+        if start < cursor || start == end {
+            continue;
+        }
+
+        let original = &text[start..end];
+        let placeholder = match replacements.iter().find(|r| r.original == original) {
+            Some(existing) => existing.placeholder.clone(),
+            None => {
+                let counter = counters.entry(kind.to_string()).or_insert(0);
+                *counter += 1;
+                let placeholder = format!("[{}_{}]", kind, counter);
+                replacements.push(Replacement {
+                    placeholder: placeholder.clone(),
+                    original: original.to_string(),
+                });
+                placeholder
+            }
+        };
+
+        output.push_str(&text[cursor..start]);
+        output.push_str(&placeholder);
+        cursor = end;
+    }
+
+    output.push_str(&text[cursor..]);
+    output
+}
+
+fn placeholder_kind(placeholder: &str) -> Option<&str> {
+    let inner = placeholder.strip_prefix('[')?.strip_suffix(']')?;
+    inner.rsplit_once('_').map(|(kind, _)| kind)
+}
+
+/// Put the original values back into a model response
+pub fn restore(text: &str, replacements: &[Replacement]) -> String {
+    replacements.iter().fold(text.to_string(), |acc, r| {
+        acc.replace(&r.placeholder, &r.original)
+    })
+}
+
+/// Luhn checksum, to avoid redacting arbitrary long numbers as cards
+fn luhn_valid(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    if digits.len() < 13 || digits.len() > 19 {
+        return false;
+    }
+
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                d
+            }
+        })
+        .sum();
+
+    sum.is_multiple_of(10)
+}
+
+/// Person, place, and organization names found by NSLinguisticTagger (byte offsets)
+#[cfg(target_os = "macos")]
+unsafe fn named_entity_spans(text: &str) -> Vec<(usize, usize, &'static str)> {
+    let Some(cls) = objc::runtime::Class::get("NSLinguisticTagger") else {
+        return Vec::new();
+    };
+
+    let scheme = NSString::alloc(nil).init_str("NameType");
+    let schemes = NSArray::arrayWithObject(nil, scheme);
+    let tagger: id = msg_send![cls, alloc];
+    let tagger: id = msg_send![tagger, initWithTagSchemes: schemes options: 0u64];
+    if tagger == nil {
+        return Vec::new();
+    }
+
+    let ns_text = NSString::alloc(nil).init_str(text);
+    let _: () = msg_send![tagger, setString: ns_text];
+
+    let found: Arc<Mutex<Vec<(u64, u64, &'static str)>>> = Arc::new(Mutex::new(Vec::new()));
+    let sink = found.clone();
+    let block = ConcreteBlock::new(move |tag: id, range: NSRange, _stop: *mut BOOL| {
+        let kind = match nsstring_to_string(tag).as_deref() {
+            Some("PersonalName") => "NAME",
+            Some("PlaceName") => "PLACE",
+            Some("OrganizationName") => "ORG",
+            _ => return,
+        };
+        if let Ok(mut spans) = sink.lock() {
+            spans.push((range.location, range.length, kind));
+        }
+    });
+
+    let utf16_len = text.encode_utf16().count() as u64;
+    let _: () = msg_send![tagger,
+        enumerateTagsInRange: NSRange::new(0, utf16_len)
+        unit: NS_LINGUISTIC_TAGGER_UNIT_WORD
+        scheme: scheme
+        options: NS_LINGUISTIC_TAGGER_OPTIONS
+        usingBlock: &*block];
+    let _: () = msg_send![tagger, release];
+
+    let spans = found.lock().map(|spans| spans.clone()).unwrap_or_default();
+    spans
+        .into_iter()
+        .filter_map(|(location, length, kind)| {
+            let start = utf16_to_byte_offset(text, location as usize)?;
+            let end = utf16_to_byte_offset(text, (location + length) as usize)?;
+            Some((start, end, kind))
+        })
+        .collect()
+}
+
+/// Names are only tagged with NSLinguisticTagger so far; elsewhere just the patterns apply
+#[cfg(not(target_os = "macos"))]
+unsafe fn named_entity_spans(_text: &str) -> Vec<(usize, usize, &'static str)> {
+    Vec::new()
+}
+
+#[cfg(target_os = "macos")]
+fn utf16_to_byte_offset(text: &str, utf16_offset: usize) -> Option<usize> {
+    let mut units = 0;
+    for (byte_index, c) in text.char_indices() {
+        if units == utf16_offset {
+            return Some(byte_index);
+        }
+        units += c.len_utf16();
+    }
+    (units == utf16_offset).then_some(text.len())
+}
+
+#[tauri::command]
+pub fn redact_text(text: String) -> Result<RedactedText, PromptOsError> {
+    Ok(redact(&text))
+}
+
+/// Redact several related texts (e.g. prompt + context) with one shared placeholder table
+#[tauri::command]
+pub fn redact_texts(texts: Vec<String>) -> Result<(Vec<String>, Vec<Replacement>), PromptOsError> {
+    let mut replacements = Vec::new();
+    let redacted = texts
+        .iter()
+        .map(|text| redact_with(text, &mut replacements))
+        .collect();
+    Ok((redacted, replacements))
+}
+
+#[tauri::command]
+pub fn restore_redacted(
+    text: String,
+    replacements: Vec<Replacement>,
+) -> Result<String, PromptOsError> {
+    Ok(restore(&text, &replacements))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replacement(placeholder: &str, original: &str) -> Replacement {
+        Replacement {
+            placeholder: placeholder.to_string(),
+            original: original.to_string(),
+        }
+    }
+
+    #[test]
+    fn phone_numbers_need_phone_structure() {
+        for phone in [
+            "+1 (555) 123-4567",
+            "+44 20 7946 0958",
+            "+14155552671",
+            "(555) 123-4567",
+            "555-123-4567",
+            "555.123.4567",
+            "020 7946 0958",
+            "090-1234-5678",
+        ] {
+            let found = phone_regex().find(phone).map(|m| m.as_str());
+            assert_eq!(found, Some(phone), "{}", phone);
+        }
+
+        for text in [
+            "Order 12345678",
+            "build 20241016",
+            "invoice 2024-001-0042",
+            "ISBN 978 0134 6850",
+            "due 2024-10-16",
+        ] {
+            assert!(!phone_regex().is_match(text), "{}", text);
+        }
+    }
+
+    #[test]
+    fn overlapping_spans_keep_the_earliest_then_the_longest() {
+        let text = "mail ann@example.com now";
+        let spans = vec![(5, 8, "NAME"), (8, 20, "CUSTOM"), (5, 20, "EMAIL")];
+        let mut replacements = Vec::new();
+
+        assert_eq!(
+            apply_spans(text, spans, &mut replacements),
+            "mail [EMAIL_1] now"
+        );
+        assert_eq!(
+            replacements,
+            vec![replacement("[EMAIL_1]", "ann@example.com")]
+        );
+    }
+
+    #[test]
+    fn redact_with_shares_placeholders_across_calls() {
+        // Every test that turns redaction on uses this same config
+        apply(RedactionConfig {
+            enabled: true,
+            ..RedactionConfig::default()
+        })
+        .unwrap();
+
+        let mut replacements = Vec::new();
+        let prompt = redact_with("Reply to ann@example.com", &mut replacements);
+        let context = redact_with(
+            "From ann@example.com, cc bob@example.com",
+            &mut replacements,
+        );
+
+        assert_eq!(prompt, "Reply to [EMAIL_1]");
+        assert_eq!(context, "From [EMAIL_1], cc [EMAIL_2]");
+        assert_eq!(replacements.len(), 2);
+    }
+
+    #[test]
+    fn counters_continue_after_earlier_placeholders() {
+        let mut replacements = vec![
+            replacement("[PHONE_1]", "555-123-4567"),
+            replacement("[CUSTOM_1]", "Project X"),
+        ];
+        let text = "call 555-123-4567 or 555-765-4321";
+        let spans = vec![(5, 17, "PHONE"), (21, 33, "PHONE")];
+
+        assert_eq!(
+            apply_spans(text, spans, &mut replacements),
+            "call [PHONE_1] or [PHONE_2]"
+        );
+        assert_eq!(replacements[2], replacement("[PHONE_2]", "555-765-4321"));
+    }
+
+    #[test]
+    fn restore_tells_single_from_double_digit_placeholders() {
+        let replacements: Vec<_> = (1..=10)
+            .map(|n| replacement(&format!("[NAME_{}]", n), &format!("Person {}", n)))
+            .collect();
+
+        assert_eq!(
+            restore("[NAME_10] met [NAME_1]", &replacements),
+            "Person 10 met Person 1"
+        );
+    }
+
+    #[test]
+    fn luhn_accepts_only_valid_card_numbers() {
+        assert!(luhn_valid("4111 1111 1111 1111"));
+        assert!(luhn_valid("5500-0000-0000-0004"));
+        assert!(!luhn_valid("4111 1111 1111 1112"));
+        // Too short for a card, even with a valid checksum
+        assert!(!luhn_valid("4242 4242 42"));
+    }
+
+    #[test]
+    fn placeholder_kind_keeps_underscores_in_the_kind() {
+        assert_eq!(placeholder_kind("[PHONE_12]"), Some("PHONE"));
+        assert_eq!(placeholder_kind("[MY_KIND_3]"), Some("MY_KIND"));
+        assert_eq!(placeholder_kind("PHONE_1"), None);
+    }
+}
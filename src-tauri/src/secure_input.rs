@@ -0,0 +1,69 @@
+// Secure keyboard entry (password prompts, some terminals), during which keystrokes reach no tap
+// Reports which process turned it on, so the UI can explain why triggers have gone quiet
+
+use crate::error::PromptOsError;
+use crate::frontmost_app::{running_app, FrontmostApp};
+use core_foundation::base::{CFType, TCFType};
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+use core_foundation::number::CFNumber;
+use core_foundation::string::CFString;
+
+#[link(name = "Carbon", kind = "framework")]
+extern "C" {
+    fn IsSecureEventInputEnabled() -> u8;
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGSessionCopyCurrentDictionary() -> CFDictionaryRef;
+}
+
+// In the login session's dictionary while some process has secure input on
+const SECURE_INPUT_PID_KEY: &str = "kCGSSessionSecureInputPID";
+
+#[derive(serde::Serialize, Clone)]
+pub struct SecureInputStatus {
+    pub active: bool,
+    /// Process that turned it on; it stays on until that process turns it off or quits
+    pub pid: Option<i32>,
+    pub app: Option<FrontmostApp>,
+}
+
+/// Whether some process has secure keyboard entry on
+pub fn enabled() -> bool {
+    unsafe { IsSecureEventInputEnabled() != 0 }
+}
+
+/// The process holding secure input, if the session says
+fn owner_pid() -> Option<i32> {
+    unsafe {
+        let session = CGSessionCopyCurrentDictionary();
+        if session.is_null() {
+            return None;
+        }
+        let session: CFDictionary<CFString, CFType> = CFDictionary::wrap_under_create_rule(session);
+        session
+            .find(CFString::new(SECURE_INPUT_PID_KEY))?
+            .downcast::<CFNumber>()?
+            .to_i32()
+            .filter(|pid| *pid > 0)
+    }
+}
+
+#[tauri::command]
+pub fn check_secure_input() -> Result<SecureInputStatus, PromptOsError> {
+    if !enabled() {
+        return Ok(SecureInputStatus {
+            active: false,
+            pid: None,
+            app: None,
+        });
+    }
+
+    let pid = owner_pid();
+    Ok(SecureInputStatus {
+        active: true,
+        pid,
+        app: pid.and_then(running_app),
+    })
+}
@@ -0,0 +1,251 @@
+// Overlay sessions: one per trigger, holding every prompt/response turn
+// Kept in memory so a whole assisted-writing session can be exported or injected back into its origin
+
+use crate::context::{prompt_context, PromptContext};
+use crate::error::PromptOsError;
+use crate::text_field_detector::copy_focused_element;
+use crate::text_injector::{focus_element, inject, InjectionStrategy};
+use accessibility_sys::AXUIElementRef;
+use core_foundation::base::CFTypeRef;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Time for the original app to come to the front before writing into it
+const ACTIVATION_DELAY: Duration = Duration::from_millis(150);
+
+// Older sessions are dropped once this many have been started
+const MAX_SESSIONS: usize = 20;
+
+#[derive(serde::Serialize, Clone)]
+pub struct SessionTurn {
+    /// RFC 3339 local timestamp
+    pub timestamp: String,
+    pub prompt: String,
+    pub response: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct Session {
+    pub id: u64,
+    pub started_at: String,
+    pub context: PromptContext,
+    pub turns: Vec<SessionTurn>,
+}
+
+/// The field a session was started from (retained AXUIElement, stored as usize for thread safety)
+struct FieldOrigin {
+    pid: i32,
+    element: Option<usize>,
+}
+
+impl Drop for FieldOrigin {
+    fn drop(&mut self) {
+        if let Some(element) = self.element.take() {
+            unsafe { core_foundation::base::CFRelease(element as CFTypeRef) };
+        }
+    }
+}
+
+struct SessionStore {
+    next_id: u64,
+    sessions: VecDeque<(Session, Option<FieldOrigin>)>,
+}
+
+static SESSIONS: Mutex<SessionStore> = Mutex::new(SessionStore {
+    next_id: 1,
+    sessions: VecDeque::new(),
+});
+
+/// Run a closure against one session
+pub fn with_session<T>(
+    session_id: u64,
+    f: impl FnOnce(&mut Session) -> T,
+) -> Result<T, PromptOsError> {
+    let mut store = SESSIONS
+        .lock()
+        .map_err(|_| "Failed to lock sessions".to_string())?;
+    let (session, _) = store
+        .sessions
+        .iter_mut()
+        .find(|(session, _)| session.id == session_id)
+        .ok_or_else(|| format!("Unknown session {}", session_id))?;
+    Ok(f(session))
+}
+
+/// Start a session for the current trigger, collecting its prompt context
+#[tauri::command]
+pub async fn start_session() -> Result<Session, PromptOsError> {
+    // Collecting context can wait seconds on the mail app or OCR
+    tauri::async_runtime::spawn_blocking(start)
+        .await
+        .map_err(|e| format!("Failed to start session: {}", e))?
+}
+
+fn start() -> Result<Session, PromptOsError> {
+    let context = prompt_context()?;
+
+    // The target app keeps its own focused element even after the overlay takes focus
+    let origin = context.app.as_ref().map(|app| FieldOrigin {
+        pid: app.pid,
+        element: unsafe { copy_focused_element(Some(app.pid)) }.map(|element| element as usize),
+    });
+
+    let mut store = SESSIONS
+        .lock()
+        .map_err(|_| "Failed to lock sessions".to_string())?;
+    let session = Session {
+        id: store.next_id,
+        started_at: chrono::Local::now().to_rfc3339(),
+        context,
+        turns: Vec::new(),
+    };
+    store.next_id += 1;
+
+    store.sessions.push_back((session.clone(), origin));
+    while store.sessions.len() > MAX_SESSIONS {
+        store.sessions.pop_front();
+    }
+    Ok(session)
+}
+
+#[tauri::command]
+pub fn add_session_turn(
+    session_id: u64,
+    prompt: String,
+    response: String,
+) -> Result<(), PromptOsError> {
+    with_session(session_id, |session| {
+        session.turns.push(SessionTurn {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            prompt,
+            response,
+        })
+    })
+}
+
+/// Inject into the field the session started from, even if focus wandered meanwhile: the
+/// original app and field get focus back, then the text goes in the way inject_text writes it
+/// (`render_markdown` is passed on); returns the strategy that worked
+#[tauri::command]
+pub async fn inject_into_origin(
+    session_id: u64,
+    text: String,
+    render_markdown: Option<bool>,
+) -> Result<InjectionStrategy, PromptOsError> {
+    // Waits for the app to come forward and may type for minutes; keep it off the main thread
+    tauri::async_runtime::spawn_blocking(move || inject_into(session_id, text, render_markdown))
+        .await
+        .map_err(|e| format!("Injection failed: {}", e))?
+}
+
+fn inject_into(
+    session_id: u64,
+    text: String,
+    render_markdown: Option<bool>,
+) -> Result<InjectionStrategy, PromptOsError> {
+    let text = crate::content_filter::filter_for_injection(&text)
+        .inspect_err(|_| crate::telemetry::record(crate::telemetry::EVENT_INJECT_BLOCKED))?;
+
+    let (pid, element) = {
+        let store = SESSIONS
+            .lock()
+            .map_err(|_| "Failed to lock sessions".to_string())?;
+        let (_, origin) = store
+            .sessions
+            .iter()
+            .find(|(session, _)| session.id == session_id)
+            .ok_or_else(|| format!("Unknown session {}", session_id))?;
+        let origin = origin.as_ref().ok_or("Session has no original app")?;
+
+        // Keep the element alive after the lock is released
+        let element = origin.element.map(|element| unsafe {
+            core_foundation::base::CFRetain(element as CFTypeRef) as usize
+        });
+        (origin.pid, element)
+    };
+
+    let focused = unsafe { restore_focus(pid, element) };
+    if let Some(element) = element {
+        unsafe { core_foundation::base::CFRelease(element as CFTypeRef) };
+    }
+    focused?;
+    inject(text, render_markdown)
+}
+
+/// Bring the original app to the front and give its field focus again; the element is only
+/// focused, never written to directly, so every injection safeguard still applies
+unsafe fn restore_focus(pid: i32, element: Option<usize>) -> Result<(), PromptOsError> {
+    crate::frontmost_app::activate_app(pid)?;
+    std::thread::sleep(ACTIVATION_DELAY);
+    if let Some(element) = element {
+        if let Err(e) = focus_element(element as AXUIElementRef) {
+            eprintln!("[DEBUG] {}; writing into the app's focused field", e);
+        }
+    }
+    Ok(())
+}
+
+/// Transcript of a session as "markdown" or "json"
+#[tauri::command]
+pub fn export_session(session_id: u64, format: String) -> Result<String, PromptOsError> {
+    let session = with_session(session_id, |session| session.clone())?;
+
+    match format.as_str() {
+        "markdown" | "md" => Ok(to_markdown(&session)),
+        "json" => serde_json::to_string_pretty(&session)
+            .map_err(|e| format!("Failed to serialize session: {}", e).into()),
+        other => Err(format!("Unsupported export format: {}", other).into()),
+    }
+}
+
+fn to_markdown(session: &Session) -> String {
+    let context = &session.context;
+    let mut out = String::from("# Prompt OS session\n\n");
+
+    out.push_str(&format!("- Started: {}\n", session.started_at));
+    if let Some(app) = &context.app {
+        let name = app.name.as_deref().unwrap_or("Unknown app");
+        match app.bundle_id.as_deref() {
+            Some(bundle_id) => out.push_str(&format!("- App: {} ({})\n", name, bundle_id)),
+            None => out.push_str(&format!("- App: {}\n", name)),
+        }
+    }
+    if let Some(editor) = &context.editor {
+        if let Some(file) = editor.file_path.as_deref().or(editor.file_name.as_deref()) {
+            out.push_str(&format!("- File: {}\n", file));
+        }
+        if let Some(language) = &editor.language {
+            out.push_str(&format!("- Code language: {}\n", language));
+        }
+    }
+    if let Some(document) = context
+        .document
+        .as_ref()
+        .filter(|_| context.editor.is_none())
+    {
+        let document = document.path.as_deref().unwrap_or(&document.url);
+        out.push_str(&format!("- Document: {}\n", document));
+    }
+    if let Some(language) = &context.language {
+        out.push_str(&format!("- Text language: {}\n", language.name));
+    }
+    if let Some(reply_to) = &context.reply_to {
+        let subject = reply_to.subject.as_deref().unwrap_or("(no subject)");
+        match reply_to.sender.as_deref() {
+            Some(sender) => out.push_str(&format!("- Replying to: {} from {}\n", subject, sender)),
+            None => out.push_str(&format!("- Replying to: {}\n", subject)),
+        }
+    }
+
+    for (i, turn) in session.turns.iter().enumerate() {
+        out.push_str(&format!("\n## Turn {} ({})\n\n", i + 1, turn.timestamp));
+        out.push_str(&format!("**Prompt**\n\n{}\n\n", turn.prompt.trim()));
+        out.push_str(&format!("**Response**\n\n{}\n", turn.response.trim()));
+    }
+
+    if session.turns.is_empty() {
+        out.push_str("\n_No turns yet._\n");
+    }
+    out
+}
@@ -0,0 +1,48 @@
+// Saved settings: each one is a JSON file in the app data directory
+// Modules restore theirs during setup through the same validation as their set_ command
+
+use crate::error::PromptOsError;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Remember where settings are saved (called once during setup, before anything is restored)
+pub fn init(data_dir: PathBuf) -> Result<(), PromptOsError> {
+    fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+    let _ = DATA_DIR.set(data_dir);
+    Ok(())
+}
+
+/// Save a setting; without a data directory it only lasts until quit
+pub fn save<T: Serialize>(file: &str, value: &T) -> Result<(), PromptOsError> {
+    let Some(data_dir) = DATA_DIR.get() else {
+        return Ok(());
+    };
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", file, e))?;
+    fs::write(data_dir.join(file), json)
+        .map_err(|e| format!("Failed to save {}: {}", file, e).into())
+}
+
+/// Apply a saved setting, if there is one; one that no longer parses or validates is ignored
+pub fn restore<T: DeserializeOwned>(
+    file: &str,
+    apply: impl FnOnce(T) -> Result<(), PromptOsError>,
+) {
+    let Some(data_dir) = DATA_DIR.get() else {
+        return;
+    };
+    let Ok(json) = fs::read_to_string(data_dir.join(file)) else {
+        return;
+    };
+    let restored = serde_json::from_str(&json)
+        .map_err(|e| PromptOsError::from(e.to_string()))
+        .and_then(apply);
+    if let Err(e) = restored {
+        eprintln!("[ERROR] Ignoring saved {}: {}", file, e);
+    }
+}
@@ -0,0 +1,121 @@
+// macOS fallback for when the event tap can't be created (Accessibility access denied)
+// Registers the trigger chords as global shortcuts, which need no permission; typed triggers,
+// snippets, and the hold key stay unavailable until the tap can be created
+
+use crate::error::PromptOsError;
+use crate::hotkey::{Chord, FLAG_ALTERNATE, FLAG_COMMAND, FLAG_CONTROL, FLAG_SHIFT};
+use prompt_os_pipeline::{Trigger, TriggerSink};
+use std::str::FromStr;
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+
+// Opens the overlay when no chord is configured for it, as on Wayland
+const DEFAULT_OVERLAY_SHORTCUT: &str = "ctrl+alt+/";
+
+/// Payload of the "monitor-fallback" event
+#[derive(serde::Serialize, Clone)]
+pub struct ShortcutFallback {
+    /// Why the event tap couldn't be created
+    pub reason: String,
+    /// The shortcuts that still work, e.g. "ctrl+alt+/"
+    pub shortcuts: Vec<String>,
+}
+
+/// The global shortcut for a chord; None for a key the shortcut API has no code for
+fn shortcut(chord: &Chord) -> Option<Shortcut> {
+    let name = crate::hotkey::key_name(chord.keycode)?;
+    let code = match name {
+        "/" => "Slash".to_string(),
+        ";" => "Semicolon".to_string(),
+        "," => "Comma".to_string(),
+        "." => "Period".to_string(),
+        "return" => "Enter".to_string(),
+        "tab" => "Tab".to_string(),
+        "space" => "Space".to_string(),
+        digit if digit.chars().all(|c| c.is_ascii_digit()) => format!("Digit{}", digit),
+        letter => format!("Key{}", letter.to_uppercase()),
+    };
+    let code = Code::from_str(&code).ok()?;
+
+    let mut modifiers = Modifiers::empty();
+    for (flag, modifier) in [
+        (FLAG_SHIFT, Modifiers::SHIFT),
+        (FLAG_CONTROL, Modifiers::CONTROL),
+        (FLAG_ALTERNATE, Modifiers::ALT),
+        (FLAG_COMMAND, Modifiers::SUPER),
+    ] {
+        if chord.modifiers & flag != 0 {
+            modifiers |= modifier;
+        }
+    }
+    Some(Shortcut::new(Some(modifiers), code))
+}
+
+/// Readable form of a chord, e.g. "cmd+shift+space"
+fn describe(chord: &Chord) -> String {
+    let mut parts = crate::hotkey::modifier_names(chord.modifiers);
+    parts.push(crate::hotkey::key_name(chord.keycode).unwrap_or("?"));
+    parts.join("+")
+}
+
+/// Register the chords that work without Accessibility (the overlay, named triggers, and the
+/// quick transform) in place of the event tap; returns them as registered
+pub fn start(
+    app: &AppHandle,
+    chords: Vec<(Chord, Trigger)>,
+    sink: TriggerSink,
+) -> Result<Vec<String>, PromptOsError> {
+    stop(app);
+
+    let mut chords: Vec<(Chord, Trigger)> = chords
+        .into_iter()
+        .filter(|(_, trigger)| {
+            matches!(
+                trigger,
+                Trigger::Slash | Trigger::Named { .. } | Trigger::QuickTransform
+            )
+        })
+        .collect();
+    if !chords.iter().any(|(_, trigger)| *trigger == Trigger::Slash) {
+        chords.push((
+            crate::hotkey::parse(DEFAULT_OVERLAY_SHORTCUT)?,
+            Trigger::Slash,
+        ));
+    }
+
+    let mut registered = Vec::new();
+    for (chord, trigger) in chords {
+        let Some(shortcut) = shortcut(&chord) else {
+            continue;
+        };
+        let sink = sink.clone();
+        let result = app
+            .global_shortcut()
+            .on_shortcut(shortcut, move |_app, _shortcut, event| {
+                if event.state == ShortcutState::Pressed {
+                    sink(trigger.clone());
+                }
+            });
+        // Another app may own the shortcut already; the others still work
+        match result {
+            Ok(()) => registered.push(describe(&chord)),
+            Err(e) => eprintln!("[ERROR] Failed to register {}: {}", describe(&chord), e),
+        }
+    }
+
+    if registered.is_empty() {
+        return Err("No fallback shortcut could be registered".into());
+    }
+    eprintln!(
+        "[DEBUG] Shortcut fallback active: {}",
+        registered.join(", ")
+    );
+    Ok(registered)
+}
+
+/// Drop the fallback shortcuts (the event tap is running, or monitoring stopped)
+pub fn stop(app: &AppHandle) {
+    if let Err(e) = app.global_shortcut().unregister_all() {
+        eprintln!("[ERROR] Failed to unregister fallback shortcuts: {}", e);
+    }
+}
@@ -0,0 +1,194 @@
+// Text-to-speech read-back using AVSpeechSynthesizer
+// Reads generated text aloud so it can be proofread by ear before injecting
+
+use crate::error::PromptOsError;
+use crate::objc_util::nsstring_to_string;
+use cocoa::base::{id, nil};
+use cocoa::foundation::NSString;
+use objc::msg_send;
+use objc::sel;
+use objc::sel_impl;
+use std::sync::Mutex;
+
+// AVSpeechSynthesizer lives in AVFoundation
+#[link(name = "AVFoundation", kind = "framework")]
+extern "C" {}
+
+// AVSpeechBoundary values
+const AV_SPEECH_BOUNDARY_IMMEDIATE: i64 = 0;
+const AV_SPEECH_BOUNDARY_WORD: i64 = 1;
+
+// AVSpeechUtteranceMinimumSpeechRate / AVSpeechUtteranceMaximumSpeechRate
+const MIN_SPEECH_RATE: f32 = 0.0;
+const MAX_SPEECH_RATE: f32 = 1.0;
+
+// Shared synthesizer, kept alive for the lifetime of the app (stored as usize for thread safety)
+static SYNTHESIZER: Mutex<Option<usize>> = Mutex::new(None);
+
+#[derive(serde::Serialize, Clone)]
+pub struct SpeechVoice {
+    pub identifier: String,
+    pub name: String,
+    pub language: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct SpeechStatus {
+    pub speaking: bool,
+    pub paused: bool,
+}
+
+/// Get (or lazily create) the shared AVSpeechSynthesizer
+unsafe fn synthesizer() -> Result<id, PromptOsError> {
+    let mut guard = SYNTHESIZER
+        .lock()
+        .map_err(|_| "Failed to lock speech synthesizer".to_string())?;
+
+    if let Some(addr) = *guard {
+        return Ok(addr as id);
+    }
+
+    let cls = objc::runtime::Class::get("AVSpeechSynthesizer")
+        .ok_or("Failed to get AVSpeechSynthesizer class")?;
+    let synth: id = msg_send![cls, new];
+    if synth == nil {
+        return Err("Failed to create speech synthesizer".into());
+    }
+
+    *guard = Some(synth as usize);
+    Ok(synth)
+}
+
+/// Resolve a voice by identifier first, then by language code (e.g. "en-US")
+unsafe fn resolve_voice(voice: &str) -> Option<id> {
+    let cls = objc::runtime::Class::get("AVSpeechSynthesisVoice")?;
+    let ns_voice = NSString::alloc(nil).init_str(voice);
+
+    let by_identifier: id = msg_send![cls, voiceWithIdentifier: ns_voice];
+    if by_identifier != nil {
+        return Some(by_identifier);
+    }
+
+    let by_language: id = msg_send![cls, voiceWithLanguage: ns_voice];
+    if by_language != nil {
+        return Some(by_language);
+    }
+
+    None
+}
+
+/// Speak text aloud, interrupting anything currently being read
+#[tauri::command]
+pub fn speak_text(
+    text: String,
+    voice: Option<String>,
+    rate: Option<f32>,
+) -> Result<(), PromptOsError> {
+    if text.trim().is_empty() {
+        return Err("Nothing to speak".into());
+    }
+
+    unsafe {
+        let synth = synthesizer()?;
+
+        // Only one read-back at a time
+        let _: bool = msg_send![synth, stopSpeakingAtBoundary: AV_SPEECH_BOUNDARY_IMMEDIATE];
+
+        let utterance_cls = objc::runtime::Class::get("AVSpeechUtterance")
+            .ok_or("Failed to get AVSpeechUtterance class")?;
+        let ns_text = NSString::alloc(nil).init_str(&text);
+        let utterance: id = msg_send![utterance_cls, speechUtteranceWithString: ns_text];
+        if utterance == nil {
+            return Err("Failed to create speech utterance".into());
+        }
+
+        if let Some(voice) = voice.as_deref() {
+            match resolve_voice(voice) {
+                Some(v) => {
+                    let _: () = msg_send![utterance, setVoice: v];
+                }
+                None => return Err(format!("Unknown voice: {}", voice).into()),
+            }
+        }
+
+        if let Some(rate) = rate {
+            let rate = rate.clamp(MIN_SPEECH_RATE, MAX_SPEECH_RATE);
+            let _: () = msg_send![utterance, setRate: rate];
+        }
+
+        eprintln!("[DEBUG] Speaking {} chars", text.chars().count());
+        let _: () = msg_send![synth, speakUtterance: utterance];
+    }
+
+    Ok(())
+}
+
+/// Pause read-back at the end of the current word
+#[tauri::command]
+pub fn pause_speech() -> Result<(), PromptOsError> {
+    unsafe {
+        let synth = synthesizer()?;
+        let _: bool = msg_send![synth, pauseSpeakingAtBoundary: AV_SPEECH_BOUNDARY_WORD];
+    }
+    Ok(())
+}
+
+/// Resume paused read-back
+#[tauri::command]
+pub fn resume_speech() -> Result<(), PromptOsError> {
+    unsafe {
+        let synth = synthesizer()?;
+        let _: bool = msg_send![synth, continueSpeaking];
+    }
+    Ok(())
+}
+
+/// Stop read-back immediately
+#[tauri::command]
+pub fn stop_speech() -> Result<(), PromptOsError> {
+    unsafe {
+        let synth = synthesizer()?;
+        let _: bool = msg_send![synth, stopSpeakingAtBoundary: AV_SPEECH_BOUNDARY_IMMEDIATE];
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_speech_status() -> Result<SpeechStatus, PromptOsError> {
+    unsafe {
+        let synth = synthesizer()?;
+        let speaking: bool = msg_send![synth, isSpeaking];
+        let paused: bool = msg_send![synth, isPaused];
+        Ok(SpeechStatus { speaking, paused })
+    }
+}
+
+/// List installed voices for the settings voice picker
+#[tauri::command]
+pub fn list_speech_voices() -> Result<Vec<SpeechVoice>, PromptOsError> {
+    unsafe {
+        let cls = objc::runtime::Class::get("AVSpeechSynthesisVoice")
+            .ok_or("Failed to get AVSpeechSynthesisVoice class")?;
+        let voices: id = msg_send![cls, speechVoices];
+        if voices == nil {
+            return Ok(Vec::new());
+        }
+
+        let count: usize = msg_send![voices, count];
+        let mut result = Vec::with_capacity(count);
+        for i in 0..count {
+            let voice: id = msg_send![voices, objectAtIndex: i];
+            let identifier: id = msg_send![voice, identifier];
+            let name: id = msg_send![voice, name];
+            let language: id = msg_send![voice, language];
+
+            result.push(SpeechVoice {
+                identifier: nsstring_to_string(identifier).unwrap_or_default(),
+                name: nsstring_to_string(name).unwrap_or_default(),
+                language: nsstring_to_string(language).unwrap_or_default(),
+            });
+        }
+
+        Ok(result)
+    }
+}
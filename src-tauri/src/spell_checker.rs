@@ -0,0 +1,189 @@
+// Local spelling and grammar checking using NSSpellChecker
+// Lets the overlay offer instant fixes for trivial mistakes without an API call
+
+use crate::error::PromptOsError;
+use crate::objc_util::nsstring_to_string;
+use cocoa::base::{id, nil};
+use cocoa::foundation::{NSRange, NSString};
+use objc::msg_send;
+use objc::sel;
+use objc::sel_impl;
+
+// NSTextCheckingType flags
+const NS_TEXT_CHECKING_TYPE_SPELLING: u64 = 1 << 1;
+const NS_TEXT_CHECKING_TYPE_GRAMMAR: u64 = 1 << 2;
+
+const MAX_SUGGESTIONS: usize = 5;
+
+#[derive(serde::Serialize, Clone)]
+pub struct TextIssue {
+    /// "spelling" or "grammar"
+    pub kind: String,
+    /// UTF-16 offsets, matching JavaScript string indexing
+    pub start: usize,
+    pub length: usize,
+    pub text: String,
+    pub message: Option<String>,
+    pub suggestions: Vec<String>,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct TextCheckResult {
+    pub issues: Vec<TextIssue>,
+    /// Input with every issue's top suggestion applied
+    pub corrected: String,
+}
+
+/// Check text for spelling and grammar issues using the system spell checker
+#[tauri::command]
+pub fn check_text(text: String) -> Result<TextCheckResult, PromptOsError> {
+    if text.trim().is_empty() {
+        return Ok(TextCheckResult {
+            issues: Vec::new(),
+            corrected: text,
+        });
+    }
+
+    let utf16: Vec<u16> = text.encode_utf16().collect();
+
+    unsafe {
+        let cls = objc::runtime::Class::get("NSSpellChecker")
+            .ok_or("Failed to get NSSpellChecker class")?;
+        let checker: id = msg_send![cls, sharedSpellChecker];
+        if checker == nil {
+            return Err("Failed to get shared spell checker".into());
+        }
+
+        let ns_text = NSString::alloc(nil).init_str(&text);
+        let range = NSRange::new(0, utf16.len() as u64);
+        let types = NS_TEXT_CHECKING_TYPE_SPELLING | NS_TEXT_CHECKING_TYPE_GRAMMAR;
+
+        let results: id = msg_send![checker,
+            checkString: ns_text
+            range: range
+            types: types
+            options: nil
+            inSpellDocumentWithTag: 0i64
+            orthography: std::ptr::null_mut::<id>()
+            wordCount: std::ptr::null_mut::<i64>()];
+
+        let mut issues = Vec::new();
+        if results != nil {
+            let count: usize = msg_send![results, count];
+            for i in 0..count {
+                let result: id = msg_send![results, objectAtIndex: i];
+                let result_type: u64 = msg_send![result, resultType];
+                let range: NSRange = msg_send![result, range];
+
+                if result_type == NS_TEXT_CHECKING_TYPE_SPELLING {
+                    let guesses: id = msg_send![checker,
+                        guessesForWordRange: range
+                        inString: ns_text
+                        language: nil
+                        inSpellDocumentWithTag: 0i64];
+
+                    issues.push(TextIssue {
+                        kind: "spelling".to_string(),
+                        start: range.location as usize,
+                        length: range.length as usize,
+                        text: utf16_slice(&utf16, range),
+                        message: None,
+                        suggestions: nsstring_array(guesses),
+                    });
+                } else if result_type == NS_TEXT_CHECKING_TYPE_GRAMMAR {
+                    issues.extend(grammar_issues(result, &utf16));
+                }
+            }
+        }
+
+        issues.sort_by_key(|issue| issue.start);
+        let corrected = apply_top_suggestions(&utf16, &issues);
+
+        Ok(TextCheckResult { issues, corrected })
+    }
+}
+
+/// Expand a grammar result into one issue per detail entry
+unsafe fn grammar_issues(result: id, utf16: &[u16]) -> Vec<TextIssue> {
+    let details: id = msg_send![result, grammarDetails];
+    if details == nil {
+        return Vec::new();
+    }
+
+    let result_range: NSRange = msg_send![result, range];
+    let range_key = NSString::alloc(nil).init_str("NSGrammarRange");
+    let description_key = NSString::alloc(nil).init_str("NSGrammarUserDescription");
+    let corrections_key = NSString::alloc(nil).init_str("NSGrammarCorrections");
+
+    let count: usize = msg_send![details, count];
+    let mut issues = Vec::with_capacity(count);
+    for i in 0..count {
+        let detail: id = msg_send![details, objectAtIndex: i];
+
+        // Detail ranges are relative to the result's range
+        let range_value: id = msg_send![detail, objectForKey: range_key];
+        let range = if range_value != nil {
+            let relative: NSRange = msg_send![range_value, rangeValue];
+            NSRange::new(result_range.location + relative.location, relative.length)
+        } else {
+            result_range
+        };
+
+        let description: id = msg_send![detail, objectForKey: description_key];
+        let corrections: id = msg_send![detail, objectForKey: corrections_key];
+
+        issues.push(TextIssue {
+            kind: "grammar".to_string(),
+            start: range.location as usize,
+            length: range.length as usize,
+            text: utf16_slice(utf16, range),
+            message: nsstring_to_string(description),
+            suggestions: nsstring_array(corrections),
+        });
+    }
+
+    issues
+}
+
+/// Rebuild the text with each non-overlapping issue replaced by its first suggestion
+fn apply_top_suggestions(utf16: &[u16], issues: &[TextIssue]) -> String {
+    let mut corrected: Vec<u16> = Vec::with_capacity(utf16.len());
+    let mut cursor = 0;
+
+    for issue in issues {
+        let Some(suggestion) = issue.suggestions.first() else {
+            continue;
+        };
+        if issue.start < cursor || issue.start + issue.length > utf16.len() {
+            continue;
+        }
+
+        corrected.extend_from_slice(&utf16[cursor..issue.start]);
+        corrected.extend(suggestion.encode_utf16());
+        cursor = issue.start + issue.length;
+    }
+
+    corrected.extend_from_slice(&utf16[cursor..]);
+    String::from_utf16_lossy(&corrected)
+}
+
+fn utf16_slice(utf16: &[u16], range: NSRange) -> String {
+    let start = (range.location as usize).min(utf16.len());
+    let end = (start + range.length as usize).min(utf16.len());
+    String::from_utf16_lossy(&utf16[start..end])
+}
+
+/// Convert an NSArray of NSStrings, keeping at most MAX_SUGGESTIONS
+unsafe fn nsstring_array(array: id) -> Vec<String> {
+    if array == nil {
+        return Vec::new();
+    }
+
+    let count: usize = msg_send![array, count];
+    (0..count.min(MAX_SUGGESTIONS))
+        .filter_map(|i| {
+            let item: id = msg_send![array, objectAtIndex: i];
+            nsstring_to_string(item)
+        })
+        .collect()
+}
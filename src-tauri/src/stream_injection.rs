@@ -0,0 +1,198 @@
+// Streaming injection: writes a response into the target field while it is still generating
+// Chunks are buffered and written in batches, so a fast model doesn't mean one AX write per token
+
+use crate::error::PromptOsError;
+use crate::injection_undo::{replace_inserted, text_in_range};
+use crate::permissions::{require, Permission};
+use crate::text_field_detector::{
+    copy_focused_element, is_secure_field, select_range, selection_range,
+};
+use crate::text_injector::{
+    field_writability, insert_into_element, remove_trigger_text, set_selected_text, strategy_chain,
+    InjectionStrategy,
+};
+use accessibility_sys::AXUIElementRef;
+use core_foundation::base::CFTypeRef;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Buffered text is written at most this often...
+const FLUSH_INTERVAL: Duration = Duration::from_millis(80);
+// ...unless this much has piled up
+const MAX_PENDING_CHARS: usize = 400;
+
+/// The field being streamed into (retained AXUIElement, stored as usize for thread safety)
+struct Stream {
+    element: usize,
+    /// UTF-16 offset the stream started writing at, where what it wrote can be found again
+    start: Option<usize>,
+    /// Everything received so far
+    received: String,
+    /// Byte length of `received` that has been run through the filter and written
+    flushed: usize,
+    /// The filtered text in the field
+    written: String,
+    last_flush: Instant,
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        unsafe { core_foundation::base::CFRelease(self.element as CFTypeRef) };
+    }
+}
+
+static STREAM: Mutex<Option<Stream>> = Mutex::new(None);
+
+impl Stream {
+    /// Write the pending text up to its last whitespace, keeping a trailing partial word back so
+    /// the content filter sees whole words; `all` writes everything (at the end of the stream),
+    /// as does a backlog with no whitespace in it (CJK text has none to wait for)
+    ///
+    /// The filter runs over the whole response so far, so a length limit or a banned phrase
+    /// split across batches is caught; only what it adds to the written text goes out.
+    fn flush(&mut self, all: bool) -> Result<(), PromptOsError> {
+        let pending = &self.received[self.flushed..];
+        let end = if all || pending.chars().count() >= MAX_PENDING_CHARS {
+            self.received.len()
+        } else {
+            let last_space = pending
+                .char_indices()
+                .rev()
+                .find(|(_, c)| c.is_whitespace());
+            match last_space {
+                Some((index, space)) => self.flushed + index + space.len_utf8(),
+                None => return Ok(()),
+            }
+        };
+        if end == self.flushed {
+            return Ok(());
+        }
+
+        let filtered = match crate::content_filter::filter_for_injection(&self.received[..end]) {
+            Ok(filtered) => filtered,
+            Err(e) => {
+                crate::telemetry::record(crate::telemetry::EVENT_INJECT_BLOCKED);
+                // A blocked response must not stay in the field half written
+                if let Err(removed) = self.rewrite("") {
+                    eprintln!("[ERROR] Failed to remove the blocked response: {}", removed);
+                }
+                return Err(e);
+            }
+        };
+        match filtered.strip_prefix(self.written.as_str()) {
+            Some(added) => {
+                if !added.is_empty() {
+                    unsafe { insert_into_element(self.element as AXUIElementRef, added)? };
+                    self.written.push_str(added);
+                }
+            }
+            // The rest of a banned phrase arrived, masking part of what was already written
+            None => self.rewrite(&filtered)?,
+        }
+        self.flushed = end;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+
+    /// Replace everything written so far with `text`, as long as it's still in the field as it
+    /// was written
+    fn rewrite(&mut self, text: &str) -> Result<(), PromptOsError> {
+        if self.written.is_empty() && text.is_empty() {
+            return Ok(());
+        }
+        let element = self.element as AXUIElementRef;
+        let start = self
+            .start
+            .ok_or("Can't find the streamed text in the field to correct it")?;
+        let length = self.written.encode_utf16().count();
+        unsafe {
+            if text_in_range(element, start, length).as_deref() != Some(self.written.as_str())
+                || !select_range(element, start, length)
+            {
+                return Err("The field changed while streaming into it".into());
+            }
+            set_selected_text(element, text)?;
+            replace_inserted(element, start, text);
+        }
+        self.written = text.to_string();
+        Ok(())
+    }
+}
+
+/// Start streaming into the focused field of the app being written to, replacing its selection
+#[tauri::command]
+pub fn start_stream_injection() -> Result<(), PromptOsError> {
+    require(Permission::Accessibility)?;
+    if crate::secure_input::enabled() {
+        return Err(PromptOsError::SecureField);
+    }
+    let app = crate::context::write_target_app().ok_or(PromptOsError::NoFocus)?;
+    // Streaming writes through Accessibility, which the app's chain may leave out (terminals)
+    if !strategy_chain(&app).contains(&InjectionStrategy::Accessibility) {
+        return Err("This app needs the response pasted once it's complete".into());
+    }
+
+    unsafe {
+        let focused = copy_focused_element(Some(app.pid)).ok_or(PromptOsError::NoFocus)?;
+        let element = focused as AXUIElementRef;
+        if is_secure_field(element) {
+            core_foundation::base::CFRelease(focused);
+            return Err(PromptOsError::SecureField);
+        }
+        if !field_writability(element).selected_text {
+            core_foundation::base::CFRelease(focused);
+            return Err(
+                "The focused field doesn't accept text via Accessibility; paste instead".into(),
+            );
+        }
+
+        remove_trigger_text(element);
+        let start = selection_range(element).map(|(location, _)| location);
+
+        // Replacing an unfinished stream releases its element
+        *STREAM
+            .lock()
+            .map_err(|_| "Failed to lock stream".to_string())? = Some(Stream {
+            element: focused as usize,
+            start,
+            received: String::new(),
+            flushed: 0,
+            written: String::new(),
+            last_flush: Instant::now(),
+        });
+    }
+    Ok(())
+}
+
+/// Add generated text to the stream; it reaches the field with the next batch
+#[tauri::command]
+pub fn push_chunk(chunk: String) -> Result<(), PromptOsError> {
+    let mut guard = STREAM
+        .lock()
+        .map_err(|_| "Failed to lock stream".to_string())?;
+    let stream = guard.as_mut().ok_or("No stream injection in progress")?;
+
+    stream.received.push_str(&chunk);
+    let pending = stream.received[stream.flushed..].chars().count();
+    if stream.last_flush.elapsed() < FLUSH_INTERVAL && pending < MAX_PENDING_CHARS {
+        return Ok(());
+    }
+    // A failed write ends the stream; what was written stays in the field
+    if let Err(e) = stream.flush(false) {
+        *guard = None;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Write whatever is still buffered and end the stream; returns how many characters were written
+#[tauri::command]
+pub fn end_stream() -> Result<usize, PromptOsError> {
+    let mut stream = STREAM
+        .lock()
+        .map_err(|_| "Failed to lock stream".to_string())?
+        .take()
+        .ok_or("No stream injection in progress")?;
+    stream.flush(true)?;
+    Ok(stream.written.chars().count())
+}
@@ -0,0 +1,246 @@
+// Per-app writing style memory (opt-in)
+// Learns formality, emoji usage, length, and sign-off from accepted outputs in history
+
+use crate::error::PromptOsError;
+use crate::history::{with_entries, HistoryEntry};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Need a few samples before a profile says anything useful
+const MIN_SAMPLES: usize = 3;
+
+// Only the most recent outputs reflect current habits
+const MAX_SAMPLES: usize = 50;
+
+const SIGN_OFF_MAX_WORDS: usize = 4;
+
+const SIGN_OFFS: &[&str] = &[
+    "best",
+    "best regards",
+    "kind regards",
+    "regards",
+    "thanks",
+    "thank you",
+    "many thanks",
+    "cheers",
+    "sincerely",
+    "yours",
+    "talk soon",
+    "warmly",
+    "thx",
+];
+
+const INFORMAL_MARKERS: &[&str] = &[
+    "hey", "hi", "lol", "haha", "btw", "gonna", "wanna", "yeah", "yep", "nope", "thx", "pls",
+    "cool", "awesome", "omg",
+];
+
+const FORMAL_MARKERS: &[&str] = &[
+    "dear",
+    "sincerely",
+    "regards",
+    "furthermore",
+    "however",
+    "therefore",
+    "kindly",
+    "please find",
+    "i would like",
+    "we would like",
+    "thank you for",
+];
+
+const SETTINGS_FILE: &str = "style_memory.json";
+
+static STYLE_MEMORY: AtomicBool = AtomicBool::new(false);
+
+#[derive(serde::Serialize, Clone)]
+pub struct StyleProfile {
+    pub app_bundle_id: String,
+    pub sample_count: usize,
+    /// 0.0 = very casual, 1.0 = very formal
+    pub formality: f64,
+    /// Share of outputs containing at least one emoji
+    pub emoji_rate: f64,
+    pub average_words: usize,
+    pub sign_off: Option<String>,
+    /// One-line description fed into the system prompt
+    pub summary: String,
+}
+
+pub fn style_memory_enabled() -> bool {
+    STYLE_MEMORY.load(Ordering::Relaxed)
+}
+
+#[tauri::command]
+pub fn set_style_memory_enabled(enabled: bool) -> Result<(), PromptOsError> {
+    STYLE_MEMORY.store(enabled, Ordering::Relaxed);
+    crate::settings::save(SETTINGS_FILE, &enabled)
+}
+
+/// Restore the saved setting (called once during setup)
+pub fn load_settings() {
+    crate::settings::restore(SETTINGS_FILE, |enabled| {
+        STYLE_MEMORY.store(enabled, Ordering::Relaxed);
+        Ok(())
+    });
+}
+
+#[tauri::command]
+pub fn get_style_memory_enabled() -> Result<bool, PromptOsError> {
+    Ok(style_memory_enabled())
+}
+
+/// Learned profile for an app, if style memory is on and enough history exists
+pub fn style_profile(app_bundle_id: &str, app_name: Option<&str>) -> Option<StyleProfile> {
+    if !style_memory_enabled() {
+        return None;
+    }
+
+    let samples: Vec<String> = with_entries(|entries: &[HistoryEntry]| {
+        entries
+            .iter()
+            .rev()
+            .filter(|e| e.app_bundle_id.as_deref() == Some(app_bundle_id))
+            .take(MAX_SAMPLES)
+            .map(|e| e.response.clone())
+            .collect()
+    })
+    .ok()?;
+
+    build_profile(app_bundle_id, app_name.unwrap_or(app_bundle_id), &samples)
+}
+
+#[tauri::command]
+pub fn get_style_profile(app_bundle_id: String) -> Result<Option<StyleProfile>, PromptOsError> {
+    Ok(style_profile(&app_bundle_id, None))
+}
+
+fn build_profile(app_bundle_id: &str, app_name: &str, samples: &[String]) -> Option<StyleProfile> {
+    if samples.len() < MIN_SAMPLES {
+        return None;
+    }
+
+    let count = samples.len() as f64;
+    let formality = samples.iter().map(|s| formality_score(s)).sum::<f64>() / count;
+    let emoji_rate = samples.iter().filter(|s| s.chars().any(is_emoji)).count() as f64 / count;
+    let average_words = samples
+        .iter()
+        .map(|s| s.split_whitespace().count())
+        .sum::<usize>()
+        / samples.len();
+    let sign_off = common_sign_off(samples);
+
+    let summary = summarize(
+        app_name,
+        formality,
+        emoji_rate,
+        average_words,
+        sign_off.as_deref(),
+    );
+
+    Some(StyleProfile {
+        app_bundle_id: app_bundle_id.to_string(),
+        sample_count: samples.len(),
+        formality,
+        emoji_rate,
+        average_words,
+        sign_off,
+        summary,
+    })
+}
+
+/// Marker-based formality estimate for one output (0.5 = neutral)
+fn formality_score(text: &str) -> f64 {
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let informal = words
+        .iter()
+        .filter(|w| INFORMAL_MARKERS.contains(w))
+        .count()
+        + words.iter().filter(|w| w.contains('\'')).count()
+        + text.matches('!').count()
+        + text.chars().filter(|c| is_emoji(*c)).count();
+    let formal = FORMAL_MARKERS.iter().filter(|m| lower.contains(*m)).count() * 2;
+
+    let total = informal + formal;
+    if total == 0 {
+        return 0.5;
+    }
+    formal as f64 / total as f64
+}
+
+/// Most frequent short closing line, if it recurs in at least a third of outputs
+fn common_sign_off(samples: &[String]) -> Option<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+
+    for sample in samples {
+        let lines: Vec<&str> = sample
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect();
+        if lines.len() < 2 {
+            continue;
+        }
+
+        // The sign-off is usually the last or second-to-last line (before a name)
+        for line in lines.iter().rev().take(2) {
+            let normalized = line.trim_end_matches([',', '!', '.']).to_lowercase();
+            if line.split_whitespace().count() <= SIGN_OFF_MAX_WORDS
+                && SIGN_OFFS.contains(&normalized.as_str())
+            {
+                *counts.entry(line.to_string()).or_insert(0) += 1;
+                break;
+            }
+        }
+    }
+
+    counts
+        .into_iter()
+        .filter(|(_, n)| *n * 3 >= samples.len())
+        .max_by_key(|(_, n)| *n)
+        .map(|(line, _)| line)
+}
+
+fn summarize(
+    app_name: &str,
+    formality: f64,
+    emoji_rate: f64,
+    average_words: usize,
+    sign_off: Option<&str>,
+) -> String {
+    let tone = if formality >= 0.65 {
+        "formally"
+    } else if formality <= 0.35 {
+        "casually"
+    } else {
+        "in a neutral, friendly tone"
+    };
+
+    let emoji = if emoji_rate >= 0.5 {
+        "often uses emoji"
+    } else if emoji_rate >= 0.15 {
+        "occasionally uses emoji"
+    } else {
+        "rarely uses emoji"
+    };
+
+    let mut summary = format!(
+        "In {} the user writes {}, {}, and keeps messages around {} words",
+        app_name, tone, emoji, average_words
+    );
+    if let Some(sign_off) = sign_off {
+        summary.push_str(&format!(", signing off with \"{}\"", sign_off));
+    }
+    summary.push('.');
+    summary
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32,
+        0x1F300..=0x1FAFF | 0x2600..=0x27BF | 0x1F000..=0x1F2FF)
+}
@@ -0,0 +1,237 @@
+// Opt-in anonymized telemetry
+// Counts events (never content, ids, or app names) and batches them to a configurable endpoint
+// The opt-in and the unsent counts are saved, so a restart neither forgets consent nor loses them
+
+use crate::error::PromptOsError;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// Unsent counts are saved this often when they changed
+const SAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const SEND_TIMEOUT: Duration = Duration::from_secs(15);
+
+const SETTINGS_FILE: &str = "telemetry.json";
+
+// Every event name that may be recorded; anything else is dropped
+pub const EVENT_TRIGGER_SLASH: &str = "trigger.slash";
+pub const EVENT_TRIGGER_QUICK_TRANSFORM: &str = "trigger.quick_transform";
+pub const EVENT_TRIGGER_ACTION: &str = "trigger.action";
+pub const EVENT_TRIGGER_SNIPPET: &str = "trigger.snippet";
+pub const EVENT_TRIGGER_SELECTION: &str = "trigger.selection";
+pub const EVENT_TRIGGER_GESTURE: &str = "trigger.gesture";
+pub const EVENT_INJECT_INSERT_FAILED: &str = "inject.insert_failed";
+pub const EVENT_INJECT_NOT_LANDED: &str = "inject.not_landed";
+pub const EVENT_INJECT_PASTE: &str = "inject.paste_fallback";
+pub const EVENT_INJECT_TYPING: &str = "inject.typing";
+pub const EVENT_INJECT_BLOCKED: &str = "inject.blocked_by_filter";
+pub const EVENT_LLM_ERROR: &str = "llm.error";
+pub const EVENT_QUICK_TRANSFORM_FAILED: &str = "quick_transform.failed";
+
+const KNOWN_EVENTS: &[&str] = &[
+    EVENT_TRIGGER_SLASH,
+    EVENT_TRIGGER_QUICK_TRANSFORM,
+    EVENT_TRIGGER_ACTION,
+    EVENT_TRIGGER_SNIPPET,
+    EVENT_TRIGGER_SELECTION,
+    EVENT_TRIGGER_GESTURE,
+    EVENT_INJECT_INSERT_FAILED,
+    EVENT_INJECT_NOT_LANDED,
+    EVENT_INJECT_PASTE,
+    EVENT_INJECT_TYPING,
+    EVENT_INJECT_BLOCKED,
+    EVENT_LLM_ERROR,
+    EVENT_QUICK_TRANSFORM_FAILED,
+];
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct TelemetryConfig {
+    pub enabled: bool,
+    pub endpoint: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct TelemetryBatch {
+    pub app_version: String,
+    pub os: String,
+    /// RFC 3339 UTC, truncated to the hour
+    pub period_start: String,
+    pub counts: BTreeMap<String, u64>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct TelemetryState {
+    config: TelemetryConfig,
+    period_start: String,
+    counts: BTreeMap<String, u64>,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STATE: Mutex<Option<TelemetryState>> = Mutex::new(None);
+// Counts changed since they were last saved
+static UNSAVED: AtomicBool = AtomicBool::new(false);
+
+fn hour_now() -> String {
+    chrono::Utc::now().format("%Y-%m-%dT%H:00:00Z").to_string()
+}
+
+/// Count one occurrence of an event (no-op unless the user opted in)
+pub fn record(event: &'static str) {
+    if !ENABLED.load(Ordering::Relaxed) || !KNOWN_EVENTS.contains(&event) {
+        return;
+    }
+
+    if let Ok(mut guard) = STATE.lock() {
+        if let Some(state) = guard.as_mut() {
+            *state.counts.entry(event.to_string()).or_insert(0) += 1;
+            UNSAVED.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_telemetry_config() -> Result<TelemetryConfig, PromptOsError> {
+    let guard = STATE
+        .lock()
+        .map_err(|_| "Failed to lock telemetry".to_string())?;
+    Ok(guard
+        .as_ref()
+        .map(|state| state.config.clone())
+        .unwrap_or_default())
+}
+
+/// Turning telemetry off discards anything not yet sent
+#[tauri::command]
+pub fn set_telemetry_config(config: TelemetryConfig) -> Result<(), PromptOsError> {
+    let mut guard = STATE
+        .lock()
+        .map_err(|_| "Failed to lock telemetry".to_string())?;
+
+    ENABLED.store(config.enabled, Ordering::Relaxed);
+    if !config.enabled {
+        *guard = None;
+    } else {
+        match guard.as_mut() {
+            Some(state) => state.config = config,
+            None => {
+                *guard = Some(TelemetryState {
+                    config,
+                    period_start: hour_now(),
+                    counts: BTreeMap::new(),
+                })
+            }
+        }
+    }
+    UNSAVED.store(false, Ordering::Relaxed);
+    crate::settings::save(SETTINGS_FILE, &*guard)
+}
+
+/// Restore the opt-in and any counts not sent before the last quit (called once during setup)
+pub fn load_settings() {
+    crate::settings::restore(SETTINGS_FILE, |state: Option<TelemetryState>| {
+        ENABLED.store(state.is_some(), Ordering::Relaxed);
+        *STATE
+            .lock()
+            .map_err(|_| "Failed to lock telemetry".to_string())? = state;
+        Ok(())
+    });
+}
+
+/// Save counts recorded since the last save (also runs every few minutes in the background)
+pub fn save_pending() -> Result<(), PromptOsError> {
+    if !UNSAVED.swap(false, Ordering::Relaxed) {
+        return Ok(());
+    }
+    let guard = STATE
+        .lock()
+        .map_err(|_| "Failed to lock telemetry".to_string())?;
+    crate::settings::save(SETTINGS_FILE, &*guard)
+}
+
+fn current_batch(state: &TelemetryState) -> TelemetryBatch {
+    TelemetryBatch {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        period_start: state.period_start.clone(),
+        counts: state.counts.clone(),
+    }
+}
+
+/// Exactly what the next send would contain
+#[tauri::command]
+pub fn preview_telemetry() -> Result<Option<TelemetryBatch>, PromptOsError> {
+    let guard = STATE
+        .lock()
+        .map_err(|_| "Failed to lock telemetry".to_string())?;
+    Ok(guard.as_ref().map(current_batch))
+}
+
+/// Send the pending batch now (also runs hourly in the background)
+#[tauri::command]
+pub async fn flush_telemetry() -> Result<(), PromptOsError> {
+    // The POST can take up to SEND_TIMEOUT; keep it off the main thread
+    tauri::async_runtime::spawn_blocking(send_batch)
+        .await
+        .map_err(|e| format!("Telemetry send failed: {}", e))?
+}
+
+fn send_batch() -> Result<(), PromptOsError> {
+    let (batch, endpoint) = {
+        let mut guard = STATE
+            .lock()
+            .map_err(|_| "Failed to lock telemetry".to_string())?;
+        let Some(state) = guard.as_mut() else {
+            return Ok(());
+        };
+        let Some(endpoint) = state.config.endpoint.clone() else {
+            return Ok(());
+        };
+        if state.counts.is_empty() {
+            return Ok(());
+        }
+
+        let batch = current_batch(state);
+        state.counts.clear();
+        state.period_start = hour_now();
+        UNSAVED.store(true, Ordering::Relaxed);
+        (batch, endpoint)
+    };
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(SEND_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    // A failed batch is dropped rather than retried, so failures never pile up
+    client
+        .post(&endpoint)
+        .json(&batch)
+        .send()
+        .and_then(|res| res.error_for_status())
+        .map(|_| ())
+        .map_err(|e| format!("Telemetry send failed: {}", e).into())
+}
+
+/// Start the hourly batch sender, which also saves unsent counts (called once during setup)
+pub fn start() {
+    std::thread::spawn(|| {
+        let mut last_flush = Instant::now();
+        loop {
+            std::thread::sleep(crate::power::poll_interval(SAVE_INTERVAL));
+            if !ENABLED.load(Ordering::Relaxed) {
+                continue;
+            }
+            if last_flush.elapsed() >= crate::power::poll_interval(FLUSH_INTERVAL) {
+                last_flush = Instant::now();
+                if let Err(e) = send_batch() {
+                    eprintln!("[ERROR] {}", e);
+                }
+            }
+            if let Err(e) = save_pending() {
+                eprintln!("[ERROR] Failed to save telemetry: {}", e);
+            }
+        }
+    });
+}
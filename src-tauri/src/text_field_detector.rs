@@ -1,14 +1,74 @@
 // Text field detection using macOS Accessibility API
-// Simplified: just checks if a text field is focused and gets cursor position
+// Checks that a text field is focused and finds its caret, falling back to the mouse position
 
+use crate::error::PromptOsError;
+use crate::objc_util::cf_release;
 use accessibility_sys::*;
 use cocoa::base::{id, nil};
-use core_foundation::base::{CFTypeRef, TCFType};
-use core_foundation::string::CFString;
+use core_foundation::array::{
+    CFArrayGetCount, CFArrayGetTypeID, CFArrayGetValueAtIndex, CFArrayRef,
+};
+use core_foundation::base::{CFGetTypeID, CFRetain, CFTypeRef, TCFType};
+use core_foundation::boolean::CFBoolean;
+use core_foundation::number::{CFNumber, CFNumberRef};
+use core_foundation::string::{CFString, CFStringRef};
 use objc::msg_send;
 use objc::sel;
 use objc::sel_impl;
+use prompt_os_pipeline::{detect_direction, CoordinateSpace, InjectStrategy, WritingDirection};
+use std::ffi::c_void;
+use std::ops::RangeInclusive;
 use std::ptr;
+use std::sync::{mpsc, Once};
+use std::time::Duration;
+
+// How long one Accessibility message waits on a busy app (the system default is 6 seconds)
+const AX_MESSAGING_TIMEOUT_SECS: f32 = 0.5;
+// How long a whole detection, which can take many messages, may run before Timeout
+const DETECTION_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Characters either side of the caret that get_surrounding_text returns unless asked otherwise
+const DEFAULT_SURROUNDING_CHARS: usize = 1000;
+const SURROUNDING_CHARS_RANGE: RangeInclusive<usize> = 1..=20_000;
+
+// Web content sits several levels below the window, and a page's DOM can nest far deeper
+const MAX_WEB_TREE_DEPTH: usize = 60;
+// Bounds the Accessibility round trips spent looking through a large page
+const MAX_WEB_NODES: usize = 2000;
+// Roles that take typing; contenteditable regions without one are found by a settable AXValue
+const EDITABLE_ROLES: &[&str] = &["AXTextField", "AXTextArea", "AXComboBox"];
+
+// Hints for classifying the focused field; window titles and placeholders are matched lowercased
+const TERMINAL_BUNDLE_IDS: &[&str] = &[
+    "com.apple.Terminal",
+    "com.googlecode.iterm2",
+    "dev.warp.Warp-Stable",
+    "net.kovidgoyal.kitty",
+    "org.alacritty",
+    "com.github.wez.wezterm",
+    "com.mitchellh.ghostty",
+    "co.zeit.hyper",
+];
+const CHAT_BUNDLE_IDS: &[&str] = &[
+    "com.tinyspeck.slackmacgap",
+    "com.hnc.Discord",
+    "com.apple.MobileSMS",
+    "com.microsoft.teams2",
+    "net.whatsapp.WhatsApp",
+    "ru.keepcoder.Telegram",
+    "com.openai.chat",
+    "com.anthropic.claudefordesktop",
+];
+const CHAT_TITLES: &[&str] = &[
+    "chatgpt",
+    "claude",
+    "gemini",
+    "slack",
+    "discord",
+    "messenger",
+];
+const CHAT_PLACEHOLDERS: &[&str] = &["message", "reply", "ask anything", "chat"];
+const WEBMAIL_TITLES: &[&str] = &["gmail", "outlook", "yahoo mail", "proton mail", "fastmail"];
 
 #[derive(serde::Serialize, Clone)]
 pub struct TextFieldBounds {
@@ -16,6 +76,98 @@ pub struct TextFieldBounds {
     pub y: f64,
     pub width: f64,
     pub height: f64,
+    /// CGDirectDisplayID of the screen containing the point
+    pub display_id: Option<u32>,
+    /// Backing scale factor of that screen (2.0 on Retina)
+    pub scale_factor: Option<f64>,
+    /// Always logical: Accessibility and Cocoa measure in points
+    pub coordinates: CoordinateSpace,
+}
+
+impl TextFieldBounds {
+    /// Bounds in points, tagged with the screen their origin is on
+    fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        let display_id = display_at(x, y);
+        TextFieldBounds {
+            x,
+            y,
+            width,
+            height,
+            display_id,
+            scale_factor: display_id.and_then(scale_factor),
+            coordinates: CoordinateSpace::Logical,
+        }
+    }
+}
+
+/// What the focused field is for, so default prompts can suit it
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldKind {
+    Chat,
+    CodeEditor,
+    EmailComposer,
+    Terminal,
+    SearchBar,
+    Generic,
+}
+
+impl FieldKind {
+    /// How a field of this kind takes text: terminals only by paste
+    pub fn inject_strategy(self) -> InjectStrategy {
+        match self {
+            FieldKind::Terminal => InjectStrategy::Paste,
+            _ => InjectStrategy::Insert,
+        }
+    }
+}
+
+/// What kind of element has focus and where it lives, so prompts can be tailored to it
+#[derive(serde::Serialize, Clone)]
+pub struct FocusedFieldInfo {
+    pub role: Option<String>,
+    pub subrole: Option<String>,
+    /// The app owning the element, which isn't always the frontmost one (e.g. a floating panel)
+    pub bundle_id: Option<String>,
+    pub app_name: Option<String>,
+    pub window_title: Option<String>,
+    pub placeholder: Option<String>,
+    /// Whether AXValue can be written, i.e. text can be set directly instead of typed or pasted
+    pub value_settable: bool,
+    pub kind: FieldKind,
+    /// How a response should be written into it, from kind
+    pub strategy: InjectStrategy,
+    /// Script and writing direction of its text, else of its placeholder; None without letters
+    pub direction: Option<WritingDirection>,
+    /// Screen frame of the window it's in, for docking the overlay to the window's edge
+    pub window_frame: Option<TextFieldBounds>,
+}
+
+/// What is already written in the focused field, and where the caret sits in it
+#[derive(serde::Serialize, Clone)]
+pub struct FieldContent {
+    pub value: Option<String>,
+    /// Caret offset, or where the selection starts, in UTF-16 code units of value
+    pub selection_start: Option<usize>,
+    /// 0 when nothing is selected
+    pub selection_length: Option<usize>,
+}
+
+/// Text either side of the caret (or selection), cut to a limit so a long document stays local
+#[derive(serde::Serialize, Clone)]
+pub struct SurroundingText {
+    pub before: String,
+    pub after: String,
+    /// The field holds more text before before, or after after, than was returned
+    pub truncated_before: bool,
+    pub truncated_after: bool,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct CFRange {
+    location: isize,
+    length: isize,
 }
 
 #[repr(C)]
@@ -39,96 +191,802 @@ struct CGRect {
     size: CGSize,
 }
 
-#[tauri::command]
-pub fn check_accessibility_permission() -> Result<bool, String> {
-    unsafe { Ok(AXIsProcessTrusted()) }
+static SYSTEM_TIMEOUT_SET: Once = Once::new();
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGGetDisplaysWithPoint(
+        point: CGPoint,
+        max_displays: u32,
+        displays: *mut u32,
+        matching_display_count: *mut u32,
+    ) -> i32;
+    fn CGDisplayCopyDisplayMode(display: u32) -> *mut c_void;
+    fn CGDisplayModeGetWidth(mode: *mut c_void) -> usize;
+    fn CGDisplayModeGetPixelWidth(mode: *mut c_void) -> usize;
+    fn CGDisplayModeRelease(mode: *mut c_void);
 }
 
-/// Check if a text field is currently focused (simple boolean check)
-pub fn is_text_field_focused() -> bool {
+/// Display containing a point in global coordinates (top-left origin, y growing downwards)
+fn display_at(x: f64, y: f64) -> Option<u32> {
+    let mut display = 0;
+    let mut count = 0;
+    let result = unsafe { CGGetDisplaysWithPoint(CGPoint { x, y }, 1, &mut display, &mut count) };
+    (result == 0 && count > 0).then_some(display)
+}
+
+/// Pixels per point on a display, from its current mode (scaled Retina modes included)
+fn scale_factor(display: u32) -> Option<f64> {
     unsafe {
-        let system_wide = AXUIElementCreateSystemWide();
-        if system_wide.is_null() {
-            return false;
+        let mode = CGDisplayCopyDisplayMode(display);
+        if mode.is_null() {
+            return None;
         }
+        let points = CGDisplayModeGetWidth(mode);
+        let pixels = CGDisplayModeGetPixelWidth(mode);
+        CGDisplayModeRelease(mode);
+        (points > 0).then(|| pixels as f64 / points as f64)
+    }
+}
 
-        let focused_attr = CFString::new("AXFocusedUIElement");
-        let mut focused_element_ref: CFTypeRef = ptr::null();
+#[tauri::command]
+pub fn check_accessibility_permission() -> Result<bool, PromptOsError> {
+    unsafe { Ok(AXIsProcessTrusted()) }
+}
 
-        let result = AXUIElementCopyAttributeValue(
-            system_wide,
-            focused_attr.as_concrete_TypeRef(),
-            &mut focused_element_ref,
-        );
+/// Check if a text field is currently focused; a password field, or any field while secure
+/// keyboard entry is on, is refused with SecureField
+pub fn is_text_field_focused() -> Result<bool, PromptOsError> {
+    if crate::secure_input::enabled() {
+        return Err(PromptOsError::SecureField);
+    }
+    if focused_text_input()? {
+        return Ok(true);
+    }
 
-        let is_focused = result == 0 && !focused_element_ref.is_null();
+    // Electron apps only build their accessibility tree once asked to; look again if just asked
+    let enabled = crate::frontmost_app::frontmost_app()
+        .is_some_and(|app| crate::electron::enable_accessibility(app.pid));
+    if !enabled {
+        return Ok(false);
+    }
+    focused_text_input()
+}
 
-        // Check if the focused element is a text input (has AXValue attribute)
-        let is_text_input = if is_focused {
-            let value_attr = CFString::new("AXValue");
-            let mut value_ref: CFTypeRef = ptr::null();
-            let value_result = AXUIElementCopyAttributeValue(
-                focused_element_ref as AXUIElementRef,
-                value_attr.as_concrete_TypeRef(),
-                &mut value_ref,
-            );
-            let has_value = value_result == 0;
-            cf_release(value_ref);
-            has_value
-        } else {
-            false
+/// Whether the focused element takes text; a password field is refused with SecureField
+fn focused_text_input() -> Result<bool, PromptOsError> {
+    unsafe {
+        let Some(focused_element_ref) = copy_focused_element(None) else {
+            return Ok(false);
         };
+        let is_secure = is_secure_field(focused_element_ref as AXUIElementRef);
+
+        // Check if the focused element is a text input (has AXValue attribute)
+        let value_attr = CFString::new("AXValue");
+        let mut value_ref: CFTypeRef = ptr::null();
+        let value_result = AXUIElementCopyAttributeValue(
+            focused_element_ref as AXUIElementRef,
+            value_attr.as_concrete_TypeRef(),
+            &mut value_ref,
+        );
+        let is_text_input = value_result == 0;
+        cf_release(value_ref);
 
         cf_release(focused_element_ref);
-        cf_release(system_wide as CFTypeRef);
 
-        is_text_input
+        if is_secure {
+            return Err(PromptOsError::SecureField);
+        }
+        Ok(is_text_input)
     }
 }
 
 /// Get current mouse cursor position - for overlay placement
 #[tauri::command]
-pub fn get_cursor_position() -> Result<TextFieldBounds, String> {
+pub fn get_cursor_position() -> Result<TextFieldBounds, PromptOsError> {
     unsafe {
         // Use objc to get NSEvent.mouseLocation
         let cls = objc::runtime::Class::get("NSEvent").ok_or("Failed to get NSEvent class")?;
         let point: CGPoint = msg_send![cls, mouseLocation];
 
-        // Get main screen height for coordinate conversion (macOS uses bottom-left origin)
+        // Cocoa's global coordinates start at the bottom-left of the menu bar screen (the first
+        // one, not mainScreen, which follows the key window), whatever screen the cursor is on
         let screen_cls = objc::runtime::Class::get("NSScreen").ok_or("Failed to get NSScreen class")?;
-        let main_screen: id = msg_send![screen_cls, mainScreen];
-        let frame: CGRect = msg_send![main_screen, frame];
+        let screens: id = msg_send![screen_cls, screens];
+        let primary_screen: id = msg_send![screens, firstObject];
+        if primary_screen == nil {
+            return Err("No screens".into());
+        }
+        let frame: CGRect = msg_send![primary_screen, frame];
         let screen_height = frame.size.height;
 
-        // Convert from bottom-left to top-left coordinates
+        // Convert from bottom-left to top-left coordinates; screens above or left of the menu bar
+        // screen come out negative, as in Accessibility and CoreGraphics
         let y = screen_height - point.y;
+        let bounds = TextFieldBounds::new(point.x, y, 0.0, 0.0);
 
-        eprintln!("[DEBUG] Cursor position: x={}, y={} (screen height={})", point.x, y, screen_height);
+        eprintln!(
+            "[DEBUG] Cursor position: x={}, y={} (display {:?})",
+            point.x, y, bounds.display_id
+        );
 
-        Ok(TextFieldBounds {
-            x: point.x,
-            y,
-            width: 0.0,  // Not applicable for cursor position
+        Ok(bounds)
+    }
+}
+
+/// Bounds of the caret if a text field is focused, or the mouse position when the field can't
+/// say where its caret is
+#[tauri::command]
+pub fn get_focused_text_field_bounds() -> Result<TextFieldBounds, PromptOsError> {
+    bounded("Finding the caret", detect_text_field_bounds)
+}
+
+fn detect_text_field_bounds() -> Result<TextFieldBounds, PromptOsError> {
+    if !is_text_field_focused()? {
+        eprintln!("[DEBUG] No text field focused");
+        return Err(PromptOsError::NoFocus);
+    }
+    if let Some(caret) = unsafe { focused_caret_bounds() } {
+        eprintln!("[DEBUG] Caret bounds: x={}, y={}", caret.x, caret.y);
+        return Ok(caret);
+    }
+    eprintln!("[DEBUG] No caret bounds, getting cursor position...");
+    get_cursor_position()
+}
+
+/// Caret of the focused element, from its selected range
+unsafe fn focused_caret_bounds() -> Option<TextFieldBounds> {
+    let focused = copy_focused_element(None)?;
+    let bounds = caret_bounds(focused as AXUIElementRef);
+    cf_release(focused);
+    bounds
+}
+
+/// Screen bounds (top-left origin, like the cursor position) of the element's selected range,
+/// which is the caret when nothing is selected
+pub(crate) unsafe fn caret_bounds(element: AXUIElementRef) -> Option<TextFieldBounds> {
+    let range = copy_attribute(element, "AXSelectedTextRange")?;
+    let attr = CFString::new("AXBoundsForRange");
+    let mut bounds_ref: CFTypeRef = ptr::null();
+    let result = AXUIElementCopyParameterizedAttributeValue(
+        element,
+        attr.as_concrete_TypeRef(),
+        range,
+        &mut bounds_ref,
+    );
+    cf_release(range);
+    if result != 0 || bounds_ref.is_null() {
+        cf_release(bounds_ref);
+        return None;
+    }
+
+    let mut rect = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: CGSize {
+            width: 0.0,
             height: 0.0,
-        })
+        },
+    };
+    let read = AXValueGetValue(
+        bounds_ref as AXValueRef,
+        kAXValueTypeCGRect,
+        &mut rect as *mut CGRect as *mut c_void,
+    );
+    cf_release(bounds_ref);
+
+    // Some apps answer with an empty rect at the screen origin rather than failing
+    let empty = rect.origin.x == 0.0 && rect.origin.y == 0.0 && rect.size.height == 0.0;
+    if !read || empty {
+        return None;
+    }
+
+    Some(TextFieldBounds::new(
+        rect.origin.x,
+        rect.origin.y,
+        rect.size.width,
+        rect.size.height,
+    ))
+}
+
+/// Screen frame (top-left origin) of the focused element, e.g. the whole text field
+pub fn focused_field_frame() -> Option<TextFieldBounds> {
+    unsafe {
+        let focused = copy_focused_element(None)?;
+        let frame = element_frame(focused as AXUIElementRef);
+        cf_release(focused);
+        frame
+    }
+}
+
+/// Screen frame of the window containing the focused element
+pub fn focused_window_frame() -> Option<TextFieldBounds> {
+    unsafe {
+        let focused = copy_focused_element(None)?;
+        let frame = copy_attribute(focused as AXUIElementRef, "AXWindow").and_then(|window| {
+            let frame = element_frame(window as AXUIElementRef);
+            cf_release(window);
+            frame
+        });
+        cf_release(focused);
+        frame
     }
 }
 
-/// Simplified: returns cursor position if a text field is focused
+/// Screen frame of an element, from its AXPosition and AXSize
+pub(crate) unsafe fn element_frame(element: AXUIElementRef) -> Option<TextFieldBounds> {
+    let mut origin = CGPoint { x: 0.0, y: 0.0 };
+    let mut size = CGSize {
+        width: 0.0,
+        height: 0.0,
+    };
+    let read = read_ax_value(
+        element,
+        "AXPosition",
+        kAXValueTypeCGPoint,
+        &mut origin as *mut CGPoint as *mut c_void,
+    ) && read_ax_value(
+        element,
+        "AXSize",
+        kAXValueTypeCGSize,
+        &mut size as *mut CGSize as *mut c_void,
+    );
+
+    read.then(|| TextFieldBounds::new(origin.x, origin.y, size.width, size.height))
+}
+
+/// Unpack an AXValue attribute (point, size, rect, or range) into out
+unsafe fn read_ax_value(
+    element: AXUIElementRef,
+    attribute: &str,
+    value_type: AXValueType,
+    out: *mut c_void,
+) -> bool {
+    let Some(value) = copy_attribute(element, attribute) else {
+        return false;
+    };
+    let read = AXValueGetValue(value as AXValueRef, value_type, out);
+    cf_release(value);
+    read
+}
+
+/// Role, owning app, window, and placeholder of the focused element
 #[tauri::command]
-pub fn get_focused_text_field_bounds() -> Result<TextFieldBounds, String> {
-    if is_text_field_focused() {
-        eprintln!("[DEBUG] Text field IS focused, getting cursor position...");
-        get_cursor_position()
+pub fn get_focused_field_info() -> Result<FocusedFieldInfo, PromptOsError> {
+    bounded("Reading the focused field", detect_field_info)
+}
+
+fn detect_field_info() -> Result<FocusedFieldInfo, PromptOsError> {
+    unsafe {
+        let focused = copy_focused_element(None).ok_or(PromptOsError::NoFocus)?;
+        let element = focused as AXUIElementRef;
+
+        let mut pid = 0;
+        let app = if AXUIElementGetPid(element, &mut pid) == 0 {
+            crate::frontmost_app::running_app(pid)
+        } else {
+            None
+        };
+
+        let (window_title, window_frame) = match copy_attribute(element, "AXWindow") {
+            Some(window) => {
+                let title = copy_string_attribute(window as AXUIElementRef, "AXTitle");
+                let frame = element_frame(window as AXUIElementRef);
+                cf_release(window);
+                (title, frame)
+            }
+            None => (None, None),
+        };
+
+        let value_settable = value_settable(element);
+        let value = copy_string_attribute(element, "AXValue");
+
+        let mut info = FocusedFieldInfo {
+            role: copy_string_attribute(element, "AXRole"),
+            subrole: copy_string_attribute(element, "AXSubrole"),
+            bundle_id: app.as_ref().and_then(|app| app.bundle_id.clone()),
+            app_name: app.and_then(|app| app.name),
+            window_title,
+            placeholder: copy_string_attribute(element, "AXPlaceholderValue"),
+            value_settable,
+            kind: FieldKind::Generic,
+            strategy: InjectStrategy::Insert,
+            direction: None,
+            window_frame,
+        };
+        cf_release(focused);
+        info.kind = classify_field(&info);
+        info.strategy = info.kind.inject_strategy();
+        info.direction = value
+            .as_deref()
+            .or(info.placeholder.as_deref())
+            .and_then(detect_direction);
+        Ok(info)
+    }
+}
+
+/// Whether the app is a terminal emulator
+pub fn is_terminal(bundle_id: &str) -> bool {
+    TERMINAL_BUNDLE_IDS.contains(&bundle_id)
+}
+
+/// Best guess at what a field is for, from its subrole, placeholder, app, and window title
+fn classify_field(info: &FocusedFieldInfo) -> FieldKind {
+    let bundle_id = info.bundle_id.as_deref().unwrap_or_default();
+    let placeholder = info
+        .placeholder
+        .as_deref()
+        .unwrap_or_default()
+        .to_lowercase();
+    let title = info
+        .window_title
+        .as_deref()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    if info.subrole.as_deref() == Some("AXSearchField") || placeholder.contains("search") {
+        FieldKind::SearchBar
+    } else if is_terminal(bundle_id) {
+        FieldKind::Terminal
+    } else if crate::editor_context::is_code_editor(bundle_id) {
+        FieldKind::CodeEditor
+    } else if crate::mail_context::is_mail_app(bundle_id)
+        || WEBMAIL_TITLES.iter().any(|hint| title.contains(hint))
+    {
+        FieldKind::EmailComposer
+    } else if CHAT_BUNDLE_IDS.contains(&bundle_id)
+        || CHAT_TITLES.iter().any(|hint| title.contains(hint))
+        || CHAT_PLACEHOLDERS
+            .iter()
+            .any(|hint| placeholder.contains(hint))
+    {
+        FieldKind::Chat
     } else {
-        eprintln!("[DEBUG] No text field focused");
-        Err("No focused text field".to_string())
+        FieldKind::Generic
+    }
+}
+
+/// Run a detection on its own thread, failing with Timeout if the target app keeps it waiting
+/// (the messaging timeout bounds each call, but a detection can make hundreds)
+fn bounded<T, F>(what: &str, detect: F) -> Result<T, PromptOsError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, PromptOsError> + Send + 'static,
+{
+    let (done, result) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = done.send(detect());
+    });
+    result.recv_timeout(DETECTION_TIMEOUT).unwrap_or_else(|_| {
+        eprintln!("[ERROR] {} timed out", what);
+        Err(PromptOsError::Timeout(format!(
+            "{} took longer than {} seconds; the app may be busy",
+            what,
+            DETECTION_TIMEOUT.as_secs()
+        )))
+    })
+}
+
+/// An app's root element, with the messaging timeout set so a hung app can't block the caller;
+/// null if the app is gone
+pub(crate) unsafe fn application_element(pid: i32) -> AXUIElementRef {
+    let element = AXUIElementCreateApplication(pid);
+    if !element.is_null() {
+        AXUIElementSetMessagingTimeout(element, AX_MESSAGING_TIMEOUT_SECS);
+    }
+    element
+}
+
+/// The system-wide element; the first call sets the messaging timeout for every element that
+/// doesn't have its own
+unsafe fn system_wide_element() -> AXUIElementRef {
+    let element = AXUIElementCreateSystemWide();
+    if !element.is_null() {
+        SYSTEM_TIMEOUT_SET
+            .call_once(|| _ = AXUIElementSetMessagingTimeout(element, AX_MESSAGING_TIMEOUT_SECS));
     }
+    element
 }
 
-/// Safe CFRelease wrapper
-unsafe fn cf_release(cf: CFTypeRef) {
-    if !cf.is_null() {
-        core_foundation::base::CFRelease(cf);
+/// Copy the focused UI element (system-wide, or within one app); caller must release
+pub(crate) unsafe fn copy_focused_element(pid: Option<i32>) -> Option<CFTypeRef> {
+    let root = match pid {
+        Some(pid) => application_element(pid),
+        None => system_wide_element(),
+    };
+    if root.is_null() {
+        return None;
     }
+
+    let focused = copy_attribute(root, "AXFocusedUIElement");
+    cf_release(root as CFTypeRef);
+
+    // Browsers often report the whole web area as focused (or nothing at all), not the field
+    // inside it
+    let web_area = match focused {
+        Some(element) => {
+            copy_string_attribute(element as AXUIElementRef, "AXRole").as_deref()
+                == Some("AXWebArea")
+        }
+        None => true,
+    };
+    if !web_area {
+        return focused;
+    }
+    let web_element = pid
+        .or_else(|| crate::frontmost_app::frontmost_app().map(|app| app.pid))
+        .and_then(|pid| copy_focused_web_element(pid));
+    match web_element {
+        Some(element) => {
+            if let Some(focused) = focused {
+                cf_release(focused);
+            }
+            Some(element)
+        }
+        None => focused,
+    }
+}
+
+/// The focused editable node under an AXWebArea in the app's focused window; caller must release
+unsafe fn copy_focused_web_element(pid: i32) -> Option<CFTypeRef> {
+    let app_element = application_element(pid);
+    if app_element.is_null() {
+        return None;
+    }
+
+    let window = copy_attribute(app_element, "AXFocusedWindow");
+    cf_release(app_element as CFTypeRef);
+    let window = window?;
+
+    let mut visited = 0;
+    let found = find_focused_editable(window as AXUIElementRef, 0, false, &mut visited);
+    cf_release(window);
+    found
+}
+
+/// Depth-first search for a focused, editable node inside a web area; the result is retained
+unsafe fn find_focused_editable(
+    element: AXUIElementRef,
+    depth: usize,
+    in_web_area: bool,
+    visited: &mut usize,
+) -> Option<CFTypeRef> {
+    *visited += 1;
+    if depth > MAX_WEB_TREE_DEPTH || *visited > MAX_WEB_NODES {
+        return None;
+    }
+
+    let role = copy_string_attribute(element, "AXRole");
+    let in_web_area = in_web_area || role.as_deref() == Some("AXWebArea");
+    let focused_field = in_web_area
+        && bool_attribute(element, "AXFocused")
+        && is_editable(element, role.as_deref());
+    if focused_field {
+        return Some(CFRetain(element as CFTypeRef));
+    }
+
+    let children = copy_attribute(element, "AXChildren")?;
+    let mut found = None;
+    if CFGetTypeID(children) == CFArrayGetTypeID() {
+        let count = CFArrayGetCount(children as CFArrayRef);
+        for i in 0..count {
+            // Array elements are borrowed, not owned
+            let child = CFArrayGetValueAtIndex(children as CFArrayRef, i);
+            found = find_focused_editable(child as AXUIElementRef, depth + 1, in_web_area, visited);
+            if found.is_some() {
+                break;
+            }
+        }
+    }
+    cf_release(children);
+    found
+}
+
+/// Whether the element takes typing, given its AXRole
+pub(crate) unsafe fn is_editable(element: AXUIElementRef, role: Option<&str>) -> bool {
+    role.is_some_and(|role| EDITABLE_ROLES.contains(&role)) || value_settable(element)
+}
+
+/// Whether text can be written straight into the element's AXValue
+unsafe fn value_settable(element: AXUIElementRef) -> bool {
+    attribute_settable(element, "AXValue")
+}
+
+/// Whether the element reports an AX attribute as settable, false when it can't say
+pub(crate) unsafe fn attribute_settable(element: AXUIElementRef, attribute: &str) -> bool {
+    let attr = CFString::new(attribute);
+    let mut settable = 0;
+    AXUIElementIsAttributeSettable(element, attr.as_concrete_TypeRef(), &mut settable) == 0
+        && settable != 0
+}
+
+/// Read a boolean AX attribute, false when missing
+unsafe fn bool_attribute(element: AXUIElementRef, attribute: &str) -> bool {
+    match copy_attribute(element, attribute) {
+        Some(value) => {
+            let is_true = value == CFBoolean::true_value().as_CFTypeRef();
+            cf_release(value);
+            is_true
+        }
+        None => false,
+    }
+}
+
+/// Selection and full value of the focused field (pid targets an app that may not be frontmost)
+pub fn read_field_text(pid: Option<i32>) -> (Option<String>, Option<String>) {
+    unsafe {
+        let Some(focused) = copy_focused_element(pid) else {
+            return (None, None);
+        };
+
+        let selection = copy_string_attribute(focused as AXUIElementRef, "AXSelectedText");
+        let value = copy_string_attribute(focused as AXUIElementRef, "AXValue");
+        cf_release(focused);
+
+        (selection, value)
+    }
+}
+
+/// What the user highlighted in the focused element, read without touching the clipboard (None
+/// when nothing is selected)
+#[tauri::command]
+pub fn get_selected_text() -> Result<Option<String>, PromptOsError> {
+    bounded("Reading the selection", read_selected_text)
+}
+
+fn read_selected_text() -> Result<Option<String>, PromptOsError> {
+    if crate::secure_input::enabled() {
+        return Err(PromptOsError::SecureField);
+    }
+
+    unsafe {
+        let focused = copy_focused_element(None).ok_or(PromptOsError::NoFocus)?;
+        let element = focused as AXUIElementRef;
+        let selection = if is_secure_field(element) {
+            Err(PromptOsError::SecureField)
+        } else {
+            Ok(copy_string_attribute(element, "AXSelectedText").filter(|text| !text.is_empty()))
+        };
+        cf_release(focused);
+        selection
+    }
+}
+
+/// Full value of the focused field plus the caret offset and selection, for completions that
+/// follow on from what is already written
+#[tauri::command]
+pub fn get_field_content() -> Result<FieldContent, PromptOsError> {
+    bounded("Reading the focused field", read_field_content)
+}
+
+fn read_field_content() -> Result<FieldContent, PromptOsError> {
+    if crate::secure_input::enabled() {
+        return Err(PromptOsError::SecureField);
+    }
+
+    unsafe {
+        let focused = copy_focused_element(None).ok_or(PromptOsError::NoFocus)?;
+        let element = focused as AXUIElementRef;
+        if is_secure_field(element) {
+            cf_release(focused);
+            return Err(PromptOsError::SecureField);
+        }
+
+        let value = copy_string_attribute(element, "AXValue");
+        let range = selected_range(element);
+        cf_release(focused);
+
+        Ok(FieldContent {
+            value,
+            selection_start: range.map(|range| range.location as usize),
+            selection_length: range.map(|range| range.length as usize),
+        })
+    }
+}
+
+/// Up to chars characters (UTF-16 code units) before and after the caret in the focused field,
+/// 1000 unless set
+#[tauri::command]
+pub fn get_surrounding_text(chars: Option<usize>) -> Result<SurroundingText, PromptOsError> {
+    bounded("Reading around the caret", move || {
+        read_surrounding_text(chars)
+    })
+}
+
+fn read_surrounding_text(chars: Option<usize>) -> Result<SurroundingText, PromptOsError> {
+    let chars = chars.unwrap_or(DEFAULT_SURROUNDING_CHARS);
+    if !SURROUNDING_CHARS_RANGE.contains(&chars) {
+        return Err(format!(
+            "Surrounding text of {} characters must be {} to {} characters",
+            chars,
+            SURROUNDING_CHARS_RANGE.start(),
+            SURROUNDING_CHARS_RANGE.end()
+        )
+        .into());
+    }
+    if crate::secure_input::enabled() {
+        return Err(PromptOsError::SecureField);
+    }
+
+    unsafe {
+        let focused = copy_focused_element(None).ok_or(PromptOsError::NoFocus)?;
+        let element = focused as AXUIElementRef;
+        if is_secure_field(element) {
+            cf_release(focused);
+            return Err(PromptOsError::SecureField);
+        }
+
+        let surrounding = surrounding_text(element, chars);
+        cf_release(focused);
+        surrounding.ok_or(PromptOsError::NoFocus)
+    }
+}
+
+/// Text either side of the element's selection, fetched by range so a huge document is never
+/// read whole; apps without AXStringForRange fall back to slicing AXValue
+unsafe fn surrounding_text(element: AXUIElementRef, chars: usize) -> Option<SurroundingText> {
+    let selection = selected_range(element)?;
+    let start = selection.location as usize;
+    let end = start + selection.length as usize;
+    let before_start = start.saturating_sub(chars);
+
+    if let Some(total) = number_of_characters(element).filter(|total| *total >= end) {
+        let after_end = total.min(end + chars);
+        let before = string_for_range(element, before_start, start - before_start);
+        let after = string_for_range(element, end, after_end - end);
+        if let (Some(before), Some(after)) = (before, after) {
+            return Some(SurroundingText {
+                before,
+                after,
+                truncated_before: before_start > 0,
+                truncated_after: after_end < total,
+            });
+        }
+    }
+
+    let value: Vec<u16> = copy_string_attribute(element, "AXValue")?
+        .encode_utf16()
+        .collect();
+    let start = start.min(value.len());
+    let end = end.clamp(start, value.len());
+    let before_start = start.saturating_sub(chars);
+    let after_end = value.len().min(end + chars);
+    Some(SurroundingText {
+        before: String::from_utf16_lossy(&value[before_start..start]),
+        after: String::from_utf16_lossy(&value[end..after_end]),
+        truncated_before: before_start > 0,
+        truncated_after: after_end < value.len(),
+    })
+}
+
+/// Length of the element's value in UTF-16 code units, without reading it
+pub(crate) unsafe fn number_of_characters(element: AXUIElementRef) -> Option<usize> {
+    let count_ref = copy_attribute(element, "AXNumberOfCharacters")?;
+    if CFGetTypeID(count_ref) != CFNumber::type_id() {
+        cf_release(count_ref);
+        return None;
+    }
+    let count = CFNumber::wrap_under_create_rule(count_ref as CFNumberRef).to_i64()?;
+    usize::try_from(count).ok()
+}
+
+/// Part of the element's value, by UTF-16 offset and length
+pub(crate) unsafe fn string_for_range(
+    element: AXUIElementRef,
+    location: usize,
+    length: usize,
+) -> Option<String> {
+    if length == 0 {
+        return Some(String::new());
+    }
+
+    let range = CFRange {
+        location: location as isize,
+        length: length as isize,
+    };
+    let range_value = AXValueCreate(
+        kAXValueTypeCFRange,
+        &range as *const CFRange as *const c_void,
+    );
+    if range_value.is_null() {
+        return None;
+    }
+
+    let attr = CFString::new("AXStringForRange");
+    let mut string_ref: CFTypeRef = ptr::null();
+    let result = AXUIElementCopyParameterizedAttributeValue(
+        element,
+        attr.as_concrete_TypeRef(),
+        range_value as CFTypeRef,
+        &mut string_ref,
+    );
+    cf_release(range_value as CFTypeRef);
+    if result != 0 || string_ref.is_null() {
+        cf_release(string_ref);
+        return None;
+    }
+    if CFGetTypeID(string_ref) != CFString::type_id() {
+        cf_release(string_ref);
+        return None;
+    }
+    Some(CFString::wrap_under_create_rule(string_ref as CFStringRef).to_string())
+}
+
+/// The element's AXSelectedTextRange, which is the caret when its length is 0
+unsafe fn selected_range(element: AXUIElementRef) -> Option<CFRange> {
+    let range_ref = copy_attribute(element, "AXSelectedTextRange")?;
+    let mut range = CFRange {
+        location: 0,
+        length: 0,
+    };
+    let read = AXValueGetValue(
+        range_ref as AXValueRef,
+        kAXValueTypeCFRange,
+        &mut range as *mut CFRange as *mut c_void,
+    );
+    cf_release(range_ref);
+    (read && range.location >= 0 && range.length >= 0).then_some(range)
+}
+
+/// The element's selection as a UTF-16 (offset, length)
+pub(crate) unsafe fn selection_range(element: AXUIElementRef) -> Option<(usize, usize)> {
+    selected_range(element).map(|range| (range.location as usize, range.length as usize))
+}
+
+/// Select part of the element's value, by UTF-16 offset and length
+pub(crate) unsafe fn select_range(element: AXUIElementRef, location: usize, length: usize) -> bool {
+    let range = CFRange {
+        location: location as isize,
+        length: length as isize,
+    };
+    let range_value = AXValueCreate(
+        kAXValueTypeCFRange,
+        &range as *const CFRange as *const c_void,
+    );
+    if range_value.is_null() {
+        return false;
+    }
+
+    let attr = CFString::new("AXSelectedTextRange");
+    let result = AXUIElementSetAttributeValue(
+        element,
+        attr.as_concrete_TypeRef(),
+        range_value as CFTypeRef,
+    );
+    cf_release(range_value as CFTypeRef);
+    result == 0
+}
+
+/// Password fields refuse (and must never receive) injected text
+pub(crate) unsafe fn is_secure_field(element: AXUIElementRef) -> bool {
+    copy_string_attribute(element, "AXSubrole").as_deref() == Some("AXSecureTextField")
+}
+
+/// Copy an AX attribute value; the caller owns (and must release) the result
+pub(crate) unsafe fn copy_attribute(element: AXUIElementRef, attribute: &str) -> Option<CFTypeRef> {
+    let attr = CFString::new(attribute);
+    let mut value_ref: CFTypeRef = ptr::null();
+
+    let result = AXUIElementCopyAttributeValue(element, attr.as_concrete_TypeRef(), &mut value_ref);
+
+    if result != 0 || value_ref.is_null() {
+        cf_release(value_ref);
+        return None;
+    }
+
+    Some(value_ref)
+}
+
+/// Read a string-valued AX attribute
+pub(crate) unsafe fn copy_string_attribute(
+    element: AXUIElementRef,
+    attribute: &str,
+) -> Option<String> {
+    let value_ref = copy_attribute(element, attribute)?;
+
+    if CFGetTypeID(value_ref) != CFString::type_id() {
+        cf_release(value_ref);
+        return None;
+    }
+
+    Some(CFString::wrap_under_create_rule(value_ref as CFStringRef).to_string())
 }
@@ -1,90 +1,820 @@
 // Text injection using macOS Accessibility API and clipboard fallback
 // Inserts AI-generated text into the focused text field
 
+use crate::error::PromptOsError;
+use crate::frontmost_app::FrontmostApp;
+use crate::injection_undo::{record, snapshot, text_in_range, InjectionMethod, Snapshot};
+use crate::objc_util::{cf_release, nsstring_to_string};
+use crate::permissions::{require, Permission};
+use crate::text_field_detector::{
+    attribute_settable, copy_focused_element, copy_string_attribute, is_editable, is_secure_field,
+    is_terminal, is_text_field_focused, number_of_characters, select_range, selection_range,
+};
 use accessibility_sys::*;
-use cocoa::appkit::{NSPasteboard, NSPasteboardTypeString};
+use cocoa::appkit::{
+    NSPasteboard, NSPasteboardTypeHTML, NSPasteboardTypeRTF, NSPasteboardTypeString,
+};
 use cocoa::base::{id, nil};
-use cocoa::foundation::{NSArray, NSString};
-use core_foundation::base::{CFTypeRef, TCFType};
+use cocoa::foundation::{NSArray, NSData, NSString};
+use core_foundation::base::{CFEqual, CFTypeRef, TCFType};
+use core_foundation::boolean::CFBoolean;
 use core_foundation::string::CFString;
 use core_graphics::event::{CGEvent, CGEventFlags, CGEventTapLocation, CGKeyCode};
 use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
 use objc::msg_send;
 use objc::sel;
 use objc::sel_impl;
-use std::ptr;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+// Most UTF-16 units one keyboard event reliably types via CGEventKeyboardSetUnicodeString
+const TYPING_CHUNK_UNITS: usize = 20;
+// Pause after each synthesized keystroke unless the caller picks another
+const DEFAULT_TYPING_DELAY_MS: u64 = 5;
+const CHARS_PER_SECOND_RANGE: std::ops::RangeInclusive<u32> = 2..=60;
+const MAX_JITTER_PERCENT: u32 = 90;
+// Pressed for each newline; many apps drop a typed "\n"
+const RETURN_KEYCODE: CGKeyCode = 0x24;
+// Reading the field back after an Accessibility write, until the text shows up
+const VERIFY_ATTEMPTS: u32 = 3;
+const VERIFY_INTERVAL: Duration = Duration::from_millis(50);
+// Watching for a paste to land before the clipboard is restored: how often, and for how long
+const RESTORE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const PASTE_TIMEOUT: Duration = Duration::from_secs(3);
+// How long a paste gets when the field can't be read back
+const UNREADABLE_PASTE_GRACE: Duration = Duration::from_millis(500);
+
+const USER_STRATEGIES_FILE: &str = "injection_strategies.json";
+const TYPING_PACE_FILE: &str = "typing_pace.json";
+
+/// Pacing for typing injection: humanized, it types a character at a time at about a person's
+/// speed, so web apps' bot heuristics and screen-shared meetings see ordinary typing
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug)]
+pub struct TypingPace {
+    pub humanized: bool,
+    /// Average speed when humanized
+    pub chars_per_second: u32,
+    /// How far each pause may stray from the average, as a percentage of it
+    pub jitter_percent: u32,
+}
+
+// Set by cancel_typing; checked before each keystroke and cleared when typing starts
+static TYPING_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+static TYPING_PACE: RwLock<TypingPace> = RwLock::new(TypingPace {
+    humanized: false,
+    chars_per_second: 12,
+    jitter_percent: 40,
+});
+
+/// One way of getting text into a field
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectionStrategy {
+    /// Write AXSelectedText (insert_text)
+    Accessibility,
+    /// Clipboard and Cmd+V (insert_text_via_paste)
+    Paste,
+    /// Synthesized keystrokes (insert_text_via_typing)
+    Typing,
+}
+
+// Apps without an entry of their own
+const DEFAULT_CHAIN: &[InjectionStrategy] = &[
+    InjectionStrategy::Accessibility,
+    InjectionStrategy::Paste,
+    InjectionStrategy::Typing,
+];
+// Terminals garble Accessibility writes; their paste is bracketed, so lines don't run one by one
+const TERMINAL_CHAIN: &[InjectionStrategy] = &[InjectionStrategy::Paste, InjectionStrategy::Typing];
+// Electron apps often report an Accessibility write as done without showing the text
+const ELECTRON_CHAIN: &[InjectionStrategy] = &[
+    InjectionStrategy::Paste,
+    InjectionStrategy::Accessibility,
+    InjectionStrategy::Typing,
+];
+
+// Bundle ids, or prefixes ending in '.', and their chains, shipped with the app; terminals and
+// other Electron apps are recognized without an entry
+const BUILTIN_STRATEGIES: &[(&str, &[InjectionStrategy])] = &[
+    ("com.tinyspeck.slackmacgap", ELECTRON_CHAIN),
+    ("com.hnc.Discord", ELECTRON_CHAIN),
+    ("com.microsoft.VSCode", ELECTRON_CHAIN),
+    ("com.todesktop.", ELECTRON_CHAIN),
+    ("notion.id", ELECTRON_CHAIN),
+    ("md.obsidian", ELECTRON_CHAIN),
+    // JetBrains editors are Swing components that ignore Accessibility writes, like terminals
+    ("com.jetbrains.", TERMINAL_CHAIN),
+];
+
+/// Where insert_text puts the text in the field
+#[derive(serde::Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InsertMode {
+    /// At the caret, or over the selection
+    #[default]
+    ReplaceSelection,
+    /// After the field's existing text, e.g. to continue writing
+    Append,
+    /// At a UTF-16 offset into the field's text
+    AtOffset,
+}
+
+/// Which Accessibility writes a field accepts, probed before injecting into it
+#[derive(serde::Serialize, Clone, Copy, Debug)]
+pub struct FieldWritability {
+    /// AXSelectedText is settable: insert at the caret or over the selection
+    pub selected_text: bool,
+    /// AXValue is settable: only the whole value can be replaced
+    pub value: bool,
+}
+
+pub(crate) unsafe fn field_writability(element: AXUIElementRef) -> FieldWritability {
+    FieldWritability {
+        selected_text: attribute_settable(element, "AXSelectedText"),
+        value: attribute_settable(element, "AXValue"),
+    }
+}
+
+/// The field the overlay was opened over (retained AXUIElement, stored as usize for thread
+/// safety), which must still have focus when the response is written
+struct InjectionTarget {
+    pid: i32,
+    app_name: Option<String>,
+    element: Option<usize>,
+    /// The trigger's typed text, in case erasing it with backspaces never reached the field
+    trigger_text: Option<TriggerText>,
+}
+
+/// Typed trigger text and the UTF-16 offset just past it, where the caret was when it fired
+struct TriggerText {
+    end: usize,
+    text: String,
+}
+
+impl Drop for InjectionTarget {
+    fn drop(&mut self) {
+        if let Some(element) = self.element.take() {
+            unsafe { core_foundation::base::CFRelease(element as CFTypeRef) };
+        }
+    }
+}
+
+static INJECTION_TARGET: Mutex<Option<InjectionTarget>> = Mutex::new(None);
+
+/// Remember the app's focused field as where the overlay's response goes
+pub fn remember_injection_target(app: &FrontmostApp) {
+    let target = InjectionTarget {
+        pid: app.pid,
+        app_name: app.name.clone(),
+        element: unsafe { copy_focused_element(Some(app.pid)) }.map(|element| element as usize),
+        trigger_text: None,
+    };
+    if let Ok(mut guard) = INJECTION_TARGET.lock() {
+        *guard = Some(target);
+    }
+}
+
+/// Note the `chars` characters before the caret of the remembered field as the trigger typed
+/// there, just before the monitor erases them with backspaces
+pub fn remember_trigger_text(chars: usize) {
+    let Ok(mut guard) = INJECTION_TARGET.lock() else {
+        return;
+    };
+    let Some(target) = guard.as_mut() else {
+        return;
+    };
+    let Some(element) = target.element else {
+        return;
+    };
+    let element = element as AXUIElementRef;
+
+    unsafe {
+        let Some((end, 0)) = selection_range(element) else {
+            return;
+        };
+        // A character may take two UTF-16 units, so read enough for that and trim to `chars`
+        let start = end.saturating_sub(chars * 2);
+        let Some(before) = text_in_range(element, start, end - start) else {
+            return;
+        };
+        let skip = before.chars().count().saturating_sub(chars);
+        let text: String = before.chars().skip(skip).collect();
+        if text.chars().count() == chars {
+            target.trigger_text = Some(TriggerText { end, text });
+        }
+    }
+}
+
+/// Delete trigger text the backspaces didn't erase from the element, if it's still right before
+/// the caret exactly as typed; anything else means the field moved on and is left alone
+pub(crate) unsafe fn remove_trigger_text(element: AXUIElementRef) {
+    let Ok(mut guard) = INJECTION_TARGET.lock() else {
+        return;
+    };
+    // Checked once, before the first write after the trigger
+    let Some(target) = guard.as_mut() else {
+        return;
+    };
+    let Some(trigger) = target.trigger_text.take() else {
+        return;
+    };
+    let same_field = target
+        .element
+        .is_some_and(|target| CFEqual(target as CFTypeRef, element as CFTypeRef) != 0);
+    if !same_field || selection_range(element) != Some((trigger.end, 0)) {
+        return;
+    }
+
+    let start = trigger.end - trigger.text.encode_utf16().count();
+    let length = trigger.end - start;
+    if text_in_range(element, start, length).as_deref() != Some(trigger.text.as_str()) {
+        return;
+    }
+    eprintln!("[DEBUG] Trigger text is still in the field; deleting it");
+    if !select_range(element, start, length) || set_selected_text(element, "").is_err() {
+        eprintln!("[ERROR] Failed to delete the trigger text");
+    }
+}
+
+/// remove_trigger_text for the focused field, before pasting or typing into it
+unsafe fn remove_trigger_text_from_focused() {
+    if let Some(focused) = copy_focused_element(None) {
+        remove_trigger_text(focused as AXUIElementRef);
+        cf_release(focused);
+    }
+}
+
+/// Confirm the field the overlay was opened over still has focus, so a response can't land in an
+/// app or field the user switched to while it was being generated
 #[tauri::command]
-pub fn insert_text(text: String) -> Result<(), String> {
+pub fn verify_injection_target() -> Result<(), PromptOsError> {
+    require(Permission::Accessibility)?;
+    let guard = INJECTION_TARGET
+        .lock()
+        .map_err(|_| "Failed to lock injection target".to_string())?;
+    // Opened without a trigger (e.g. from the tray), so there's nothing to compare against
+    let Some(target) = guard.as_ref() else {
+        return Ok(());
+    };
+    let original_app = target.app_name.as_deref().unwrap_or("the original app");
+
+    let app = crate::context::write_target_app().ok_or(PromptOsError::NoFocus)?;
+    if app.pid != target.pid {
+        return Err(PromptOsError::FocusChanged(format!(
+            "Focus moved from {} to {}",
+            original_app,
+            app.name.as_deref().unwrap_or("another app")
+        )));
+    }
+
+    let Some(element) = target.element else {
+        return Ok(());
+    };
     unsafe {
-        // 1. Create system-wide AXUIElement
-        let system_wide = AXUIElementCreateSystemWide();
-        if system_wide.is_null() {
-            return Err("Failed to create system-wide AXUIElement".to_string());
+        let focused = copy_focused_element(Some(target.pid)).ok_or(PromptOsError::NoFocus)?;
+        let same = CFEqual(focused, element as CFTypeRef) != 0;
+        cf_release(focused);
+        if !same {
+            return Err(PromptOsError::FocusChanged(format!(
+                "Focus moved to another field in {}",
+                original_app
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// An app's strategies, tried in order until one works
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct AppInjectionStrategy {
+    /// Bundle id, or a prefix ending in '.'
+    pub bundle_id: String,
+    pub chain: Vec<InjectionStrategy>,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct InjectionStrategies {
+    pub default_chain: Vec<InjectionStrategy>,
+    pub builtin: Vec<AppInjectionStrategy>,
+    /// The user's entries, which win over built-in ones
+    pub user: Vec<AppInjectionStrategy>,
+}
+
+struct StrategyStore {
+    data_dir: PathBuf,
+    user: Vec<AppInjectionStrategy>,
+}
+
+static STRATEGIES: Mutex<Option<StrategyStore>> = Mutex::new(None);
+
+/// Load the user's per-app strategies from the app data directory (called once during setup)
+pub fn init(data_dir: PathBuf) -> Result<(), PromptOsError> {
+    fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+    let user = fs::read_to_string(data_dir.join(USER_STRATEGIES_FILE))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default();
+
+    *STRATEGIES
+        .lock()
+        .map_err(|_| "Failed to lock injection strategies".to_string())? =
+        Some(StrategyStore { data_dir, user });
+    Ok(())
+}
+
+fn matches(entry: &str, bundle_id: &str) -> bool {
+    if entry.ends_with('.') {
+        bundle_id.starts_with(entry)
+    } else {
+        bundle_id == entry
+    }
+}
+
+/// The strategies to try for an app, in order: the user's entry, a built-in one, then what its
+/// kind of app needs
+pub fn strategy_chain(app: &FrontmostApp) -> Vec<InjectionStrategy> {
+    let bundle_id = app.bundle_id.as_deref().unwrap_or_default();
+
+    let user = STRATEGIES.lock().ok().and_then(|guard| {
+        guard
+            .as_ref()?
+            .user
+            .iter()
+            .find(|entry| matches(&entry.bundle_id, bundle_id))
+            .map(|entry| entry.chain.clone())
+    });
+    if let Some(chain) = user {
+        return chain;
+    }
+
+    let builtin = BUILTIN_STRATEGIES
+        .iter()
+        .find(|(entry, _)| matches(entry, bundle_id))
+        .map(|(_, chain)| *chain);
+    let chain = builtin.unwrap_or_else(|| {
+        if is_terminal(bundle_id) {
+            TERMINAL_CHAIN
+        } else if crate::electron::is_electron(app.pid) {
+            ELECTRON_CHAIN
+        } else {
+            DEFAULT_CHAIN
+        }
+    });
+    chain.to_vec()
+}
+
+fn strategies(store: Option<&StrategyStore>) -> InjectionStrategies {
+    InjectionStrategies {
+        default_chain: DEFAULT_CHAIN.to_vec(),
+        builtin: BUILTIN_STRATEGIES
+            .iter()
+            .map(|(bundle_id, chain)| AppInjectionStrategy {
+                bundle_id: bundle_id.to_string(),
+                chain: chain.to_vec(),
+            })
+            .collect(),
+        user: store.map(|s| s.user.clone()).unwrap_or_default(),
+    }
+}
+
+#[tauri::command]
+pub fn get_injection_strategies() -> Result<InjectionStrategies, PromptOsError> {
+    let guard = STRATEGIES
+        .lock()
+        .map_err(|_| "Failed to lock injection strategies".to_string())?;
+    Ok(strategies(guard.as_ref()))
+}
+
+/// Set the strategies to try for an app (or a prefix ending in '.'), replacing its entry
+#[tauri::command]
+pub fn set_app_injection_strategy(
+    bundle_id: String,
+    chain: Vec<InjectionStrategy>,
+) -> Result<InjectionStrategies, PromptOsError> {
+    let bundle_id = bundle_id.trim().to_string();
+    if bundle_id.is_empty() {
+        return Err("Bundle id is empty".into());
+    }
+    let mut deduped = Vec::new();
+    for strategy in chain {
+        if !deduped.contains(&strategy) {
+            deduped.push(strategy);
         }
+    }
+    if deduped.is_empty() {
+        return Err("Pick at least one way to insert text".into());
+    }
+
+    let mut guard = STRATEGIES
+        .lock()
+        .map_err(|_| "Failed to lock injection strategies".to_string())?;
+    let store = guard
+        .as_mut()
+        .ok_or("Injection strategies not initialized")?;
+
+    store.user.retain(|entry| entry.bundle_id != bundle_id);
+    store.user.push(AppInjectionStrategy {
+        bundle_id,
+        chain: deduped,
+    });
+    save_user_strategies(store)?;
+    Ok(strategies(Some(store)))
+}
+
+/// Remove the user's entry for an app, so the built-in chain applies again
+#[tauri::command]
+pub fn remove_app_injection_strategy(
+    bundle_id: String,
+) -> Result<InjectionStrategies, PromptOsError> {
+    let mut guard = STRATEGIES
+        .lock()
+        .map_err(|_| "Failed to lock injection strategies".to_string())?;
+    let store = guard
+        .as_mut()
+        .ok_or("Injection strategies not initialized")?;
+
+    store.user.retain(|entry| entry.bundle_id != bundle_id);
+    save_user_strategies(store)?;
+    Ok(strategies(Some(store)))
+}
+
+fn save_user_strategies(store: &StrategyStore) -> Result<(), PromptOsError> {
+    let json = serde_json::to_string_pretty(&store.user)
+        .map_err(|e| format!("Failed to serialize injection strategies: {}", e))?;
+    fs::write(store.data_dir.join(USER_STRATEGIES_FILE), json)
+        .map_err(|e| format!("Failed to save injection strategies: {}", e).into())
+}
+
+/// Which ways of writing should work in the field a response would go to
+#[derive(serde::Serialize, Clone)]
+pub struct InjectionCapabilities {
+    #[serde(flatten)]
+    pub writability: FieldWritability,
+    /// Clipboard and a synthetic Cmd+V
+    pub paste: bool,
+    /// Synthetic keystrokes
+    pub typing: bool,
+}
 
-        // 2. Get focused element
-        let focused_attr = CFString::new("AXFocusedUIElement");
-        let mut focused_element_ref: CFTypeRef = ptr::null();
+/// Inspect the focused element of the app being written to (the trigger's app while the overlay
+/// has focus), so the overlay can warn before a prompt is composed that nothing will land
+#[tauri::command]
+pub fn probe_injection_capabilities() -> Result<InjectionCapabilities, PromptOsError> {
+    require(Permission::Accessibility)?;
+    if crate::secure_input::enabled() {
+        return Err(PromptOsError::SecureField);
+    }
 
-        let result = AXUIElementCopyAttributeValue(
-            system_wide,
-            focused_attr.as_concrete_TypeRef(),
-            &mut focused_element_ref,
-        );
+    let app = crate::context::write_target_app().ok_or(PromptOsError::NoFocus)?;
 
-        if result != 0 || focused_element_ref.is_null() {
-            cf_release(system_wide as CFTypeRef);
-            return Err("No focused element found".to_string());
+    unsafe {
+        let focused = copy_focused_element(Some(app.pid)).ok_or(PromptOsError::NoFocus)?;
+        let element = focused as AXUIElementRef;
+        if is_secure_field(element) {
+            cf_release(focused);
+            return Err(PromptOsError::SecureField);
         }
 
+        let writability = field_writability(element);
+        let role = copy_string_attribute(element, "AXRole");
+        // Terminals refuse Accessibility writes but take keystrokes and pastes
+        let takes_keys = is_editable(element, role.as_deref())
+            || app.bundle_id.as_deref().is_some_and(is_terminal);
+        cf_release(focused);
+
+        Ok(InjectionCapabilities {
+            writability,
+            paste: takes_keys,
+            typing: takes_keys,
+        })
+    }
+}
+
+/// Write text into the focused field through Accessibility; `offset` is required by at_offset,
+/// and `render_markdown` writes Markdown with its markers stripped (fields here are plain text)
+#[tauri::command]
+pub fn insert_text(
+    text: String,
+    mode: Option<InsertMode>,
+    offset: Option<usize>,
+    render_markdown: Option<bool>,
+) -> Result<(), PromptOsError> {
+    // Content filter may sanitize or block the response outright
+    let text = crate::content_filter::filter_for_injection(&text)
+        .inspect_err(|_| crate::telemetry::record(crate::telemetry::EVENT_INJECT_BLOCKED))?;
+    let text = rendered_plain(text, render_markdown);
+    require(Permission::Accessibility)?;
+
+    unsafe {
+        // 1-2. Get the focused element (inside a browser's web area, the field itself)
+        let focused_element_ref = copy_focused_element(None).ok_or(PromptOsError::NoFocus)?;
+
         let focused_element = focused_element_ref as AXUIElementRef;
+        if is_secure_field(focused_element) || crate::secure_input::enabled() {
+            cf_release(focused_element as CFTypeRef);
+            return Err(PromptOsError::SecureField);
+        }
+
+        // 3. Fail before writing when the field won't take it, so the paste fallback runs at once
+        let writability = field_writability(focused_element);
+        if !writability.selected_text {
+            cf_release(focused_element as CFTypeRef);
+            crate::telemetry::record(crate::telemetry::EVENT_INJECT_INSERT_FAILED);
+            eprintln!("[DEBUG] Focused field isn't writable: {:?}", writability);
+            return Err(if writability.value {
+                "The focused field only lets Accessibility replace its whole value; paste instead"
+            } else {
+                "The focused field doesn't accept text via Accessibility; paste instead"
+            }
+            .into());
+        }
+
+        // 4. Clear a leftover trigger first; an explicit offset counts the text as the caller read it
+        if mode != Some(InsertMode::AtOffset) {
+            remove_trigger_text(focused_element);
+        }
 
-        // 3. Set the selected text attribute (inserts at cursor/replaces selection)
-        let selected_text_attr = CFString::new("AXSelectedText");
-        let text_value = CFString::new(&text);
+        // 5. Move the caret where the mode puts the text
+        if let Err(e) = place_caret(focused_element, mode.unwrap_or_default(), offset) {
+            cf_release(focused_element as CFTypeRef);
+            return Err(e);
+        }
 
-        let set_result = AXUIElementSetAttributeValue(
-            focused_element,
-            selected_text_attr.as_concrete_TypeRef(),
-            text_value.as_concrete_TypeRef() as CFTypeRef,
-        );
+        // 6. Set the selected text attribute (inserts at cursor/replaces selection)
+        let before = BeforeWrite::read(focused_element);
+        let mut result = insert_into_element(focused_element, &text);
 
+        // 7. Some apps report the write as done and leave the field as it was; fail so the next
+        // strategy runs (one that changed the field at all must not be written over again)
+        if result.is_ok() && !landed(focused_element, &before, &text) {
+            crate::telemetry::record(crate::telemetry::EVENT_INJECT_NOT_LANDED);
+            result = Err("The focused field accepted the text but doesn't show it".into());
+        }
         cf_release(focused_element as CFTypeRef);
-        cf_release(system_wide as CFTypeRef);
+        if result.is_err() {
+            crate::telemetry::record(crate::telemetry::EVENT_INJECT_INSERT_FAILED);
+        }
+        result
+    }
+}
 
-        if set_result != 0 {
-            return Err("Failed to insert text via Accessibility API".to_string());
+/// Read the field back after an Accessibility write to confirm it took; a field that can't be
+/// read or compared counts, since writing it again by another strategy could double the text
+unsafe fn landed(element: AXUIElementRef, before: &BeforeWrite, text: &str) -> bool {
+    for attempt in 0..VERIFY_ATTEMPTS {
+        // The value may be updated asynchronously after the write returns
+        if attempt > 0 {
+            thread::sleep(VERIFY_INTERVAL);
         }
+        if before.shows(element, text) != Some(false) {
+            return true;
+        }
+    }
+    eprintln!("[DEBUG] Inserted text didn't show up in the field");
+    false
+}
 
-        Ok(())
+/// A field just before a write into it, to tell afterwards whether the write took
+struct BeforeWrite {
+    /// UTF-16 offset the text is written at
+    location: Option<usize>,
+    value: Option<String>,
+}
+
+impl BeforeWrite {
+    unsafe fn read(element: AXUIElementRef) -> Self {
+        BeforeWrite {
+            location: selection_range(element).map(|(location, _)| location),
+            value: copy_string_attribute(element, "AXValue"),
+        }
     }
+
+    /// Whether the field shows the write: the text where it went, or else a value that changed
+    /// (the app may have reformatted it); None when the field can't be compared
+    ///
+    /// The text turning up anywhere else in the value proves nothing: a short reply, or the same
+    /// one inserted again, is likely there already.
+    unsafe fn shows(&self, element: AXUIElementRef, text: &str) -> Option<bool> {
+        // Apps may store line breaks as "\r"
+        let normalize = |text: &str| text.replace("\r\n", "\n").replace('\r', "\n");
+
+        let at_location = self
+            .location
+            .and_then(|location| text_in_range(element, location, text.encode_utf16().count()));
+        if at_location.is_some_and(|found| normalize(&found) == normalize(text)) {
+            return Some(true);
+        }
+        match (&self.value, copy_string_attribute(element, "AXValue")) {
+            (Some(before), Some(value)) => Some(value != *before),
+            _ => None,
+        }
+    }
+}
+
+/// Markdown with its markers stripped when asked for, else the text as it is
+fn rendered_plain(text: String, render_markdown: Option<bool>) -> String {
+    if render_markdown == Some(true) {
+        prompt_os_pipeline::render_markdown(&text).plain
+    } else {
+        text
+    }
+}
+
+/// Collapse the selection to where the mode inserts; replace_selection leaves it as it is
+unsafe fn place_caret(
+    element: AXUIElementRef,
+    mode: InsertMode,
+    offset: Option<usize>,
+) -> Result<(), PromptOsError> {
+    let length = || {
+        number_of_characters(element).or_else(|| {
+            copy_string_attribute(element, "AXValue").map(|value| value.encode_utf16().count())
+        })
+    };
+
+    let location = match mode {
+        InsertMode::ReplaceSelection => return Ok(()),
+        InsertMode::Append => length().ok_or("Can't tell where the focused field's text ends")?,
+        InsertMode::AtOffset => {
+            let offset = offset.ok_or("Inserting at an offset needs the offset")?;
+            if let Some(length) = length().filter(|length| offset > *length) {
+                return Err(format!(
+                    "Offset {} is past the end of the field ({} characters)",
+                    offset, length
+                )
+                .into());
+            }
+            offset
+        }
+    };
+
+    if !select_range(element, location, 0) {
+        return Err("Failed to move the caret in the focused field".into());
+    }
+    Ok(())
+}
+
+/// Replace the selection of a specific element (which need not be focused or frontmost), keeping
+/// what it replaced so undo_last_injection can put it back
+pub(crate) unsafe fn insert_into_element(
+    element: AXUIElementRef,
+    text: &str,
+) -> Result<(), PromptOsError> {
+    let before = snapshot(element);
+    set_selected_text(element, text)?;
+    if let Some(before) = before {
+        let mut pid = 0;
+        AXUIElementGetPid(element, &mut pid);
+        record(element, pid, before, text, InjectionMethod::Accessibility);
+    }
+    Ok(())
+}
+
+/// Write AXSelectedText, which inserts at the caret or replaces the selection
+pub(crate) unsafe fn set_selected_text(
+    element: AXUIElementRef,
+    text: &str,
+) -> Result<(), PromptOsError> {
+    let selected_text_attr = CFString::new("AXSelectedText");
+    let text_value = CFString::new(text);
+
+    let set_result = AXUIElementSetAttributeValue(
+        element,
+        selected_text_attr.as_concrete_TypeRef(),
+        text_value.as_concrete_TypeRef() as CFTypeRef,
+    );
+    if set_result != 0 {
+        return Err("Failed to insert text via Accessibility API".into());
+    }
+    Ok(())
+}
+
+/// Give keyboard focus back to an element (before pasting into it)
+pub(crate) unsafe fn focus_element(element: AXUIElementRef) -> Result<(), PromptOsError> {
+    let focused_attr = CFString::new("AXFocused");
+    let set_result = AXUIElementSetAttributeValue(
+        element,
+        focused_attr.as_concrete_TypeRef(),
+        CFBoolean::true_value().as_CFTypeRef(),
+    );
+    if set_result != 0 {
+        return Err("Failed to focus the original field".into());
+    }
+    Ok(())
+}
+
+/// Paste text into the focused field; `render_markdown` pastes Markdown as formatted text, which
+/// editors that take HTML or RTF keep and the rest get with its markers stripped
+#[tauri::command]
+pub fn insert_text_via_paste(
+    text: String,
+    render_markdown: Option<bool>,
+) -> Result<(), PromptOsError> {
+    crate::telemetry::record(crate::telemetry::EVENT_INJECT_PASTE);
+    let text = crate::content_filter::filter_for_injection(&text)
+        .inspect_err(|_| crate::telemetry::record(crate::telemetry::EVENT_INJECT_BLOCKED))?;
+    if render_markdown != Some(true) {
+        return paste(&text, &RichText::default());
+    }
+    let rendered = prompt_os_pipeline::render_markdown(&text);
+    let rich = RichText {
+        html: Some(rendered.html),
+        rtf: Some(rendered.rtf),
+    };
+    paste(&rendered.plain, &rich)
+}
+
+/// Formatted versions of pasted text, offered alongside the plain text
+#[derive(Default)]
+struct RichText {
+    html: Option<String>,
+    rtf: Option<String>,
 }
 
+/// Paste formatted text: apps that take HTML or RTF (Mail, Notes, Google Docs) keep the bold,
+/// lists, and links, and the rest get the plain text
 #[tauri::command]
-pub fn insert_text_via_paste(text: String) -> Result<(), String> {
+pub fn insert_rich_text(
+    text: String,
+    html: Option<String>,
+    rtf: Option<String>,
+) -> Result<(), PromptOsError> {
+    crate::telemetry::record(crate::telemetry::EVENT_INJECT_PASTE);
+    let filtered = crate::content_filter::filter_for_injection(&text)
+        .inspect_err(|_| crate::telemetry::record(crate::telemetry::EVENT_INJECT_BLOCKED))?;
+    // The filter only rewrites plain text, so anything it changed would survive in the markup
+    let rich = if filtered == text {
+        RichText { html, rtf }
+    } else {
+        eprintln!("[DEBUG] Content filter changed the text; pasting it without formatting");
+        RichText::default()
+    };
+    paste(&filtered, &rich)
+}
+
+/// Put the text (and any formatted versions) on the clipboard, press Cmd+V, and restore the
+/// clipboard afterwards
+fn paste(text: &str, rich: &RichText) -> Result<(), PromptOsError> {
+    // Synthetic Cmd+V is silently dropped without Accessibility
+    require(Permission::Accessibility)?;
+    // While a password prompt owns the keyboard, Cmd+V could land in it rather than the field
+    if crate::secure_input::enabled() {
+        return Err(PromptOsError::SecureField);
+    }
+    // A password field would take the paste as readily as typing
+    is_text_field_focused()?;
+
     unsafe {
         // 1. Get the general pasteboard
         let pasteboard: id = NSPasteboard::generalPasteboard(nil);
 
-        // 2. Save current clipboard contents
-        let saved_contents = get_clipboard_string(pasteboard);
+        // 2. Save current clipboard contents, every item in every type (images, files, rich text)
+        let saved_contents = save_pasteboard(pasteboard);
 
-        // 3. Clear and set new clipboard content
+        // 3. Clear and set new clipboard content, richest type first
         let _: () = msg_send![pasteboard, clearContents];
 
         let ns_string = NSString::alloc(nil);
-        let ns_string = NSString::init_str(ns_string, &text);
+        let ns_string = NSString::init_str(ns_string, text);
 
-        let array = NSArray::arrayWithObject(nil, NSPasteboardTypeString);
+        let mut types = Vec::new();
+        if rich.rtf.is_some() {
+            types.push(NSPasteboardTypeRTF);
+        }
+        if rich.html.is_some() {
+            types.push(NSPasteboardTypeHTML);
+        }
+        types.push(NSPasteboardTypeString);
+        let array = NSArray::arrayWithObjects(nil, &types);
         let _: bool = msg_send![pasteboard, declareTypes:array owner:nil];
-        let success: bool = msg_send![pasteboard, setString:ns_string forType:NSPasteboardTypeString];
+
+        let mut success: bool =
+            msg_send![pasteboard, setString:ns_string forType:NSPasteboardTypeString];
+        if let Some(rtf) = &rich.rtf {
+            let data = NSData::dataWithBytes_length_(
+                nil,
+                rtf.as_ptr() as *const std::ffi::c_void,
+                rtf.len() as u64,
+            );
+            let set: bool = msg_send![pasteboard, setData:data forType:NSPasteboardTypeRTF];
+            success &= set;
+        }
+        if let Some(html) = &rich.html {
+            let html = NSString::alloc(nil).init_str(html);
+            let set: bool = msg_send![pasteboard, setString:html forType:NSPasteboardTypeHTML];
+            success &= set;
+        }
 
         if !success {
-            return Err("Failed to set clipboard content".to_string());
+            restore_pasteboard(pasteboard, &saved_contents);
+            return Err("Failed to set clipboard content".into());
         }
+        let pasted_change: i64 = msg_send![pasteboard, changeCount];
 
         // 4. Simulate Cmd+V
         let v_keycode: CGKeyCode = 0x09; // V key
@@ -103,70 +833,446 @@ pub fn insert_text_via_paste(text: String) -> Result<(), String> {
             .map_err(|_| "Failed to create key up event".to_string())?;
         key_up.set_flags(CGEventFlags::CGEventFlagCommand);
 
+        // The trigger monitor must not react to our own Cmd+V
+        crate::event_tap::mark_synthetic(&key_down);
+        crate::event_tap::mark_synthetic(&key_up);
+
+        // What the paste replaces, so it can be undone
+        remove_trigger_text_from_focused();
+        let target = focused_snapshot();
+
+        // The field to watch for the paste (retained), and how it looked before
+        let watched = copy_focused_element(None).map(|element| {
+            (
+                element as usize,
+                BeforeWrite::read(element as AXUIElementRef),
+            )
+        });
+
+        // Secure input may have come on while the clipboard was being swapped
+        if crate::secure_input::enabled() {
+            restore_pasteboard(pasteboard, &saved_contents);
+            finish_keystroke_injection(target, text, false);
+            if let Some((element, _)) = watched {
+                cf_release(element as CFTypeRef);
+            }
+            return Err(PromptOsError::SecureField);
+        }
+
         // Post events
         key_down.post(CGEventTapLocation::HID);
         key_up.post(CGEventTapLocation::HID);
+        finish_keystroke_injection(target, text, true);
+
+        // 5. Restore clipboard once the paste has landed
+        let text = text.to_string();
+        thread::spawn(move || unsafe {
+            restore_after_paste(saved_contents, pasted_change, watched, &text);
+        });
+
+        Ok(())
+    }
+}
+
+/// Put the saved clipboard back once the pasted text shows up in the watched field (or after a
+/// grace period when it can't be read), unless something was copied in the meantime
+unsafe fn restore_after_paste(
+    saved: SavedPasteboard,
+    pasted_change: i64,
+    watched: Option<(usize, BeforeWrite)>,
+    text: &str,
+) {
+    let pasteboard: id = NSPasteboard::generalPasteboard(nil);
+    let still_ours = || {
+        let change: i64 = msg_send![pasteboard, changeCount];
+        change == pasted_change
+    };
+
+    let started = Instant::now();
+    let restore = loop {
+        thread::sleep(RESTORE_POLL_INTERVAL);
+        // The user's new copy wins over what they had before the paste
+        if !still_ours() {
+            eprintln!("[DEBUG] Clipboard changed since the paste; not restoring it");
+            break false;
+        }
+        let shown = watched
+            .as_ref()
+            .and_then(|(element, before)| before.shows(*element as AXUIElementRef, text));
+        let waited = started.elapsed();
+        match shown {
+            Some(true) => break true,
+            None if waited >= UNREADABLE_PASTE_GRACE => break true,
+            _ if waited >= PASTE_TIMEOUT => {
+                eprintln!("[DEBUG] Paste never showed up in the field; restoring the clipboard");
+                break true;
+            }
+            _ => {}
+        }
+    };
+    if let Some((element, _)) = watched {
+        cf_release(element as CFTypeRef);
+    }
+
+    if restore && still_ours() {
+        restore_pasteboard(pasteboard, &saved);
+    }
+}
+
+/// Type text as synthesized Unicode keystrokes, for apps where neither Accessibility writes nor
+/// pasting work (terminals, some Java apps); `delay_ms` paces the keystrokes for slow apps, and
+/// `render_markdown` types Markdown with its markers stripped
+#[tauri::command]
+pub async fn insert_text_via_typing(
+    text: String,
+    delay_ms: Option<u64>,
+    render_markdown: Option<bool>,
+) -> Result<(), PromptOsError> {
+    // Humanized pacing can take minutes; keep it off the main thread
+    tauri::async_runtime::spawn_blocking(move || type_into_focused(text, delay_ms, render_markdown))
+        .await
+        .map_err(|e| format!("Typing failed: {}", e))?
+}
 
+fn type_into_focused(
+    text: String,
+    delay_ms: Option<u64>,
+    render_markdown: Option<bool>,
+) -> Result<(), PromptOsError> {
+    crate::telemetry::record(crate::telemetry::EVENT_INJECT_TYPING);
+    let text = crate::content_filter::filter_for_injection(&text)
+        .inspect_err(|_| crate::telemetry::record(crate::telemetry::EVENT_INJECT_BLOCKED))?;
+    let text = rendered_plain(text, render_markdown);
+    // Synthetic keystrokes are silently dropped without Accessibility
+    require(Permission::Accessibility)?;
+    // A password field would take the keystrokes like any other
+    is_text_field_focused()?;
 
-        // 5. Restore clipboard after delay
-        thread::spawn(move || {
-            thread::sleep(Duration::from_millis(500));
+    let text = text.replace("\r\n", "\n");
+    // An explicit delay is for a slow app, and wins over the humanized pace
+    let pace = TYPING_PACE
+        .read()
+        .map(|pace| *pace)
+        .map_err(|_| "Failed to lock typing pace".to_string())?;
+    let pacing = match delay_ms {
+        None if pace.humanized => Pacing::Humanized(pace),
+        _ => Pacing::Fixed(Duration::from_millis(
+            delay_ms.unwrap_or(DEFAULT_TYPING_DELAY_MS),
+        )),
+    };
 
-            unsafe {
-                let pasteboard: id = NSPasteboard::generalPasteboard(nil);
-                let _: () = msg_send![pasteboard, clearContents];
+    let target = unsafe {
+        remove_trigger_text_from_focused();
+        focused_snapshot()
+    };
+    let typed = type_text(&text, pacing);
+    unsafe { finish_keystroke_injection(target, &text, typed.is_ok()) };
+    typed
+}
 
-                if let Some(original_content) = saved_contents {
-                    let ns_string = NSString::alloc(nil);
-                    let ns_string = NSString::init_str(ns_string, &original_content);
+/// Write text into the focused field of the app being written to, trying that app's strategies
+/// in order; returns the one that worked (`render_markdown` is passed on to each)
+#[tauri::command]
+pub async fn inject_text(
+    text: String,
+    render_markdown: Option<bool>,
+) -> Result<InjectionStrategy, PromptOsError> {
+    // Typing may be in the chain, and that can take minutes
+    tauri::async_runtime::spawn_blocking(move || inject(text, render_markdown))
+        .await
+        .map_err(|e| format!("Injection failed: {}", e))?
+}
 
-                    let array = NSArray::arrayWithObject(nil, NSPasteboardTypeString);
-                    let _: bool = msg_send![pasteboard, declareTypes:array owner:nil];
-                    let _: bool =
-                        msg_send![pasteboard, setString:ns_string forType:NSPasteboardTypeString];
-                }
+/// inject_text, on the calling thread
+pub(crate) fn inject(
+    text: String,
+    render_markdown: Option<bool>,
+) -> Result<InjectionStrategy, PromptOsError> {
+    let app = crate::context::write_target_app().ok_or(PromptOsError::NoFocus)?;
+    let chain = strategy_chain(&app);
+
+    let mut last_error = None;
+    for strategy in chain {
+        let result = match strategy {
+            InjectionStrategy::Accessibility => {
+                insert_text(text.clone(), None, None, render_markdown)
             }
-        });
+            InjectionStrategy::Paste => insert_text_via_paste(text.clone(), render_markdown),
+            InjectionStrategy::Typing => type_into_focused(text.clone(), None, render_markdown),
+        };
+        match result {
+            Ok(()) => return Ok(strategy),
+            // No other strategy gets past these either
+            Err(
+                e @ (PromptOsError::NoPermission(_)
+                | PromptOsError::SecureField
+                | PromptOsError::Blocked(_)),
+            ) => return Err(e),
+            // The user stopped typing partway; another strategy would write the text again
+            Err(e) if TYPING_CANCELLED.load(Ordering::SeqCst) => return Err(e),
+            Err(e) => {
+                eprintln!("[DEBUG] {:?} injection failed: {}", strategy, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(last_error.unwrap_or_else(|| "No injection strategy to try".into()))
+}
+
+/// Stop typing injection that's under way, leaving what was typed so far in the field
+#[tauri::command]
+pub fn cancel_typing() {
+    TYPING_CANCELLED.store(true, Ordering::SeqCst);
+}
+
+#[tauri::command]
+pub fn get_typing_pace() -> Result<TypingPace, PromptOsError> {
+    TYPING_PACE
+        .read()
+        .map(|pace| *pace)
+        .map_err(|_| "Failed to lock typing pace".into())
+}
+
+#[tauri::command]
+pub fn set_typing_pace(pace: TypingPace) -> Result<(), PromptOsError> {
+    apply_typing_pace(pace)?;
+    crate::settings::save(TYPING_PACE_FILE, &pace)
+}
+
+fn apply_typing_pace(pace: TypingPace) -> Result<(), PromptOsError> {
+    if !CHARS_PER_SECOND_RANGE.contains(&pace.chars_per_second) {
+        return Err(format!(
+            "Typing speed of {} characters a second must be {} to {}",
+            pace.chars_per_second,
+            CHARS_PER_SECOND_RANGE.start(),
+            CHARS_PER_SECOND_RANGE.end()
+        )
+        .into());
+    }
+    if pace.jitter_percent > MAX_JITTER_PERCENT {
+        return Err(format!("Jitter can be at most {}%", MAX_JITTER_PERCENT).into());
+    }
+    *TYPING_PACE
+        .write()
+        .map_err(|_| "Failed to lock typing pace".to_string())? = pace;
+    Ok(())
+}
+
+/// Restore the saved typing pace (called once during setup)
+pub fn load_settings() {
+    crate::settings::restore(TYPING_PACE_FILE, apply_typing_pace);
+}
+
+/// How typed text is broken up and spaced out
+enum Pacing {
+    /// Chunks of several characters, with the same pause after each
+    Fixed(Duration),
+    /// A character at a time, with a pause that varies around the pace's average
+    Humanized(TypingPace),
+}
+
+impl Pacing {
+    fn chunks(&self, line: &str) -> Vec<String> {
+        match self {
+            Pacing::Fixed(_) => typing_chunks(line),
+            Pacing::Humanized(_) => line.chars().map(String::from).collect(),
+        }
+    }
 
+    fn pause(&self) -> Duration {
+        match self {
+            Pacing::Fixed(delay) => *delay,
+            Pacing::Humanized(pace) => {
+                let average = 1.0 / pace.chars_per_second as f64;
+                let jitter = pace.jitter_percent as f64 / 100.0 * random_unit();
+                Duration::from_secs_f64(average * (1.0 + jitter))
+            }
+        }
+    }
+}
+
+/// A random number in [-1, 1]; every RandomState is freshly keyed, which is random enough to
+/// vary keystroke timing
+fn random_unit() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+    let bits = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    bits as f64 / u64::MAX as f64 * 2.0 - 1.0
+}
+
+fn type_text(text: &str, pacing: Pacing) -> Result<(), PromptOsError> {
+    let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState)
+        .map_err(|_| "Failed to create event source".to_string())?;
+    TYPING_CANCELLED.store(false, Ordering::SeqCst);
+    let cancelled = || -> Result<(), PromptOsError> {
+        if TYPING_CANCELLED.load(Ordering::SeqCst) {
+            return Err("Typing was stopped".into());
+        }
         Ok(())
+    };
+
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            cancelled()?;
+            post_keystroke(&source, RETURN_KEYCODE, None)?;
+            thread::sleep(pacing.pause());
+        }
+        for chunk in pacing.chunks(line) {
+            cancelled()?;
+            post_keystroke(&source, 0, Some(&chunk))?;
+            thread::sleep(pacing.pause());
+        }
     }
+    Ok(())
 }
 
-/// Get string content from clipboard if available
-unsafe fn get_clipboard_string(pasteboard: id) -> Option<String> {
-    let types: id = msg_send![pasteboard, types];
-    if types == nil {
-        return None;
+/// The focused element (retained) and its selection, before pasting or typing into it
+unsafe fn focused_snapshot() -> Option<(CFTypeRef, Snapshot)> {
+    let focused = copy_focused_element(None)?;
+    match snapshot(focused as AXUIElementRef) {
+        Some(before) => Some((focused, before)),
+        None => {
+            cf_release(focused);
+            None
+        }
     }
+}
 
-    let string_type = NSPasteboardTypeString;
-    let type_array = NSArray::arrayWithObject(nil, string_type);
-    let available: id = msg_send![pasteboard, availableTypeFromArray: type_array];
+/// Record a paste or typed text as undoable (with Cmd+Z) once it went out, and release the element
+unsafe fn finish_keystroke_injection(
+    target: Option<(CFTypeRef, Snapshot)>,
+    text: &str,
+    sent: bool,
+) {
+    let Some((focused, before)) = target else {
+        return;
+    };
+    if sent {
+        let element = focused as AXUIElementRef;
+        let mut pid = 0;
+        AXUIElementGetPid(element, &mut pid);
+        record(element, pid, before, text, InjectionMethod::Keystrokes);
+    }
+    cf_release(focused);
+}
 
-    if available == nil {
-        return None;
+/// Press and release a key, typing `string` in place of the key's own character when given
+fn post_keystroke(
+    source: &CGEventSource,
+    keycode: CGKeyCode,
+    string: Option<&str>,
+) -> Result<(), PromptOsError> {
+    for key_down in [true, false] {
+        let event = CGEvent::new_keyboard_event(source.clone(), keycode, key_down)
+            .map_err(|_| "Failed to create keyboard event".to_string())?;
+        // Modifiers still held from the trigger would turn the text into shortcuts
+        event.set_flags(CGEventFlags::CGEventFlagNull);
+        if let Some(string) = string {
+            event.set_string(string);
+        }
+        // The trigger monitor must not react to the text it types
+        crate::event_tap::mark_synthetic(&event);
+        event.post(CGEventTapLocation::HID);
     }
+    Ok(())
+}
 
-    let ns_string: id = msg_send![pasteboard, stringForType: string_type];
-    if ns_string == nil {
-        return None;
+/// Split text into pieces of at most TYPING_CHUNK_UNITS UTF-16 units, never inside a character
+fn typing_chunks(text: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    let mut units = 0;
+    for c in text.chars() {
+        if units + c.len_utf16() > TYPING_CHUNK_UNITS {
+            chunks.push(std::mem::take(&mut chunk));
+            units = 0;
+        }
+        chunk.push(c);
+        units += c.len_utf16();
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
     }
+    chunks
+}
+
+/// Pasteboard contents as plain data, so they can be put back from another thread: each item's
+/// types and the data for each
+type SavedPasteboard = Vec<Vec<(String, Vec<u8>)>>;
 
-    let c_str: *const i8 = msg_send![ns_string, UTF8String];
-    if c_str.is_null() {
-        return None;
+/// Copy out every item on the pasteboard in every type it offers
+unsafe fn save_pasteboard(pasteboard: id) -> SavedPasteboard {
+    let items: id = msg_send![pasteboard, pasteboardItems];
+    if items == nil {
+        return Vec::new();
     }
 
-    Some(
-        std::ffi::CStr::from_ptr(c_str)
-            .to_string_lossy()
-            .into_owned(),
-    )
+    let count: usize = msg_send![items, count];
+    (0..count)
+        .map(|i| {
+            let item: id = msg_send![items, objectAtIndex: i];
+            let types: id = msg_send![item, types];
+            let type_count: usize = if types == nil {
+                0
+            } else {
+                msg_send![types, count]
+            };
+            (0..type_count)
+                .filter_map(|j| {
+                    let pasteboard_type: id = msg_send![types, objectAtIndex: j];
+                    // Data the owner only promised is produced now, while it's still around
+                    let data: id = msg_send![item, dataForType: pasteboard_type];
+                    if data == nil {
+                        return None;
+                    }
+                    let bytes: *const u8 = msg_send![data, bytes];
+                    let length: usize = msg_send![data, length];
+                    let data = if bytes.is_null() || length == 0 {
+                        Vec::new()
+                    } else {
+                        std::slice::from_raw_parts(bytes, length).to_vec()
+                    };
+                    Some((nsstring_to_string(pasteboard_type)?, data))
+                })
+                .collect()
+        })
+        .collect()
 }
 
-/// Safe CFRelease wrapper
-unsafe fn cf_release(cf: CFTypeRef) {
-    if !cf.is_null() {
-        core_foundation::base::CFRelease(cf);
+/// Replace the pasteboard's contents with saved ones (leaving it empty if it was)
+unsafe fn restore_pasteboard(pasteboard: id, saved: &SavedPasteboard) {
+    let _: () = msg_send![pasteboard, clearContents];
+    if saved.is_empty() {
+        return;
+    }
+    let Some(item_cls) = objc::runtime::Class::get("NSPasteboardItem") else {
+        return;
+    };
+
+    let items: Vec<id> = saved
+        .iter()
+        .map(|saved_item| {
+            let item: id = msg_send![item_cls, new];
+            for (pasteboard_type, data) in saved_item {
+                let ns_type = NSString::alloc(nil).init_str(pasteboard_type);
+                let ns_data = NSData::dataWithBytes_length_(
+                    nil,
+                    data.as_ptr() as *const std::ffi::c_void,
+                    data.len() as u64,
+                );
+                let _: bool = msg_send![item, setData: ns_data forType: ns_type];
+            }
+            item
+        })
+        .collect();
+
+    let array = NSArray::arrayWithObjects(nil, &items);
+    let written: bool = msg_send![pasteboard, writeObjects: array];
+    for item in items {
+        let _: () = msg_send![item, release];
+    }
+    if !written {
+        eprintln!("[ERROR] Failed to restore the clipboard");
     }
 }
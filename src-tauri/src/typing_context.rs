@@ -0,0 +1,166 @@
+// What the user typed into the focused field before a trigger (opt-in, in memory only)
+// Fed from the key monitor; forgotten on focus change, never recorded over secure input
+
+use crate::app_blocklist::is_blocked;
+use crate::error::PromptOsError;
+use prompt_os_pipeline::TypedKey;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+const DEFAULT_MAX_CHARS: usize = 200;
+const MAX_CHARS_RANGE: std::ops::RangeInclusive<usize> = 1..=2000;
+
+const SETTINGS_FILE: &str = "typing_context.json";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static MAX_CHARS: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_CHARS);
+static BUFFER: Mutex<TypedText> = Mutex::new(TypedText {
+    pid: None,
+    chars: VecDeque::new(),
+});
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy)]
+pub struct TypingContextConfig {
+    pub enabled: bool,
+    /// How many of the last typed characters to keep
+    pub max_chars: usize,
+}
+
+/// The last characters typed into one field
+struct TypedText {
+    /// App the field belongs to; typing in another app starts over
+    pid: Option<i32>,
+    chars: VecDeque<char>,
+}
+
+impl TypedText {
+    fn clear(&mut self) {
+        self.pid = None;
+        self.chars.clear();
+    }
+}
+
+/// Record a key-down the monitor saw outside our own windows; `trigger_chars` is set when it
+/// completed a trigger, whose characters are erased from the field rather than typed
+pub fn record(key: &TypedKey, trigger_chars: Option<usize>) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let Ok(mut buffer) = BUFFER.lock() else {
+        return;
+    };
+
+    // Another app has focus, or one whose fields we must never read
+    let app = crate::frontmost_app::frontmost_app();
+    if app
+        .as_ref()
+        .and_then(|app| app.bundle_id.as_deref())
+        .is_some_and(is_blocked)
+    {
+        buffer.clear();
+        return;
+    }
+    let pid = app.map(|app| app.pid);
+    if buffer.pid != pid {
+        buffer.clear();
+        buffer.pid = pid;
+    }
+
+    if let Some(trigger_chars) = trigger_chars {
+        for _ in 0..trigger_chars {
+            buffer.chars.pop_back();
+        }
+        return;
+    }
+    match key {
+        TypedKey::Text(text) => buffer.chars.extend(text.chars()),
+        TypedKey::Return => buffer.chars.push_back('\n'),
+        TypedKey::Backspace => {
+            buffer.chars.pop_back();
+        }
+        // Tab, a shortcut, or navigation: the caret may be in another field now
+        TypedKey::Reset => buffer.clear(),
+    }
+
+    let excess = buffer
+        .chars
+        .len()
+        .saturating_sub(MAX_CHARS.load(Ordering::Relaxed));
+    buffer.chars.drain(..excess);
+}
+
+/// Forget what was typed: focus moved (a click), or secure input came on
+pub fn focus_changed() {
+    if let Ok(mut buffer) = BUFFER.lock() {
+        buffer.clear();
+    }
+}
+
+/// What is recorded and the app it was typed into; None when disabled or nothing was typed
+fn recorded() -> Option<(Option<i32>, String)> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    let buffer = BUFFER.lock().ok()?;
+    let text: String = buffer.chars.iter().collect();
+    (!text.trim().is_empty()).then_some((buffer.pid, text))
+}
+
+/// What was typed into the app a trigger came from, right before it
+#[cfg(target_os = "macos")]
+pub fn typed_before(pid: Option<i32>) -> Option<String> {
+    recorded()
+        .filter(|(typed_in, _)| *typed_in == pid)
+        .map(|(_, text)| text)
+}
+
+#[tauri::command]
+pub fn get_typing_context_config() -> Result<TypingContextConfig, PromptOsError> {
+    Ok(TypingContextConfig {
+        enabled: ENABLED.load(Ordering::Relaxed),
+        max_chars: MAX_CHARS.load(Ordering::Relaxed),
+    })
+}
+
+/// Turning it off also forgets what was recorded
+#[tauri::command]
+pub fn set_typing_context_config(config: TypingContextConfig) -> Result<(), PromptOsError> {
+    apply(config)?;
+    crate::settings::save(SETTINGS_FILE, &config)
+}
+
+fn apply(config: TypingContextConfig) -> Result<(), PromptOsError> {
+    if !MAX_CHARS_RANGE.contains(&config.max_chars) {
+        return Err(format!(
+            "Typing context of {} characters must be {} to {} characters",
+            config.max_chars,
+            MAX_CHARS_RANGE.start(),
+            MAX_CHARS_RANGE.end()
+        )
+        .into());
+    }
+    MAX_CHARS.store(config.max_chars, Ordering::Relaxed);
+    ENABLED.store(config.enabled, Ordering::Relaxed);
+    if !config.enabled {
+        focus_changed();
+    }
+    Ok(())
+}
+
+/// Restore the saved typing context settings (called once during setup)
+pub fn load_settings() {
+    crate::settings::restore(SETTINGS_FILE, apply);
+}
+
+/// What is recorded right now, to show the user
+#[tauri::command]
+pub fn read_typing_context() -> Result<Option<String>, PromptOsError> {
+    Ok(recorded().map(|(_, text)| text))
+}
+
+#[tauri::command]
+pub fn purge_typing_context() -> Result<(), PromptOsError> {
+    focus_changed();
+    Ok(())
+}
@@ -0,0 +1,580 @@
+// Windows text field detection using UI Automation
+// Same commands and TextFieldBounds contract as text_field_detector, in physical screen pixels
+
+use crate::error::PromptOsError;
+use prompt_os_pipeline::{detect_direction, CoordinateSpace, InjectStrategy, WritingDirection};
+use std::ops::RangeInclusive;
+use windows::core::BSTR;
+use windows::Win32::Foundation::BOOL;
+use windows::Win32::System::Com::{
+    CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED,
+};
+use windows::Win32::System::Ole::SafeArrayDestroy;
+use windows::Win32::UI::Accessibility::*;
+use windows_sys::Win32::Foundation::{CloseHandle, POINT, RECT};
+use windows_sys::Win32::Graphics::Gdi::{MonitorFromPoint, MONITOR_DEFAULTTONEAREST};
+use windows_sys::Win32::System::Threading::{
+    OpenProcess, QueryFullProcessImageNameW, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+use windows_sys::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+    GetCursorPos, GetForegroundWindow, GetWindowRect, GetWindowTextW,
+};
+
+// DPI of a monitor at 100% scaling
+const BASE_DPI: f64 = 96.0;
+
+// Characters either side of the caret that get_surrounding_text returns unless asked otherwise
+const DEFAULT_SURROUNDING_CHARS: usize = 1000;
+const SURROUNDING_CHARS_RANGE: RangeInclusive<usize> = 1..=20_000;
+
+// Control types that take typing; anything else counts when its ValuePattern is writable
+const EDITABLE_CONTROL_TYPES: &[UIA_CONTROLTYPE_ID] =
+    &[UIA_EditControlTypeId, UIA_ComboBoxControlTypeId];
+
+// Hints for classifying the focused field, by lowercased executable name
+const TERMINAL_EXECUTABLES: &[&str] = &[
+    "windowsterminal.exe",
+    "conhost.exe",
+    "wezterm-gui.exe",
+    "alacritty.exe",
+    "mintty.exe",
+];
+const CODE_EDITOR_EXECUTABLES: &[&str] = &[
+    "code.exe",
+    "cursor.exe",
+    "devenv.exe",
+    "idea64.exe",
+    "sublime_text.exe",
+    "notepad++.exe",
+];
+const EMAIL_EXECUTABLES: &[&str] = &["outlook.exe", "olk.exe", "thunderbird.exe"];
+const CHAT_EXECUTABLES: &[&str] = &[
+    "slack.exe",
+    "discord.exe",
+    "ms-teams.exe",
+    "whatsapp.exe",
+    "telegram.exe",
+    "chatgpt.exe",
+    "claude.exe",
+];
+const CHAT_TITLES: &[&str] = &[
+    "chatgpt",
+    "claude",
+    "gemini",
+    "slack",
+    "discord",
+    "messenger",
+];
+
+#[derive(serde::Serialize, Clone)]
+pub struct TextFieldBounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Always None: Windows has no CGDirectDisplayID
+    pub display_id: Option<u32>,
+    /// The monitor's effective DPI over 96 (1.5 at 150% scaling)
+    pub scale_factor: Option<f64>,
+    /// Always physical: UI Automation measures in device pixels
+    pub coordinates: CoordinateSpace,
+}
+
+impl TextFieldBounds {
+    /// Bounds in pixels, with the scale of the monitor their origin is on
+    fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        TextFieldBounds {
+            x,
+            y,
+            width,
+            height,
+            display_id: None,
+            scale_factor: scale_factor_at(x, y),
+            coordinates: CoordinateSpace::Physical,
+        }
+    }
+}
+
+/// What the focused field is for, so default prompts can suit it
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldKind {
+    Chat,
+    CodeEditor,
+    EmailComposer,
+    Terminal,
+    SearchBar,
+    Generic,
+}
+
+impl FieldKind {
+    /// How a field of this kind takes text: terminals only by paste
+    pub fn inject_strategy(self) -> InjectStrategy {
+        match self {
+            FieldKind::Terminal => InjectStrategy::Paste,
+            _ => InjectStrategy::Insert,
+        }
+    }
+}
+
+/// What kind of element has focus and where it lives, so prompts can be tailored to it
+#[derive(serde::Serialize, Clone)]
+pub struct FocusedFieldInfo {
+    /// Control type, e.g. "Edit" or "Document"
+    pub role: Option<String>,
+    /// Window class name, the closest thing to an AXSubrole
+    pub subrole: Option<String>,
+    /// Executable name (e.g. "slack.exe"), standing in for a bundle id
+    pub bundle_id: Option<String>,
+    pub app_name: Option<String>,
+    pub window_title: Option<String>,
+    /// Help text, where browsers and WinUI put a field's placeholder
+    pub placeholder: Option<String>,
+    /// Whether the ValuePattern can be written, i.e. text can be set directly
+    pub value_settable: bool,
+    pub kind: FieldKind,
+    /// How a response should be written into it, from kind
+    pub strategy: InjectStrategy,
+    /// Script and writing direction of its text, else of its placeholder; None without letters
+    pub direction: Option<WritingDirection>,
+    /// Frame of the foreground window, for docking the overlay to the window's edge
+    pub window_frame: Option<TextFieldBounds>,
+}
+
+/// What is already written in the focused field, and where the caret sits in it
+#[derive(serde::Serialize, Clone)]
+pub struct FieldContent {
+    pub value: Option<String>,
+    /// Caret offset, or where the selection starts, in UTF-16 code units of value
+    pub selection_start: Option<usize>,
+    /// 0 when nothing is selected
+    pub selection_length: Option<usize>,
+}
+
+/// Text either side of the caret (or selection), cut to a limit so a long document stays local
+#[derive(serde::Serialize, Clone)]
+pub struct SurroundingText {
+    pub before: String,
+    pub after: String,
+    /// The field holds more text before before, or after after, than was returned
+    pub truncated_before: bool,
+    pub truncated_after: bool,
+}
+
+/// A UI Automation client; commands run on Tauri's thread pool, which may not have joined COM
+fn automation() -> Result<IUIAutomation, PromptOsError> {
+    unsafe {
+        // Fails harmlessly when this thread already joined COM
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+        CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER)
+            .map_err(|e| format!("Failed to create UI Automation client: {}", e).into())
+    }
+}
+
+/// The element with keyboard focus, in whatever app has it
+fn focused_element() -> Result<IUIAutomationElement, PromptOsError> {
+    let automation = automation()?;
+    unsafe { automation.GetFocusedElement() }.map_err(|_| PromptOsError::NoFocus)
+}
+
+/// The focused element, refused with SecureField when it is a password box
+fn focused_text_element() -> Result<IUIAutomationElement, PromptOsError> {
+    let element = focused_element()?;
+    if unsafe { is_password(&element) } {
+        return Err(PromptOsError::SecureField);
+    }
+    Ok(element)
+}
+
+/// UI Automation needs no permission on Windows
+#[tauri::command]
+pub fn check_accessibility_permission() -> Result<bool, PromptOsError> {
+    Ok(true)
+}
+
+/// Check if a text field is currently focused; a password box is refused with SecureField
+pub fn is_text_field_focused() -> Result<bool, PromptOsError> {
+    let Ok(element) = focused_element() else {
+        return Ok(false);
+    };
+    unsafe {
+        if is_password(&element) {
+            return Err(PromptOsError::SecureField);
+        }
+        Ok(is_editable(&element))
+    }
+}
+
+/// Get the mouse cursor position as fallback
+#[tauri::command]
+pub fn get_cursor_position() -> Result<TextFieldBounds, PromptOsError> {
+    let mut point = POINT { x: 0, y: 0 };
+    if unsafe { GetCursorPos(&mut point) } == 0 {
+        return Err("Failed to get cursor position".into());
+    }
+    Ok(TextFieldBounds::new(
+        point.x as f64,
+        point.y as f64,
+        0.0,
+        0.0,
+    ))
+}
+
+/// Bounds of the caret if a text field is focused, or the mouse position when the field can't
+/// say where its caret is
+#[tauri::command]
+pub fn get_focused_text_field_bounds() -> Result<TextFieldBounds, PromptOsError> {
+    if !is_text_field_focused()? {
+        eprintln!("[DEBUG] No text field focused");
+        return Err(PromptOsError::NoFocus);
+    }
+    if let Some(caret) = focused_element()
+        .ok()
+        .and_then(|element| unsafe { caret_bounds(&element) })
+    {
+        eprintln!("[DEBUG] Caret bounds: x={}, y={}", caret.x, caret.y);
+        return Ok(caret);
+    }
+    eprintln!("[DEBUG] No caret bounds, getting cursor position...");
+    get_cursor_position()
+}
+
+/// Caret of the element: TextPattern2's caret range, else the start of its selection
+unsafe fn caret_bounds(element: &IUIAutomationElement) -> Option<TextFieldBounds> {
+    let caret_range = element
+        .GetCurrentPatternAs::<IUIAutomationTextPattern2>(UIA_TextPattern2Id)
+        .ok()
+        .and_then(|pattern| {
+            let mut active = BOOL::default();
+            pattern.GetCaretRange(&mut active).ok()
+        });
+    let range = match caret_range {
+        Some(range) => range,
+        None => first_selection(&text_pattern(element)?)?,
+    };
+
+    // A collapsed range has no rectangles; the character after the caret stands in for it
+    let (x, y, width, height) = match range_rect(&range) {
+        Some(rect) => rect,
+        None => {
+            let character = range.Clone().ok()?;
+            character.ExpandToEnclosingUnit(TextUnit_Character).ok()?;
+            let (x, y, _, height) = range_rect(&character)?;
+            (x, y, 0.0, height)
+        }
+    };
+    Some(TextFieldBounds::new(x, y, width, height))
+}
+
+/// Scale of the monitor nearest a point (needs per-monitor DPI awareness, which Tauri sets)
+fn scale_factor_at(x: f64, y: f64) -> Option<f64> {
+    unsafe {
+        let point = POINT {
+            x: x as i32,
+            y: y as i32,
+        };
+        let monitor = MonitorFromPoint(point, MONITOR_DEFAULTTONEAREST);
+        if monitor.is_null() {
+            return None;
+        }
+        let (mut dpi_x, mut dpi_y) = (0, 0);
+        let result = GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y);
+        (result == 0 && dpi_x > 0).then(|| dpi_x as f64 / BASE_DPI)
+    }
+}
+
+/// First bounding rectangle (left, top, width, height) of a text range, i.e. its first line
+unsafe fn range_rect(range: &IUIAutomationTextRange) -> Option<(f64, f64, f64, f64)> {
+    let array = range.GetBoundingRectangles().ok()?;
+    if array.is_null() {
+        return None;
+    }
+    let count = (*array).rgsabound[0].cElements as usize;
+    let rect = (count >= 4).then(|| {
+        let values = std::slice::from_raw_parts((*array).pvData as *const f64, 4);
+        (values[0], values[1], values[2], values[3])
+    });
+    let _ = SafeArrayDestroy(array);
+    rect
+}
+
+/// Control type, owning app, window, and help text of the focused element
+#[tauri::command]
+pub fn get_focused_field_info() -> Result<FocusedFieldInfo, PromptOsError> {
+    let element = focused_element()?;
+    unsafe {
+        let control_type = element.CurrentControlType().ok();
+        let executable = element.CurrentProcessId().ok().and_then(executable_name);
+        let value = field_value(&element);
+        let mut info = FocusedFieldInfo {
+            role: control_type.map(control_type_name),
+            subrole: non_empty(element.CurrentClassName()),
+            app_name: executable
+                .as_deref()
+                .map(|name| name.trim_end_matches(".exe").to_string()),
+            bundle_id: executable,
+            window_title: foreground_window_title(),
+            placeholder: non_empty(element.CurrentHelpText()),
+            value_settable: value_settable(&element),
+            kind: FieldKind::Generic,
+            strategy: InjectStrategy::Insert,
+            direction: None,
+            window_frame: focused_window_frame(),
+        };
+        info.kind = classify_field(&element, &info);
+        info.strategy = info.kind.inject_strategy();
+        info.direction = value
+            .as_deref()
+            .or(info.placeholder.as_deref())
+            .and_then(detect_direction);
+        Ok(info)
+    }
+}
+
+/// Whether the executable is a terminal, whose prompt takes keystrokes but not UI Automation writes
+pub fn is_terminal(executable: &str) -> bool {
+    TERMINAL_EXECUTABLES.contains(&executable.to_lowercase().as_str())
+}
+
+/// Guess what the field is for from its app, window title, and name
+unsafe fn classify_field(element: &IUIAutomationElement, info: &FocusedFieldInfo) -> FieldKind {
+    let executable = info.bundle_id.as_deref().unwrap_or_default().to_lowercase();
+    let title = info
+        .window_title
+        .as_deref()
+        .unwrap_or_default()
+        .to_lowercase();
+    let name = non_empty(element.CurrentName())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    if TERMINAL_EXECUTABLES.contains(&executable.as_str()) {
+        FieldKind::Terminal
+    } else if CODE_EDITOR_EXECUTABLES.contains(&executable.as_str()) {
+        FieldKind::CodeEditor
+    } else if EMAIL_EXECUTABLES.contains(&executable.as_str()) {
+        FieldKind::EmailComposer
+    } else if CHAT_EXECUTABLES.contains(&executable.as_str())
+        || CHAT_TITLES.iter().any(|chat| title.contains(chat))
+    {
+        FieldKind::Chat
+    } else if name.contains("search") {
+        FieldKind::SearchBar
+    } else {
+        FieldKind::Generic
+    }
+}
+
+fn control_type_name(control_type: UIA_CONTROLTYPE_ID) -> String {
+    match control_type {
+        UIA_EditControlTypeId => "Edit".to_string(),
+        UIA_DocumentControlTypeId => "Document".to_string(),
+        UIA_ComboBoxControlTypeId => "ComboBox".to_string(),
+        UIA_GroupControlTypeId => "Group".to_string(),
+        UIA_PaneControlTypeId => "Pane".to_string(),
+        other => format!("ControlType({})", other.0),
+    }
+}
+
+/// Selection and full value of the focused field (UI Automation can't ask another app for its
+/// focus, so pid is ignored)
+pub fn read_field_text(_pid: Option<i32>) -> (Option<String>, Option<String>) {
+    let Ok(element) = focused_text_element() else {
+        return (None, None);
+    };
+    unsafe {
+        let selection = text_pattern(&element)
+            .and_then(|pattern| first_selection(&pattern))
+            .and_then(|range| non_empty(range.GetText(-1)));
+        (selection, field_value(&element))
+    }
+}
+
+/// What the user highlighted in the focused element, read without touching the clipboard (None
+/// when nothing is selected)
+#[tauri::command]
+pub fn get_selected_text() -> Result<Option<String>, PromptOsError> {
+    let element = focused_text_element()?;
+    unsafe {
+        Ok(text_pattern(&element)
+            .and_then(|pattern| first_selection(&pattern))
+            .and_then(|range| non_empty(range.GetText(-1))))
+    }
+}
+
+/// Full value of the focused field plus the caret offset and selection, for completions that
+/// follow on from what is already written
+#[tauri::command]
+pub fn get_field_content() -> Result<FieldContent, PromptOsError> {
+    let element = focused_text_element()?;
+    unsafe {
+        let offsets = text_pattern(&element).and_then(|pattern| selection_offsets(&pattern));
+        Ok(FieldContent {
+            value: field_value(&element),
+            selection_start: offsets.map(|(start, _)| start),
+            selection_length: offsets.map(|(_, length)| length),
+        })
+    }
+}
+
+/// Up to chars characters (UTF-16 code units) before and after the caret in the focused field,
+/// 1000 unless set
+#[tauri::command]
+pub fn get_surrounding_text(chars: Option<usize>) -> Result<SurroundingText, PromptOsError> {
+    let chars = chars.unwrap_or(DEFAULT_SURROUNDING_CHARS);
+    if !SURROUNDING_CHARS_RANGE.contains(&chars) {
+        return Err(format!(
+            "Surrounding text of {} characters must be {} to {} characters",
+            chars,
+            SURROUNDING_CHARS_RANGE.start(),
+            SURROUNDING_CHARS_RANGE.end()
+        )
+        .into());
+    }
+
+    let element = focused_text_element()?;
+    unsafe {
+        let (start, length) = text_pattern(&element)
+            .and_then(|pattern| selection_offsets(&pattern))
+            .ok_or(PromptOsError::NoFocus)?;
+        let value: Vec<u16> = field_value(&element)
+            .ok_or(PromptOsError::NoFocus)?
+            .encode_utf16()
+            .collect();
+
+        let start = start.min(value.len());
+        let end = (start + length).min(value.len());
+        let before_start = start.saturating_sub(chars);
+        let after_end = value.len().min(end + chars);
+        Ok(SurroundingText {
+            before: String::from_utf16_lossy(&value[before_start..start]),
+            after: String::from_utf16_lossy(&value[end..after_end]),
+            truncated_before: before_start > 0,
+            truncated_after: after_end < value.len(),
+        })
+    }
+}
+
+/// Where the selection starts and how long it is, in UTF-16 code units of the document
+unsafe fn selection_offsets(pattern: &IUIAutomationTextPattern) -> Option<(usize, usize)> {
+    let selection = first_selection(pattern)?;
+    let before = pattern.DocumentRange().ok()?;
+    before
+        .MoveEndpointByRange(
+            TextPatternRangeEndpoint_End,
+            &selection,
+            TextPatternRangeEndpoint_Start,
+        )
+        .ok()?;
+    let start = before.GetText(-1).ok()?.len();
+    let length = selection.GetText(-1).ok()?.len();
+    Some((start, length))
+}
+
+unsafe fn text_pattern(element: &IUIAutomationElement) -> Option<IUIAutomationTextPattern> {
+    element
+        .GetCurrentPatternAs::<IUIAutomationTextPattern>(UIA_TextPatternId)
+        .ok()
+}
+
+/// The selection, or the collapsed range at the caret when nothing is selected
+unsafe fn first_selection(pattern: &IUIAutomationTextPattern) -> Option<IUIAutomationTextRange> {
+    let selection = pattern.GetSelection().ok()?;
+    if selection.Length().ok()? == 0 {
+        return None;
+    }
+    selection.GetElement(0).ok()
+}
+
+/// The ValuePattern's value, or the whole document of a TextPattern-only field
+unsafe fn field_value(element: &IUIAutomationElement) -> Option<String> {
+    let value = element
+        .GetCurrentPatternAs::<IUIAutomationValuePattern>(UIA_ValuePatternId)
+        .ok()
+        .and_then(|pattern| pattern.CurrentValue().ok())
+        .map(|value| value.to_string());
+    value.or_else(|| {
+        let document = text_pattern(element)?.DocumentRange().ok()?;
+        document.GetText(-1).ok().map(|text| text.to_string())
+    })
+}
+
+/// Whether the element takes typing, from its control type or a writable ValuePattern
+unsafe fn is_editable(element: &IUIAutomationElement) -> bool {
+    element
+        .CurrentControlType()
+        .is_ok_and(|control_type| EDITABLE_CONTROL_TYPES.contains(&control_type))
+        || value_settable(element)
+}
+
+unsafe fn value_settable(element: &IUIAutomationElement) -> bool {
+    element
+        .GetCurrentPatternAs::<IUIAutomationValuePattern>(UIA_ValuePatternId)
+        .and_then(|pattern| pattern.CurrentIsReadOnly())
+        .is_ok_and(|read_only| !read_only.as_bool())
+}
+
+/// Password boxes refuse (and must never receive) injected text
+unsafe fn is_password(element: &IUIAutomationElement) -> bool {
+    element
+        .CurrentIsPassword()
+        .is_ok_and(|password| password.as_bool())
+}
+
+fn non_empty(text: windows::core::Result<BSTR>) -> Option<String> {
+    text.ok()
+        .map(|text| text.to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// File name of a process's executable, e.g. "slack.exe"
+fn executable_name(pid: i32) -> Option<String> {
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid as u32);
+        if process.is_null() {
+            return None;
+        }
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        let ok = QueryFullProcessImageNameW(process, 0, buffer.as_mut_ptr(), &mut len);
+        CloseHandle(process);
+        if ok == 0 {
+            return None;
+        }
+        let path = String::from_utf16_lossy(&buffer[..len as usize]);
+        path.rsplit('\\').next().map(str::to_string)
+    }
+}
+
+/// Frame of the foreground window, in pixels
+pub fn focused_window_frame() -> Option<TextFieldBounds> {
+    unsafe {
+        let window = GetForegroundWindow();
+        if window.is_null() {
+            return None;
+        }
+        let mut rect: RECT = std::mem::zeroed();
+        if GetWindowRect(window, &mut rect) == 0 {
+            return None;
+        }
+        Some(TextFieldBounds::new(
+            rect.left as f64,
+            rect.top as f64,
+            (rect.right - rect.left) as f64,
+            (rect.bottom - rect.top) as f64,
+        ))
+    }
+}
+
+fn foreground_window_title() -> Option<String> {
+    unsafe {
+        let window = GetForegroundWindow();
+        if window.is_null() {
+            return None;
+        }
+        let mut buffer = [0u16; 512];
+        let len = GetWindowTextW(window, buffer.as_mut_ptr(), buffer.len() as i32);
+        (len > 0).then(|| String::from_utf16_lossy(&buffer[..len as usize]))
+    }
+}
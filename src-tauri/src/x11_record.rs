@@ -0,0 +1,358 @@
+// Linux keystroke monitor: the X11 RECORD extension, polled on its own thread
+// RECORD only observes, so a matched command is erased with XTest and Ctrl+Alt+R is grabbed
+// Needs an X11 session (or XWayland apps); chord activation and action hotkeys are not matched yet
+
+use crate::error::PromptOsError;
+use crate::keystroke_monitor;
+use prompt_os_pipeline::{Monitor, Trigger, TriggerMatcher, TriggerSink, TypedKey};
+use std::cell::RefCell;
+use std::ffi::c_char;
+use std::os::raw::{c_int, c_uint, c_ulong};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use x11::keysym::{
+    XK_BackSpace, XK_Delete, XK_Down, XK_End, XK_Escape, XK_Home, XK_KP_Enter, XK_Left, XK_Next,
+    XK_Prior, XK_Return, XK_Right, XK_Tab, XK_Up, XK_r,
+};
+use x11::xlib::{
+    self, ControlMask, Display, GrabModeAsync, KeyPress, LockMask, Mod1Mask, Mod2Mask, Mod4Mask,
+    ShiftMask, XErrorEvent,
+};
+use x11::xrecord::{self, XRecordAllClients, XRecordFromServer, XRecordInterceptData};
+use x11::xtest;
+
+// How long the thread sleeps between draining recorded events
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// Keys that move the caret or leave the field, so earlier keystrokes no longer lead up to it
+const RESET_KEYSYMS: &[c_uint] = &[
+    XK_Return, XK_Tab, XK_Escape, XK_Home, XK_End, XK_Prior, XK_Next, XK_Delete, XK_Left, XK_Right,
+    XK_Up, XK_Down,
+];
+
+// Quick-transform hotkey: Ctrl+Alt+R, matching Ctrl+Option+R on macOS
+const QUICK_TRANSFORM_MODIFIERS: c_uint = ControlMask | Mod1Mask;
+
+// Caps Lock and Num Lock change the reported state, so the grab covers every combination
+const LOCK_VARIANTS: [c_uint; 4] = [0, LockMask, Mod2Mask, LockMask | Mod2Mask];
+
+// A recorded core key event, and where its modifier state sits
+const CORE_EVENT_BYTES: usize = 32;
+const EVENT_STATE_OFFSET: usize = 28;
+
+thread_local! {
+    // Recorded events are delivered on the polling thread, so no lock is needed
+    static MATCHER: RefCell<TriggerMatcher> = RefCell::new(TriggerMatcher::default());
+}
+
+/// Handed to the RECORD callback; lives on the monitor thread's stack
+struct RecordContext {
+    /// The control connection, used for keysym lookups and XTest
+    display: *mut Display,
+    sink: TriggerSink,
+}
+
+/// The character a keysym types, for Latin-1 and Unicode keysyms
+fn keysym_char(keysym: c_ulong) -> Option<char> {
+    match keysym {
+        0x20..=0x7e | 0xa0..=0xff => char::from_u32(keysym as u32),
+        _ if keysym & 0xff00_0000 == 0x0100_0000 => char::from_u32((keysym & 0x00ff_ffff) as u32),
+        _ => None,
+    }
+}
+
+/// Classify a key press for the trigger matcher
+fn typed_key(keysym: c_ulong, state: c_uint) -> TypedKey {
+    // Shortcuts and navigation aren't typing
+    let shortcut = (state & ControlMask != 0 && state & Mod1Mask == 0) || state & Mod4Mask != 0;
+    if shortcut {
+        return TypedKey::Reset;
+    }
+    let key = keysym as c_uint;
+    if key == XK_Return || key == XK_KP_Enter {
+        TypedKey::Return
+    } else if key == XK_BackSpace {
+        TypedKey::Backspace
+    } else if RESET_KEYSYMS.contains(&key) {
+        TypedKey::Reset
+    } else {
+        TypedKey::Text(keysym_char(keysym).map(String::from).unwrap_or_default())
+    }
+}
+
+/// Delete a command or abbreviation; unlike a tap or hook, RECORD can't stop any of its keys
+unsafe fn erase_typed(display: *mut Display, count: usize) {
+    let keycode = xlib::XKeysymToKeycode(display, XK_BackSpace as c_ulong) as c_uint;
+    if keycode == 0 {
+        return;
+    }
+    for _ in 0..count {
+        xtest::XTestFakeKeyEvent(display, keycode, xlib::True, 0);
+        xtest::XTestFakeKeyEvent(display, keycode, xlib::False, 0);
+    }
+    xlib::XFlush(display);
+}
+
+unsafe extern "C" fn record_callback(closure: *mut c_char, data: *mut XRecordInterceptData) {
+    let record = &*data;
+    // data_len counts 4-byte units
+    if record.category == XRecordFromServer && record.data_len as usize * 4 >= CORE_EVENT_BYTES {
+        let event = std::slice::from_raw_parts(record.data, CORE_EVENT_BYTES);
+        if event[0] as c_int == KeyPress {
+            let context = &*(closure as *const RecordContext);
+            handle_key_press(context, event, record.client_swapped != 0);
+        }
+    }
+    xrecord::XRecordFreeData(data);
+}
+
+unsafe fn handle_key_press(context: &RecordContext, event: &[u8], swapped: bool) {
+    let keycode = event[1];
+    let mut state = u16::from_ne_bytes([event[EVENT_STATE_OFFSET], event[EVENT_STATE_OFFSET + 1]]);
+    if swapped {
+        state = state.swap_bytes();
+    }
+    let state = state as c_uint;
+
+    // The state is from before this key, which is what picks the shifted level
+    let level = c_int::from(state & ShiftMask != 0);
+    let keysym = xlib::XkbKeycodeToKeysym(context.display, keycode, 0, level);
+
+    let quick_transform = keysym == XK_r as c_ulong
+        && state & QUICK_TRANSFORM_MODIFIERS == QUICK_TRANSFORM_MODIFIERS
+        && state & ShiftMask == 0;
+
+    let mut typed_len = 0;
+    let trigger = if quick_transform {
+        Some(Trigger::QuickTransform)
+    } else if let Some((trigger, len)) = MATCHER.with(|matcher| {
+        // xlib's KeyPress is the event type, hence the full path
+        let press = prompt_os_pipeline::KeyPress {
+            key: typed_key(keysym, state),
+            chord: None,
+            repeat: false,
+            at: Instant::now(),
+        };
+        keystroke_monitor::feed(&mut matcher.borrow_mut(), press)
+    }) {
+        // The key that ended the command or completed the abbreviation reached the field too
+        typed_len = len + 1;
+        Some(trigger)
+    } else {
+        None
+    };
+
+    // The grab already kept the hotkey from the focused app; commands and abbreviations are erased
+    if let Some(trigger) = trigger {
+        if (context.sink)(trigger) && typed_len > 0 {
+            erase_typed(context.display, typed_len);
+        }
+    }
+}
+
+unsafe extern "C" fn ignore_x_error(_display: *mut Display, _event: *mut XErrorEvent) -> c_int {
+    0
+}
+
+/// Grab (or release) Ctrl+Alt+R; another client holding it just leaves the hotkey unsuppressed
+unsafe fn grab_quick_transform(display: *mut Display, grab: bool) {
+    let root = xlib::XDefaultRootWindow(display);
+    let keycode = xlib::XKeysymToKeycode(display, XK_r as c_ulong) as c_int;
+
+    let previous = xlib::XSetErrorHandler(Some(ignore_x_error));
+    for lock in LOCK_VARIANTS {
+        let modifiers = QUICK_TRANSFORM_MODIFIERS | lock;
+        if grab {
+            xlib::XGrabKey(
+                display,
+                keycode,
+                modifiers,
+                root,
+                xlib::False,
+                GrabModeAsync,
+                GrabModeAsync,
+            );
+        } else {
+            xlib::XUngrabKey(display, keycode, modifiers, root);
+        }
+    }
+    xlib::XSync(display, xlib::False);
+    xlib::XSetErrorHandler(previous);
+}
+
+/// The polling thread: owns both X connections and the RECORD context
+struct MonitorActor {
+    stopping: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl MonitorActor {
+    /// Open the connections on a new thread, returning once recording has started (or failed)
+    fn spawn(sink: TriggerSink) -> Result<Self, PromptOsError> {
+        let stopping = Arc::new(AtomicBool::new(false));
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        let thread_stopping = stopping.clone();
+        let thread =
+            std::thread::spawn(move || unsafe { run_record(sink, thread_stopping, ready_tx) });
+
+        match ready_rx.recv() {
+            Ok(Ok(())) => Ok(Self { stopping, thread }),
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => Err("X11 monitor thread exited during startup".into()),
+        }
+    }
+
+    /// Stop polling and wait for the RECORD context to be freed
+    fn shutdown(self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        if self.thread.join().is_err() {
+            eprintln!("[ERROR] X11 monitor thread panicked");
+        }
+    }
+}
+
+unsafe fn run_record(
+    sink: TriggerSink,
+    stopping: Arc<AtomicBool>,
+    ready: mpsc::Sender<Result<(), PromptOsError>>,
+) {
+    eprintln!("[DEBUG] X11 monitor thread started");
+
+    // RECORD replies arrive on their own connection; lookups, grabs, and XTest use the other
+    let control = xlib::XOpenDisplay(std::ptr::null());
+    let data = xlib::XOpenDisplay(std::ptr::null());
+    if control.is_null() || data.is_null() {
+        for display in [control, data] {
+            if !display.is_null() {
+                xlib::XCloseDisplay(display);
+            }
+        }
+        eprintln!("[ERROR] Failed to open X display");
+        let _ = ready.send(Err(
+            "Failed to open X display. Is this an X11 session?".into()
+        ));
+        return;
+    }
+
+    let (mut major, mut minor, mut event_base, mut error_base) = (0, 0, 0, 0);
+    let has_record = xrecord::XRecordQueryVersion(control, &mut major, &mut minor) != 0;
+    let has_xtest = xtest::XTestQueryExtension(
+        control,
+        &mut event_base,
+        &mut error_base,
+        &mut major,
+        &mut minor,
+    ) != 0;
+    if !has_record || !has_xtest {
+        xlib::XCloseDisplay(data);
+        xlib::XCloseDisplay(control);
+        eprintln!("[ERROR] X server lacks the RECORD or XTEST extension");
+        let _ = ready.send(Err(
+            "The X server lacks the RECORD or XTEST extension".into()
+        ));
+        return;
+    }
+
+    // Only key presses from the devices themselves
+    let range = xrecord::XRecordAllocRange();
+    (*range).device_events.first = KeyPress as u8;
+    (*range).device_events.last = KeyPress as u8;
+    let mut clients: c_ulong = XRecordAllClients;
+    let mut ranges = [range];
+    let record_context =
+        xrecord::XRecordCreateContext(control, 0, &mut clients, 1, ranges.as_mut_ptr(), 1);
+    xlib::XFree(range.cast());
+    xlib::XSync(control, xlib::False);
+
+    let context = RecordContext {
+        display: control,
+        sink,
+    };
+    let enabled = record_context != 0
+        && xrecord::XRecordEnableContextAsync(
+            data,
+            record_context,
+            Some(record_callback),
+            &context as *const RecordContext as *mut c_char,
+        ) != 0;
+    if !enabled {
+        if record_context != 0 {
+            xrecord::XRecordFreeContext(control, record_context);
+        }
+        xlib::XCloseDisplay(data);
+        xlib::XCloseDisplay(control);
+        eprintln!("[ERROR] Failed to enable X11 record context");
+        let _ = ready.send(Err("Failed to start recording keystrokes".into()));
+        return;
+    }
+
+    grab_quick_transform(control, true);
+    let _ = ready.send(Ok(()));
+    eprintln!("[DEBUG] X11 record context enabled, polling...");
+
+    while !stopping.load(Ordering::SeqCst) {
+        xrecord::XRecordProcessReplies(data);
+
+        // Grabbed hotkey presses queue up here; RECORD already reported them
+        while xlib::XPending(control) > 0 {
+            let mut event: xlib::XEvent = std::mem::zeroed();
+            xlib::XNextEvent(control, &mut event);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+
+    grab_quick_transform(control, false);
+    xrecord::XRecordDisableContext(control, record_context);
+    xrecord::XRecordFreeContext(control, record_context);
+    xlib::XSync(control, xlib::False);
+    xlib::XCloseDisplay(data);
+    xlib::XCloseDisplay(control);
+    eprintln!("[DEBUG] X11 record context removed");
+}
+
+/// The X11 RECORD monitor behind the pipeline's Monitor trait, kept in Tauri managed state
+#[derive(Default)]
+pub struct XRecordMonitor {
+    actor: Mutex<Option<MonitorActor>>,
+}
+
+impl Monitor for XRecordMonitor {
+    /// Starting again replaces the running monitor
+    fn start(&self, sink: TriggerSink) -> Result<(), PromptOsError> {
+        let mut guard = self
+            .actor
+            .lock()
+            .map_err(|_| "Failed to lock X11 monitor".to_string())?;
+        if let Some(actor) = guard.take() {
+            actor.shutdown();
+        }
+        *guard = Some(MonitorActor::spawn(sink)?);
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), PromptOsError> {
+        let actor = self
+            .actor
+            .lock()
+            .map_err(|_| "Failed to lock X11 monitor".to_string())?
+            .take();
+        if let Some(actor) = actor {
+            actor.shutdown();
+        }
+        Ok(())
+    }
+
+    fn died(&self) -> bool {
+        self.actor.lock().is_ok_and(|actor| {
+            actor
+                .as_ref()
+                .is_some_and(|actor| actor.thread.is_finished())
+        })
+    }
+}
@@ -0,0 +1,388 @@
+// In-memory backends for exercising the pipeline without macOS permissions
+// Harness wires them together the same way the app wires the real backends
+
+use prompt_os_pipeline::{
+    AppInfo, CoordinateSpace, Debouncer, Detector, FieldBounds, FieldText, Generator, InjectMethod,
+    InjectStrategy, Injector, Monitor, OverlayRoute, Pipeline, PromptOsError, Trigger,
+    TriggerHandler, TriggerSink,
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const QUICK_ACTION: &str = "Fix the spelling and grammar";
+
+/// Detector whose focused field is set by the test
+#[derive(Default)]
+pub struct MockDetector {
+    bounds: Mutex<Option<FieldBounds>>,
+    app: Mutex<Option<AppInfo>>,
+    field: Mutex<FieldText>,
+    strategy: Mutex<InjectStrategy>,
+}
+
+impl MockDetector {
+    pub fn focus_field(&self, bounds: FieldBounds, app: AppInfo, field: FieldText) {
+        *self.bounds.lock().unwrap() = Some(bounds);
+        *self.app.lock().unwrap() = Some(app);
+        *self.field.lock().unwrap() = field;
+    }
+
+    pub fn clear_focus(&self) {
+        *self.bounds.lock().unwrap() = None;
+        *self.field.lock().unwrap() = FieldText::default();
+        *self.strategy.lock().unwrap() = InjectStrategy::default();
+    }
+
+    /// Simulate a field detected as paste-only (e.g. a terminal)
+    pub fn set_strategy(&self, strategy: InjectStrategy) {
+        *self.strategy.lock().unwrap() = strategy;
+    }
+}
+
+impl Detector for MockDetector {
+    fn focused_field_bounds(&self) -> Result<FieldBounds, PromptOsError> {
+        self.bounds.lock().unwrap().ok_or(PromptOsError::NoFocus)
+    }
+
+    fn frontmost_app(&self) -> Option<AppInfo> {
+        self.app.lock().unwrap().clone()
+    }
+
+    fn field_text(&self, _pid: Option<i32>) -> FieldText {
+        self.field.lock().unwrap().clone()
+    }
+
+    fn inject_strategy(&self) -> InjectStrategy {
+        *self.strategy.lock().unwrap()
+    }
+}
+
+/// Injector that records what would have been typed
+#[derive(Default)]
+pub struct MockInjector {
+    pub inserted: Mutex<Vec<String>>,
+    pub pasted: Mutex<Vec<String>>,
+    insert_error: Mutex<Option<PromptOsError>>,
+    paste_fails: AtomicBool,
+}
+
+impl MockInjector {
+    /// Simulate apps that ignore AXSelectedText
+    pub fn fail_insert(&self, fail: bool) {
+        *self.insert_error.lock().unwrap() =
+            fail.then(|| "Failed to insert text via Accessibility API".into());
+    }
+
+    /// Simulate a typed insertion failure (secure field, missing permission, ...)
+    pub fn fail_insert_with(&self, error: PromptOsError) {
+        *self.insert_error.lock().unwrap() = Some(error);
+    }
+
+    pub fn fail_paste(&self, fail: bool) {
+        self.paste_fails.store(fail, Ordering::SeqCst);
+    }
+}
+
+impl Injector for MockInjector {
+    fn insert(&self, text: &str) -> Result<(), PromptOsError> {
+        if let Some(error) = self.insert_error.lock().unwrap().clone() {
+            return Err(error);
+        }
+        self.inserted.lock().unwrap().push(text.to_string());
+        Ok(())
+    }
+
+    fn paste(&self, text: &str) -> Result<(), PromptOsError> {
+        if self.paste_fails.load(Ordering::SeqCst) {
+            return Err("Failed to set clipboard content".into());
+        }
+        self.pasted.lock().unwrap().push(text.to_string());
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenerateRequest {
+    pub prompt: String,
+    pub system_prompt: Option<String>,
+}
+
+/// Generator with a canned reply that records every request
+pub struct MockGenerator {
+    reply: Mutex<Result<String, PromptOsError>>,
+    pub requests: Mutex<Vec<GenerateRequest>>,
+}
+
+impl Default for MockGenerator {
+    fn default() -> Self {
+        Self {
+            reply: Mutex::new(Ok("generated".to_string())),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl MockGenerator {
+    pub fn set_reply(&self, reply: Result<&str, PromptOsError>) {
+        *self.reply.lock().unwrap() = reply.map(str::to_string);
+    }
+}
+
+impl Generator for MockGenerator {
+    fn generate(&self, prompt: &str, system_prompt: Option<&str>) -> Result<String, PromptOsError> {
+        self.requests.lock().unwrap().push(GenerateRequest {
+            prompt: prompt.to_string(),
+            system_prompt: system_prompt.map(str::to_string),
+        });
+        self.reply.lock().unwrap().clone()
+    }
+}
+
+/// Monitor driven by the test instead of a keyboard
+#[derive(Default)]
+pub struct MockMonitor {
+    sink: Mutex<Option<TriggerSink>>,
+}
+
+impl MockMonitor {
+    /// Deliver a trigger; None when the monitor isn't running
+    pub fn fire(&self, trigger: Trigger) -> Option<bool> {
+        let sink = self.sink.lock().unwrap().clone()?;
+        Some(sink(trigger))
+    }
+}
+
+impl Monitor for MockMonitor {
+    /// Like the platform monitors, starting again replaces the running sink
+    fn start(&self, sink: TriggerSink) -> Result<(), PromptOsError> {
+        *self.sink.lock().unwrap() = Some(sink);
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), PromptOsError> {
+        *self.sink.lock().unwrap() = None;
+        Ok(())
+    }
+
+    /// Nothing to crash
+    fn died(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum HarnessEvent {
+    OverlayShown {
+        bounds: FieldBounds,
+        app: Option<AppInfo>,
+        /// The slash command typed after the trigger, if any
+        command: Option<String>,
+        /// The action a named trigger routes to, if any
+        action: Option<String>,
+        /// The selected text a selection trigger pre-fills the overlay with
+        selection: Option<String>,
+    },
+    QuickTransform(Result<String, PromptOsError>),
+    SnippetExpanded(Result<InjectMethod, PromptOsError>),
+}
+
+/// How the harness reacts to each trigger, standing in for the app's windows and network calls
+struct HarnessTriggers {
+    pipeline: Pipeline,
+    events: Arc<Mutex<Vec<HarnessEvent>>>,
+    actions: Arc<Mutex<HashMap<String, String>>>,
+    debouncer: Debouncer,
+    debounce: Arc<Mutex<Duration>>,
+    permitted: Arc<AtomicBool>,
+}
+
+impl HarnessTriggers {
+    fn push(&self, event: HarnessEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+impl TriggerHandler for HarnessTriggers {
+    fn debounced(&self, trigger: &Trigger) -> bool {
+        let window = *self.debounce.lock().unwrap();
+        self.debouncer.debounced(trigger, window)
+    }
+
+    fn permitted(&self) -> bool {
+        self.permitted.load(Ordering::SeqCst)
+    }
+
+    /// There's no cursor to open the overlay at, so every trigger passes through
+    fn clipboard_only(&self, _trigger: Trigger) -> bool {
+        false
+    }
+
+    fn quick_transform(&self, action: Option<String>) -> bool {
+        let template = match action {
+            // A hotkey whose action was removed passes through
+            Some(id) => match self.actions.lock().unwrap().get(&id).cloned() {
+                Some(template) => template,
+                None => return false,
+            },
+            None => QUICK_ACTION.to_string(),
+        };
+        let result = self.pipeline.quick_transform(&template);
+        self.push(HarnessEvent::QuickTransform(result));
+        true
+    }
+
+    fn open_overlay(&self, _trigger_id: &'static str, route: OverlayRoute) -> bool {
+        match self.pipeline.overlay_target() {
+            Ok((bounds, app)) => {
+                self.push(HarnessEvent::OverlayShown {
+                    bounds,
+                    app,
+                    command: route.command,
+                    action: route.action,
+                    selection: None,
+                });
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Clicking with nothing selected is just a click
+    fn open_selection_overlay(&self) -> bool {
+        match self.pipeline.selection_target() {
+            Ok((bounds, app, selection)) => {
+                self.push(HarnessEvent::OverlayShown {
+                    bounds,
+                    app,
+                    command: None,
+                    action: None,
+                    selection: Some(selection),
+                });
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Nothing to replace outside a text field, so the abbreviation stays as typed
+    fn expand_snippet(&self, expansion: String) -> bool {
+        if self.pipeline.overlay_target().is_err() {
+            return false;
+        }
+        let result = self.pipeline.inject(&expansion);
+        self.push(HarnessEvent::SnippetExpanded(result));
+        true
+    }
+}
+
+/// All mocks plus a pipeline wired to them, reacting to triggers like the app does
+pub struct Harness {
+    pub detector: Arc<MockDetector>,
+    pub injector: Arc<MockInjector>,
+    pub generator: Arc<MockGenerator>,
+    pub monitor: Arc<MockMonitor>,
+    pub pipeline: Pipeline,
+    pub events: Arc<Mutex<Vec<HarnessEvent>>>,
+    /// User-defined actions by id, as loaded from the actions directory
+    pub actions: Arc<Mutex<HashMap<String, String>>>,
+    debounce: Arc<Mutex<Duration>>,
+    permitted: Arc<AtomicBool>,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        let detector = Arc::new(MockDetector::default());
+        let injector = Arc::new(MockInjector::default());
+        let generator = Arc::new(MockGenerator::default());
+        let pipeline = Pipeline::new(detector.clone(), injector.clone(), generator.clone());
+
+        Self {
+            detector,
+            injector,
+            generator,
+            monitor: Arc::new(MockMonitor::default()),
+            pipeline,
+            events: Arc::new(Mutex::new(Vec::new())),
+            actions: Arc::new(Mutex::new(HashMap::new())),
+            // Off unless a test sets it, so triggers can be fired back to back
+            debounce: Arc::new(Mutex::new(Duration::ZERO)),
+            permitted: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Start the monitor with a sink that runs the app's trigger dispatch: opening the overlay,
+    /// running the quick action, or expanding a snippet
+    pub fn start(&self) -> Result<(), PromptOsError> {
+        let handler = HarnessTriggers {
+            pipeline: self.pipeline.clone(),
+            events: self.events.clone(),
+            actions: self.actions.clone(),
+            debouncer: Debouncer::new(),
+            debounce: self.debounce.clone(),
+            permitted: self.permitted.clone(),
+        };
+        self.monitor.start(Arc::new(move |trigger| {
+            prompt_os_pipeline::dispatch(&handler, trigger)
+        }))
+    }
+
+    /// Ignore a trigger this soon after the previous one, like the app's activation setting
+    pub fn set_debounce(&self, debounce: Duration) {
+        *self.debounce.lock().unwrap() = debounce;
+    }
+
+    /// Simulate Accessibility access being granted or revoked while running
+    pub fn set_permitted(&self, permitted: bool) {
+        self.permitted.store(permitted, Ordering::SeqCst);
+    }
+
+    /// Add or replace a user-defined action
+    pub fn add_action(&self, id: &str, template: &str) {
+        self.actions
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), template.to_string());
+    }
+
+    pub fn remove_action(&self, id: &str) {
+        self.actions.lock().unwrap().remove(id);
+    }
+
+    pub fn events(&self) -> Vec<HarnessEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// Focus a typical text field with an optional selection
+    pub fn focus_text_field(&self, app_name: &str, selection: Option<&str>) -> AppInfo {
+        let app = AppInfo {
+            bundle_id: Some(format!("com.example.{}", app_name.to_lowercase())),
+            name: Some(app_name.to_string()),
+            pid: 4242,
+        };
+        self.detector.focus_field(
+            FieldBounds {
+                x: 100.0,
+                y: 200.0,
+                width: 300.0,
+                height: 24.0,
+                display_id: Some(1),
+                scale_factor: Some(2.0),
+                coordinates: CoordinateSpace::Logical,
+            },
+            app.clone(),
+            FieldText {
+                selection: selection.map(str::to_string),
+                value: selection.map(str::to_string),
+            },
+        );
+        app
+    }
+}
@@ -0,0 +1,37 @@
+use prompt_os_pipeline::{detect_direction, Script, TextDirection};
+
+#[test]
+fn english_is_left_to_right() {
+    let detected = detect_direction("Thanks for the update!").unwrap();
+    assert_eq!(detected.direction, TextDirection::Ltr);
+    assert_eq!(detected.script, Script::Latin);
+}
+
+#[test]
+fn arabic_and_hebrew_are_right_to_left() {
+    let arabic = detect_direction("شكرا على التحديث").unwrap();
+    assert_eq!(arabic.direction, TextDirection::Rtl);
+    assert_eq!(arabic.script, Script::Arabic);
+
+    let hebrew = detect_direction("תודה על העדכון").unwrap();
+    assert_eq!(hebrew.direction, TextDirection::Rtl);
+    assert_eq!(hebrew.script, Script::Hebrew);
+}
+
+#[test]
+fn the_dominant_script_wins_over_embedded_latin() {
+    let detected = detect_direction("راجع ملف README قبل الاجتماع").unwrap();
+    assert_eq!(detected.direction, TextDirection::Rtl);
+}
+
+#[test]
+fn digits_and_punctuation_have_no_direction() {
+    assert_eq!(detect_direction("12:30 - 14:00 (+3)"), None);
+    assert_eq!(detect_direction(""), None);
+}
+
+#[test]
+fn a_tie_goes_to_the_first_script() {
+    let detected = detect_direction("ab אב").unwrap();
+    assert_eq!(detected.script, Script::Latin);
+}
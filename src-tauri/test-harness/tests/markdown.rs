@@ -0,0 +1,56 @@
+use prompt_os_pipeline::render_markdown;
+
+#[test]
+fn emphasis_code_and_links_render_as_markup() {
+    let rendered =
+        render_markdown("Use **bold**, *italic*, `code`, and [docs](https://example.com).");
+
+    assert_eq!(
+        rendered.plain,
+        "Use bold, italic, code, and docs (https://example.com)."
+    );
+    assert!(rendered.html.contains(
+        "<p>Use <strong>bold</strong>, <em>italic</em>, <code>code</code>, and \
+         <a href=\"https://example.com\">docs</a>.</p>"
+    ));
+    assert!(rendered.rtf.contains("{\\b bold}"));
+    assert!(rendered.rtf.contains("{\\i italic}"));
+    assert!(rendered.rtf.contains("HYPERLINK \"https://example.com\""));
+}
+
+#[test]
+fn headings_and_lists_become_blocks() {
+    let rendered = render_markdown("## Plan\n\n- draft\n- review\n\n3. ship\n4. celebrate");
+
+    assert_eq!(
+        rendered.plain,
+        "Plan\n\n• draft\n• review\n\n3. ship\n4. celebrate"
+    );
+    assert!(rendered
+        .html
+        .contains("<h2>Plan</h2><ul><li>draft</li><li>review</li></ul><ol start=\"3\">"));
+    assert!(rendered.rtf.contains("\\bullet\\tab draft\\par"));
+}
+
+#[test]
+fn code_blocks_and_words_with_underscores_are_left_alone() {
+    let rendered = render_markdown("Call snake_case_name:\n\n```\nlet x = a * b * c;\n```");
+
+    assert_eq!(
+        rendered.plain,
+        "Call snake_case_name:\n\nlet x = a * b * c;"
+    );
+    assert!(rendered
+        .html
+        .contains("<pre><code>let x = a * b * c;</code></pre>"));
+}
+
+#[test]
+fn markup_characters_are_escaped() {
+    let rendered = render_markdown("1 < 2 & {braces} \\*not italic\\* – done");
+
+    assert_eq!(rendered.plain, "1 < 2 & {braces} *not italic* – done");
+    assert!(rendered.html.contains("1 &lt; 2 &amp; {braces}"));
+    assert!(rendered.rtf.contains("\\{braces\\}"));
+    assert!(rendered.rtf.contains("\\u8211?"));
+}
@@ -0,0 +1,198 @@
+use prompt_os_pipeline::{Chord, KeyPress, MatcherConfig, Trigger, TriggerMatcher, TypedKey};
+use std::time::{Duration, Instant};
+
+const CMD_SHIFT: u64 = 0x0012_0000;
+const SPACE: i64 = 0x31;
+
+fn config() -> MatcherConfig {
+    MatcherConfig {
+        sequences: vec!["/".to_string()],
+        snippets: vec![(":sig".to_string(), "Best regards".to_string())],
+        ..MatcherConfig::default()
+    }
+}
+
+/// Type each character one second apart: '\n' is Return, '<' Backspace, '!' a caret move
+fn type_keys(
+    matcher: &mut TriggerMatcher,
+    config: &MatcherConfig,
+    keys: &str,
+) -> Vec<(Trigger, usize)> {
+    let start = Instant::now();
+    keys.chars()
+        .enumerate()
+        .filter_map(|(i, c)| {
+            let key = match c {
+                '\n' => TypedKey::Return,
+                '<' => TypedKey::Backspace,
+                '!' => TypedKey::Reset,
+                c => TypedKey::Text(c.to_string()),
+            };
+            let at = start + Duration::from_secs(i as u64);
+            matcher.feed(
+                KeyPress {
+                    key,
+                    chord: None,
+                    repeat: false,
+                    at,
+                },
+                config,
+            )
+        })
+        .collect()
+}
+
+fn matches(keys: &str) -> Vec<(Trigger, usize)> {
+    type_keys(&mut TriggerMatcher::default(), &config(), keys)
+}
+
+#[test]
+fn sequence_then_return_or_space_ends_the_command() {
+    assert_eq!(matches("/\n"), vec![(Trigger::Slash, 1)]);
+    assert_eq!(
+        matches("hi /fix "),
+        vec![(Trigger::Command("fix".to_string()), 4)]
+    );
+    // Backspacing inside the word edits it
+    assert_eq!(
+        matches("/fi<<x\n"),
+        vec![(Trigger::Command("x".to_string()), 2)]
+    );
+}
+
+#[test]
+fn sequence_in_prose_is_left_alone() {
+    assert!(matches("and/or ").is_empty());
+    assert!(matches("a / b").is_empty());
+    assert!(matches("/fi<<<x ").is_empty());
+    assert!(matches("/a.b ").is_empty());
+    assert!(matches("/fi!x ").is_empty());
+}
+
+#[test]
+fn abbreviation_fires_on_its_last_character() {
+    assert_eq!(
+        matches("hello:sig"),
+        vec![(Trigger::Snippet("Best regards".to_string()), 3)]
+    );
+    // A cancelled command gives way to a snippet typed right after it
+    assert_eq!(
+        matches("/:sig"),
+        vec![(Trigger::Snippet("Best regards".to_string()), 3)]
+    );
+}
+
+#[test]
+fn typed_trigger_fires_like_an_abbreviation() {
+    let fix = Trigger::Named {
+        name: "Grammar".to_string(),
+        action: Some("fix".to_string()),
+    };
+    let config = MatcherConfig {
+        typed: vec![("::fix".to_string(), fix.clone())],
+        ..config()
+    };
+    let typed = |keys| type_keys(&mut TriggerMatcher::default(), &config, keys);
+
+    assert_eq!(typed("so::fix"), vec![(fix, 4)]);
+    // Unlike a sequence, it doesn't wait for a command word
+    assert!(typed("::fi").is_empty());
+    assert_eq!(
+        typed(":sig"),
+        vec![(Trigger::Snippet("Best regards".to_string()), 3)]
+    );
+}
+
+#[test]
+fn chord_fires_on_its_key_and_clears_typing() {
+    let chord = Chord {
+        keycode: SPACE,
+        modifiers: CMD_SHIFT,
+    };
+    let config = MatcherConfig {
+        chords: vec![(chord, Trigger::Slash)],
+        ..config()
+    };
+    let mut matcher = TriggerMatcher::default();
+    let now = Instant::now();
+
+    assert!(type_keys(&mut matcher, &config, "/fi").is_empty());
+    let press = KeyPress {
+        key: TypedKey::Reset,
+        chord: Some(chord),
+        repeat: false,
+        at: now,
+    };
+    assert_eq!(matcher.feed(press, &config), Some((Trigger::Slash, 0)));
+
+    // The command in progress was abandoned
+    assert!(type_keys(&mut matcher, &config, "x\n").is_empty());
+
+    // Other modifiers on the same key are just a shortcut
+    let press = KeyPress {
+        key: TypedKey::Reset,
+        chord: Some(Chord {
+            keycode: SPACE,
+            modifiers: 0,
+        }),
+        repeat: false,
+        at: now,
+    };
+    assert_eq!(matcher.feed(press, &config), None);
+}
+
+#[test]
+fn held_chord_fires_once() {
+    let chord = Chord {
+        keycode: SPACE,
+        modifiers: CMD_SHIFT,
+    };
+    let config = MatcherConfig {
+        chords: vec![(chord, Trigger::Slash)],
+        ..config()
+    };
+    let mut matcher = TriggerMatcher::default();
+    let press = |repeat| KeyPress {
+        key: TypedKey::Reset,
+        chord: Some(chord),
+        repeat,
+        at: Instant::now(),
+    };
+
+    assert_eq!(
+        matcher.feed(press(false), &config),
+        Some((Trigger::Slash, 0))
+    );
+    assert_eq!(matcher.feed(press(true), &config), None);
+    assert_eq!(matcher.feed(press(true), &config), None);
+    // Pressing it again fires again
+    assert_eq!(
+        matcher.feed(press(false), &config),
+        Some((Trigger::Slash, 0))
+    );
+}
+
+#[test]
+fn pause_longer_than_the_timeout_starts_over() {
+    let patient = MatcherConfig {
+        sequences: vec!["::".to_string()],
+        timeout: Some(Duration::from_secs(2)),
+        ..MatcherConfig::default()
+    };
+    assert_eq!(
+        type_keys(&mut TriggerMatcher::default(), &patient, "::\n"),
+        vec![(Trigger::Slash, 2)]
+    );
+
+    let hasty = MatcherConfig {
+        timeout: Some(Duration::from_millis(500)),
+        ..patient
+    };
+    assert!(type_keys(&mut TriggerMatcher::default(), &hasty, "::\n").is_empty());
+}
+
+#[test]
+fn no_sequences_means_typing_never_triggers() {
+    let chords_only = MatcherConfig::default();
+    assert!(type_keys(&mut TriggerMatcher::default(), &chords_only, "/fix ").is_empty());
+}
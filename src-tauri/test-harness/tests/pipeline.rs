@@ -0,0 +1,332 @@
+use prompt_os_pipeline::{InjectMethod, InjectStrategy, Monitor, PromptOsError, Trigger};
+use prompt_os_test_harness::{Harness, HarnessEvent, QUICK_ACTION};
+use std::time::Duration;
+
+#[test]
+fn slash_in_text_field_opens_overlay_and_swallows_key() {
+    let harness = Harness::new();
+    harness.start().unwrap();
+    let app = harness.focus_text_field("Notes", None);
+
+    assert_eq!(harness.monitor.fire(Trigger::Slash), Some(true));
+
+    match harness.events().as_slice() {
+        [HarnessEvent::OverlayShown {
+            bounds,
+            app: shown,
+            command: None,
+            action: None,
+            selection: None,
+        }] => {
+            assert_eq!(bounds.x, 100.0);
+            assert_eq!(shown.as_ref(), Some(&app));
+        }
+        events => panic!("unexpected events: {:?}", events),
+    }
+}
+
+#[test]
+fn slash_command_opens_overlay_with_the_command() {
+    let harness = Harness::new();
+    harness.start().unwrap();
+    harness.focus_text_field("Notes", None);
+
+    let trigger = Trigger::Command("fix".to_string());
+    assert_eq!(harness.monitor.fire(trigger), Some(true));
+
+    match harness.events().as_slice() {
+        [HarnessEvent::OverlayShown { command, .. }] => {
+            assert_eq!(command.as_deref(), Some("fix"));
+        }
+        events => panic!("unexpected events: {:?}", events),
+    }
+
+    // Like a bare trigger, a command outside a text field passes through
+    harness.detector.clear_focus();
+    let trigger = Trigger::Command("fix".to_string());
+    assert_eq!(harness.monitor.fire(trigger), Some(false));
+}
+
+#[test]
+fn named_trigger_opens_overlay_routed_to_its_action() {
+    let harness = Harness::new();
+    harness.start().unwrap();
+    harness.focus_text_field("Notes", None);
+
+    let trigger = Trigger::Named {
+        name: "Translate".to_string(),
+        action: Some("translate".to_string()),
+    };
+    assert_eq!(harness.monitor.fire(trigger), Some(true));
+
+    match harness.events().as_slice() {
+        [HarnessEvent::OverlayShown {
+            command: None,
+            action,
+            ..
+        }] => {
+            assert_eq!(action.as_deref(), Some("translate"));
+        }
+        events => panic!("unexpected events: {:?}", events),
+    }
+}
+
+#[test]
+fn selection_trigger_prefills_the_overlay_with_the_selection() {
+    let harness = Harness::new();
+    harness.start().unwrap();
+
+    // A click with nothing (or only whitespace) selected passes through
+    harness.focus_text_field("Notes", Some("  "));
+    assert_eq!(harness.monitor.fire(Trigger::Selection), Some(false));
+
+    harness.focus_text_field("Notes", Some("the quick brown fox"));
+    assert_eq!(harness.monitor.fire(Trigger::Selection), Some(true));
+    match harness.events().as_slice() {
+        [HarnessEvent::OverlayShown { selection, .. }] => {
+            assert_eq!(selection.as_deref(), Some("the quick brown fox"));
+        }
+        events => panic!("unexpected events: {:?}", events),
+    }
+}
+
+#[test]
+fn snippet_injects_its_expansion_without_generating() {
+    let harness = Harness::new();
+    harness.start().unwrap();
+    harness.focus_text_field("Mail", None);
+
+    let trigger = Trigger::Snippet("Best regards,\nAlex".to_string());
+    assert_eq!(harness.monitor.fire(trigger), Some(true));
+
+    assert_eq!(
+        harness.events(),
+        vec![HarnessEvent::SnippetExpanded(Ok(InjectMethod::Insert))]
+    );
+    assert_eq!(
+        *harness.injector.inserted.lock().unwrap(),
+        vec!["Best regards,\nAlex".to_string()]
+    );
+    assert!(harness.generator.requests.lock().unwrap().is_empty());
+
+    // Outside a text field the abbreviation is left alone
+    harness.detector.clear_focus();
+    let trigger = Trigger::Snippet("Best regards,\nAlex".to_string());
+    assert_eq!(harness.monitor.fire(trigger), Some(false));
+}
+
+#[test]
+fn slash_without_text_field_passes_key_through() {
+    let harness = Harness::new();
+    harness.start().unwrap();
+
+    assert_eq!(harness.monitor.fire(Trigger::Slash), Some(false));
+    assert!(harness.events().is_empty());
+}
+
+#[test]
+fn trigger_right_after_another_is_debounced_but_snippets_are_not() {
+    let harness = Harness::new();
+    harness.set_debounce(Duration::from_secs(60));
+    harness.start().unwrap();
+    harness.focus_text_field("Notes", None);
+
+    assert_eq!(harness.monitor.fire(Trigger::Slash), Some(true));
+    // A bounce stays in the field as typed
+    assert_eq!(harness.monitor.fire(Trigger::Slash), Some(false));
+    assert_eq!(harness.events().len(), 1);
+
+    let snippet = Trigger::Snippet("Best,\nAnn".to_string());
+    assert_eq!(harness.monitor.fire(snippet.clone()), Some(true));
+    assert_eq!(harness.monitor.fire(snippet), Some(true));
+}
+
+#[test]
+fn revoked_permissions_pass_triggers_through() {
+    let harness = Harness::new();
+    harness.start().unwrap();
+    harness.focus_text_field("Notes", Some("teh"));
+    harness.set_permitted(false);
+
+    assert_eq!(harness.monitor.fire(Trigger::Slash), Some(false));
+    assert_eq!(harness.monitor.fire(Trigger::QuickTransform), Some(false));
+    assert!(harness.events().is_empty());
+    assert!(harness.generator.requests.lock().unwrap().is_empty());
+
+    harness.set_permitted(true);
+    assert_eq!(harness.monitor.fire(Trigger::Slash), Some(true));
+}
+
+#[test]
+fn stopped_monitor_delivers_nothing() {
+    let harness = Harness::new();
+    harness.start().unwrap();
+    harness.monitor.stop().unwrap();
+    harness.focus_text_field("Notes", None);
+
+    assert_eq!(harness.monitor.fire(Trigger::Slash), None);
+    assert!(harness.events().is_empty());
+
+    // Restartable after a stop
+    harness.start().unwrap();
+    assert_eq!(harness.monitor.fire(Trigger::Slash), Some(true));
+}
+
+#[test]
+fn restarting_replaces_the_running_monitor() {
+    let harness = Harness::new();
+    harness.start().unwrap();
+    harness.start().unwrap();
+    harness.focus_text_field("Notes", None);
+
+    // One trigger, one overlay: the first sink is gone rather than running alongside
+    assert_eq!(harness.monitor.fire(Trigger::Slash), Some(true));
+    assert_eq!(harness.events().len(), 1);
+}
+
+#[test]
+fn quick_transform_replaces_selection() {
+    let harness = Harness::new();
+    harness.start().unwrap();
+    harness.focus_text_field("Mail", Some("teh quick fox"));
+    harness.generator.set_reply(Ok("the quick fox"));
+
+    assert_eq!(harness.monitor.fire(Trigger::QuickTransform), Some(true));
+
+    let requests = harness.generator.requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].prompt, "teh quick fox");
+    assert_eq!(requests[0].system_prompt.as_deref(), Some(QUICK_ACTION));
+    assert_eq!(
+        *harness.injector.inserted.lock().unwrap(),
+        vec!["the quick fox".to_string()]
+    );
+}
+
+#[test]
+fn action_hotkey_uses_its_template_until_removed() {
+    let harness = Harness::new();
+    harness.start().unwrap();
+    harness.focus_text_field("Notes", Some("hello there"));
+    harness.generator.set_reply(Ok("Ahoy there"));
+    harness.add_action("pirate", "Rewrite this like a pirate");
+
+    let trigger = Trigger::Action("pirate".to_string());
+    assert_eq!(harness.monitor.fire(trigger.clone()), Some(true));
+
+    let requests = harness.generator.requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].system_prompt.as_deref(),
+        Some("Rewrite this like a pirate")
+    );
+
+    harness.remove_action("pirate");
+    assert_eq!(harness.monitor.fire(trigger), Some(false));
+    assert_eq!(harness.generator.requests.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn quick_transform_without_selection_skips_generation() {
+    let harness = Harness::new();
+    harness.start().unwrap();
+    harness.focus_text_field("Mail", Some("   "));
+
+    harness.monitor.fire(Trigger::QuickTransform);
+
+    assert_eq!(
+        harness.events(),
+        vec![HarnessEvent::QuickTransform(Err("Nothing selected".into()))]
+    );
+    assert!(harness.generator.requests.lock().unwrap().is_empty());
+    assert!(harness.injector.inserted.lock().unwrap().is_empty());
+}
+
+#[test]
+fn run_passes_context_to_generator_and_injects() {
+    let harness = Harness::new();
+    harness.focus_text_field("Slack", Some("lunch at noon?"));
+    harness.generator.set_reply(Ok("Sounds good!"));
+
+    let output = harness
+        .pipeline
+        .run("reply yes", Some("Be brief."))
+        .unwrap();
+    assert_eq!(output, "Sounds good!");
+
+    let requests = harness.generator.requests.lock().unwrap().clone();
+    let system_prompt = requests[0].system_prompt.clone().unwrap();
+    assert!(system_prompt.starts_with("Be brief."));
+    assert!(system_prompt.contains("The user is writing in Slack."));
+    assert!(system_prompt.contains("lunch at noon?"));
+
+    assert_eq!(
+        *harness.injector.inserted.lock().unwrap(),
+        vec!["Sounds good!".to_string()]
+    );
+}
+
+#[test]
+fn insert_failure_falls_back_to_paste() {
+    let harness = Harness::new();
+    harness.injector.fail_insert(true);
+
+    assert_eq!(harness.pipeline.inject("hello"), Ok(InjectMethod::Paste));
+    assert!(harness.injector.inserted.lock().unwrap().is_empty());
+    assert_eq!(
+        *harness.injector.pasted.lock().unwrap(),
+        vec!["hello".to_string()]
+    );
+}
+
+#[test]
+fn paste_only_field_skips_direct_insertion() {
+    let harness = Harness::new();
+    harness.detector.set_strategy(InjectStrategy::Paste);
+
+    assert_eq!(harness.pipeline.inject("ls -la"), Ok(InjectMethod::Paste));
+    assert!(harness.injector.inserted.lock().unwrap().is_empty());
+    assert_eq!(
+        *harness.injector.pasted.lock().unwrap(),
+        vec!["ls -la".to_string()]
+    );
+}
+
+#[test]
+fn both_injection_strategies_failing_reports_both_errors() {
+    let harness = Harness::new();
+    harness.injector.fail_insert(true);
+    harness.injector.fail_paste(true);
+
+    let error = harness.pipeline.inject("hello").unwrap_err();
+    assert_eq!(error.code(), "internal");
+    assert!(error.to_string().contains("Accessibility API"));
+    assert!(error.to_string().contains("paste fallback"));
+}
+
+#[test]
+fn secure_field_is_never_pasted_into() {
+    let harness = Harness::new();
+    harness
+        .injector
+        .fail_insert_with(PromptOsError::SecureField);
+
+    let error = harness.pipeline.inject("hunter2").unwrap_err();
+    assert_eq!(error, PromptOsError::SecureField);
+    assert_eq!(error.code(), "secure_field");
+    assert!(harness.injector.pasted.lock().unwrap().is_empty());
+}
+
+#[test]
+fn generator_error_injects_nothing() {
+    let harness = Harness::new();
+    harness.focus_text_field("Notes", None);
+    harness.generator.set_reply(Err(PromptOsError::RateLimited));
+
+    assert_eq!(
+        harness.pipeline.run("hi", None),
+        Err(PromptOsError::RateLimited)
+    );
+    assert!(harness.injector.inserted.lock().unwrap().is_empty());
+    assert!(harness.injector.pasted.lock().unwrap().is_empty());
+}
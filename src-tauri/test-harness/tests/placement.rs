@@ -0,0 +1,101 @@
+use prompt_os_pipeline::{dock_to_window, place_overlay, Rect, OVERLAY_GAP};
+
+const SIZE: (f64, f64) = (500.0, 140.0);
+
+fn rect(x: f64, y: f64, width: f64, height: f64) -> Rect {
+    Rect {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+fn screen() -> Rect {
+    rect(0.0, 25.0, 1440.0, 875.0)
+}
+
+#[test]
+fn sits_below_the_caret_when_there_is_room() {
+    let caret = rect(200.0, 300.0, 1.0, 18.0);
+    let field = rect(180.0, 280.0, 600.0, 200.0);
+
+    assert_eq!(
+        place_overlay(caret, Some(field), SIZE, screen()),
+        (200.0, 318.0 + OVERLAY_GAP)
+    );
+}
+
+#[test]
+fn flips_above_the_field_near_the_bottom_edge() {
+    let caret = rect(200.0, 860.0, 1.0, 18.0);
+    let field = rect(180.0, 840.0, 600.0, 50.0);
+
+    let (_, y) = place_overlay(caret, Some(field), SIZE, screen());
+    assert_eq!(y, 840.0 - OVERLAY_GAP - SIZE.1);
+}
+
+#[test]
+fn goes_above_the_caret_in_a_field_taller_than_the_room_above_it() {
+    let caret = rect(200.0, 820.0, 1.0, 18.0);
+    let field = rect(0.0, 25.0, 1440.0, 875.0);
+
+    let (_, y) = place_overlay(caret, Some(field), SIZE, screen());
+    assert_eq!(y, 820.0 - OVERLAY_GAP - SIZE.1);
+}
+
+#[test]
+fn stays_on_screen_at_the_right_edge() {
+    let caret = rect(1400.0, 300.0, 1.0, 18.0);
+
+    let (x, _) = place_overlay(caret, None, SIZE, screen());
+    assert_eq!(x, 1440.0 - SIZE.0);
+}
+
+#[test]
+fn follows_a_screen_left_of_the_main_one() {
+    let secondary = rect(-1920.0, 0.0, 1920.0, 1080.0);
+    let caret = rect(-1910.0, 500.0, 1.0, 18.0);
+
+    assert_eq!(
+        place_overlay(caret, None, SIZE, secondary),
+        (-1910.0, 518.0 + OVERLAY_GAP)
+    );
+}
+
+#[test]
+fn never_leaves_a_screen_too_short_for_either_side() {
+    let short = rect(0.0, 0.0, 800.0, 200.0);
+    let caret = rect(100.0, 90.0, 1.0, 18.0);
+
+    let (_, y) = place_overlay(caret, None, SIZE, short);
+    assert!(y >= 0.0 && y + SIZE.1 <= 200.0);
+}
+
+#[test]
+fn docks_outside_the_right_edge_of_the_window() {
+    let window = rect(100.0, 200.0, 700.0, 500.0);
+
+    assert_eq!(
+        dock_to_window(window, SIZE, screen()),
+        (800.0 + OVERLAY_GAP, 200.0)
+    );
+}
+
+#[test]
+fn docks_left_of_a_window_at_the_right_edge_of_the_screen() {
+    let window = rect(800.0, 200.0, 640.0, 500.0);
+
+    let (x, _) = dock_to_window(window, SIZE, screen());
+    assert_eq!(x, 800.0 - OVERLAY_GAP - SIZE.0);
+}
+
+#[test]
+fn docks_inside_a_window_that_fills_the_screen() {
+    let window = rect(0.0, 0.0, 1440.0, 900.0);
+
+    assert_eq!(
+        dock_to_window(window, SIZE, screen()),
+        (1440.0 - OVERLAY_GAP - SIZE.0, 25.0)
+    );
+}
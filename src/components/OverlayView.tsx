@@ -1,41 +1,122 @@
 import { useState, useEffect, useRef } from "react";
 import { listen } from "@tauri-apps/api/event";
-import { getCurrentWindow, PhysicalPosition } from "@tauri-apps/api/window";
-import { insertText, insertTextViaPaste } from "../lib/commands";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import {
+  addSessionTurn,
+  filterResponse,
+  fuzzySearchPrompts,
+  getPermissionStatus,
+  injectIntoOrigin,
+  injectText,
+  listPresets,
+  probeInjectionCapabilities,
+  recordHistoryEntry,
+  startSession,
+  verifyInjectionTarget,
+} from "../lib/commands";
 import { streamGemini } from "../lib/gemini";
-import type { TextFieldBounds } from "../lib/commands";
+import { buildSystemPrompt } from "../lib/context";
+import { errorRemediation } from "../lib/errors";
+import type {
+  PromptContext,
+  PromptSuggestion,
+  TriggerDetected,
+} from "../lib/commands";
+
+const MAX_SUGGESTIONS = 5;
 
 export function OverlayView() {
   const [prompt, setPrompt] = useState("");
   const [response, setResponse] = useState("");
   const [isGenerating, setIsGenerating] = useState(false);
+  // Why the content filter refused the response; kept apart so it's never inserted
+  const [blockedReason, setBlockedReason] = useState<string | null>(null);
+  // Why inserting failed; the response stays so it can still be copied
+  const [insertError, setInsertError] = useState<string | null>(null);
+  const [suggestions, setSuggestions] = useState<PromptSuggestion[]>([]);
+  const [clipboardOnly, setClipboardOnly] = useState(false);
+  const [cannotInsert, setCannotInsert] = useState(false);
+  const [light, setLight] = useState(false);
   const inputRef = useRef<HTMLTextAreaElement>(null);
   const abortRef = useRef<AbortController | null>(null);
+  const contextRef = useRef<PromptContext | null>(null);
+  const sessionIdRef = useRef<number | null>(null);
 
   useEffect(() => {
     // Listen for trigger from Rust keystroke monitor
     const setupListener = async () => {
-      const unlisten = await listen<TextFieldBounds>(
+      const unlisten = await listen<TriggerDetected>(
         "trigger-detected",
         async (event) => {
-          const bounds = event.payload;
+          const { command, action, selection, appearance } = event.payload;
           const appWindow = getCurrentWindow();
 
-          // Position overlay near text field
-          const padding = 8;
-          const y = bounds.y + bounds.height + padding;
-
-          await appWindow.setPosition(
-            new PhysicalPosition(Math.round(bounds.x), Math.round(y))
-          );
+          // Rust has already placed the window next to the caret
           await appWindow.show();
           await appWindow.setFocus();
 
           // Reset state
           setPrompt("");
           setResponse("");
+          setBlockedReason(null);
+          setInsertError(null);
           setIsGenerating(false);
+          setLight(appearance?.appearance === "light");
           inputRef.current?.focus();
+
+          // A mouse trigger or the selection hotkey pre-fills the prompt with what was selected
+          if (selection) setPrompt(selection);
+
+          // A named trigger pre-selects the action it routes to
+          if (action) {
+            listPresets()
+              .then((presets) => {
+                const preset = presets.find((p) => p.id === `action:${action}`);
+                if (preset) setPrompt(preset.prompt);
+              })
+              .catch((err) => console.error("[Presets] Failed:", err));
+          }
+
+          // "/fix" pre-selects the preset or action named fix; otherwise it seeds the suggestions
+          if (command) {
+            listPresets()
+              .then((presets) => {
+                const name = command.toLowerCase();
+                const preset = presets.find(
+                  (p) =>
+                    p.id.toLowerCase() === name ||
+                    p.id.toLowerCase() === `action:${name}` ||
+                    p.label.toLowerCase() === name
+                );
+                setPrompt(preset ? preset.prompt : command);
+              })
+              .catch(() => setPrompt(command));
+          }
+
+          contextRef.current = null;
+          sessionIdRef.current = null;
+
+          // Without Accessibility there is no field to read or write
+          const permissions = await getPermissionStatus().catch(() => null);
+          const copyOnly = permissions?.clipboard_only ?? false;
+          setClipboardOnly(copyOnly);
+          setCannotInsert(false);
+          if (copyOnly) return;
+
+          // Warn before the prompt is written if nothing will land in this field
+          probeInjectionCapabilities()
+            .then(({ selected_text, value, paste, typing }) =>
+              setCannotInsert(!(selected_text || value || paste || typing))
+            )
+            .catch((err) => console.error("[Inject] Probe failed:", err));
+
+          // Start a session, gathering context for the app the trigger came from
+          startSession()
+            .then((session) => {
+              contextRef.current = session.context;
+              sessionIdRef.current = session.id;
+            })
+            .catch((err) => console.error("[Session] Failed to start:", err));
         }
       );
 
@@ -49,19 +130,51 @@ export function OverlayView() {
     };
   }, []);
 
+  // Fuzzy-match presets and past prompts as the user types
+  useEffect(() => {
+    if (!prompt.trim() || response) {
+      setSuggestions([]);
+      return;
+    }
+
+    let cancelled = false;
+    fuzzySearchPrompts(prompt, MAX_SUGGESTIONS)
+      .then((results) => {
+        if (!cancelled) setSuggestions(results);
+      })
+      .catch((err) => console.error("[Suggestions] Failed:", err));
+
+    return () => {
+      cancelled = true;
+    };
+  }, [prompt, response]);
+
   const handleSubmit = async () => {
     if (!prompt.trim() || isGenerating) return;
     setIsGenerating(true);
     setResponse("");
+    setBlockedReason(null);
+    setInsertError(null);
 
     abortRef.current = new AbortController();
 
     try {
+      let fullResponse = "";
       await streamGemini(
         prompt,
-        (chunk) => setResponse((prev) => prev + chunk),
-        abortRef.current.signal
+        (chunk) => {
+          fullResponse += chunk;
+          setResponse((prev) => prev + chunk);
+        },
+        abortRef.current.signal,
+        buildSystemPrompt(contextRef.current)
       );
+
+      if (sessionIdRef.current !== null) {
+        addSessionTurn(sessionIdRef.current, prompt, fullResponse).catch((err) =>
+          console.error("[Session] Failed to record turn:", err)
+        );
+      }
     } catch (err: unknown) {
       const error = err as Error;
       if (error.name !== "AbortError") {
@@ -73,12 +186,44 @@ export function OverlayView() {
   };
 
   const handleInsert = async () => {
+    // Surface the content filter's reason instead of silently failing
+    const filtered = await filterResponse(response);
+    if (filtered.blocked) {
+      setBlockedReason(filtered.reasons.join("; "));
+      return;
+    }
+
+    if (clipboardOnly || cannotInsert) {
+      await navigator.clipboard.writeText(filtered.text);
+      // Record what was copied, which the filter may have sanitized
+      recordHistoryEntry(prompt, filtered.text).catch((err) =>
+        console.error("[History] Failed to record:", err)
+      );
+      await getCurrentWindow().hide();
+      return;
+    }
+
+    setInsertError(null);
     try {
-      await insertText(response);
-    } catch {
-      // Fallback to paste method
-      await insertTextViaPaste(response);
+      // Return to the original field even if focus wandered while generating
+      if (sessionIdRef.current !== null) {
+        await injectIntoOrigin(sessionIdRef.current, response);
+      } else {
+        // Don't write into whatever app or field the user switched to meanwhile
+        await verifyInjectionTarget();
+        // Tries the app's strategies in order, paste and typing included
+        await injectText(response);
+      }
+    } catch (err) {
+      // Each path already tried every strategy the app allows; pasting anyway could land the
+      // text twice, or somewhere the user never meant it to go
+      setInsertError(errorRemediation(err));
+      return;
     }
+
+    recordHistoryEntry(prompt, response).catch((err) =>
+      console.error("[History] Failed to record:", err)
+    );
     await getCurrentWindow().hide();
   };
 
@@ -87,7 +232,17 @@ export function OverlayView() {
     await getCurrentWindow().hide();
   };
 
+  const applySuggestion = (suggestion: PromptSuggestion) => {
+    setPrompt(suggestion.text);
+    setSuggestions([]);
+    inputRef.current?.focus();
+  };
+
   const handleKeyDown = (e: React.KeyboardEvent) => {
+    if (e.key === "Tab" && suggestions.length > 0) {
+      e.preventDefault();
+      applySuggestion(suggestions[0]);
+    }
     if (e.key === "Enter" && !e.shiftKey) {
       e.preventDefault();
       handleSubmit();
@@ -98,7 +253,9 @@ export function OverlayView() {
   };
 
   return (
-    <div className="overlay-container">
+    <div
+      className={light ? "overlay-container overlay-light" : "overlay-container"}
+    >
       <textarea
         ref={inputRef}
         className="overlay-input"
@@ -107,9 +264,53 @@ export function OverlayView() {
         onKeyDown={handleKeyDown}
         placeholder="Ask AI anything..."
         rows={2}
+        dir="auto"
       />
 
-      {response && <div className="overlay-response">{response}</div>}
+      {suggestions.length > 0 && (
+        <ul className="overlay-suggestions">
+          {suggestions.map((suggestion) => (
+            <li
+              key={`${suggestion.kind}-${suggestion.history_id ?? suggestion.text}`}
+              onMouseDown={(e) => {
+                e.preventDefault();
+                applySuggestion(suggestion);
+              }}
+            >
+              {suggestion.label ?? suggestion.text}
+            </li>
+          ))}
+        </ul>
+      )}
+
+      {response && (
+        // Arabic or Hebrew replies read right to left, like the field they go into
+        <div className="overlay-response" dir="auto">
+          {response}
+        </div>
+      )}
+
+      {blockedReason && (
+        <div className="overlay-notice">Blocked: {blockedReason}</div>
+      )}
+
+      {insertError && (
+        <div className="overlay-notice">Error: {insertError}</div>
+      )}
+
+      {clipboardOnly && (
+        <div className="overlay-notice">
+          Accessibility access is off. Responses are copied to the clipboard;
+          paste them with ⌘V.
+        </div>
+      )}
+
+      {cannotInsert && !clipboardOnly && (
+        <div className="overlay-notice">
+          This field doesn't accept inserted text. Responses are copied to the
+          clipboard instead.
+        </div>
+      )}
 
       <div className="overlay-actions">
         <button onClick={handleCancel} className="btn-cancel">
@@ -126,10 +327,14 @@ export function OverlayView() {
           )}
           <button
             onClick={handleInsert}
-            disabled={!response || isGenerating}
+            disabled={!response || isGenerating || blockedReason !== null}
             className="btn-primary"
           >
-            {isGenerating ? "Generating..." : "Insert"}
+            {isGenerating
+              ? "Generating..."
+              : clipboardOnly || cannotInsert
+                ? "Copy & Close"
+                : "Insert"}
           </button>
         </div>
       </div>
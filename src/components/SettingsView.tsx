@@ -1,5 +1,81 @@
 import { useState, useEffect } from "react";
-import { storeApiKey, retrieveApiKey } from "../lib/commands";
+import { listen } from "@tauri-apps/api/event";
+import {
+  storeApiKey,
+  retrieveApiKey,
+  listApiKeys,
+  addApiKey,
+  removeApiKey,
+  getKeyRotationMode,
+  setKeyRotationMode,
+  getActivationConfig,
+  setActivationConfig,
+  getTriggerSequences,
+  setTriggerSequences,
+  getListenOnly,
+  setListenOnly,
+  getTriggerAppFilter,
+  setTriggerAppFilter,
+  startMonitoring,
+  getMonitorState,
+  checkSecureInput,
+  getSnippets,
+  setSnippets,
+  getNamedTriggers,
+  setNamedTriggers,
+  getMouseTriggers,
+  setMouseTriggers,
+  getGestureTrigger,
+  getFocusPauseConfig,
+  setFocusPauseConfig,
+  setGestureTrigger,
+  getOverlayPlacement,
+  setOverlayPlacement,
+  getCaretTracking,
+  setCaretTracking,
+  getTypingPace,
+  setTypingPace,
+  getSelectionHotkey,
+  setSelectionHotkey,
+  getTypingContextConfig,
+  setTypingContextConfig,
+  readTypingContext,
+  purgeTypingContext,
+  getRedactionConfig,
+  setRedactionConfig,
+  getContentFilterConfig,
+  setContentFilterConfig,
+  getAutoLanguageMode,
+  setAutoLanguageMode,
+  getStyleMemoryEnabled,
+  setStyleMemoryEnabled,
+  getClipboardSuggestionsEnabled,
+  setClipboardSuggestionsEnabled,
+  getTelemetryConfig,
+  setTelemetryConfig,
+  previewTelemetry,
+  type ApiKeyInfo,
+  type RotationMode,
+  type ActivationMode,
+  type ModifierKey,
+  type AppFilterMode,
+  type Snippet,
+  type NamedTrigger,
+  type NamedTriggerKind,
+  type MouseTriggers,
+  type Gesture,
+  type PlacementMode,
+  type TypingPace,
+  type FocusPauseConfig,
+  type TypingContextConfig,
+  type RedactionConfig,
+  type ContentFilterConfig,
+  type TelemetryConfig,
+  type TelemetryBatch,
+  type MonitorState,
+  type MonitorRestart,
+  type SecureInputStatus,
+} from "../lib/commands";
 import { useSupabase } from "../hooks/useSupabase";
 
 export function SettingsView() {
@@ -64,10 +140,1339 @@ function GeneralSettings() {
           {saved ? "Saved" : "Save"}
         </button>
       </div>
+      <ApiKeyPool />
+      <ActivationSettings />
+      <AppFilterSettings />
+      <FocusPauseSettings />
+      <SnippetSettings />
+      <NamedTriggerSettings />
+      <MouseTriggerSettings />
+      <GestureSettings />
+      <PlacementSettings />
+      <TypingPaceSettings />
+      <SelectionHotkeySettings />
+      <TypingContextSettings />
+      <RedactionSettings />
+      <ContentFilterSettings />
+      <WritingSettings />
+      <TelemetrySettings />
     </div>
   );
 }
 
+function ActivationSettings() {
+  const [mode, setMode] = useState<ActivationMode>("sequence");
+  const [chords, setChords] = useState("cmd+shift+space");
+  const [sequences, setSequences] = useState("/");
+  const [listenOnly, setListenOnlyState] = useState(false);
+  const [timeoutMs, setTimeoutMs] = useState<number | null>(null);
+  const [holdKey, setHoldKey] = useState("/");
+  const [holdMs, setHoldMs] = useState(400);
+  const [modifierKey, setModifierKey] = useState<ModifierKey>("fn");
+  const [debounceMs, setDebounceMs] = useState(300);
+  const [error, setError] = useState("");
+  const [saved, setSaved] = useState(false);
+
+  useEffect(() => {
+    getActivationConfig().then((config) => {
+      setMode(config.mode);
+      if (config.chords.length > 0) setChords(config.chords.join(", "));
+      setTimeoutMs(config.timeout_ms ?? null);
+      if (config.hold_key) setHoldKey(config.hold_key);
+      if (config.hold_ms) setHoldMs(config.hold_ms);
+      if (config.modifier_key) setModifierKey(config.modifier_key);
+      setDebounceMs(config.debounce_ms ?? 300);
+    });
+    getListenOnly().then(setListenOnlyState);
+    getTriggerSequences().then((current) => setSequences(current.join(", ")));
+  }, []);
+
+  const handleSave = async () => {
+    setError("");
+    try {
+      await setTriggerSequences(
+        sequences
+          .split(",")
+          .map((sequence) => sequence.trim())
+          .filter(Boolean)
+      );
+      await setActivationConfig({
+        mode,
+        chords: chords
+          .split(",")
+          .map((chord) => chord.trim())
+          .filter(Boolean),
+        timeout_ms: timeoutMs,
+        hold_key: holdKey.trim() || null,
+        hold_ms: holdMs,
+        modifier_key: modifierKey,
+        debounce_ms: debounceMs,
+      });
+      await setListenOnly(listenOnly);
+      // Shortcuts bound at start (the Wayland portal) and listen-only change on a restart
+      await startMonitoring();
+      setSaved(true);
+      setTimeout(() => setSaved(false), 2000);
+    } catch (err) {
+      setError(String((err as { message?: string }).message ?? err));
+    }
+  };
+
+  return (
+    <>
+      <h3>Activation</h3>
+      <p className="settings-hint">
+        Open the overlay by typing "/" (or sequences of your own such as "//"
+        or "::ai"), with a shortcut such as
+        cmd+shift+space (separate several with commas), by holding a key
+        (macOS only; a quick tap still types it), or by tapping Fn or a Hyper
+        key on its own (macOS only).
+      </p>
+      <MonitorStatus />
+      <div className="settings-row">
+        <select
+          value={mode}
+          onChange={(e) => setMode(e.target.value as ActivationMode)}
+        >
+          <option value="sequence">Typed trigger</option>
+          <option value="chord">Keyboard shortcut</option>
+          <option value="hold">Hold a key</option>
+          <option value="modifier">Tap a modifier</option>
+        </select>
+        <input
+          value={chords}
+          onChange={(e) => setChords(e.target.value)}
+          placeholder="cmd+shift+space"
+          disabled={mode !== "chord"}
+        />
+        <button onClick={handleSave} className="btn-primary">
+          {saved ? "Saved" : "Save"}
+        </button>
+      </div>
+      {mode === "sequence" && (
+        <div className="settings-row">
+          <input
+            value={sequences}
+            onChange={(e) => setSequences(e.target.value)}
+            placeholder="/"
+          />
+        </div>
+      )}
+      {mode === "hold" && (
+        <div className="settings-row">
+          <input
+            value={holdKey}
+            onChange={(e) => setHoldKey(e.target.value)}
+            placeholder="/"
+          />
+          <input
+            type="number"
+            min={150}
+            max={2000}
+            step={50}
+            value={holdMs}
+            onChange={(e) => setHoldMs(Number(e.target.value))}
+          />{" "}
+          ms
+        </div>
+      )}
+      {mode === "modifier" && (
+        <div className="settings-row">
+          <select
+            value={modifierKey}
+            onChange={(e) => setModifierKey(e.target.value as ModifierKey)}
+          >
+            <option value="fn">Fn / Globe</option>
+            <option value="hyper">Hyper (Control+Option+Shift+Command)</option>
+          </select>
+        </div>
+      )}
+      <div className="settings-row">
+        <label>
+          <input
+            type="checkbox"
+            checked={listenOnly}
+            onChange={(e) => setListenOnlyState(e.target.checked)}
+          />{" "}
+          Never swallow keystrokes (the key that opens the overlay is also
+          typed into the app)
+        </label>
+      </div>
+      <div className="settings-row">
+        <label>
+          Ignore repeat triggers within{" "}
+          <input
+            type="number"
+            min={0}
+            max={2000}
+            step={50}
+            value={debounceMs}
+            onChange={(e) => setDebounceMs(Number(e.target.value))}
+          />{" "}
+          ms
+        </label>
+      </div>
+      {error && (
+        <p style={{ color: "#ff6b6b", fontSize: 12, marginBottom: 8 }}>
+          {error}
+        </p>
+      )}
+    </>
+  );
+}
+
+const MONITOR_STATE_LABELS: Record<MonitorState, string> = {
+  running: "Triggers are active.",
+  stopped: "Triggers are paused.",
+  failed: "Triggers couldn't start.",
+  permission_lost: "Triggers stopped: Accessibility access was turned off.",
+  shortcuts_only:
+    "Only shortcuts work: grant Accessibility access for typed triggers.",
+};
+
+// Each status event moves the monitor into one state
+const MONITOR_STATE_EVENTS: [string, MonitorState][] = [
+  ["monitor-started", "running"],
+  ["monitor-stopped", "stopped"],
+  ["monitor-error", "failed"],
+  ["monitor-permission-lost", "permission_lost"],
+  ["monitor-fallback", "shortcuts_only"],
+];
+
+function MonitorStatus() {
+  const [state, setState] = useState<MonitorState>("stopped");
+  const [restarts, setRestarts] = useState(0);
+  const [secureInput, setSecureInput] = useState<SecureInputStatus | null>(
+    null
+  );
+
+  useEffect(() => {
+    getMonitorState().then(setState);
+    checkSecureInput().then(setSecureInput);
+    const unlisteners = [
+      ...MONITOR_STATE_EVENTS.map(([event, next]) =>
+        listen(event, () => setState(next))
+      ),
+      listen<MonitorRestart>("monitor-restarted", (event) =>
+        setRestarts(event.payload.restarts)
+      ),
+      listen("secure-input-active", () =>
+        checkSecureInput().then(setSecureInput)
+      ),
+    ];
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten.then((stop) => stop()));
+    };
+  }, []);
+
+  return (
+    <p className="settings-hint">
+      {MONITOR_STATE_LABELS[state]}
+      {restarts > 0 &&
+        ` Restarted ${restarts} time${restarts === 1 ? "" : "s"} after stopping unexpectedly.`}
+      {secureInput?.active &&
+        ` Paused while ${secureInput.app?.name ?? "another app"} has secure keyboard entry on.`}
+    </p>
+  );
+}
+
+function AppFilterSettings() {
+  const [mode, setMode] = useState<AppFilterMode>("deny");
+  const [apps, setApps] = useState("");
+  const [saved, setSaved] = useState(false);
+
+  useEffect(() => {
+    getTriggerAppFilter().then((filter) => {
+      setMode(filter.mode);
+      setApps(filter.apps.join(", "));
+    });
+  }, []);
+
+  const handleSave = async () => {
+    const filter = await setTriggerAppFilter({
+      mode,
+      apps: apps
+        .split(",")
+        .map((app) => app.trim())
+        .filter(Boolean),
+    });
+    setApps(filter.apps.join(", "));
+    setSaved(true);
+    setTimeout(() => setSaved(false), 2000);
+  };
+
+  return (
+    <>
+      <h3>Apps</h3>
+      <p className="settings-hint">
+        Bundle ids such as com.apple.Terminal, or prefixes ending in "."
+        (separate several with commas).
+      </p>
+      <div className="settings-row">
+        <select
+          value={mode}
+          onChange={(e) => setMode(e.target.value as AppFilterMode)}
+        >
+          <option value="deny">Never trigger in</option>
+          <option value="allow">Only trigger in</option>
+        </select>
+        <input
+          value={apps}
+          onChange={(e) => setApps(e.target.value)}
+          placeholder="com.apple.Terminal, com.valvesoftware."
+        />
+        <button onClick={handleSave} className="btn-primary">
+          {saved ? "Saved" : "Save"}
+        </button>
+      </div>
+    </>
+  );
+}
+
+function FocusPauseSettings() {
+  const [config, setConfig] = useState<FocusPauseConfig>({
+    enabled: false,
+    pause_when_presenting: true,
+    focus_modes: [],
+    full_screen_apps: {},
+  });
+  const [modes, setModes] = useState("");
+  const [bundleId, setBundleId] = useState("");
+
+  useEffect(() => {
+    getFocusPauseConfig().then((current) => {
+      setConfig(current);
+      setModes(current.focus_modes.join(", "));
+    });
+  }, []);
+
+  const save = (changed: Partial<FocusPauseConfig>) => {
+    const next = { ...config, ...changed };
+    setConfig(next);
+    setFocusPauseConfig(next);
+  };
+
+  const saveModes = () =>
+    save({
+      focus_modes: modes
+        .split(",")
+        .map((mode) => mode.trim())
+        .filter(Boolean),
+    });
+
+  const setFullScreenApp = (id: string, pause: boolean | null) => {
+    const apps = { ...config.full_screen_apps };
+    if (pause === null) {
+      delete apps[id];
+    } else {
+      apps[id] = pause;
+    }
+    save({ full_screen_apps: apps });
+  };
+
+  return (
+    <>
+      <h3>Focus</h3>
+      <p className="settings-hint">
+        Pause triggers during a Focus or over full-screen apps; the menu bar
+        icon dims while they are paused.
+      </p>
+      <div className="settings-row">
+        <label>
+          <input
+            type="checkbox"
+            checked={config.enabled}
+            onChange={(e) => save({ enabled: e.target.checked })}
+          />{" "}
+          Pause during a Focus
+        </label>
+      </div>
+      <div className="settings-row">
+        <label>
+          <input
+            type="checkbox"
+            checked={config.pause_when_presenting}
+            onChange={(e) => save({ pause_when_presenting: e.target.checked })}
+          />{" "}
+          Pause while presenting or playing a full-screen game
+        </label>
+      </div>
+      {Object.entries(config.full_screen_apps).map(([id, pause]) => (
+        <div className="settings-row" key={id}>
+          <span>{id}</span>
+          <select
+            value={pause ? "pause" : "allow"}
+            onChange={(e) => setFullScreenApp(id, e.target.value === "pause")}
+          >
+            <option value="pause">Pause in full screen</option>
+            <option value="allow">Never pause</option>
+          </select>
+          <button onClick={() => setFullScreenApp(id, null)}>Remove</button>
+        </div>
+      ))}
+      <div className="settings-row">
+        <input
+          value={bundleId}
+          onChange={(e) => setBundleId(e.target.value)}
+          placeholder="Bundle ID, e.g. com.apple.Safari"
+        />
+        <button
+          onClick={() => {
+            setFullScreenApp(bundleId.trim(), true);
+            setBundleId("");
+          }}
+          disabled={!bundleId.trim()}
+        >
+          Add app
+        </button>
+      </div>
+      <div className="settings-row">
+        <input
+          value={modes}
+          onChange={(e) => setModes(e.target.value)}
+          onBlur={saveModes}
+          placeholder="Only these Focus modes, e.g. Work, Sleep (empty: any)"
+        />
+      </div>
+    </>
+  );
+}
+
+function SnippetSettings() {
+  const [snippets, setSnippetList] = useState<Snippet[]>([]);
+  const [abbreviation, setAbbreviation] = useState("");
+  const [expansion, setExpansion] = useState("");
+  const [error, setError] = useState("");
+
+  useEffect(() => {
+    getSnippets().then(setSnippetList);
+  }, []);
+
+  const save = async (next: Snippet[]) => {
+    setError("");
+    try {
+      await setSnippets(next);
+      setSnippetList(next);
+      return true;
+    } catch (err) {
+      setError(String((err as { message?: string }).message ?? err));
+      return false;
+    }
+  };
+
+  const handleAdd = async () => {
+    if (await save([...snippets, { abbreviation, expansion }])) {
+      setAbbreviation("");
+      setExpansion("");
+    }
+  };
+
+  const handleRemove = (removed: string) =>
+    save(snippets.filter((snippet) => snippet.abbreviation !== removed));
+
+  return (
+    <>
+      <h3>Snippets</h3>
+      <p className="settings-hint">
+        Typing an abbreviation anywhere replaces it with its expansion.
+      </p>
+      {snippets.map((snippet) => (
+        <div className="settings-row" key={snippet.abbreviation}>
+          <span>
+            {snippet.abbreviation} → {snippet.expansion}
+          </span>
+          <button
+            onClick={() => handleRemove(snippet.abbreviation)}
+            className="btn-secondary"
+          >
+            Remove
+          </button>
+        </div>
+      ))}
+      <div className="settings-row">
+        <input
+          value={abbreviation}
+          onChange={(e) => setAbbreviation(e.target.value)}
+          placeholder=":sig"
+        />
+        <input
+          value={expansion}
+          onChange={(e) => setExpansion(e.target.value)}
+          placeholder="Best regards, ..."
+        />
+        <button onClick={handleAdd} className="btn-primary">
+          Add
+        </button>
+      </div>
+      {error && (
+        <p style={{ color: "#ff6b6b", fontSize: 12, marginBottom: 8 }}>
+          {error}
+        </p>
+      )}
+    </>
+  );
+}
+
+function NamedTriggerSettings() {
+  const [triggers, setTriggerList] = useState<NamedTrigger[]>([]);
+  const [name, setName] = useState("");
+  const [kind, setKind] = useState<NamedTriggerKind>("typed");
+  const [keys, setKeys] = useState("");
+  const [action, setAction] = useState("");
+  const [error, setError] = useState("");
+
+  useEffect(() => {
+    getNamedTriggers().then(setTriggerList);
+  }, []);
+
+  const save = async (next: NamedTrigger[]) => {
+    setError("");
+    try {
+      await setNamedTriggers(next);
+      setTriggerList(next);
+      return true;
+    } catch (err) {
+      setError(String((err as { message?: string }).message ?? err));
+      return false;
+    }
+  };
+
+  const handleAdd = async () => {
+    const added = { name, kind, keys, action: action.trim() || null };
+    if (await save([...triggers, added])) {
+      setName("");
+      setKeys("");
+      setAction("");
+    }
+  };
+
+  const handleRemove = (removed: string) =>
+    save(triggers.filter((trigger) => trigger.name !== removed));
+
+  return (
+    <>
+      <h3>Triggers</h3>
+      <p className="settings-hint">
+        Extra shortcuts or typed text that open the overlay with an action
+        already chosen (leave the action empty for a plain prompt).
+      </p>
+      {triggers.map((trigger) => (
+        <div className="settings-row" key={trigger.name}>
+          <span>
+            {trigger.name}: {trigger.keys} → {trigger.action ?? "prompt"}
+          </span>
+          <button
+            onClick={() => handleRemove(trigger.name)}
+            className="btn-secondary"
+          >
+            Remove
+          </button>
+        </div>
+      ))}
+      <div className="settings-row">
+        <input
+          value={name}
+          onChange={(e) => setName(e.target.value)}
+          placeholder="Grammar"
+        />
+        <select
+          value={kind}
+          onChange={(e) => setKind(e.target.value as NamedTriggerKind)}
+        >
+          <option value="typed">Typed</option>
+          <option value="chord">Shortcut</option>
+        </select>
+        <input
+          value={keys}
+          onChange={(e) => setKeys(e.target.value)}
+          placeholder={kind === "chord" ? "cmd+shift+t" : "::fix"}
+        />
+        <input
+          value={action}
+          onChange={(e) => setAction(e.target.value)}
+          placeholder="Action id"
+        />
+        <button onClick={handleAdd} className="btn-primary">
+          Add
+        </button>
+      </div>
+      {error && (
+        <p style={{ color: "#ff6b6b", fontSize: 12, marginBottom: 8 }}>
+          {error}
+        </p>
+      )}
+    </>
+  );
+}
+
+function MouseTriggerSettings() {
+  const [triggers, setTriggers] = useState<MouseTriggers>({
+    triple_click: false,
+    middle_click: false,
+  });
+
+  useEffect(() => {
+    getMouseTriggers().then(setTriggers);
+  }, []);
+
+  const toggle = (changed: Partial<MouseTriggers>) => {
+    const next = { ...triggers, ...changed };
+    setTriggers(next);
+    setMouseTriggers(next);
+  };
+
+  return (
+    <>
+      <h3>Mouse</h3>
+      <p className="settings-hint">
+        Open the overlay with the selected text already filled in (macOS only).
+      </p>
+      <div className="settings-row">
+        <label>
+          <input
+            type="checkbox"
+            checked={triggers.triple_click}
+            onChange={(e) => toggle({ triple_click: e.target.checked })}
+          />{" "}
+          Triple-click a paragraph
+        </label>
+      </div>
+      <div className="settings-row">
+        <label>
+          <input
+            type="checkbox"
+            checked={triggers.middle_click}
+            onChange={(e) => toggle({ middle_click: e.target.checked })}
+          />{" "}
+          Middle-click a selection
+        </label>
+      </div>
+    </>
+  );
+}
+
+function GestureSettings() {
+  const [gesture, setGesture] = useState<Gesture>("off");
+
+  useEffect(() => {
+    getGestureTrigger().then(setGesture);
+  }, []);
+
+  const handleChange = (next: Gesture) => {
+    setGesture(next);
+    setGestureTrigger(next);
+  };
+
+  return (
+    <>
+      <h3>Trackpad</h3>
+      <p className="settings-hint">
+        Open the overlay at the pointer with a gesture instead of typing
+        (macOS only).
+      </p>
+      <div className="settings-row">
+        <select
+          value={gesture}
+          onChange={(e) => handleChange(e.target.value as Gesture)}
+        >
+          <option value="off">Off</option>
+          <option value="force_click">Force click</option>
+          <option value="three_finger_tap">
+            Three-finger tap (set Look up to it in Trackpad settings)
+          </option>
+        </select>
+      </div>
+    </>
+  );
+}
+
+function PlacementSettings() {
+  const [mode, setMode] = useState<PlacementMode>("caret");
+  const [followCaret, setFollowCaret] = useState(true);
+
+  useEffect(() => {
+    getOverlayPlacement().then(setMode);
+    getCaretTracking().then(setFollowCaret);
+  }, []);
+
+  const handleChange = (next: PlacementMode) => {
+    setMode(next);
+    setOverlayPlacement(next);
+  };
+
+  return (
+    <>
+      <h3>Placement</h3>
+      <p className="settings-hint">
+        Where the overlay opens (macOS only). Docked, it sits beside the window
+        you are writing in instead of over it.
+      </p>
+      <div className="settings-row">
+        <select
+          value={mode}
+          onChange={(e) => handleChange(e.target.value as PlacementMode)}
+        >
+          <option value="caret">Next to the caret</option>
+          <option value="window_edge">Docked to the window's edge</option>
+        </select>
+      </div>
+      <div className="settings-row">
+        <label>
+          <input
+            type="checkbox"
+            checked={followCaret}
+            onChange={(e) => {
+              setFollowCaret(e.target.checked);
+              setCaretTracking(e.target.checked);
+            }}
+          />{" "}
+          Move with the caret while it is open
+        </label>
+      </div>
+    </>
+  );
+}
+
+function TypingPaceSettings() {
+  const [pace, setPace] = useState<TypingPace>({
+    humanized: false,
+    chars_per_second: 12,
+    jitter_percent: 40,
+  });
+  const [error, setError] = useState("");
+
+  useEffect(() => {
+    getTypingPace().then(setPace);
+  }, []);
+
+  const save = async (next: TypingPace) => {
+    setError("");
+    setPace(next);
+    try {
+      await setTypingPace(next);
+    } catch (err) {
+      setError(String((err as { message?: string }).message ?? err));
+    }
+  };
+
+  return (
+    <>
+      <h3>Typing speed</h3>
+      <p className="settings-hint">
+        For apps that only take typed text. Humanized, responses are typed at
+        about a person's pace with uneven pauses, so web apps don't flag them
+        and they look natural in screen-shared meetings.
+      </p>
+      <div className="settings-row">
+        <label>
+          <input
+            type="checkbox"
+            checked={pace.humanized}
+            onChange={(e) => save({ ...pace, humanized: e.target.checked })}
+          />{" "}
+          Type like a person at
+        </label>
+        <input
+          type="number"
+          min={2}
+          max={60}
+          value={pace.chars_per_second}
+          onChange={(e) =>
+            setPace({ ...pace, chars_per_second: Number(e.target.value) })
+          }
+          onBlur={() => save(pace)}
+        />
+        characters a second, varying by
+        <input
+          type="number"
+          min={0}
+          max={90}
+          value={pace.jitter_percent}
+          onChange={(e) =>
+            setPace({ ...pace, jitter_percent: Number(e.target.value) })
+          }
+          onBlur={() => save(pace)}
+        />
+        %
+      </div>
+      {error && (
+        <p style={{ color: "#ff6b6b", fontSize: 12, marginBottom: 8 }}>
+          {error}
+        </p>
+      )}
+    </>
+  );
+}
+
+function SelectionHotkeySettings() {
+  const [hotkey, setHotkey] = useState("");
+  const [error, setError] = useState("");
+  const [saved, setSaved] = useState(false);
+
+  useEffect(() => {
+    getSelectionHotkey().then((current) => setHotkey(current ?? ""));
+  }, []);
+
+  const handleSave = async () => {
+    setError("");
+    try {
+      await setSelectionHotkey(hotkey.trim() || null);
+      setSaved(true);
+      setTimeout(() => setSaved(false), 2000);
+    } catch (err) {
+      setError(String((err as { message?: string }).message ?? err));
+    }
+  };
+
+  return (
+    <>
+      <h3>Rewrite selection</h3>
+      <p className="settings-hint">
+        With text selected, this shortcut opens the overlay with the selection
+        filled in (leave empty to turn it off).
+      </p>
+      <div className="settings-row">
+        <input
+          value={hotkey}
+          onChange={(e) => setHotkey(e.target.value)}
+          placeholder="cmd+shift+r"
+        />
+        <button onClick={handleSave} className="btn-primary">
+          {saved ? "Saved" : "Save"}
+        </button>
+      </div>
+      {error && (
+        <p style={{ color: "#ff6b6b", fontSize: 12, marginBottom: 8 }}>
+          {error}
+        </p>
+      )}
+    </>
+  );
+}
+
+function TypingContextSettings() {
+  const [config, setConfig] = useState<TypingContextConfig>({
+    enabled: false,
+    max_chars: 200,
+  });
+  const [recorded, setRecorded] = useState<string | null>(null);
+  const [error, setError] = useState("");
+
+  useEffect(() => {
+    getTypingContextConfig().then(setConfig);
+  }, []);
+
+  const save = async (next: TypingContextConfig) => {
+    setError("");
+    setConfig(next);
+    try {
+      await setTypingContextConfig(next);
+      if (!next.enabled) setRecorded(null);
+    } catch (err) {
+      setError(String((err as { message?: string }).message ?? err));
+    }
+  };
+
+  const handlePurge = async () => {
+    await purgeTypingContext();
+    setRecorded(null);
+  };
+
+  return (
+    <>
+      <h3>Typing context</h3>
+      <p className="settings-hint">
+        Include what you typed just before a trigger in the prompt. Kept in
+        memory only, forgotten when you switch fields, and never recorded in
+        password fields.
+      </p>
+      <div className="settings-row">
+        <label>
+          <input
+            type="checkbox"
+            checked={config.enabled}
+            onChange={(e) => save({ ...config, enabled: e.target.checked })}
+          />{" "}
+          Remember the last
+        </label>
+        <input
+          type="number"
+          min={1}
+          max={2000}
+          value={config.max_chars}
+          onChange={(e) =>
+            setConfig({ ...config, max_chars: Number(e.target.value) })
+          }
+          onBlur={() => save(config)}
+        />
+        characters
+      </div>
+      {config.enabled && (
+        <div className="settings-row">
+          <button onClick={async () => setRecorded(await readTypingContext())}>
+            Show
+          </button>
+          <button onClick={handlePurge}>Clear</button>
+        </div>
+      )}
+      {recorded && <pre className="settings-hint">{recorded}</pre>}
+      {error && (
+        <p style={{ color: "#ff6b6b", fontSize: 12, marginBottom: 8 }}>
+          {error}
+        </p>
+      )}
+    </>
+  );
+}
+
+function RedactionSettings() {
+  const [config, setConfig] = useState<RedactionConfig>({
+    enabled: false,
+    emails: true,
+    phone_numbers: true,
+    credit_cards: true,
+    custom_patterns: [],
+    named_entities: false,
+  });
+  const [patterns, setPatterns] = useState("");
+  const [error, setError] = useState("");
+
+  useEffect(() => {
+    getRedactionConfig().then((current) => {
+      setConfig(current);
+      setPatterns(current.custom_patterns.join("\n"));
+    });
+  }, []);
+
+  const save = async (changed: Partial<RedactionConfig>) => {
+    const next = { ...config, ...changed };
+    setError("");
+    setConfig(next);
+    try {
+      await setRedactionConfig(next);
+    } catch (err) {
+      setError(String((err as { message?: string }).message ?? err));
+    }
+  };
+
+  const savePatterns = () =>
+    save({
+      custom_patterns: patterns
+        .split("\n")
+        .map((pattern) => pattern.trim())
+        .filter(Boolean),
+    });
+
+  return (
+    <>
+      <h3>Redaction</h3>
+      <p className="settings-hint">
+        Replace personal details with placeholders before a prompt leaves your
+        Mac; the response gets them back.
+      </p>
+      <div className="settings-row">
+        <label>
+          <input
+            type="checkbox"
+            checked={config.enabled}
+            onChange={(e) => save({ enabled: e.target.checked })}
+          />{" "}
+          Redact prompts and context
+        </label>
+      </div>
+      {config.enabled && (
+        <>
+          <div className="settings-row">
+            <label>
+              <input
+                type="checkbox"
+                checked={config.emails}
+                onChange={(e) => save({ emails: e.target.checked })}
+              />{" "}
+              Email addresses
+            </label>
+            <label>
+              <input
+                type="checkbox"
+                checked={config.phone_numbers}
+                onChange={(e) => save({ phone_numbers: e.target.checked })}
+              />{" "}
+              Phone numbers
+            </label>
+            <label>
+              <input
+                type="checkbox"
+                checked={config.credit_cards}
+                onChange={(e) => save({ credit_cards: e.target.checked })}
+              />{" "}
+              Card numbers
+            </label>
+            <label>
+              <input
+                type="checkbox"
+                checked={config.named_entities}
+                onChange={(e) => save({ named_entities: e.target.checked })}
+              />{" "}
+              Names of people, places, and organizations
+            </label>
+          </div>
+          <div className="settings-row">
+            <textarea
+              value={patterns}
+              onChange={(e) => setPatterns(e.target.value)}
+              onBlur={savePatterns}
+              placeholder="Extra patterns, one regex per line"
+              rows={3}
+            />
+          </div>
+        </>
+      )}
+      {error && (
+        <p style={{ color: "#ff6b6b", fontSize: 12, marginBottom: 8 }}>
+          {error}
+        </p>
+      )}
+    </>
+  );
+}
+
+function ContentFilterSettings() {
+  const [config, setConfig] = useState<ContentFilterConfig>({
+    enabled: false,
+    profanity: true,
+    banned_patterns: [],
+    max_length: null,
+    action: "sanitize",
+  });
+  const [patterns, setPatterns] = useState("");
+  const [error, setError] = useState("");
+
+  useEffect(() => {
+    getContentFilterConfig().then((current) => {
+      setConfig(current);
+      setPatterns(current.banned_patterns.join("\n"));
+    });
+  }, []);
+
+  const save = async (changed: Partial<ContentFilterConfig>) => {
+    const next = { ...config, ...changed };
+    setError("");
+    setConfig(next);
+    try {
+      await setContentFilterConfig(next);
+    } catch (err) {
+      setError(String((err as { message?: string }).message ?? err));
+    }
+  };
+
+  const savePatterns = () =>
+    save({
+      banned_patterns: patterns
+        .split("\n")
+        .map((pattern) => pattern.trim())
+        .filter(Boolean),
+    });
+
+  return (
+    <>
+      <h3>Content filter</h3>
+      <p className="settings-hint">
+        Check responses before they are written into an app.
+      </p>
+      <div className="settings-row">
+        <label>
+          <input
+            type="checkbox"
+            checked={config.enabled}
+            onChange={(e) => save({ enabled: e.target.checked })}
+          />{" "}
+          Filter responses
+        </label>
+      </div>
+      {config.enabled && (
+        <>
+          <div className="settings-row">
+            <select
+              value={config.action}
+              onChange={(e) =>
+                save({
+                  action: e.target.value as ContentFilterConfig["action"],
+                })
+              }
+            >
+              <option value="sanitize">Mask matches and shorten</option>
+              <option value="block">Refuse to insert</option>
+            </select>
+            <label>
+              <input
+                type="checkbox"
+                checked={config.profanity}
+                onChange={(e) => save({ profanity: e.target.checked })}
+              />{" "}
+              Profanity
+            </label>
+          </div>
+          <div className="settings-row">
+            <label>
+              At most{" "}
+              <input
+                type="number"
+                min={1}
+                value={config.max_length ?? ""}
+                onChange={(e) =>
+                  setConfig({
+                    ...config,
+                    max_length: e.target.value ? Number(e.target.value) : null,
+                  })
+                }
+                onBlur={() => save({})}
+                placeholder="any"
+              />{" "}
+              characters
+            </label>
+          </div>
+          <div className="settings-row">
+            <textarea
+              value={patterns}
+              onChange={(e) => setPatterns(e.target.value)}
+              onBlur={savePatterns}
+              placeholder="Banned terms, one regex per line"
+              rows={3}
+            />
+          </div>
+        </>
+      )}
+      {error && (
+        <p style={{ color: "#ff6b6b", fontSize: 12, marginBottom: 8 }}>
+          {error}
+        </p>
+      )}
+    </>
+  );
+}
+
+function WritingSettings() {
+  const [autoLanguage, setAutoLanguage] = useState(false);
+  const [styleMemory, setStyleMemory] = useState(false);
+  const [clipboardSuggestions, setClipboardSuggestions] = useState(false);
+
+  useEffect(() => {
+    getAutoLanguageMode().then(setAutoLanguage);
+    getStyleMemoryEnabled().then(setStyleMemory);
+    getClipboardSuggestionsEnabled().then(setClipboardSuggestions);
+  }, []);
+
+  return (
+    <>
+      <h3>Writing</h3>
+      <div className="settings-row">
+        <label>
+          <input
+            type="checkbox"
+            checked={autoLanguage}
+            onChange={(e) => {
+              setAutoLanguage(e.target.checked);
+              setAutoLanguageMode(e.target.checked);
+            }}
+          />{" "}
+          Respond in the language the field is written in
+        </label>
+      </div>
+      <div className="settings-row">
+        <label>
+          <input
+            type="checkbox"
+            checked={styleMemory}
+            onChange={(e) => {
+              setStyleMemory(e.target.checked);
+              setStyleMemoryEnabled(e.target.checked);
+            }}
+          />{" "}
+          Match the style of what you've inserted in each app before
+        </label>
+      </div>
+      <div className="settings-row">
+        <label>
+          <input
+            type="checkbox"
+            checked={clipboardSuggestions}
+            onChange={(e) => {
+              setClipboardSuggestions(e.target.checked);
+              setClipboardSuggestionsEnabled(e.target.checked);
+            }}
+          />{" "}
+          Suggest actions when you copy a long passage (never passwords or
+          keys)
+        </label>
+      </div>
+    </>
+  );
+}
+
+function TelemetrySettings() {
+  const [config, setConfig] = useState<TelemetryConfig>({
+    enabled: false,
+    endpoint: null,
+  });
+  const [preview, setPreview] = useState<TelemetryBatch | null>(null);
+  const [error, setError] = useState("");
+
+  useEffect(() => {
+    getTelemetryConfig().then(setConfig);
+  }, []);
+
+  const save = async (next: TelemetryConfig) => {
+    setError("");
+    setConfig(next);
+    try {
+      await setTelemetryConfig(next);
+      if (!next.enabled) setPreview(null);
+    } catch (err) {
+      setError(String((err as { message?: string }).message ?? err));
+    }
+  };
+
+  return (
+    <>
+      <h3>Usage statistics</h3>
+      <p className="settings-hint">
+        Send hourly counts of triggers and failures to help improve Prompt OS.
+        Never what you write, which apps you use, or anything that identifies
+        you.
+      </p>
+      <div className="settings-row">
+        <label>
+          <input
+            type="checkbox"
+            checked={config.enabled}
+            onChange={(e) => save({ ...config, enabled: e.target.checked })}
+          />{" "}
+          Share usage counts
+        </label>
+      </div>
+      {config.enabled && (
+        <>
+          <div className="settings-row">
+            <input
+              value={config.endpoint ?? ""}
+              onChange={(e) =>
+                setConfig({ ...config, endpoint: e.target.value || null })
+              }
+              onBlur={() => save(config)}
+              placeholder="https://telemetry.example.com/batch"
+            />
+            <button onClick={async () => setPreview(await previewTelemetry())}>
+              Preview
+            </button>
+          </div>
+          {preview && (
+            <pre className="settings-hint">
+              {JSON.stringify(preview, null, 2)}
+            </pre>
+          )}
+        </>
+      )}
+      {error && (
+        <p style={{ color: "#ff6b6b", fontSize: 12, marginBottom: 8 }}>
+          {error}
+        </p>
+      )}
+    </>
+  );
+}
+
+function ApiKeyPool() {
+  const [keys, setKeys] = useState<ApiKeyInfo[]>([]);
+  const [mode, setMode] = useState<RotationMode>("round_robin");
+  const [label, setLabel] = useState("");
+  const [newKey, setNewKey] = useState("");
+  const [error, setError] = useState("");
+
+  const refresh = () => listApiKeys().then(setKeys);
+
+  useEffect(() => {
+    refresh();
+    getKeyRotationMode().then(setMode);
+  }, []);
+
+  const handleAdd = async () => {
+    setError("");
+    try {
+      await addApiKey(label, newKey);
+      setLabel("");
+      setNewKey("");
+      refresh();
+    } catch (err) {
+      setError(String((err as { message?: string }).message ?? err));
+    }
+  };
+
+  const handleRemove = async (id: string) => {
+    await removeApiKey(id);
+    refresh();
+  };
+
+  const handleMode = async (next: RotationMode) => {
+    await setKeyRotationMode(next);
+    setMode(next);
+  };
+
+  return (
+    <>
+      <h3>Additional Keys</h3>
+      <p className="settings-hint">
+        Requests rotate across every stored key and move on to the next key
+        after a rate limit or server error.
+      </p>
+      <div className="settings-row">
+        <select
+          value={mode}
+          onChange={(e) => handleMode(e.target.value as RotationMode)}
+        >
+          <option value="round_robin">Round-robin</option>
+          <option value="failover">Primary first, failover only</option>
+        </select>
+      </div>
+      {keys.map((key) => (
+        <div className="settings-row" key={key.id}>
+          <span>
+            {key.label} ····{key.hint} — {key.usage.requests} requests,{" "}
+            {key.usage.rate_limited} rate limited
+            {key.usage.cooling_down ? " (cooling down)" : ""}
+          </span>
+          <button
+            onClick={() => handleRemove(key.id)}
+            className="btn-secondary"
+          >
+            Remove
+          </button>
+        </div>
+      ))}
+      <div className="settings-row">
+        <input
+          value={label}
+          onChange={(e) => setLabel(e.target.value)}
+          placeholder="Label"
+        />
+        <input
+          type="password"
+          value={newKey}
+          onChange={(e) => setNewKey(e.target.value)}
+          placeholder="Another Gemini API key"
+        />
+        <button onClick={handleAdd} className="btn-primary">
+          Add
+        </button>
+      </div>
+      {error && (
+        <p style={{ color: "#ff6b6b", fontSize: 12, marginBottom: 8 }}>
+          {error}
+        </p>
+      )}
+    </>
+  );
+}
+
 function AccountSettings() {
   const { user, signIn, signUp, signOut, getRemainingTokens, loading } =
     useSupabase();
@@ -1,10 +1,54 @@
 import { invoke } from "@tauri-apps/api/core";
+import type { PromptOsError } from "./errors";
 
 export interface TextFieldBounds {
   x: number;
   y: number;
   width: number;
   height: number;
+  // CGDirectDisplayID of the screen the field or cursor is on
+  display_id: number | null;
+  // Backing scale factor of that screen (2 on Retina, 1.5 at 150% on Windows)
+  scale_factor: number | null;
+  // Points on macOS; device pixels on Windows and Linux
+  coordinates: "logical" | "physical";
+}
+
+// Light or dark UI around the caret
+export interface TargetAppearance {
+  appearance: "light" | "dark";
+  // Read off the pixels around the caret (needs Screen Recording) rather than the system setting
+  sampled: boolean;
+}
+
+// Payload of the "trigger-detected" event
+export interface TriggerDetected extends TextFieldBounds {
+  // Slash command typed after the trigger, e.g. "fix" from "/fix"
+  command: string | null;
+  // Name of the named trigger that fired, if one did
+  trigger: string | null;
+  // Action id the named trigger routes to
+  action: string | null;
+  // Selected text a mouse trigger or the selection hotkey pre-fills the overlay with
+  selection: string | null;
+  // Kind of trigger that fired
+  trigger_id:
+    | "slash"
+    | "command"
+    | "quick_transform"
+    | "named"
+    | "selection"
+    | "gesture";
+  // Modifier keys held as it fired, e.g. ["cmd", "shift"]; empty off macOS
+  modifiers: string[];
+  // App the overlay was opened over
+  bundle_id: string | null;
+  // So the overlay opens in a matching theme; only sent on macOS
+  appearance?: TargetAppearance | null;
+  // Frame of the window the field is in, which window-edge placement docks the overlay to
+  window: TextFieldBounds | null;
+  // When it fired (RFC 3339)
+  timestamp: string;
 }
 
 // Text field detection
@@ -12,13 +56,284 @@ export async function getFocusedTextFieldBounds(): Promise<TextFieldBounds> {
   return invoke<TextFieldBounds>("get_focused_text_field_bounds");
 }
 
+// Best guess at what the focused field is for
+export type FieldKind =
+  | "chat"
+  | "code_editor"
+  | "email_composer"
+  | "terminal"
+  | "search_bar"
+  | "generic";
+
+// Dominant script of a field's text and the direction it's written in
+export interface WritingDirection {
+  direction: "ltr" | "rtl";
+  // e.g. "latin", "arabic", "hebrew", "han", "kana"
+  script: string;
+}
+
+// How a response should be written into a field
+export type InjectStrategy = "insert" | "paste";
+
+// What the focused element is, e.g. role "AXTextArea" in a "Mail" window
+export interface FocusedFieldInfo {
+  role: string | null;
+  subrole: string | null;
+  // The app owning the element, not necessarily the frontmost one
+  bundle_id: string | null;
+  app_name: string | null;
+  window_title: string | null;
+  placeholder: string | null;
+  // Text can be set directly instead of typed or pasted
+  value_settable: boolean;
+  kind: FieldKind;
+  // "paste" for terminals, which garble Accessibility writes; their paste is bracketed
+  strategy: InjectStrategy;
+  // From the field's text, else its placeholder; null when it has no letters
+  direction: WritingDirection | null;
+  // Frame of the window it's in
+  window_frame: TextFieldBounds | null;
+}
+
+export async function getFocusedFieldInfo(): Promise<FocusedFieldInfo> {
+  return invoke<FocusedFieldInfo>("get_focused_field_info");
+}
+
+// What is highlighted in the focused field, read without the clipboard; null when nothing is
+export async function getSelectedText(): Promise<string | null> {
+  return invoke<string | null>("get_selected_text");
+}
+
+// The focused field's text and caret; offsets count UTF-16 code units, like JS strings
+export interface FieldContent {
+  value: string | null;
+  // Caret offset, or where the selection starts
+  selection_start: number | null;
+  // 0 when nothing is selected
+  selection_length: number | null;
+}
+
+export async function getFieldContent(): Promise<FieldContent> {
+  return invoke<FieldContent>("get_field_content");
+}
+
+// Text either side of the caret, cut to a limit so long documents stay local
+export interface SurroundingText {
+  before: string;
+  after: string;
+  truncated_before: boolean;
+  truncated_after: boolean;
+}
+
+// chars: UTF-16 code units on each side (1-20000, 1000 when omitted)
+export async function getSurroundingText(
+  chars?: number
+): Promise<SurroundingText> {
+  return invoke<SurroundingText>("get_surrounding_text", { chars });
+}
+
+// PNG of the focused field (or its window) in the app being written to; needs Screen Recording
+export interface FieldScreenshot {
+  png_base64: string;
+  // Captured area in screen points
+  bounds: TextFieldBounds;
+  // Points times the screen's scale factor
+  pixel_width: number;
+  pixel_height: number;
+}
+
+export async function captureFieldScreenshot(
+  region?: "field" | "window"
+): Promise<FieldScreenshot> {
+  return invoke<FieldScreenshot>("capture_field_screenshot", { region });
+}
+
+// Text read off a capture of the field by OCR, when Accessibility exposes none
+export interface RecognizedText {
+  text: string;
+  // Mean recognition confidence, 0-1
+  confidence: number;
+  source: "ocr";
+}
+
+// Null when nothing legible was recognized; needs Screen Recording
+export async function recognizeFieldText(
+  region?: "field" | "window"
+): Promise<RecognizedText | null> {
+  return invoke<RecognizedText | null>("recognize_field_text", { region });
+}
+
+// Payload of "field-focused", sent as focus lands on a field that can take text; "field-blurred"
+// (no payload) follows once focus leaves it
+export interface FieldFocused {
+  role: string | null;
+  bundle_id: string | null;
+  app_name: string | null;
+}
+
+// While the overlay is open, "caret-moved" (TextFieldBounds) follows the target field's caret and
+// the overlay moves with it; on unless turned off (which also stops an open overlay following)
+export async function setCaretTracking(enabled: boolean): Promise<void> {
+  return invoke("set_caret_tracking", { enabled });
+}
+
+export async function getCaretTracking(): Promise<boolean> {
+  return invoke<boolean>("get_caret_tracking");
+}
+
+export async function getTargetAppearance(): Promise<TargetAppearance> {
+  return invoke<TargetAppearance>("get_target_appearance");
+}
+
 // Text insertion
-export async function insertText(text: string): Promise<void> {
-  return invoke("insert_text", { text });
+// Rejects with "focus_changed" when the field the overlay was opened over no longer has focus
+export async function verifyInjectionTarget(): Promise<void> {
+  return invoke("verify_injection_target");
+}
+
+// Where insertText puts the text: over the selection (default), after the field's text, or at
+// a UTF-16 offset into it
+export type InsertMode = "replace_selection" | "append" | "at_offset";
+
+// Reads the field back afterwards and rejects if the text didn't show up, so paste can take over
+export async function insertText(
+  text: string,
+  mode?: InsertMode,
+  offset?: number,
+  renderMarkdown?: boolean
+): Promise<void> {
+  return invoke("insert_text", { text, mode, offset, renderMarkdown });
+}
+
+export async function insertTextViaPaste(
+  text: string,
+  renderMarkdown?: boolean
+): Promise<void> {
+  return invoke("insert_text_via_paste", { text, renderMarkdown });
 }
 
-export async function insertTextViaPaste(text: string): Promise<void> {
-  return invoke("insert_text_via_paste", { text });
+// Pastes HTML and/or RTF alongside the plain text, so editors that take formatting keep it; if
+// the content filter rewrites the text, only the plain text goes out
+export async function insertRichText(
+  text: string,
+  html?: string,
+  rtf?: string
+): Promise<void> {
+  return invoke("insert_rich_text", { text, html, rtf });
+}
+
+// Types the text as keystrokes, for apps that take neither direct insertion nor paste; delayMs
+// (default 5) paces them for apps that drop fast input
+export async function insertTextViaTyping(
+  text: string,
+  delayMs?: number,
+  renderMarkdown?: boolean
+): Promise<void> {
+  return invoke("insert_text_via_typing", { text, delayMs, renderMarkdown });
+}
+
+// Stops typing that's under way (also in the tray menu); what was typed so far stays
+export async function cancelTyping(): Promise<void> {
+  return invoke("cancel_typing");
+}
+
+// Ways of getting text into a field; each app has a chain of them, tried in order
+export type InjectionStrategy = "accessibility" | "paste" | "typing";
+
+// Inserts through the target app's chain and resolves to the strategy that worked; an
+// Accessibility write only counts once the text reads back from the field. renderMarkdown
+// pastes Markdown formatted (where the app takes rich text) and strips its markers otherwise
+export async function injectText(
+  text: string,
+  renderMarkdown?: boolean
+): Promise<InjectionStrategy> {
+  return invoke<InjectionStrategy>("inject_text", { text, renderMarkdown });
+}
+
+export interface AppInjectionStrategy {
+  // Bundle id, or a prefix ending in "."
+  bundle_id: string;
+  chain: InjectionStrategy[];
+}
+
+// Built-in chains; the user's entries win over them
+export interface InjectionStrategies {
+  default_chain: InjectionStrategy[];
+  builtin: AppInjectionStrategy[];
+  user: AppInjectionStrategy[];
+}
+
+export async function getInjectionStrategies(): Promise<InjectionStrategies> {
+  return invoke<InjectionStrategies>("get_injection_strategies");
+}
+
+export async function setAppInjectionStrategy(
+  bundleId: string,
+  chain: InjectionStrategy[]
+): Promise<InjectionStrategies> {
+  return invoke<InjectionStrategies>("set_app_injection_strategy", {
+    bundleId,
+    chain,
+  });
+}
+
+export async function removeAppInjectionStrategy(
+  bundleId: string
+): Promise<InjectionStrategies> {
+  return invoke<InjectionStrategies>("remove_app_injection_strategy", {
+    bundleId,
+  });
+}
+
+// Typing injection's pacing: humanized, it types a character at a time at about
+// chars_per_second, each pause varying by up to jitter_percent (unless delayMs is given)
+export interface TypingPace {
+  humanized: boolean;
+  chars_per_second: number;
+  jitter_percent: number;
+}
+
+export async function getTypingPace(): Promise<TypingPace> {
+  return invoke<TypingPace>("get_typing_pace");
+}
+
+export async function setTypingPace(pace: TypingPace): Promise<void> {
+  return invoke("set_typing_pace", { pace });
+}
+
+// Take the last insertion into the focused field back out, restoring what it replaced; rejects
+// when the field was edited since
+export async function undoLastInjection(): Promise<void> {
+  return invoke("undo_last_injection");
+}
+
+// Streaming injection: write a response into the field as it generates. Chunks are batched, and a
+// trailing partial word is held back until the next one (or endStream) so filtering sees it whole
+export async function startStreamInjection(): Promise<void> {
+  return invoke("start_stream_injection");
+}
+
+export async function pushChunk(chunk: string): Promise<void> {
+  return invoke("push_chunk", { chunk });
+}
+
+// Resolves with how many characters were written in all
+export async function endStream(): Promise<number> {
+  return invoke<number>("end_stream");
+}
+
+// Which ways of writing should work in the field a response would go to
+export interface InjectionCapabilities {
+  // AXSelectedText: insert at the caret
+  selected_text: boolean;
+  // AXValue: replace the whole value
+  value: boolean;
+  paste: boolean;
+  typing: boolean;
+}
+
+export async function probeInjectionCapabilities(): Promise<InjectionCapabilities> {
+  return invoke<InjectionCapabilities>("probe_injection_capabilities");
 }
 
 // Keychain
@@ -34,16 +349,956 @@ export async function deleteApiKey(): Promise<void> {
   return invoke("delete_api_key");
 }
 
+// API key rotation: the primary key has id "default"; others are pooled
+export type RotationMode = "round_robin" | "failover";
+
+export interface KeyUsage {
+  requests: number;
+  successes: number;
+  rate_limited: number;
+  server_errors: number;
+  other_errors: number;
+  last_used: string | null;
+  cooling_down: boolean;
+}
+
+export interface ApiKeyInfo {
+  id: string;
+  label: string;
+  hint: string;
+  usage: KeyUsage;
+}
+
+export interface RotatingKey {
+  id: string;
+  key: string;
+}
+
+export async function listApiKeys(): Promise<ApiKeyInfo[]> {
+  return invoke<ApiKeyInfo[]>("list_api_keys");
+}
+
+export async function addApiKey(
+  label: string,
+  key: string
+): Promise<ApiKeyInfo> {
+  return invoke<ApiKeyInfo>("add_api_key", { label, key });
+}
+
+export async function removeApiKey(id: string): Promise<void> {
+  return invoke("remove_api_key", { id });
+}
+
+export async function getKeyRotationMode(): Promise<RotationMode> {
+  return invoke<RotationMode>("get_key_rotation_mode");
+}
+
+export async function setKeyRotationMode(mode: RotationMode): Promise<void> {
+  return invoke("set_key_rotation_mode", { mode });
+}
+
+export async function acquireApiKeys(): Promise<RotatingKey[]> {
+  return invoke<RotatingKey[]>("acquire_api_keys");
+}
+
+// status is null when the request never got a response
+export async function reportApiKeyResult(
+  id: string,
+  status: number | null
+): Promise<void> {
+  return invoke("report_api_key_result", { id, status });
+}
+
 // Keystroke monitoring
+// Starting again replaces the running monitor, picking up new configuration
 export async function startMonitoring(): Promise<void> {
-  return invoke("start_monitoring");
+  return invoke("start_monitoring_command");
 }
 
 export async function stopMonitoring(): Promise<void> {
   return invoke("stop_monitoring");
 }
 
+// Typed sequences that open the overlay, e.g. ["//", "::ai"]; [] restores "/"
+export async function getTriggerSequences(): Promise<string[]> {
+  return invoke<string[]>("get_trigger_sequences");
+}
+
+export async function setTriggerSequences(sequences: string[]): Promise<void> {
+  return invoke("set_trigger_sequences", { sequences });
+}
+
+// "sequence": typing a trigger sequence opens the overlay; "chord": only a chord like "cmd+shift+space";
+// "hold": holding a key (macOS only), while a quick tap still types it; "modifier": tapping Fn or
+// a Hyper key on its own (macOS only)
+export type ActivationMode = "sequence" | "chord" | "hold" | "modifier";
+
+// Fn / Globe, or Control+Option+Shift+Command at once (Caps Lock as Hyper)
+export type ModifierKey = "fn" | "hyper";
+
+export interface ActivationConfig {
+  mode: ActivationMode;
+  chords: string[];
+  // Keys typed further apart than this don't build up a sequence; null for no limit
+  timeout_ms?: number | null;
+  // Hold mode: the key to hold ("/" when null) and for how long (150-2000 ms, 400 when null)
+  hold_key?: string | null;
+  hold_ms?: number | null;
+  // Modifier mode: the modifier tapped on its own ("fn" when null)
+  modifier_key?: ModifierKey | null;
+  // Triggers this soon after the previous one are ignored (300 ms when null, 0 turns it off)
+  debounce_ms?: number | null;
+}
+
+export async function getActivationConfig(): Promise<ActivationConfig> {
+  return invoke<ActivationConfig>("get_activation_config");
+}
+
+export async function setActivationConfig(
+  config: ActivationConfig
+): Promise<void> {
+  return invoke("set_activation_config", { config });
+}
+
+// Listen-only: trigger keys are never swallowed (applies when monitoring restarts)
+export async function setListenOnly(enabled: boolean): Promise<void> {
+  return invoke("set_listen_only", { enabled });
+}
+
+export async function getListenOnly(): Promise<boolean> {
+  return invoke<boolean>("get_listen_only");
+}
+
+// "deny": trigger everywhere except these apps; "allow": only inside them
+export type AppFilterMode = "deny" | "allow";
+
+export interface AppFilter {
+  mode: AppFilterMode;
+  // Bundle ids, or prefixes ending in "."
+  apps: string[];
+}
+
+export async function getTriggerAppFilter(): Promise<AppFilter> {
+  return invoke<AppFilter>("get_trigger_app_filter");
+}
+
+export async function setTriggerAppFilter(
+  filter: AppFilter
+): Promise<AppFilter> {
+  return invoke<AppFilter>("set_trigger_app_filter", { filter });
+}
+
+// Abbreviations replaced with their expansion as soon as they are typed, e.g. ":sig"
+export interface Snippet {
+  abbreviation: string;
+  expansion: string;
+}
+
+export async function getSnippets(): Promise<Snippet[]> {
+  return invoke<Snippet[]>("get_snippets");
+}
+
+export async function setSnippets(snippets: Snippet[]): Promise<void> {
+  return invoke("set_snippets", { snippets });
+}
+
+// User-configured triggers, each opening the overlay routed to an action
+export type NamedTriggerKind = "chord" | "typed";
+
+export interface NamedTrigger {
+  name: string;
+  kind: NamedTriggerKind;
+  // "cmd+shift+t" for a chord, "::fix" for typed text
+  keys: string;
+  // User action id; null opens the plain prompt overlay
+  action: string | null;
+}
+
+export async function getNamedTriggers(): Promise<NamedTrigger[]> {
+  return invoke<NamedTrigger[]>("get_named_triggers");
+}
+
+export async function setNamedTriggers(
+  triggers: NamedTrigger[]
+): Promise<void> {
+  return invoke("set_named_triggers", { triggers });
+}
+
+// Clicks that open the overlay pre-filled with the selected text (macOS only)
+export interface MouseTriggers {
+  triple_click: boolean;
+  middle_click: boolean;
+}
+
+export async function getMouseTriggers(): Promise<MouseTriggers> {
+  return invoke<MouseTriggers>("get_mouse_triggers");
+}
+
+export async function setMouseTriggers(
+  triggers: MouseTriggers
+): Promise<void> {
+  return invoke("set_mouse_triggers", { triggers });
+}
+
+// Trackpad gesture that opens the overlay at the cursor (macOS only); "three_finger_tap" needs
+// "Look up & data detectors" set to it in Trackpad settings
+export type Gesture = "off" | "force_click" | "three_finger_tap";
+
+export async function getGestureTrigger(): Promise<Gesture> {
+  return invoke<Gesture>("get_gesture_trigger");
+}
+
+export async function setGestureTrigger(gesture: Gesture): Promise<void> {
+  return invoke("set_gesture_trigger", { gesture });
+}
+
+// What the overlay opens next to (macOS only): the caret, or the side of the focused window
+export type PlacementMode = "caret" | "window_edge";
+
+export async function getOverlayPlacement(): Promise<PlacementMode> {
+  return invoke<PlacementMode>("get_overlay_placement");
+}
+
+export async function setOverlayPlacement(mode: PlacementMode): Promise<void> {
+  return invoke("set_overlay_placement", { mode });
+}
+
+// Chord like "cmd+shift+r" that opens the overlay with the selection; null when unset
+export async function getSelectionHotkey(): Promise<string | null> {
+  return invoke<string | null>("get_selection_hotkey");
+}
+
+export async function setSelectionHotkey(hotkey: string | null): Promise<void> {
+  return invoke("set_selection_hotkey", { hotkey });
+}
+
+// Monitor status events: "monitor-started" and "monitor-stopped" (no payload), "monitor-error"
+// (a PromptOsError from starting), "monitor-permission-lost" (no payload), and
+// "monitor-tap-disabled" (MonitorHealth), "monitor-fallback" (ShortcutFallback), and
+// "monitor-restarted" (MonitorRestart)
+export type MonitorState =
+  | "stopped"
+  | "running"
+  | "failed"
+  | "permission_lost"
+  | "shortcuts_only";
+
+// The monitor died and was started again by the watchdog
+export interface MonitorRestart {
+  restarts: number;
+}
+
+// Without Accessibility access only the overlay chords work, as global shortcuts
+export interface ShortcutFallback {
+  reason: string;
+  shortcuts: string[];
+}
+
+// The state as of the last status event, for windows opened since
+export async function getMonitorState(): Promise<MonitorState> {
+  return invoke<MonitorState>("get_monitor_state");
+}
+
+// Payload of "monitor-tap-disabled": macOS switched the event tap off and it was re-enabled
+export interface MonitorHealth {
+  reason: "timeout" | "user_input";
+  recoveries: number;
+}
+
+// The "secure-input-active" event carries a boolean: true while a password prompt has secure
+// keyboard entry on (triggers are paused and the overlay is hidden), false once it is off
+
+// Who has secure keyboard entry on; it stays on until that app turns it off or quits
+export interface SecureInputStatus {
+  active: boolean;
+  pid: number | null;
+  app: FrontmostApp | null;
+}
+
+export async function checkSecureInput(): Promise<SecureInputStatus> {
+  return invoke<SecureInputStatus>("check_secure_input");
+}
+
 // Permission check
 export async function checkAccessibilityPermission(): Promise<boolean> {
   return invoke<boolean>("check_accessibility_permission");
 }
+
+export type Permission = "accessibility" | "screen_recording";
+
+// clipboard_only: a trigger permission is missing, so responses are copied instead of injected
+export interface PermissionStatus {
+  accessibility: boolean;
+  screen_recording: boolean;
+  clipboard_only: boolean;
+}
+
+export async function getPermissionStatus(): Promise<PermissionStatus> {
+  return invoke<PermissionStatus>("get_permission_status");
+}
+
+// Shows the system Accessibility prompt; "permission-status" follows once access is granted
+export async function requestAccessibilityPermission(): Promise<boolean> {
+  return invoke<boolean>("request_accessibility_permission");
+}
+
+// Deep-links to the permission's pane in System Settings > Privacy & Security
+export async function openPermissionSettings(
+  permission: Permission
+): Promise<void> {
+  return invoke("open_permission_settings", { permission });
+}
+
+// Text-to-speech read-back
+export interface SpeechVoice {
+  identifier: string;
+  name: string;
+  language: string;
+}
+
+export interface SpeechStatus {
+  speaking: boolean;
+  paused: boolean;
+}
+
+export async function speakText(
+  text: string,
+  voice?: string,
+  rate?: number
+): Promise<void> {
+  return invoke("speak_text", { text, voice, rate });
+}
+
+export async function pauseSpeech(): Promise<void> {
+  return invoke("pause_speech");
+}
+
+export async function resumeSpeech(): Promise<void> {
+  return invoke("resume_speech");
+}
+
+export async function stopSpeech(): Promise<void> {
+  return invoke("stop_speech");
+}
+
+export async function getSpeechStatus(): Promise<SpeechStatus> {
+  return invoke<SpeechStatus>("get_speech_status");
+}
+
+export async function listSpeechVoices(): Promise<SpeechVoice[]> {
+  return invoke<SpeechVoice[]>("list_speech_voices");
+}
+
+// Quick-transform hotkey (Ctrl+Option+R rewrites the selection in place)
+export async function getQuickAction(): Promise<string> {
+  return invoke<string>("get_quick_action");
+}
+
+export async function setQuickAction(instruction: string | null): Promise<void> {
+  return invoke("set_quick_action", { instruction });
+}
+
+// Prompt context
+export interface FrontmostApp {
+  bundle_id: string | null;
+  name: string | null;
+  pid: number;
+}
+
+export interface MailMessage {
+  sender: string | null;
+  subject: string | null;
+  body: string;
+  source: "apple-events" | "accessibility";
+}
+
+export interface EditorContext {
+  file_path: string | null;
+  file_name: string | null;
+  language: string | null;
+}
+
+export interface DetectedLanguage {
+  code: string;
+  name: string;
+  confidence: number;
+}
+
+export interface DateContext {
+  now: string;
+  weekday: string;
+  timezone: string;
+  utc_offset_minutes: number;
+}
+
+export interface CalendarEvent {
+  title: string;
+  start: string;
+  end: string;
+  all_day: boolean;
+  location: string | null;
+}
+
+// File or page open in the target app's focused window
+export interface WindowDocument {
+  // e.g. "file:///Users/me/report.md" or a web page's address
+  url: string;
+  // Local path, for file URLs
+  path: string | null;
+  // Last path segment, e.g. "report.md"
+  name: string | null;
+}
+
+export interface PromptContext {
+  app: FrontmostApp | null;
+  reply_to: MailMessage | null;
+  editor: EditorContext | null;
+  document: WindowDocument | null;
+  language: DetectedLanguage | null;
+  respond_in_language: string | null;
+  style_summary: string | null;
+  typed_before: string | null;
+  // OCR of a field Accessibility can't read; approximate
+  screen_text: RecognizedText | null;
+  date: DateContext;
+  calendar_events: CalendarEvent[];
+}
+
+export async function collectPromptContext(): Promise<PromptContext> {
+  return invoke<PromptContext>("collect_prompt_context");
+}
+
+// Null when the window has nothing open, or the app is blocklisted
+export async function getFocusedDocument(): Promise<WindowDocument | null> {
+  return invoke<WindowDocument | null>("get_focused_document");
+}
+
+export async function getFrontmostApp(): Promise<FrontmostApp> {
+  return invoke<FrontmostApp>("get_frontmost_app");
+}
+
+// Date & calendar
+export async function getDateContext(): Promise<DateContext> {
+  return invoke<DateContext>("get_date_context");
+}
+
+export async function checkCalendarPermission(): Promise<boolean> {
+  return invoke<boolean>("check_calendar_permission");
+}
+
+export async function requestCalendarPermission(): Promise<void> {
+  return invoke("request_calendar_permission");
+}
+
+export async function getCalendarEvents(
+  daysAhead?: number
+): Promise<CalendarEvent[]> {
+  return invoke<CalendarEvent[]>("get_calendar_events", { daysAhead });
+}
+
+// Local spelling & grammar check (offsets are UTF-16, like JS strings)
+export interface TextIssue {
+  kind: "spelling" | "grammar";
+  start: number;
+  length: number;
+  text: string;
+  message: string | null;
+  suggestions: string[];
+}
+
+export interface TextCheckResult {
+  issues: TextIssue[];
+  corrected: string;
+}
+
+export async function checkText(text: string): Promise<TextCheckResult> {
+  return invoke<TextCheckResult>("check_text", { text });
+}
+
+// PII redaction
+export interface RedactionConfig {
+  enabled: boolean;
+  emails: boolean;
+  phone_numbers: boolean;
+  credit_cards: boolean;
+  custom_patterns: string[];
+  named_entities: boolean;
+}
+
+export interface Replacement {
+  placeholder: string;
+  original: string;
+}
+
+export interface RedactedText {
+  text: string;
+  replacements: Replacement[];
+}
+
+export async function getRedactionConfig(): Promise<RedactionConfig> {
+  return invoke<RedactionConfig>("get_redaction_config");
+}
+
+export async function setRedactionConfig(
+  config: RedactionConfig
+): Promise<void> {
+  return invoke("set_redaction_config", { config });
+}
+
+export async function redactText(text: string): Promise<RedactedText> {
+  return invoke<RedactedText>("redact_text", { text });
+}
+
+export async function redactTexts(
+  texts: string[]
+): Promise<[string[], Replacement[]]> {
+  return invoke<[string[], Replacement[]]>("redact_texts", { texts });
+}
+
+export async function restoreRedacted(
+  text: string,
+  replacements: Replacement[]
+): Promise<string> {
+  return invoke<string>("restore_redacted", { text, replacements });
+}
+
+// Response content filter
+export interface ContentFilterConfig {
+  enabled: boolean;
+  profanity: boolean;
+  banned_patterns: string[];
+  max_length: number | null;
+  action: "sanitize" | "block";
+}
+
+export interface FilterResult {
+  blocked: boolean;
+  text: string;
+  reasons: string[];
+}
+
+export async function getContentFilterConfig(): Promise<ContentFilterConfig> {
+  return invoke<ContentFilterConfig>("get_content_filter_config");
+}
+
+export async function setContentFilterConfig(
+  config: ContentFilterConfig
+): Promise<void> {
+  return invoke("set_content_filter_config", { config });
+}
+
+export async function filterResponse(text: string): Promise<FilterResult> {
+  return invoke<FilterResult>("filter_response", { text });
+}
+
+// Language detection & translation
+export interface TranslationLanguage {
+  code: string;
+  name: string;
+}
+
+export async function detectTextLanguage(
+  text: string
+): Promise<DetectedLanguage | null> {
+  return invoke<DetectedLanguage | null>("detect_text_language", { text });
+}
+
+export async function getFieldLanguage(): Promise<DetectedLanguage | null> {
+  return invoke<DetectedLanguage | null>("get_field_language");
+}
+
+export async function setAutoLanguageMode(enabled: boolean): Promise<void> {
+  return invoke("set_auto_language_mode", { enabled });
+}
+
+export async function getAutoLanguageMode(): Promise<boolean> {
+  return invoke<boolean>("get_auto_language_mode");
+}
+
+export async function listTranslationLanguages(): Promise<
+  TranslationLanguage[]
+> {
+  return invoke<TranslationLanguage[]>("list_translation_languages");
+}
+
+export async function translateText(
+  text: string,
+  targetLanguage: string
+): Promise<string> {
+  return invoke<string>("translate_text", { text, targetLanguage });
+}
+
+// History
+export interface HistoryEntry {
+  id: number;
+  timestamp: string;
+  prompt: string;
+  response: string;
+  app_bundle_id: string | null;
+  app_name: string | null;
+}
+
+export async function recordHistoryEntry(
+  prompt: string,
+  response: string
+): Promise<HistoryEntry> {
+  return invoke<HistoryEntry>("record_history_entry", { prompt, response });
+}
+
+export async function getHistory(limit?: number): Promise<HistoryEntry[]> {
+  return invoke<HistoryEntry[]>("get_history", { limit });
+}
+
+export async function clearHistory(): Promise<void> {
+  return invoke("clear_history");
+}
+
+// Presets and fuzzy prompt suggestions
+export interface Preset {
+  id: string;
+  label: string;
+  prompt: string;
+}
+
+export async function listPresets(): Promise<Preset[]> {
+  return invoke<Preset[]>("list_presets");
+}
+
+// User actions: one TOML/YAML file each in the actions directory, reloaded on change
+export type PostProcess = "trim" | "strip_quotes" | "strip_code_fences";
+
+export interface Action {
+  id: string;
+  label: string;
+  template: string;
+  model: string | null;
+  params: {
+    temperature: number | null;
+    top_p: number | null;
+    max_output_tokens: number | null;
+  };
+  post_process: PostProcess[];
+  hotkey: string | null;
+  file: string;
+}
+
+// Also the payload of the "actions-changed" event
+export interface ActionsSnapshot {
+  directory: string;
+  actions: Action[];
+  errors: { file: string; message: string }[];
+}
+
+export async function listActions(): Promise<ActionsSnapshot> {
+  return invoke<ActionsSnapshot>("list_actions");
+}
+
+export async function reloadActions(): Promise<ActionsSnapshot> {
+  return invoke<ActionsSnapshot>("reload_actions");
+}
+
+export interface PromptSuggestion {
+  kind: "preset" | "history";
+  text: string;
+  label: string | null;
+  history_id: number | null;
+  score: number;
+  /** Matched character positions in the label (presets) or text (history) */
+  positions: number[];
+}
+
+export async function fuzzySearchPrompts(
+  query: string,
+  limit?: number
+): Promise<PromptSuggestion[]> {
+  return invoke<PromptSuggestion[]>("fuzzy_search_prompts", { query, limit });
+}
+
+export interface FuzzyMatch {
+  /** Index into the candidate list passed in */
+  index: number;
+  score: number;
+  /** Matched character positions, ascending */
+  positions: number[];
+}
+
+export async function fuzzyRank(
+  candidates: string[],
+  query: string,
+  limit?: number
+): Promise<FuzzyMatch[]> {
+  return invoke<FuzzyMatch[]>("fuzzy_rank", { candidates, query, limit });
+}
+
+// Sensitive-app blocklist
+export interface BlocklistInfo {
+  builtin: string[];
+  remote_version: number | null;
+  remote: string[];
+  user: string[];
+  updates_available: boolean;
+}
+
+export async function getBlocklist(): Promise<BlocklistInfo> {
+  return invoke<BlocklistInfo>("get_blocklist");
+}
+
+export async function addBlockedApp(bundleId: string): Promise<BlocklistInfo> {
+  return invoke<BlocklistInfo>("add_blocked_app", { bundleId });
+}
+
+export async function removeBlockedApp(
+  bundleId: string
+): Promise<BlocklistInfo> {
+  return invoke<BlocklistInfo>("remove_blocked_app", { bundleId });
+}
+
+export async function updateBlocklist(url?: string): Promise<BlocklistInfo> {
+  return invoke<BlocklistInfo>("update_blocklist", { url });
+}
+
+// Clipboard-change suggestions
+export interface ClipboardSuggestion {
+  text: string;
+  preview: string;
+  char_count: number;
+  actions: string[];
+  source_app: string | null;
+}
+
+export async function setClipboardSuggestionsEnabled(
+  enabled: boolean
+): Promise<void> {
+  return invoke("set_clipboard_suggestions_enabled", { enabled });
+}
+
+export async function getClipboardSuggestionsEnabled(): Promise<boolean> {
+  return invoke<boolean>("get_clipboard_suggestions_enabled");
+}
+
+// Focus / Do Not Disturb awareness; "focus-paused" carries a boolean when pausing starts or ends
+export interface FocusPauseConfig {
+  enabled: boolean;
+  pause_when_presenting: boolean;
+  /** Focus names or identifiers; empty means any Focus */
+  focus_modes: string[];
+  /** Per-app override by bundle id: whether that app in full screen pauses */
+  full_screen_apps: Record<string, boolean>;
+}
+
+export interface FocusState {
+  focused: boolean;
+  mode: string | null;
+  mode_identifier: string | null;
+  presenting: boolean;
+  paused: boolean;
+}
+
+export async function getFocusPauseConfig(): Promise<FocusPauseConfig> {
+  return invoke<FocusPauseConfig>("get_focus_pause_config");
+}
+
+export async function setFocusPauseConfig(
+  config: FocusPauseConfig
+): Promise<void> {
+  return invoke("set_focus_pause_config", { config });
+}
+
+export async function getFocusState(): Promise<FocusState> {
+  return invoke<FocusState>("get_focus_state");
+}
+
+// Power-aware throttling
+export interface PowerStatus {
+  profile: "normal" | "battery" | "low_power";
+  on_battery: boolean;
+  low_power_mode: boolean;
+  poll_factor: number;
+  deferring_writes: boolean;
+}
+
+export async function getPowerStatus(): Promise<PowerStatus> {
+  return invoke<PowerStatus>("get_power_status");
+}
+
+// Latency benchmark
+export interface StageTiming {
+  samples: number;
+  min_ms: number;
+  avg_ms: number;
+  max_ms: number;
+  error: PromptOsError | null;
+}
+
+export interface BenchmarkReport {
+  app: FrontmostApp | null;
+  ax_focus_lookup: StageTiming;
+  context_collection: StageTiming;
+  provider: {
+    time_to_first_token_ms: number | null;
+    total_ms: number | null;
+    error: PromptOsError | null;
+  };
+  injection: {
+    timing: StageTiming;
+    chars: number;
+    chars_per_second: number | null;
+  };
+}
+
+export async function runBenchmark(): Promise<BenchmarkReport> {
+  return invoke<BenchmarkReport>("run_benchmark");
+}
+
+// Opt-in anonymized telemetry
+export interface TelemetryConfig {
+  enabled: boolean;
+  endpoint: string | null;
+}
+
+export interface TelemetryBatch {
+  app_version: string;
+  os: string;
+  period_start: string;
+  counts: Record<string, number>;
+}
+
+export async function getTelemetryConfig(): Promise<TelemetryConfig> {
+  return invoke<TelemetryConfig>("get_telemetry_config");
+}
+
+export async function setTelemetryConfig(
+  config: TelemetryConfig
+): Promise<void> {
+  return invoke("set_telemetry_config", { config });
+}
+
+export async function previewTelemetry(): Promise<TelemetryBatch | null> {
+  return invoke<TelemetryBatch | null>("preview_telemetry");
+}
+
+export async function flushTelemetry(): Promise<void> {
+  return invoke("flush_telemetry");
+}
+
+// Provider quota
+export interface QuotaStatus {
+  remaining_requests: number | null;
+  limit_requests: number | null;
+  remaining_tokens: number | null;
+  limit_tokens: number | null;
+  reset_at: string | null;
+  retry_after_seconds: number | null;
+  rate_limited: boolean;
+  low: boolean;
+  updated_at: string | null;
+}
+
+export async function getQuotaStatus(): Promise<QuotaStatus | null> {
+  return invoke<QuotaStatus | null>("get_quota_status");
+}
+
+export async function reportQuotaHeaders(
+  headers: Record<string, string>,
+  status: number,
+  body?: string
+): Promise<QuotaStatus> {
+  return invoke<QuotaStatus>("report_quota_headers", { headers, status, body });
+}
+
+// Overlay sessions
+export interface SessionTurn {
+  timestamp: string;
+  prompt: string;
+  response: string;
+}
+
+export interface Session {
+  id: number;
+  started_at: string;
+  context: PromptContext;
+  turns: SessionTurn[];
+}
+
+export async function startSession(): Promise<Session> {
+  return invoke<Session>("start_session");
+}
+
+export async function addSessionTurn(
+  sessionId: number,
+  prompt: string,
+  response: string
+): Promise<void> {
+  return invoke("add_session_turn", { sessionId, prompt, response });
+}
+
+export async function exportSession(
+  sessionId: number,
+  format: "markdown" | "json"
+): Promise<string> {
+  return invoke<string>("export_session", { sessionId, format });
+}
+
+// Injects into the field the session started from, focusing it again first and then writing
+// the way injectText does; resolves to the strategy that worked
+export async function injectIntoOrigin(
+  sessionId: number,
+  text: string,
+  renderMarkdown?: boolean
+): Promise<InjectionStrategy> {
+  return invoke<InjectionStrategy>("inject_into_origin", {
+    sessionId,
+    text,
+    renderMarkdown,
+  });
+}
+
+// Per-app style memory
+export interface StyleProfile {
+  app_bundle_id: string;
+  sample_count: number;
+  formality: number;
+  emoji_rate: number;
+  average_words: number;
+  sign_off: string | null;
+  summary: string;
+}
+
+export async function setStyleMemoryEnabled(enabled: boolean): Promise<void> {
+  return invoke("set_style_memory_enabled", { enabled });
+}
+
+export async function getStyleMemoryEnabled(): Promise<boolean> {
+  return invoke<boolean>("get_style_memory_enabled");
+}
+
+export async function getStyleProfile(
+  appBundleId: string
+): Promise<StyleProfile | null> {
+  return invoke<StyleProfile | null>("get_style_profile", { appBundleId });
+}
+
+// Typing context: the last characters typed into the field before a trigger (opt-in)
+export interface TypingContextConfig {
+  enabled: boolean;
+  max_chars: number;
+}
+
+export async function getTypingContextConfig(): Promise<TypingContextConfig> {
+  return invoke<TypingContextConfig>("get_typing_context_config");
+}
+
+export async function setTypingContextConfig(
+  config: TypingContextConfig
+): Promise<void> {
+  return invoke("set_typing_context_config", { config });
+}
+
+// What is recorded right now; null when off or nothing was typed
+export async function readTypingContext(): Promise<string | null> {
+  return invoke<string | null>("read_typing_context");
+}
+
+export async function purgeTypingContext(): Promise<void> {
+  return invoke("purge_typing_context");
+}
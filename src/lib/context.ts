@@ -0,0 +1,87 @@
+import type { PromptContext } from "./commands";
+
+// Turn native prompt context into a system prompt for Gemini
+export function buildSystemPrompt(context: PromptContext | null): string | undefined {
+  if (!context) return undefined;
+
+  const sections: string[] = [];
+
+  const { date } = context;
+  sections.push(
+    `Current date and time: ${date.weekday}, ${date.now} (${date.timezone}).`
+  );
+
+  if (context.calendar_events.length > 0) {
+    const events = context.calendar_events
+      .map((e) =>
+        e.all_day
+          ? `- ${e.title} (all day)`
+          : `- ${e.title}: ${e.start} to ${e.end}`
+      )
+      .join("\n");
+    sections.push(`The user's calendar for today:\n${events}`);
+  }
+
+  if (context.app?.name) {
+    sections.push(`The user is writing in ${context.app.name}.`);
+  }
+
+  const editor = context.editor;
+  if (editor) {
+    const file = editor.file_path ?? editor.file_name;
+    sections.push(
+      `The user is editing ${file}` +
+        (editor.language ? ` (${editor.language}). Write code in ${editor.language}.` : ".")
+    );
+  } else if (context.document) {
+    const { url, path, name } = context.document;
+    sections.push(
+      path ? `The user is editing ${name ?? path}.` : `The user is on ${url}.`
+    );
+  }
+
+  const reply = context.reply_to;
+  if (reply) {
+    const header = [
+      reply.sender ? `From: ${reply.sender}` : null,
+      reply.subject ? `Subject: ${reply.subject}` : null,
+    ]
+      .filter(Boolean)
+      .join("\n");
+
+    sections.push(
+      "The user is replying to this email. Use it as context when drafting a reply:\n" +
+        (header ? `${header}\n\n` : "") +
+        reply.body
+    );
+  }
+
+  if (context.style_summary) {
+    sections.push(`Match the user's usual style. ${context.style_summary}`);
+  }
+
+  if (context.typed_before) {
+    sections.push(
+      `The user typed this just before asking:\n${context.typed_before}`
+    );
+  }
+
+  if (context.screen_text) {
+    sections.push(
+      "Text recognized on screen in the field (OCR, so it may contain errors):\n" +
+        context.screen_text.text
+    );
+  }
+
+  if (context.respond_in_language) {
+    sections.push(`Always respond in ${context.respond_in_language}.`);
+  } else if (context.language) {
+    // Without auto-language mode the field's language is only a default
+    const { name } = context.language;
+    sections.push(
+      `The text in the field is in ${name}. Respond in ${name} unless asked otherwise.`
+    );
+  }
+
+  return sections.length > 0 ? sections.join("\n\n") : undefined;
+}
@@ -0,0 +1,52 @@
+// Every Tauri command rejects with { code, message } (PromptOsError in Rust)
+export type ErrorCode =
+  | "no_permission"
+  | "no_focus"
+  | "secure_field"
+  | "focus_changed"
+  | "provider_auth"
+  | "rate_limited"
+  | "timeout"
+  | "blocked"
+  | "provider"
+  | "internal";
+
+export interface PromptOsError {
+  code: ErrorCode;
+  message: string;
+}
+
+export function isPromptOsError(err: unknown): err is PromptOsError {
+  return (
+    typeof err === "object" &&
+    err !== null &&
+    typeof (err as PromptOsError).code === "string" &&
+    typeof (err as PromptOsError).message === "string"
+  );
+}
+
+// What the user can do about it; falls back to the raw message
+export function errorRemediation(err: unknown): string {
+  if (!isPromptOsError(err)) {
+    return err instanceof Error ? err.message : String(err);
+  }
+
+  switch (err.code) {
+    case "no_permission":
+      return "Grant Prompt OS Accessibility access in System Settings > Privacy & Security, then try again.";
+    case "no_focus":
+      return "Click into a text field, then try again.";
+    case "secure_field":
+      return "Prompt OS never types into password fields. Copy the text instead.";
+    case "focus_changed":
+      return `${err.message}. Click back into the field you started from and insert again.`;
+    case "provider_auth":
+      return "Check your Gemini API key in Settings.";
+    case "rate_limited":
+      return "Gemini is rate limiting requests. Wait a moment and retry.";
+    case "timeout":
+      return "Gemini took too long to respond. Check your connection and retry.";
+    default:
+      return err.message;
+  }
+}
@@ -1,4 +1,11 @@
-import { retrieveApiKey } from "./commands";
+import {
+  acquireApiKeys,
+  redactTexts,
+  reportApiKeyResult,
+  reportQuotaHeaders,
+} from "./commands";
+import { isPromptOsError } from "./errors";
+import { createRestoringSink } from "./redaction";
 
 const BASE_URL =
   "https://generativelanguage.googleapis.com/v1beta/models/gemini-3-flash-preview:streamGenerateContent";
@@ -14,37 +21,83 @@ export class GeminiError extends Error {
   }
 }
 
+// Only headers exposed via CORS are visible here; 429 bodies still carry a retry delay
+function reportQuota(res: Response, body?: string) {
+  const headers: Record<string, string> = {};
+  res.headers.forEach((value, name) => {
+    headers[name] = value;
+  });
+  reportQuotaHeaders(headers, res.status, body).catch((err) =>
+    console.error("[Quota] Failed to report:", err)
+  );
+}
+
+function reportKeyResult(id: string, status: number | null) {
+  reportApiKeyResult(id, status).catch((err) =>
+    console.error("[Gemini] Failed to report key usage:", err)
+  );
+}
+
 export async function streamGemini(
   prompt: string,
   onChunk: (text: string) => void,
   signal?: AbortSignal,
   systemPrompt?: string
 ): Promise<void> {
-  const apiKey = await retrieveApiKey();
-  if (!apiKey) {
-    throw new GeminiError("No API key configured. Add it in Settings.");
+  let keys;
+  try {
+    keys = await acquireApiKeys();
+  } catch (err) {
+    // A locked or unreadable keychain fails here too, not only a missing key
+    throw new GeminiError(isPromptOsError(err) ? err.message : String(err));
   }
 
+  // Strip PII natively before anything leaves the machine
+  const [[redactedPrompt, redactedSystemPrompt], replacements] =
+    await redactTexts([prompt, systemPrompt ?? ""]);
+  const sink = createRestoringSink(replacements, onChunk);
+
   const body: Record<string, unknown> = {
-    contents: [{ parts: [{ text: prompt }] }],
+    contents: [{ parts: [{ text: redactedPrompt }] }],
   };
 
   if (systemPrompt) {
-    body.systemInstruction = { parts: [{ text: systemPrompt }] };
+    body.systemInstruction = { parts: [{ text: redactedSystemPrompt }] };
   }
 
   console.log(`[Gemini] Requesting: ${BASE_URL}`);
   console.log(`[Gemini] Model: gemini-3-flash-preview`);
 
-  const res = await fetch(`${BASE_URL}?key=${apiKey}&alt=sse`, {
-    method: "POST",
-    headers: { "Content-Type": "application/json" },
-    body: JSON.stringify(body),
-    signal,
-  });
+  // Try each key in rotation order, moving on after a 429 or 5xx
+  let res: Response | undefined;
+  let errorText = "";
+  for (const [index, { id, key }] of keys.entries()) {
+    try {
+      res = await fetch(`${BASE_URL}?key=${key}&alt=sse`, {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify(body),
+        signal,
+      });
+    } catch (err) {
+      reportKeyResult(id, null);
+      throw err;
+    }
+    reportKeyResult(id, res.status);
+    if (res.ok) break;
+
+    errorText = await res.text();
+    reportQuota(res, errorText);
+    const failOver = res.status === 429 || res.status >= 500;
+    if (!failOver || index === keys.length - 1) break;
+    console.warn(`[Gemini] Key returned ${res.status}, trying the next key`);
+  }
+
+  if (!res) {
+    throw new GeminiError("No API key configured. Add it in Settings.");
+  }
 
   if (!res.ok) {
-    const errorText = await res.text();
     console.error(`[Gemini] API Error ${res.status}:`, errorText);
 
     if (res.status === 429) {
@@ -59,6 +112,8 @@ export async function streamGemini(
     throw new GeminiError(`Gemini API error ${res.status}: ${errorText}`);
   }
 
+  reportQuota(res);
+
   const reader = res.body?.getReader();
   if (!reader) throw new GeminiError("No response body");
 
@@ -78,11 +133,13 @@ export async function streamGemini(
         try {
           const json = JSON.parse(line.slice(6));
           const text = json?.candidates?.[0]?.content?.parts?.[0]?.text;
-          if (text) onChunk(text);
+          if (text) sink.push(text);
         } catch {
           // Skip malformed SSE lines
         }
       }
     }
   }
+
+  sink.flush();
 }
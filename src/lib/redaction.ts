@@ -0,0 +1,47 @@
+import type { Replacement } from "./commands";
+
+// Placeholders look like "[EMAIL_1]"; never longer than this
+const MAX_PLACEHOLDER_LENGTH = 32;
+
+export function restorePlaceholders(
+  text: string,
+  replacements: Replacement[]
+): string {
+  return replacements.reduce(
+    (acc, r) => acc.split(r.placeholder).join(r.original),
+    text
+  );
+}
+
+// Wraps a chunk callback so placeholders split across chunks are restored intact
+export function createRestoringSink(
+  replacements: Replacement[],
+  onChunk: (text: string) => void
+): { push: (text: string) => void; flush: () => void } {
+  let pending = "";
+
+  const push = (text: string) => {
+    if (replacements.length === 0) {
+      onChunk(text);
+      return;
+    }
+
+    pending += text;
+    const open = pending.lastIndexOf("[");
+    const hold =
+      open !== -1 &&
+      pending.indexOf("]", open) === -1 &&
+      pending.length - open < MAX_PLACEHOLDER_LENGTH;
+
+    const ready = hold ? pending.slice(0, open) : pending;
+    pending = hold ? pending.slice(open) : "";
+    if (ready) onChunk(restorePlaceholders(ready, replacements));
+  };
+
+  const flush = () => {
+    if (pending) onChunk(restorePlaceholders(pending, replacements));
+    pending = "";
+  };
+
+  return { push, flush };
+}